@@ -94,6 +94,24 @@ pub struct RunCmd {
 	/// elapsed (i.e. until a block at height `pause_block + delay` is imported).
 	#[structopt(long = "grandpa-pause", number_of_values(2))]
 	pub grandpa_pause: Vec<u32>,
+
+	/// Number of workers validating parachain candidates.
+	///
+	/// Defaults to the number of CPUs, up to 8.
+	#[structopt(long = "validation-workers")]
+	pub validation_workers: Option<usize>,
+
+	/// Restart a validation worker once it validated this many candidates.
+	///
+	/// Defaults to 1000, `0` never restarts workers.
+	#[structopt(long = "validation-worker-max-jobs")]
+	pub validation_worker_max_jobs: Option<u64>,
+
+	/// How often idle validation workers are checked and restarted if they died, in seconds.
+	///
+	/// Defaults to 5, `0` only restarts dead workers when they are next used.
+	#[structopt(long = "validation-health-check-interval")]
+	pub validation_health_check_interval: Option<u64>,
 }
 
 #[allow(missing_docs)]
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Duration;
 use service;
 use sc_cli::{SubstrateCli, Result, RuntimeVersion, Role};
 use crate::cli::{Cli, Subcommand};
@@ -89,6 +90,19 @@ pub fn run() -> Result<()> {
 				Some((cli.run.grandpa_pause[0], cli.run.grandpa_pause[1]))
 			};
 
+			let mut validation_pool = service::ValidationPoolConfig::new(
+				service::ValidationExecutionMode::ExternalProcessSelfHost,
+			);
+			validation_pool.num_workers = cli.run.validation_workers;
+			if let Some(max_jobs) = cli.run.validation_worker_max_jobs {
+				validation_pool.max_jobs_per_worker = Some(max_jobs).filter(|&n| n != 0);
+			}
+			if let Some(secs) = cli.run.validation_health_check_interval {
+				validation_pool.health_check_interval = Some(secs)
+					.filter(|&s| s != 0)
+					.map(Duration::from_secs);
+			}
+
 			runner.run_node_until_exit(|config| {
 				let role = config.role.clone();
 
@@ -99,6 +113,7 @@ pub fn run() -> Result<()> {
 						None,
 						authority_discovery_enabled,
 						grandpa_pause,
+						validation_pool,
 					).map(|r| r.0),
 				}
 			})
@@ -0,0 +1,114 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The availability primitives, parameterised by validator count and PoV size.
+//!
+//! Compare against a saved baseline with `scripts/compare-benches.sh`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use polkadot_erasure_coding::{
+	branch_hash, branches, obtain_chunks_v1, reconstruct_v1, systematic_recovery_threshold,
+};
+use common::available_data;
+
+mod common;
+
+const VALIDATOR_COUNTS: [usize; 3] = [10, 100, 1000];
+
+const POV_SIZES: [usize; 3] = [64 * 1024, 1024 * 1024, 5 * 1024 * 1024];
+
+fn params() -> impl Iterator<Item = (usize, usize)> {
+	VALIDATOR_COUNTS.iter()
+		.flat_map(|&n_validators| POV_SIZES.iter().map(move |&pov_size| (n_validators, pov_size)))
+}
+
+fn obtain_chunks(c: &mut Criterion) {
+	let mut group = c.benchmark_group("obtain_chunks_v1");
+	group.sample_size(10);
+
+	for (n_validators, pov_size) in params() {
+		let data = available_data(pov_size);
+		group.throughput(Throughput::Bytes(pov_size as u64));
+		group.bench_with_input(
+			BenchmarkId::new(n_validators.to_string(), pov_size),
+			&data,
+			|b, data| b.iter(|| obtain_chunks_v1(n_validators, data).unwrap()),
+		);
+	}
+
+	group.finish();
+}
+
+fn reconstruct(c: &mut Criterion) {
+	let mut group = c.benchmark_group("reconstruct_v1_by_pov_size");
+	group.sample_size(10);
+
+	for (n_validators, pov_size) in params() {
+		let chunks = obtain_chunks_v1(n_validators, &available_data(pov_size)).unwrap();
+		let threshold = systematic_recovery_threshold(n_validators).unwrap();
+
+		// the last chunks, so the data is actually decoded.
+		group.throughput(Throughput::Bytes(pov_size as u64));
+		group.bench_with_input(
+			BenchmarkId::new(n_validators.to_string(), pov_size),
+			&chunks,
+			|b, chunks| b.iter(|| reconstruct_v1(
+				n_validators,
+				chunks.iter().enumerate().rev().take(threshold).map(|(i, c)| (&c[..], i)),
+			).unwrap()),
+		);
+	}
+
+	group.finish();
+}
+
+fn build_branches(c: &mut Criterion) {
+	let mut group = c.benchmark_group("branches");
+
+	for (n_validators, pov_size) in params() {
+		let chunks = obtain_chunks_v1(n_validators, &available_data(pov_size)).unwrap();
+
+		group.bench_with_input(
+			BenchmarkId::new(n_validators.to_string(), pov_size),
+			&chunks,
+			|b, chunks| b.iter(|| branches(chunks).map(|(proof, _)| proof).count()),
+		);
+	}
+
+	group.finish();
+}
+
+fn verify_branch(c: &mut Criterion) {
+	let mut group = c.benchmark_group("branch_hash");
+
+	// proofs only depend on the number of chunks.
+	for &n_validators in VALIDATOR_COUNTS.iter() {
+		let chunks = obtain_chunks_v1(n_validators, &available_data(POV_SIZES[0])).unwrap();
+		let mut proofs = branches(&chunks);
+		let root = proofs.root();
+		let index = n_validators / 2;
+		let proof = proofs.nth(index).unwrap().0;
+
+		group.bench_with_input(BenchmarkId::from_parameter(n_validators), &proof, |b, proof| {
+			b.iter(|| branch_hash(&root, proof, index).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, obtain_chunks, reconstruct, build_branches, verify_branch);
+criterion_main!(benches);
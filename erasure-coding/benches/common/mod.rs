@@ -0,0 +1,27 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers shared by the benchmarks.
+
+use primitives::v1::{AvailableData, BlockData, PoV};
+
+/// Available data with a PoV of the given size.
+pub fn available_data(pov_size: usize) -> AvailableData {
+	AvailableData {
+		pov: PoV { block_data: BlockData((0..pov_size).map(|i| i as u8).collect()) },
+		validation_data: Default::default(),
+	}
+}
@@ -0,0 +1,56 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sequential compared to parallel encoding and trie construction, as done by backers.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use polkadot_erasure_coding::{branches, branches_parallel, obtain_chunks_v1, obtain_chunks_v1_parallel};
+use common::available_data;
+
+mod common;
+
+const N_VALIDATORS: usize = 200;
+
+const POV_SIZES: [usize; 5] = [64 * 1024, 256 * 1024, 1024 * 1024, 4 * 1024 * 1024, 16 * 1024 * 1024];
+
+fn encode(c: &mut Criterion) {
+	let mut group = c.benchmark_group("obtain_chunks_and_root_v1");
+	group.sample_size(10);
+
+	for &pov_size in POV_SIZES.iter() {
+		let data = available_data(pov_size);
+		group.throughput(Throughput::Bytes(pov_size as u64));
+
+		group.bench_with_input(BenchmarkId::new("sequential", pov_size), &data, |b, data| {
+			b.iter(|| {
+				let chunks = obtain_chunks_v1(N_VALIDATORS, data).unwrap();
+				branches(&chunks).root()
+			})
+		});
+
+		group.bench_with_input(BenchmarkId::new("parallel", pov_size), &data, |b, data| {
+			b.iter(|| {
+				let chunks = obtain_chunks_v1_parallel(N_VALIDATORS, data).unwrap();
+				branches_parallel(&chunks).root()
+			})
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reconstruction from the systematic chunks compared to reconstruction from the last chunks,
+//! which needs full Reed-Solomon decoding.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use polkadot_erasure_coding::{
+	obtain_chunks_v1, reconstruct_v1, reconstruct_from_systematic_chunks,
+	systematic_recovery_threshold,
+};
+use primitives::v1::AvailableData;
+use common::available_data;
+
+mod common;
+
+const VALIDATOR_COUNTS: [usize; 4] = [100, 200, 500, 1000];
+
+const POV_SIZE: usize = 1024 * 1024;
+
+fn reconstruct(c: &mut Criterion) {
+	let data = available_data(POV_SIZE);
+	let mut group = c.benchmark_group("reconstruct_v1");
+	group.throughput(Throughput::Bytes(POV_SIZE as u64));
+	group.sample_size(10);
+
+	for &n_validators in VALIDATOR_COUNTS.iter() {
+		let chunks = obtain_chunks_v1(n_validators, &data).unwrap();
+		let threshold = systematic_recovery_threshold(n_validators).unwrap();
+
+		group.bench_with_input(BenchmarkId::new("systematic", n_validators), &chunks, |b, chunks| {
+			b.iter(|| reconstruct_from_systematic_chunks::<_, AvailableData>(
+				n_validators,
+				chunks.iter().map(|c| &c[..]),
+			).unwrap())
+		});
+
+		group.bench_with_input(BenchmarkId::new("detected_systematic", n_validators), &chunks, |b, chunks| {
+			b.iter(|| reconstruct_v1(
+				n_validators,
+				chunks.iter().enumerate().take(threshold).map(|(i, c)| (&c[..], i)),
+			).unwrap())
+		});
+
+		group.bench_with_input(BenchmarkId::new("decoded", n_validators), &chunks, |b, chunks| {
+			b.iter(|| reconstruct_v1(
+				n_validators,
+				chunks.iter().enumerate().rev().take(threshold).map(|(i, c)| (&c[..], i)),
+			).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, reconstruct);
+criterion_main!(benches);
@@ -25,6 +25,7 @@
 //! The data is coded so any f+1 chunks can be used to reconstruct the full data.
 
 use codec::{Encode, Decode};
+use rayon::prelude::*;
 use reed_solomon::galois_16::{self, ReedSolomon};
 use primitives::v0::{self, Hash as H256, BlakeTwo256, HashT};
 use primitives::v1;
@@ -45,6 +46,8 @@ pub enum Error {
 	TooManyValidators,
 	/// Cannot encode something for no validators
 	EmptyValidators,
+	/// Cannot encode something for a single validator, as there would be no parity chunks.
+	NotEnoughValidators,
 	/// Cannot reconstruct: wrong number of validators.
 	WrongValidatorCount,
 	/// Not enough chunks present.
@@ -104,10 +107,14 @@ impl CodeParams {
 		shards
 	}
 
-	// make a reed-solomon instance.
-	fn make_encoder(&self) -> ReedSolomon {
-		ReedSolomon::new(self.data_shards, self.parity_shards)
-			.expect("this struct is not created with invalid shard number; qed")
+	// make a reed-solomon instance. It needs at least one parity shard.
+	fn make_encoder(&self) -> Result<ReedSolomon, Error> {
+		if self.parity_shards == 0 {
+			return Err(Error::NotEnoughValidators);
+		}
+
+		Ok(ReedSolomon::new(self.data_shards, self.parity_shards)
+			.expect("this struct is not created with invalid shard number; qed"))
 	}
 }
 
@@ -126,7 +133,7 @@ fn code_params(n_validators: usize) -> Result<CodeParams, Error> {
 
 /// Obtain erasure-coded chunks for v0 `AvailableData`, one for each validator.
 ///
-/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+/// Works only up to 65536 validators, and `n_validators` must be at least 2.
 pub fn obtain_chunks_v0(n_validators: usize, data: &v0::AvailableData)
 	-> Result<Vec<Vec<u8>>, Error>
 {
@@ -135,7 +142,7 @@ pub fn obtain_chunks_v0(n_validators: usize, data: &v0::AvailableData)
 
 /// Obtain erasure-coded chunks for v1 `AvailableData`, one for each validator.
 ///
-/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+/// Works only up to 65536 validators, and `n_validators` must be at least 2.
 pub fn obtain_chunks_v1(n_validators: usize, data: &v1::AvailableData)
 	-> Result<Vec<Vec<u8>>, Error>
 {
@@ -144,11 +151,12 @@ pub fn obtain_chunks_v1(n_validators: usize, data: &v1::AvailableData)
 
 /// Obtain erasure-coded chunks, one for each validator.
 ///
-/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+/// Works only up to 65536 validators, and `n_validators` must be at least 2.
 fn obtain_chunks<T: Encode>(n_validators: usize, data: &T)
 	-> Result<Vec<Vec<u8>>, Error>
 {
 	let params = code_params(n_validators)?;
+	let encoder = params.make_encoder()?;
 	let encoded = data.encode();
 
 	if encoded.is_empty() {
@@ -157,12 +165,65 @@ fn obtain_chunks<T: Encode>(n_validators: usize, data: &T)
 
 	let mut shards = params.make_shards_for(&encoded[..]);
 
-	params.make_encoder().encode(&mut shards[..])
+	encoder.encode(&mut shards[..])
 		.expect("Payload non-empty, shard sizes are uniform, and validator numbers checked; qed");
 
 	Ok(shards.into_iter().map(|w| w.into_inner()).collect())
 }
 
+/// Obtain erasure-coded chunks for v1 `AvailableData`, one for each validator, spreading the
+/// work across threads.
+///
+/// The shards are cut into stripes of `STRIPE_LEN` symbols, which are encoded independently.
+/// The chunks are identical to the ones `obtain_chunks_v1` yields.
+///
+/// Works only up to 65536 validators, and `n_validators` must be at least 2.
+pub fn obtain_chunks_v1_parallel(n_validators: usize, data: &v1::AvailableData)
+	-> Result<Vec<Vec<u8>>, Error>
+{
+	obtain_chunks_parallel(n_validators, data)
+}
+
+/// The number of GF(2^16) symbols of each shard which are encoded together.
+const STRIPE_LEN: usize = 4096;
+
+/// Obtain erasure-coded chunks, one for each validator, encoding stripes of the shards in parallel.
+///
+/// Works only up to 65536 validators, and `n_validators` must be at least 2.
+fn obtain_chunks_parallel<T: Encode>(n_validators: usize, data: &T)
+	-> Result<Vec<Vec<u8>>, Error>
+{
+	let params = code_params(n_validators)?;
+	let encoder = params.make_encoder()?;
+	let encoded = data.encode();
+
+	if encoded.is_empty() {
+		return Err(Error::BadPayload);
+	}
+
+	let mut shards = params.make_shards_for(&encoded[..]);
+
+	// stripe `i` consists of the `i`-th `STRIPE_LEN` symbols of every shard. The code works
+	// symbol-wise, so encoding the stripes one by one is the same as encoding whole shards.
+	let mut stripes: Vec<Vec<&mut [[u8; 2]]>> = Vec::new();
+	for shard in shards.iter_mut() {
+		let symbols: &mut [[u8; 2]] = shard.as_mut();
+		for (i, part) in symbols.chunks_mut(STRIPE_LEN).enumerate() {
+			if stripes.len() == i {
+				stripes.push(Vec::with_capacity(n_validators));
+			}
+			stripes[i].push(part);
+		}
+	}
+
+	stripes.par_iter_mut().for_each(|stripe| {
+		encoder.encode(&mut stripe[..])
+			.expect("Payload non-empty, stripe sizes are uniform, and validator numbers checked; qed");
+	});
+
+	Ok(shards.into_iter().map(|w| w.into_inner()).collect())
+}
+
 /// Reconstruct the v0 available data from a set of chunks.
 ///
 /// Provide an iterator containing chunk data and the corresponding index.
@@ -181,7 +242,8 @@ pub fn reconstruct_v0<'a, I: 'a>(n_validators: usize, chunks: I)
 ///
 /// Provide an iterator containing chunk data and the corresponding index.
 /// The indices of the present chunks must be indicated. If too few chunks
-/// are provided, recovery is not possible.
+/// are provided, recovery is not possible. Recovery is cheapest if all of the
+/// systematic chunks are provided, see `reconstruct_from_systematic_chunks`.
 ///
 /// Works only up to 65536 validators, and `n_validators` must be non-zero.
 pub fn reconstruct_v1<'a, I: 'a>(n_validators: usize, chunks: I)
@@ -191,11 +253,50 @@ pub fn reconstruct_v1<'a, I: 'a>(n_validators: usize, chunks: I)
 	reconstruct(n_validators, chunks)
 }
 
+/// The number of chunks needed to recover the data from the systematic chunks alone, which are
+/// the chunks with the lowest indices.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn systematic_recovery_threshold(n_validators: usize) -> Result<usize, Error> {
+	code_params(n_validators).map(|params| params.data_shards)
+}
+
+/// Reconstruct decodable data from the systematic chunks, which are the first
+/// `systematic_recovery_threshold(n_validators)` chunks, in order of their index.
+///
+/// The systematic chunks are the payload itself, so this only concatenates them, without
+/// any Reed-Solomon decoding. Any chunks beyond the systematic ones are ignored.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn reconstruct_from_systematic_chunks<'a, I: 'a, T: Decode>(n_validators: usize, chunks: I)
+	-> Result<T, Error>
+	where I: IntoIterator<Item=&'a [u8]>
+{
+	let params = code_params(n_validators)?;
+	let shards: Vec<&[u8]> = chunks.into_iter().take(params.data_shards).collect();
+
+	if shards.len() < params.data_shards {
+		return Err(Error::NotEnoughChunks);
+	}
+
+	let shard_len = shards[0].len();
+	if shard_len % 2 != 0 {
+		return Err(Error::UnevenLength);
+	}
+
+	if shard_len == 0 || shards.iter().any(|shard| shard.len() != shard_len) {
+		return Err(Error::NonUniformChunks);
+	}
+
+	decode_from_shards(shard_len * params.data_shards, shards.into_iter())
+}
+
 /// Reconstruct decodable data from a set of chunks.
 ///
 /// Provide an iterator containing chunk data and the corresponding index.
 /// The indices of the present chunks must be indicated. If too few chunks
-/// are provided, recovery is not possible.
+/// are provided, recovery is not possible. If all the systematic chunks are
+/// present, no decoding is necessary and they are used directly.
 ///
 /// Works only up to 65536 validators, and `n_validators` must be non-zero.
 fn reconstruct<'a, I: 'a, T: Decode>(n_validators: usize, chunks: I) -> Result<T, Error>
@@ -222,26 +323,40 @@ fn reconstruct<'a, I: 'a, T: Decode>(n_validators: usize, chunks: I) -> Result<T
 		shards[chunk_idx] = Some(WrappedShard::new(chunk_data.to_vec()));
 	}
 
-	if let Err(e) = params.make_encoder().reconstruct(&mut shards[..]) {
-		match e {
-			reed_solomon::Error::TooFewShardsPresent => Err(Error::NotEnoughChunks)?,
-			reed_solomon::Error::InvalidShardFlags => Err(Error::WrongValidatorCount)?,
-			reed_solomon::Error::TooManyShards => Err(Error::TooManyChunks)?,
-			reed_solomon::Error::EmptyShard => panic!("chunks are all non-empty; this is checked above; qed"),
-			reed_solomon::Error::IncorrectShardSize => panic!("chunks are all same len; this is checked above; qed"),
-			_ => panic!("reed_solomon encoder returns no more variants for this function; qed"),
+	// the data shards are the payload itself, so there is nothing to decode if all are present.
+	let systematic = shards.iter().take(params.data_shards).all(Option::is_some);
+	if !systematic {
+		if let Err(e) = params.make_encoder()?.reconstruct_data(&mut shards[..]) {
+			match e {
+				reed_solomon::Error::TooFewShardsPresent => Err(Error::NotEnoughChunks)?,
+				reed_solomon::Error::InvalidShardFlags => Err(Error::WrongValidatorCount)?,
+				reed_solomon::Error::TooManyShards => Err(Error::TooManyChunks)?,
+				reed_solomon::Error::EmptyShard => panic!("chunks are all non-empty; this is checked above; qed"),
+				reed_solomon::Error::IncorrectShardSize => panic!("chunks are all same len; this is checked above; qed"),
+				_ => panic!("reed_solomon encoder returns no more variants for this function; qed"),
+			}
 		}
 	}
 
-	// lazily decode from the data shards.
-	Decode::decode(&mut ShardInput {
-		remaining_len: shard_len.map(|s| s * params.data_shards).unwrap_or(0),
-		cur_shard: None,
-		shards: shards.iter()
+	decode_from_shards(
+		shard_len.map(|s| s * params.data_shards).unwrap_or(0),
+		shards.iter()
 			.map(|x| x.as_ref())
 			.take(params.data_shards)
 			.map(|x| x.expect("all data shards have been recovered; qed"))
-			.map(|x| x.as_ref()),
+			.map(AsRef::<[u8]>::as_ref),
+	)
+}
+
+// lazily decode from the data shards.
+fn decode_from_shards<'a, T: Decode>(
+	total_len: usize,
+	shards: impl Iterator<Item=&'a [u8]>,
+) -> Result<T, Error> {
+	Decode::decode(&mut ShardInput {
+		remaining_len: total_len,
+		cur_shard: None,
+		shards,
 	}).or_else(|_| Err(Error::BadPayload))
 }
 
@@ -292,15 +407,84 @@ impl<'a, I: AsRef<[u8]>> Iterator for Branches<'a, I> {
 pub fn branches<'a, I: 'a>(chunks: &'a [I]) -> Branches<'a, I>
 	where I: AsRef<[u8]>,
 {
+	let hashes = chunks.iter().map(|chunk| BlakeTwo256::hash(chunk.as_ref()));
+	branches_from_hashes(chunks, hashes)
+}
+
+/// The number of chunks hashed in parallel while the hashes of the previous ones are inserted
+/// into the trie.
+const HASH_BATCH_LEN: usize = 64;
+
+/// Like `branches`, but hashes the chunks in parallel. The resulting trie is the same.
+///
+/// The trie is built up batch by batch as the hashes come in, rather than once all chunks are
+/// hashed.
+pub fn branches_parallel<'a, I: 'a>(chunks: &'a [I]) -> Branches<'a, I>
+	where I: AsRef<[u8]> + Sync,
+{
+	let hash_batch = |batch: &[I]| -> Vec<H256> {
+		batch.par_iter().map(|chunk| BlakeTwo256::hash(chunk.as_ref())).collect()
+	};
+
+	let mut trie_storage: MemoryDB<Blake2Hasher> = MemoryDB::default();
+	let mut root = H256::default();
+
+	// commit the empty trie, which the batches are inserted into one after the other.
+	TrieDBMut::new(&mut trie_storage, &mut root);
+
+	let mut batches = chunks.chunks(HASH_BATCH_LEN);
+	let mut hashes = batches.next().map(hash_batch).unwrap_or_default();
+	let mut first_index = 0;
+
+	while !hashes.is_empty() {
+		let next_batch = batches.next();
+		let ((), next_hashes) = rayon::join(
+			|| insert_hashes(&mut trie_storage, &mut root, first_index, &hashes),
+			|| next_batch.map(hash_batch).unwrap_or_default(),
+		);
+
+		first_index += hashes.len();
+		hashes = next_hashes;
+	}
+
+	Branches {
+		trie_storage,
+		root,
+		chunks,
+		current_pos: 0,
+	}
+}
+
+/// Insert the hashes of the chunks starting at `first_index` into the trie with the given root.
+fn insert_hashes(
+	trie_storage: &mut MemoryDB<Blake2Hasher>,
+	root: &mut H256,
+	first_index: usize,
+	hashes: &[H256],
+) {
+	let mut trie = TrieDBMut::from_existing(trie_storage, root)
+		.expect("the root was committed to this storage before; qed");
+
+	for (i, chunk_hash) in hashes.iter().enumerate() {
+		((first_index + i) as u32).using_encoded(|encoded_index| {
+			trie.insert(encoded_index, chunk_hash.as_ref())
+				.expect("a trie stored in memory cannot have errors loading nodes; qed");
+		})
+	}
+}
+
+/// Construct the trie mapping each chunk's index to the given hash of the chunk.
+fn branches_from_hashes<'a, I: 'a>(
+	chunks: &'a [I],
+	hashes: impl Iterator<Item=H256>,
+) -> Branches<'a, I> {
 	let mut trie_storage: MemoryDB<Blake2Hasher> = MemoryDB::default();
 	let mut root = H256::default();
 
-	// construct trie mapping each chunk's index to its hash.
 	{
 		let mut trie = TrieDBMut::new(&mut trie_storage, &mut root);
-		for (i, chunk) in chunks.as_ref().iter().enumerate() {
+		for (i, chunk_hash) in hashes.enumerate() {
 			(i as u32).using_encoded(|encoded_index| {
-				let chunk_hash = BlakeTwo256::hash(chunk.as_ref());
 				trie.insert(encoded_index, chunk_hash.as_ref())
 					.expect("a fresh trie stored in memory cannot have errors loading nodes; qed");
 			})
@@ -478,6 +662,103 @@ mod tests {
 		assert_eq!(reconstructed, available_data);
 	}
 
+	#[test]
+	fn systematic_chunks_are_the_payload() {
+		let available_data = AvailableData {
+			pov_block: PoVBlock { block_data: BlockData((0..255).collect()) },
+			omitted_validation: Default::default(),
+		};
+		let chunks = obtain_chunks(10, &available_data).unwrap();
+
+		assert_eq!(systematic_recovery_threshold(10), Ok(4));
+
+		let systematic: Vec<u8> = chunks.iter().take(4).flatten().copied().collect();
+		let encoded = available_data.encode();
+		assert_eq!(&systematic[..encoded.len()], &encoded[..]);
+
+		let reconstructed: AvailableData = reconstruct_from_systematic_chunks(
+			10,
+			chunks.iter().map(|c| &c[..]),
+		).unwrap();
+		assert_eq!(reconstructed, available_data);
+
+		// systematic chunks found among others are detected.
+		let reconstructed: AvailableData = reconstruct(
+			10,
+			[
+				(&*chunks[3], 3),
+				(&*chunks[7], 7),
+				(&*chunks[0], 0),
+				(&*chunks[2], 2),
+				(&*chunks[1], 1),
+			].iter().cloned(),
+		).unwrap();
+		assert_eq!(reconstructed, available_data);
+	}
+
+	#[test]
+	fn systematic_reconstruction_needs_all_systematic_chunks() {
+		let available_data = AvailableData {
+			pov_block: PoVBlock { block_data: BlockData(vec![2; 256]) },
+			omitted_validation: Default::default(),
+		};
+		let chunks = obtain_chunks(10, &available_data).unwrap();
+
+		assert_eq!(
+			reconstruct_from_systematic_chunks::<_, AvailableData>(
+				10,
+				chunks.iter().take(3).map(|c| &c[..]),
+			),
+			Err(Error::NotEnoughChunks),
+		);
+
+		let short = &chunks[2][..chunks[2].len() - 2];
+		assert_eq!(
+			reconstruct_from_systematic_chunks::<_, AvailableData>(
+				10,
+				vec![&chunks[0][..], &chunks[1][..], short, &chunks[3][..]],
+			),
+			Err(Error::NonUniformChunks),
+		);
+	}
+
+	#[test]
+	fn parallel_encoding_matches_sequential() {
+		// a single stripe, and several stripes with a partial one at the end.
+		for &len in &[1, 1000, 3 * 2 * STRIPE_LEN * 34 + 5] {
+			let available_data = AvailableData {
+				pov_block: PoVBlock { block_data: BlockData((0..len).map(|i| i as u8).collect()) },
+				omitted_validation: Default::default(),
+			};
+
+			for &n_validators in &[2, 4, 10, 100] {
+				let chunks = obtain_chunks(n_validators, &available_data).unwrap();
+				let parallel_chunks = obtain_chunks_parallel(n_validators, &available_data).unwrap();
+				assert_eq!(parallel_chunks, chunks);
+
+				let sequential = branches(&chunks);
+				let parallel = branches_parallel(&parallel_chunks);
+				assert_eq!(parallel.root(), sequential.root());
+				assert!(parallel.map(|(proof, _)| proof).eq(sequential.map(|(proof, _)| proof)));
+			}
+		}
+	}
+
+	#[test]
+	fn a_single_validator_is_not_enough_to_encode() {
+		let available_data = AvailableData {
+			pov_block: PoVBlock { block_data: BlockData(vec![2; 256]) },
+			omitted_validation: Default::default(),
+		};
+
+		assert_eq!(obtain_chunks(1, &available_data), Err(Error::NotEnoughValidators));
+		assert_eq!(obtain_chunks_parallel(1, &available_data), Err(Error::NotEnoughValidators));
+		assert_eq!(
+			reconstruct::<_, AvailableData>(1, std::iter::empty()),
+			Err(Error::NotEnoughValidators),
+		);
+	}
+
 	#[test]
 	fn construct_valid_branches() {
 		let pov_block = PoVBlock {
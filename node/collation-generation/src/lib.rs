@@ -46,6 +46,7 @@ use sp_core::crypto::Pair;
 use std::sync::Arc;
 
 /// Collation Generation Subsystem
+#[derive(Clone)]
 pub struct CollationGenerationSubsystem {
 	config: Option<Arc<CollationGenerationConfig>>,
 	metrics: Metrics,
@@ -211,9 +212,13 @@ async fn handle_new_activations<Context: SubsystemContext>(
 				CoreState::Scheduled(scheduled_core) => {
 					(scheduled_core, OccupiedCoreAssumption::Free)
 				}
-				CoreState::Occupied(_occupied_core) => {
-					// TODO: https://github.com/paritytech/polkadot/issues/1573
-					continue;
+				CoreState::Occupied(occupied_core) => {
+					// build on top of the candidate pending availability, assuming it will be
+					// included by the time our collation is backed.
+					match occupied_core.next_up_on_available {
+						Some(scheduled_core) => (scheduled_core, OccupiedCoreAssumption::Included),
+						None => continue,
+					}
 				}
 				_ => continue,
 			};
@@ -222,6 +227,14 @@ async fn handle_new_activations<Context: SubsystemContext>(
 				continue;
 			}
 
+			// a parathread core is claimed by a particular collator, which is the only one
+			// allowed to author its block.
+			if let Some(required_collator) = &scheduled_core.collator {
+				if required_collator != &config.key.public() {
+					continue;
+				}
+			}
+
 			// we get validation data synchronously for each core instead of
 			// within the subtask loop, because we have only a single mutable handle to the
 			// context, so the work can't really be distributed
@@ -364,7 +377,7 @@ mod tests {
 			subsystem_test_harness, TestSubsystemContextHandle,
 		};
 		use polkadot_primitives::v1::{
-			BlockData, BlockNumber, CollatorPair, Id as ParaId,
+			BlockData, BlockNumber, CollatorPair, Id as ParaId, OccupiedCore,
 			PersistedValidationData, PoV, ScheduledCore, ValidationData,
 		};
 		use std::pin::Pin;
@@ -540,6 +553,164 @@ mod tests {
 			assert_eq!(requested_full_validation_data, vec![[4; 32].into()]);
 		}
 
+		fn occupied_core_for<Id: Into<ParaId>>(
+			para_id: Id,
+			next_up_on_available: Option<ScheduledCore>,
+		) -> OccupiedCore {
+			OccupiedCore {
+				para_id: para_id.into(),
+				next_up_on_available,
+				occupied_since: 1,
+				time_out_at: 10,
+				next_up_on_time_out: None,
+				availability: Default::default(),
+				group_responsible: Default::default(),
+			}
+		}
+
+		#[test]
+		fn requests_validation_data_for_occupied_cores_next_up_on_available() {
+			let activated_hashes: Vec<Hash> = vec![Hash::repeat_byte(1)];
+
+			let requested_full_validation_data = Arc::new(Mutex::new(Vec::new()));
+
+			let overseer_requested_full_validation_data = requested_full_validation_data.clone();
+			let overseer = |mut handle: TestSubsystemContextHandle<CollationGenerationMessage>| async move {
+				loop {
+					match handle.try_recv().await {
+						None => break,
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							_hash,
+							RuntimeApiRequest::AvailabilityCores(tx),
+						))) => {
+							tx.send(Ok(vec![
+								// our own candidate is pending availability, and we're next up
+								CoreState::Occupied(occupied_core_for(16, Some(scheduled_core_for(16)))),
+								// another para is next up on this core
+								CoreState::Occupied(occupied_core_for(16, Some(scheduled_core_for(17)))),
+								// nothing is next up on this core
+								CoreState::Occupied(occupied_core_for(16, None)),
+							]))
+							.unwrap();
+						}
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							hash,
+							RuntimeApiRequest::FullValidationData(
+								para_id,
+								occupied_core_assumption,
+								tx,
+							),
+						))) => {
+							overseer_requested_full_validation_data
+								.lock()
+								.await
+								.push((hash, para_id, occupied_core_assumption));
+							tx.send(Ok(Default::default())).unwrap();
+						}
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							_hash,
+							RuntimeApiRequest::Validators(tx),
+						))) => {
+							tx.send(Ok(vec![Default::default(); 3])).unwrap();
+						}
+						Some(msg) => {
+							panic!("didn't expect any other overseer requests; got {:?}", msg)
+						}
+					}
+				}
+			};
+
+			let (tx, _rx) = mpsc::channel(0);
+
+			subsystem_test_harness(overseer, |mut ctx| async move {
+				handle_new_activations(test_config(16), &activated_hashes, &mut ctx, Metrics(None), &tx)
+					.await
+					.unwrap();
+			});
+
+			let requested_full_validation_data = Arc::try_unwrap(requested_full_validation_data)
+				.expect("overseer should have shut down by now")
+				.into_inner();
+
+			assert_eq!(
+				requested_full_validation_data,
+				vec![(Hash::repeat_byte(1), 16.into(), OccupiedCoreAssumption::Included)],
+			);
+		}
+
+		#[test]
+		fn only_builds_on_parathread_cores_claimed_by_us() {
+			let activated_hashes: Vec<Hash> = vec![Hash::repeat_byte(1), Hash::repeat_byte(2)];
+
+			let config = test_config(16);
+			let our_id = config.key.public();
+			let other_id = CollatorPair::generate().0.public();
+
+			let requested_full_validation_data = Arc::new(Mutex::new(Vec::new()));
+
+			let overseer_requested_full_validation_data = requested_full_validation_data.clone();
+			let overseer = |mut handle: TestSubsystemContextHandle<CollationGenerationMessage>| async move {
+				loop {
+					match handle.try_recv().await {
+						None => break,
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							hash,
+							RuntimeApiRequest::AvailabilityCores(tx),
+						))) => {
+							// the parathread slot at the first relay parent was claimed by another
+							// collator, the one at the second by us
+							let claimed_by = if hash == Hash::repeat_byte(1) {
+								other_id.clone()
+							} else {
+								our_id.clone()
+							};
+
+							tx.send(Ok(vec![
+								CoreState::Scheduled(ScheduledCore {
+									para_id: 16.into(),
+									collator: Some(claimed_by),
+								}),
+							]))
+							.unwrap();
+						}
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							hash,
+							RuntimeApiRequest::FullValidationData(_para_id, _assumption, tx),
+						))) => {
+							overseer_requested_full_validation_data
+								.lock()
+								.await
+								.push(hash);
+							tx.send(Ok(Default::default())).unwrap();
+						}
+						Some(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+							_hash,
+							RuntimeApiRequest::Validators(tx),
+						))) => {
+							tx.send(Ok(vec![Default::default(); 3])).unwrap();
+						}
+						Some(msg) => {
+							panic!("didn't expect any other overseer requests; got {:?}", msg)
+						}
+					}
+				}
+			};
+
+			let (tx, _rx) = mpsc::channel(0);
+
+			subsystem_test_harness(overseer, |mut ctx| async move {
+				handle_new_activations(config, &activated_hashes, &mut ctx, Metrics(None), &tx)
+					.await
+					.unwrap();
+			});
+
+			let requested_full_validation_data = Arc::try_unwrap(requested_full_validation_data)
+				.expect("overseer should have shut down by now")
+				.into_inner();
+
+			assert_eq!(requested_full_validation_data, vec![Hash::repeat_byte(2)]);
+		}
+
 		#[test]
 		fn sends_distribute_collation_message() {
 			let activated_hashes: Vec<Hash> = vec![
@@ -30,8 +30,9 @@ use kvdb_rocksdb::{Database, DatabaseConfig};
 use kvdb::{KeyValueDB, DBTransaction};
 
 use polkadot_primitives::v1::{
-	Hash, AvailableData, ErasureChunk, ValidatorIndex,
+	Hash, AvailableData, ErasureChunk, ValidatorIndex, PersistedValidationData,
 };
+use polkadot_node_primitives::CompressedPoV;
 use polkadot_subsystem::{
 	FromOverseer, SubsystemError, Subsystem, SubsystemContext, SpawnedSubsystem,
 	metrics::{self, prometheus},
@@ -55,15 +56,22 @@ enum Error {
 	Oneshot(oneshot::Canceled),
 	#[from]
 	Subsystem(SubsystemError),
+	Compression(polkadot_node_primitives::CompressedPoVError),
 }
 
 /// An implementation of the Availability Store subsystem.
+#[derive(Clone)]
 pub struct AvailabilityStoreSubsystem {
 	inner: Arc<dyn KeyValueDB>,
 	metrics: Metrics,
 }
 
 fn available_data_key(candidate_hash: &Hash) -> Vec<u8> {
+	(candidate_hash, 1i8).encode()
+}
+
+/// The key under which available data was stored before PoVs were compressed.
+fn legacy_available_data_key(candidate_hash: &Hash) -> Vec<u8> {
 	(candidate_hash, 0i8).encode()
 }
 
@@ -71,6 +79,18 @@ fn erasure_chunk_key(candidate_hash: &Hash, index: u32) -> Vec<u8> {
 	(candidate_hash, index, 0i8).encode()
 }
 
+/// Available data with its PoV compressed, as written to the database.
+#[derive(Encode, Decode)]
+struct CompressedAvailableData {
+	pov: CompressedPoV,
+	validation_data: PersistedValidationData,
+	n_validators: u32,
+}
+
+/// Available data with the PoV decompressed.
+///
+/// This is also the format entries were written in before PoVs were compressed,
+/// so it is used to read those back.
 #[derive(Encode, Decode)]
 struct StoredAvailableData {
 	data: AvailableData,
@@ -153,7 +173,7 @@ fn process_message(db: &Arc<dyn KeyValueDB>, metrics: &Metrics, msg: Availabilit
 			tx.send(available_data(db, &hash).map(|d| d.data)).map_err(|_| oneshot::Canceled)?;
 		}
 		QueryDataAvailability(hash, tx) => {
-			tx.send(available_data(db, &hash).is_some()).map_err(|_| oneshot::Canceled)?;
+			tx.send(has_available_data(db, &hash)).map_err(|_| oneshot::Canceled)?;
 		}
 		QueryChunk(hash, id, tx) => {
 			tx.send(get_chunk(db, &hash, id, metrics)?).map_err(|_| oneshot::Canceled)?;
@@ -189,7 +209,44 @@ fn process_message(db: &Arc<dyn KeyValueDB>, metrics: &Metrics, msg: Availabilit
 }
 
 fn available_data(db: &Arc<dyn KeyValueDB>, candidate_hash: &Hash) -> Option<StoredAvailableData> {
-	query_inner(db, columns::DATA, &available_data_key(candidate_hash))
+	let compressed: Option<CompressedAvailableData> = query_inner(
+		db,
+		columns::DATA,
+		&available_data_key(candidate_hash),
+	);
+
+	match compressed {
+		Some(CompressedAvailableData { pov, validation_data, n_validators }) => {
+			match pov.decompress() {
+				Ok(pov) => Some(StoredAvailableData {
+					data: AvailableData { pov, validation_data },
+					n_validators,
+				}),
+				Err(e) => {
+					log::warn!(
+						target: LOG_TARGET,
+						"Failed to decompress stored PoV of candidate {}: {:?}",
+						candidate_hash,
+						e,
+					);
+					None
+				}
+			}
+		}
+		None => query_inner(db, columns::DATA, &legacy_available_data_key(candidate_hash)),
+	}
+}
+
+fn has_available_data(db: &Arc<dyn KeyValueDB>, candidate_hash: &Hash) -> bool {
+	let has_key = |key: Vec<u8>| match db.get(columns::DATA, &key) {
+		Ok(value) => value.is_some(),
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Error reading from the availability store: {:?}", e);
+			false
+		}
+	};
+
+	has_key(available_data_key(candidate_hash)) || has_key(legacy_available_data_key(candidate_hash))
 }
 
 fn store_available_data(
@@ -207,8 +264,11 @@ fn store_available_data(
 		store_chunk(db, candidate_hash, n_validators, chunks[index as usize].clone())?;
 	}
 
-	let stored_data = StoredAvailableData {
-		data: available_data,
+	let pov = CompressedPoV::compress(&available_data.pov).map_err(Error::Compression)?;
+
+	let stored_data = CompressedAvailableData {
+		pov,
+		validation_data: available_data.validation_data,
 		n_validators,
 	};
 
@@ -498,6 +558,46 @@ mod tests {
 	}
 
 
+	#[test]
+	fn legacy_available_data_is_readable() {
+		let store = Arc::new(kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let test_state = TestState::default();
+		let candidate_hash = Hash::from([1; 32]);
+		let n_validators = 10;
+
+		let available_data = AvailableData {
+			pov: PoV {
+				block_data: BlockData(vec![4, 5, 6]),
+			},
+			validation_data: test_state.persisted_validation_data,
+		};
+
+		let legacy = StoredAvailableData {
+			data: available_data.clone(),
+			n_validators,
+		};
+
+		let mut tx = DBTransaction::new();
+		tx.put_vec(columns::DATA, &legacy_available_data_key(&candidate_hash), legacy.encode());
+		store.write(tx).unwrap();
+
+		test_harness(store.clone(), |test_harness| async move {
+			let TestHarness { mut virtual_overseer } = test_harness;
+
+			let (tx, rx) = oneshot::channel();
+			let query = AvailabilityStoreMessage::QueryDataAvailability(candidate_hash, tx);
+			virtual_overseer.send(FromOverseer::Communication{ msg: query }).await;
+			assert!(rx.await.unwrap());
+
+			let data = query_available_data(&mut virtual_overseer, candidate_hash).await.unwrap();
+			assert_eq!(data, available_data);
+
+			let chunk = query_chunk(&mut virtual_overseer, candidate_hash, 3).await.unwrap();
+			let chunks = erasure::obtain_chunks_v1(10, &available_data).unwrap();
+			assert_eq!(chunk.chunk, chunks[3]);
+		});
+	}
+
 	#[test]
 	fn store_pov_and_query_chunk_works() {
 		let store = Arc::new(kvdb_memorydb::create(columns::NUM_COLUMNS));
@@ -83,6 +83,26 @@ enum Error {
 	UtilError(util::Error),
 }
 
+/// Erasure code the available data on the rayon thread pool, and hand it back along with the
+/// erasure root. The encoding keeps all cores busy for a while, so it must not run on the
+/// executor the job is polled on.
+async fn compute_erasure_root(
+	n_validators: usize,
+	available_data: AvailableData,
+) -> Result<(AvailableData, Hash), Error> {
+	let (tx, rx) = oneshot::channel();
+
+	// the encoding itself is split up on the same pool.
+	rayon::spawn(move || {
+		let erasure_root = erasure_coding::obtain_chunks_v1_parallel(n_validators, &available_data)
+			.map(|chunks| erasure_coding::branches_parallel(chunks.as_ref()).root());
+
+		let _ = tx.send(erasure_root.map(|root| (available_data, root)));
+	});
+
+	Ok(rx.await??)
+}
+
 /// Holds all data needed for candidate backing job operation.
 struct CandidateBackingJob {
 	/// The hash of the relay parent on top of which this job is doing it's work.
@@ -337,7 +357,7 @@ impl CandidateBackingJob {
 	}
 
 	fn get_backed(&self) -> Vec<NewBackedCandidate> {
-		let proposed = self.table.proposed_candidates(&self.table_context);
+		let proposed = self.table.proposed_candidates(&self.table_context).attested;
 		let mut res = Vec::with_capacity(proposed.len());
 
 		for p in proposed.into_iter() {
@@ -651,13 +671,10 @@ impl CandidateBackingJob {
 			validation_data: outputs.validation_data,
 		};
 
-		let chunks = erasure_coding::obtain_chunks_v1(
+		let (available_data, erasure_root) = compute_erasure_root(
 			self.table_context.validators.len(),
-			&available_data,
-		)?;
-
-		let branches = erasure_coding::branches(chunks.as_ref());
-		let erasure_root = branches.root();
+			available_data,
+		).await?;
 
 		let commitments = CandidateCommitments {
 			fees: outputs.fees,
@@ -28,15 +28,18 @@ use polkadot_node_subsystem::{
 	errors::{ChainApiError, RuntimeApiError},
 	messages::{
 		AllMessages, CandidateBackingMessage, CandidateSelectionMessage,
-		CandidateValidationMessage, CollatorProtocolMessage,
+		CandidateValidationMessage, CollatorProtocolMessage, RuntimeApiMessage,
 	},
 	metrics::{self, prometheus},
 };
-use polkadot_node_subsystem_util::{self as util, delegated_subsystem, JobTrait, ToJobTrait};
+use polkadot_node_subsystem_util::{
+	self as util, delegated_subsystem, request_availability_cores, JobTrait, ToJobTrait,
+};
 use polkadot_primitives::v1::{
-	CandidateDescriptor, CandidateReceipt, CollatorId, Hash, Id as ParaId, PoV,
+	CandidateDescriptor, CandidateReceipt, CollatorId, CoreState, Hash, Id as ParaId, PoV,
+	ScheduledCore,
 };
-use std::{convert::TryFrom, pin::Pin, sync::Arc};
+use std::{collections::HashMap, convert::TryFrom, pin::Pin, sync::Arc};
 
 const TARGET: &'static str = "candidate_selection";
 
@@ -45,6 +48,9 @@ struct CandidateSelectionJob {
 	receiver: mpsc::Receiver<ToJob>,
 	metrics: Metrics,
 	seconded_candidate: Option<CollatorId>,
+	/// The collators which claimed the parathread cores scheduled at the relay parent.
+	/// Collations for those parathreads are only accepted from them.
+	required_collators: HashMap<ParaId, CollatorId>,
 }
 
 /// This enum defines the messages that the provisioner is prepared to receive.
@@ -89,6 +95,7 @@ enum FromJob {
 	Validation(CandidateValidationMessage),
 	Backing(CandidateBackingMessage),
 	Collator(CollatorProtocolMessage),
+	Runtime(RuntimeApiMessage),
 }
 
 impl From<FromJob> for AllMessages {
@@ -97,6 +104,7 @@ impl From<FromJob> for AllMessages {
 			FromJob::Validation(msg) => AllMessages::CandidateValidation(msg),
 			FromJob::Backing(msg) => AllMessages::CandidateBacking(msg),
 			FromJob::Collator(msg) => AllMessages::CollatorProtocol(msg),
+			FromJob::Runtime(msg) => AllMessages::RuntimeApi(msg),
 		}
 	}
 }
@@ -109,6 +117,7 @@ impl TryFrom<AllMessages> for FromJob {
 			AllMessages::CandidateValidation(msg) => Ok(FromJob::Validation(msg)),
 			AllMessages::CandidateBacking(msg) => Ok(FromJob::Backing(msg)),
 			AllMessages::CollatorProtocol(msg) => Ok(FromJob::Collator(msg)),
+			AllMessages::RuntimeApi(msg) => Ok(FromJob::Runtime(msg)),
 			_ => Err(()),
 		}
 	}
@@ -141,14 +150,26 @@ impl JobTrait for CandidateSelectionJob {
 	//
 	// this function is in charge of creating and executing the job's main loop
 	fn run(
-		_relay_parent: Hash,
+		relay_parent: Hash,
 		_run_args: Self::RunArgs,
 		metrics: Self::Metrics,
 		receiver: mpsc::Receiver<ToJob>,
-		sender: mpsc::Sender<FromJob>,
+		mut sender: mpsc::Sender<FromJob>,
 	) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send>> {
 		async move {
-			let job = CandidateSelectionJob::new(metrics, sender, receiver);
+			// without knowing which collators claimed the parathread cores we can still select
+			// parachain candidates, so the job carries on.
+			let required_collators = required_collators(relay_parent, &mut sender).await
+				.unwrap_or_else(|err| {
+					log::warn!(
+						target: TARGET,
+						"failed to learn the required collators at {:?}: {:?}",
+						relay_parent,
+						err,
+					);
+					HashMap::new()
+				});
+			let job = CandidateSelectionJob::new(metrics, sender, receiver, required_collators);
 
 			// it isn't necessary to break run_loop into its own function,
 			// but it's convenient to separate the concerns in this way
@@ -163,12 +184,14 @@ impl CandidateSelectionJob {
 		metrics: Metrics,
 		sender: mpsc::Sender<FromJob>,
 		receiver: mpsc::Receiver<ToJob>,
+		required_collators: HashMap<ParaId, CollatorId>,
 	) -> Self {
 		Self {
 			sender,
 			receiver,
 			metrics,
 			seconded_candidate: None,
+			required_collators,
 		}
 	}
 
@@ -210,6 +233,18 @@ impl CandidateSelectionJob {
 		para_id: ParaId,
 		collator_id: CollatorId,
 	) {
+		let required_collator = self.required_collators.get(&para_id).cloned();
+
+		if required_collator.as_ref().map_or(false, |required| required != &collator_id) {
+			log::debug!(
+				target: TARGET,
+				"ignoring collation for parathread {} by {:?}, which did not claim the slot",
+				para_id,
+				collator_id,
+			);
+			return;
+		}
+
 		if self.seconded_candidate.is_none() {
 			let (candidate_receipt, pov) =
 				match get_collation(
@@ -229,6 +264,17 @@ impl CandidateSelectionJob {
 					}
 				};
 
+			if let Some(required_collator) = required_collator {
+				if candidate_receipt.descriptor.collator != required_collator {
+					log::warn!(
+						target: TARGET,
+						"collation for parathread {} is not authored by the collator which claimed the slot",
+						para_id,
+					);
+					return;
+				}
+			}
+
 			let pov = Arc::new(pov);
 
 			if !candidate_is_valid(
@@ -295,6 +341,25 @@ impl CandidateSelectionJob {
 	}
 }
 
+// find out which collators claimed the parathread cores scheduled at the relay parent
+async fn required_collators(
+	relay_parent: Hash,
+	sender: &mut mpsc::Sender<FromJob>,
+) -> Result<HashMap<ParaId, CollatorId>, Error> {
+	let cores = request_availability_cores(relay_parent, sender).await?.await??;
+
+	Ok(cores
+		.into_iter()
+		.filter_map(|core| match core {
+			CoreState::Scheduled(ScheduledCore {
+				para_id,
+				collator: Some(collator),
+			}) => Some((para_id, collator)),
+			_ => None,
+		})
+		.collect())
+}
+
 // get a collation from the Collator Protocol subsystem
 //
 // note that this gets an owned clone of the sender; that's becuase unlike `forward_invalidity_note`, it's expected to take a while longer
@@ -442,6 +507,7 @@ delegated_subsystem!(CandidateSelectionJob((), Metrics) <- ToJob as CandidateSel
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_matches::assert_matches;
 	use futures::lock::Mutex;
 	use polkadot_node_primitives::ValidationOutputs;
 	use polkadot_primitives::v1::{BlockData, HeadData, PersistedValidationData};
@@ -464,6 +530,7 @@ mod tests {
 			receiver: to_job_rx,
 			metrics: Default::default(),
 			seconded_candidate: None,
+			required_collators: HashMap::new(),
 		};
 
 		preconditions(&mut job);
@@ -675,4 +742,102 @@ mod tests {
 
 		assert!(Arc::try_unwrap(sent_report).unwrap().into_inner());
 	}
+
+	/// a parathread block is fetched only from the collator which claimed the slot, and
+	/// makes it from there to backing
+	#[test]
+	fn parathread_collation_goes_from_claim_to_backing() {
+		use polkadot_node_subsystem::messages::RuntimeApiRequest;
+
+		let relay_parent = Hash::random();
+		let para_id: ParaId = 2000.into();
+		let claimant = CollatorId::from_slice(&(0..32).collect::<Vec<u8>>());
+		let other_collator = CollatorId::from_slice(&(0..32).rev().collect::<Vec<u8>>());
+
+		let mut candidate_receipt = CandidateReceipt::default();
+		candidate_receipt.descriptor.para_id = para_id;
+		candidate_receipt.descriptor.collator = claimant.clone();
+		let pov = PoV {
+			block_data: BlockData((0..32).cycle().take(256).collect()),
+		};
+
+		let was_seconded = Arc::new(Mutex::new(false));
+		let was_seconded_clone = was_seconded.clone();
+
+		let (mut to_job, to_job_rx) = mpsc::channel(0);
+		let (from_job_tx, mut from_job) = mpsc::channel(0);
+
+		let test = async move {
+			assert_matches!(
+				from_job.next().await,
+				Some(FromJob::Runtime(RuntimeApiMessage::Request(
+					got_relay_parent,
+					RuntimeApiRequest::AvailabilityCores(tx),
+				))) => {
+					assert_eq!(got_relay_parent, relay_parent);
+					tx.send(Ok(vec![
+						CoreState::Free,
+						CoreState::Scheduled(ScheduledCore {
+							para_id,
+							collator: Some(claimant.clone()),
+						}),
+					])).unwrap();
+				}
+			);
+
+			for collator in vec![other_collator, claimant.clone()] {
+				to_job
+					.send(ToJob::CandidateSelection(
+						CandidateSelectionMessage::Collation(relay_parent, para_id, collator),
+					))
+					.await
+					.unwrap();
+			}
+			std::mem::drop(to_job);
+
+			while let Some(msg) = from_job.next().await {
+				match msg {
+					FromJob::Collator(CollatorProtocolMessage::FetchCollation(
+						_,
+						collator_id,
+						_,
+						return_sender,
+					)) => {
+						assert_eq!(collator_id, claimant);
+
+						return_sender
+							.send((candidate_receipt.clone(), pov.clone()))
+							.unwrap();
+					}
+					FromJob::Validation(
+						CandidateValidationMessage::ValidateFromChainState(_, _, return_sender),
+					) => {
+						return_sender
+							.send(Ok(ValidationResult::Valid(default_validation_outputs())))
+							.unwrap();
+					}
+					FromJob::Backing(CandidateBackingMessage::Second(
+						got_relay_parent,
+						got_candidate_receipt,
+						got_pov,
+					)) => {
+						assert_eq!(got_relay_parent, relay_parent);
+						assert_eq!(got_candidate_receipt, candidate_receipt);
+						assert_eq!(got_pov, pov);
+
+						*was_seconded_clone.lock().await = true;
+					}
+					other => panic!("unexpected message from job: {:?}", other),
+				}
+			}
+		};
+
+		let (_, job_result) = futures::executor::block_on(future::join(
+			test,
+			CandidateSelectionJob::run(relay_parent, (), Default::default(), to_job_rx, from_job_tx),
+		));
+
+		assert!(job_result.is_ok());
+		assert!(Arc::try_unwrap(was_seconded).unwrap().into_inner());
+	}
 }
@@ -38,6 +38,7 @@ use polkadot_primitives::v1::{
 use polkadot_parachain::wasm_executor::{
 	self, ValidationPool, ExecutionMode, ValidationError,
 	InvalidCandidate as WasmInvalidCandidate, ValidationExecutionMode,
+	ValidationPoolConfig, ValidationPoolObserver,
 };
 use polkadot_parachain::primitives::{ValidationResult as WasmValidationResult, ValidationParams};
 
@@ -48,18 +49,24 @@ use futures::channel::oneshot;
 use futures::prelude::*;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 const LOG_TARGET: &'static str = "candidate_validation";
 
 /// The candidate validation subsystem.
+#[derive(Clone)]
 pub struct CandidateValidationSubsystem<S> {
 	spawn: S,
 	metrics: Metrics,
+	pool_config: ValidationPoolConfig,
 }
 
 #[derive(Clone)]
 struct MetricsInner {
 	validation_requests: prometheus::CounterVec<prometheus::U64>,
+	worker_queue_wait: prometheus::HistogramVec,
+	worker_execution_time: prometheus::HistogramVec,
+	worker_crashes: prometheus::CounterVec<prometheus::U64>,
 }
 
 /// Candidate validation metrics.
@@ -84,6 +91,30 @@ impl Metrics {
 	}
 }
 
+impl ValidationPoolObserver for Metrics {
+	fn on_queued(&self, worker: usize, wait: Duration) {
+		if let Some(metrics) = &self.0 {
+			metrics.worker_queue_wait
+				.with_label_values(&[&worker.to_string()])
+				.observe(wait.as_secs_f64());
+		}
+	}
+
+	fn on_executed(&self, worker: usize, duration: Duration) {
+		if let Some(metrics) = &self.0 {
+			metrics.worker_execution_time
+				.with_label_values(&[&worker.to_string()])
+				.observe(duration.as_secs_f64());
+		}
+	}
+
+	fn on_crashed(&self, worker: usize) {
+		if let Some(metrics) = &self.0 {
+			metrics.worker_crashes.with_label_values(&[&worker.to_string()]).inc();
+		}
+	}
+}
+
 impl metrics::Metrics for Metrics {
 	fn try_register(registry: &prometheus::Registry) -> Result<Self, prometheus::PrometheusError> {
 		let metrics = MetricsInner {
@@ -97,15 +128,56 @@ impl metrics::Metrics for Metrics {
 				)?,
 				registry,
 			)?,
+			worker_queue_wait: prometheus::register(
+				prometheus::HistogramVec::new(
+					prometheus::HistogramOpts::new(
+						"parachain_validation_worker_queue_wait_seconds",
+						"Time candidates waited for a validation worker to be free.",
+					),
+					&["worker"],
+				)?,
+				registry,
+			)?,
+			worker_execution_time: prometheus::register(
+				prometheus::HistogramVec::new(
+					prometheus::HistogramOpts::new(
+						"parachain_validation_worker_execution_seconds",
+						"Time validation workers took to validate a candidate.",
+					),
+					&["worker"],
+				)?,
+				registry,
+			)?,
+			worker_crashes: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_validation_worker_crashes_total",
+						"Number of times validation workers died.",
+					),
+					&["worker"],
+				)?,
+				registry,
+			)?,
 		};
 		Ok(Metrics(Some(metrics)))
 	}
 }
 
 impl<S> CandidateValidationSubsystem<S> {
-	/// Create a new `CandidateValidationSubsystem` with the given task spawner.
+	/// Create a new `CandidateValidationSubsystem` with the given task spawner, validating
+	/// candidates on a default pool of workers running the current executable.
 	pub fn new(spawn: S, metrics: Metrics) -> Self {
-		CandidateValidationSubsystem { spawn, metrics }
+		Self::with_pool_config(
+			spawn,
+			metrics,
+			ValidationPoolConfig::new(ValidationExecutionMode::ExternalProcessSelfHost),
+		)
+	}
+
+	/// Create a new `CandidateValidationSubsystem` with the given task spawner, validating
+	/// candidates on a pool of workers with the given configuration.
+	pub fn with_pool_config(spawn: S, metrics: Metrics, pool_config: ValidationPoolConfig) -> Self {
+		CandidateValidationSubsystem { spawn, metrics, pool_config }
 	}
 }
 
@@ -118,7 +190,7 @@ impl<S, C> Subsystem<C> for CandidateValidationSubsystem<S> where
 	fn start(self, ctx: C) -> SpawnedSubsystem {
 		SpawnedSubsystem {
 			name: "candidate-validation-subsystem",
-			future: run(ctx, self.spawn, self.metrics).map(|_| ()).boxed(),
+			future: run(ctx, self.spawn, self.metrics, self.pool_config).map(|_| ()).boxed(),
 		}
 	}
 }
@@ -127,10 +199,11 @@ async fn run(
 	mut ctx: impl SubsystemContext<Message = CandidateValidationMessage>,
 	spawn: impl SpawnNamed + Clone + 'static,
 	metrics: Metrics,
+	pool_config: ValidationPoolConfig,
 )
 	-> SubsystemResult<()>
 {
-	let pool = ValidationPool::new(ValidationExecutionMode::ExternalProcessSelfHost);
+	let pool = ValidationPool::with_config(pool_config, Some(Arc::new(metrics.clone())));
 
 	loop {
 		match ctx.recv().await? {
@@ -18,13 +18,18 @@
 //!
 //! Provides access to the chain data. Every request may return an error.
 //! At the moment, the implementation requires `Client` to implement `HeaderBackend`,
-//! we may add more bounds in the future if we will need e.g. block bodies.
+//! `BlockBackend` for block bodies and `AuxStore` for block weights.
 //!
 //! Supported requests:
 //! * Block hash to number
 //! * Finalized block number to hash
 //! * Last finalized block number
 //! * Ancestors
+//! * Block header
+//! * Block body
+//! * Block weight
+//! * Descendants on the best chain
+//! * Notifications of finalized blocks
 
 use polkadot_subsystem::{
 	FromOverseer, OverseerSignal,
@@ -32,12 +37,14 @@ use polkadot_subsystem::{
 	messages::ChainApiMessage,
 	metrics::{self, prometheus},
 };
-use polkadot_primitives::v1::{Block, BlockId};
+use polkadot_primitives::v1::{Block, BlockId, BlockNumber, Hash};
+use sc_client_api::{AuxStore, BlockBackend};
 use sp_blockchain::HeaderBackend;
 
-use futures::prelude::*;
+use futures::{channel::mpsc, prelude::*};
 
 /// The Chain API Subsystem implementation.
+#[derive(Clone)]
 pub struct ChainApiSubsystem<Client> {
 	client: Client,
 	metrics: Metrics,
@@ -54,7 +61,7 @@ impl<Client> ChainApiSubsystem<Client> {
 }
 
 impl<Client, Context> Subsystem<Context> for ChainApiSubsystem<Client> where
-	Client: HeaderBackend<Block> + 'static,
+	Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore + 'static,
 	Context: SubsystemContext<Message = ChainApiMessage>
 {
 	type Metrics = Metrics;
@@ -72,13 +79,34 @@ async fn run<Client>(
 	subsystem: ChainApiSubsystem<Client>,
 ) -> SubsystemResult<()>
 where
-	Client: HeaderBackend<Block>,
+	Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore,
 {
+	let mut finality_subscribers: Vec<mpsc::UnboundedSender<(Hash, BlockNumber)>> = Vec::new();
+	// the number of the last block the overseer told us is finalized.
+	let mut last_finalized = None;
+
 	loop {
 		match ctx.recv().await? {
 			FromOverseer::Signal(OverseerSignal::Conclude) => return Ok(()),
 			FromOverseer::Signal(OverseerSignal::ActiveLeaves(_)) => {},
-			FromOverseer::Signal(OverseerSignal::BlockFinalized(_)) => {},
+			FromOverseer::Signal(OverseerSignal::BlockFinalized(hash)) => {
+				// the overseer only learns about finalized blocks from the client,
+				// so the client knows their number.
+				let number = match subsystem.client.number(hash) {
+					Ok(Some(number)) => number,
+					_ => continue,
+				};
+				let previous = last_finalized.replace(number);
+
+				if finality_subscribers.is_empty() {
+					continue;
+				}
+
+				let finalized = finalized_since(&subsystem.client, previous, hash, number);
+				finality_subscribers.retain(|s| {
+					finalized.iter().all(|block| s.unbounded_send(*block).is_ok())
+				});
+			},
 			FromOverseer::Communication { msg } => match msg {
 				ChainApiMessage::BlockNumber(hash, response_channel) => {
 					let result = subsystem.client.number(hash).map_err(|e| e.to_string().into());
@@ -118,11 +146,94 @@ where
 					subsystem.metrics.on_request(result.is_ok());
 					let _ = response_channel.send(result);
 				},
+				ChainApiMessage::BlockHeader(hash, response_channel) => {
+					let result = subsystem.client
+						.header(BlockId::Hash(hash))
+						.map_err(|e| e.to_string().into());
+					subsystem.metrics.on_request(result.is_ok());
+					let _ = response_channel.send(result);
+				},
+				ChainApiMessage::BlockBody(hash, response_channel) => {
+					let result = subsystem.client
+						.block_body(&BlockId::Hash(hash))
+						.map_err(|e| e.to_string().into());
+					subsystem.metrics.on_request(result.is_ok());
+					let _ = response_channel.send(result);
+				},
+				ChainApiMessage::BlockWeight(hash, response_channel) => {
+					let result = sc_consensus_babe::aux_schema::load_block_weight(&subsystem.client, hash)
+						.map_err(|e| e.to_string().into());
+					subsystem.metrics.on_request(result.is_ok());
+					let _ = response_channel.send(result);
+				},
+				ChainApiMessage::Descendants { hash, k, response_channel } => {
+					let result = descendants(&subsystem.client, hash, k).map_err(|e| e.to_string().into());
+					subsystem.metrics.on_request(result.is_ok());
+					let _ = response_channel.send(result);
+				},
+				ChainApiMessage::FinalityNotifications(subscriber) => {
+					subsystem.metrics.on_request(true);
+					finality_subscribers.push(subscriber);
+				},
 			}
 		}
 	}
 }
 
+/// The blocks finalized after the block with number `previous`, up to and including the newly
+/// finalized block with the given hash and number, in ascending order.
+///
+/// The overseer is only told about some of the finalized blocks, so the ones in between are
+/// looked up on the finalized chain. If they can't be, only the newly finalized block is returned.
+fn finalized_since<Client>(
+	client: &Client,
+	previous: Option<BlockNumber>,
+	hash: Hash,
+	number: BlockNumber,
+) -> Vec<(Hash, BlockNumber)>
+where
+	Client: HeaderBackend<Block>,
+{
+	let first = previous.map_or(number, |previous| previous.saturating_add(1));
+	let mut finalized = (first..number)
+		.map(|n| client.hash(n).ok().flatten().map(|hash| (hash, n)))
+		.collect::<Option<Vec<_>>>()
+		.unwrap_or_default();
+	finalized.push((hash, number));
+	finalized
+}
+
+/// Up to `k` descendants of the block with the given hash on the best chain,
+/// or none if the block isn't on the best chain.
+fn descendants<Client>(client: &Client, hash: Hash, k: usize) -> sp_blockchain::Result<Vec<Hash>>
+where
+	Client: HeaderBackend<Block>,
+{
+	let mut number = match client.number(hash)? {
+		Some(number) => number,
+		None => return Ok(Vec::new()),
+	};
+
+	if client.hash(number)? != Some(hash) {
+		return Ok(Vec::new());
+	}
+
+	let mut descendants = Vec::new();
+	while descendants.len() < k {
+		number = match number.checked_add(1) {
+			Some(number) => number,
+			None => break,
+		};
+
+		match client.hash(number)? {
+			Some(hash) => descendants.push(hash),
+			None => break,
+		}
+	}
+
+	Ok(descendants)
+}
+
 #[derive(Clone)]
 struct MetricsInner {
 	chain_api_requests: prometheus::CounterVec<prometheus::U64>,
@@ -170,16 +281,19 @@ mod tests {
 	use std::collections::BTreeMap;
 	use futures::{future::BoxFuture, channel::oneshot};
 
-	use polkadot_primitives::v1::{Hash, BlockNumber, BlockId, Header};
+	use polkadot_primitives::v1::{Header, UncheckedExtrinsic};
 	use polkadot_node_subsystem_test_helpers::{make_subsystem_context, TestSubsystemContextHandle};
 	use sp_blockchain::Info as BlockInfo;
 	use sp_core::testing::TaskExecutor;
+	use sp_runtime::{Justification, generic::SignedBlock};
 
 	#[derive(Clone)]
 	struct TestClient {
 		blocks: BTreeMap<Hash, BlockNumber>,
 		finalized_blocks: BTreeMap<BlockNumber, Hash>,
 		headers: BTreeMap<Hash, Header>,
+		bodies: BTreeMap<Hash, Vec<UncheckedExtrinsic>>,
+		aux: BTreeMap<Vec<u8>, Vec<u8>>,
 	}
 
 	const ONE: Hash = Hash::repeat_byte(0x01);
@@ -230,11 +344,46 @@ mod tests {
 					ERROR_PATH => Header {
 						..default_header()
 					}
-				}
+				},
+				bodies: maplit::btreemap! {
+					TWO => Vec::new(),
+					THREE => Vec::new(),
+				},
+				aux: {
+					let mut aux = BTreeMap::new();
+					for (hash, weight) in &[(TWO, 1), (THREE, 2), (FOUR, 2)] {
+						sc_consensus_babe::aux_schema::write_block_weight(hash, *weight, |values| {
+							for (key, value) in values {
+								aux.insert(key.clone(), value.to_vec());
+							}
+						});
+					}
+					aux
+				},
+			}
+		}
+	}
+
+	impl TestClient {
+		/// A client which finalized the whole chain, for the requests about the best chain.
+		fn with_finalized_chain() -> Self {
+			Self {
+				finalized_blocks: maplit::btreemap! {
+					1 => ONE,
+					2 => TWO,
+					3 => THREE,
+					4 => FOUR,
+				},
+				..Default::default()
 			}
 		}
 	}
 
+	/// The Chain API doesn't use the rest of the client.
+	fn not_used<T>() -> sp_blockchain::Result<T> {
+		Err(sp_blockchain::Error::Backend("not used by the Chain API".into()))
+	}
+
 	fn last_key_value<K: Clone, V: Clone>(map: &BTreeMap<K, V>) -> (K, V) {
 		assert!(!map.is_empty());
 		map.iter()
@@ -281,12 +430,61 @@ mod tests {
 		}
 	}
 
+	impl BlockBackend<Block> for TestClient {
+		fn block_body(&self, id: &BlockId) -> sp_blockchain::Result<Option<Vec<UncheckedExtrinsic>>> {
+			match id {
+				// for error path testing
+				BlockId::Hash(hash) if hash.is_zero() => {
+					Err(sp_blockchain::Error::Backend("Zero hashes are illegal!".into()))
+				}
+				BlockId::Hash(hash) => {
+					Ok(self.bodies.get(hash).cloned())
+				}
+				_ => unreachable!(),
+			}
+		}
+		fn block(&self, _id: &BlockId) -> sp_blockchain::Result<Option<SignedBlock<Block>>> {
+			not_used()
+		}
+		fn block_status(&self, _id: &BlockId) -> sp_blockchain::Result<sp_consensus::BlockStatus> {
+			not_used()
+		}
+		fn justification(&self, _id: &BlockId) -> sp_blockchain::Result<Option<Justification>> {
+			not_used()
+		}
+		fn block_hash(&self, _number: BlockNumber) -> sp_blockchain::Result<Option<Hash>> {
+			not_used()
+		}
+	}
+
+	impl AuxStore for TestClient {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(&self, _insert: I, _delete: D) -> sp_blockchain::Result<()> {
+			not_used()
+		}
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.aux.get(key).cloned())
+		}
+	}
+
 	fn test_harness(
 		test: impl FnOnce(TestClient, TestSubsystemContextHandle<ChainApiMessage>)
 			-> BoxFuture<'static, ()>,
+	) {
+		test_harness_with_client(TestClient::default(), test)
+	}
+
+	fn test_harness_with_client(
+		client: TestClient,
+		test: impl FnOnce(TestClient, TestSubsystemContextHandle<ChainApiMessage>)
+			-> BoxFuture<'static, ()>,
 	) {
 		let (ctx, ctx_handle) = make_subsystem_context(TaskExecutor::new());
-		let client = TestClient::default();
 
 		let subsystem = ChainApiSubsystem::new(client.clone(), Metrics(None));
 		let chain_api_task = run(ctx, subsystem).map(|x| x.unwrap());
@@ -386,4 +584,149 @@ mod tests {
 			}.boxed()
 		})
 	}
+
+	#[test]
+	fn request_block_header() {
+		test_harness(|client, mut sender| {
+			async move {
+				let zero = Hash::zero();
+				let test_cases = [
+					(TWO, client.header(BlockId::Hash(TWO)).unwrap()),
+					(ONE, client.header(BlockId::Hash(ONE)).unwrap()), // not here
+				];
+				for (hash, expected) in &test_cases {
+					let (tx, rx) = oneshot::channel();
+
+					sender.send(FromOverseer::Communication {
+						msg: ChainApiMessage::BlockHeader(*hash, tx),
+					}).await;
+
+					assert_eq!(rx.await.unwrap().unwrap(), *expected);
+				}
+
+				let (tx, rx) = oneshot::channel();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::BlockHeader(zero, tx),
+				}).await;
+				assert!(rx.await.unwrap().is_err());
+
+				sender.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+			}.boxed()
+		})
+	}
+
+	#[test]
+	fn request_block_body() {
+		test_harness(|client, mut sender| {
+			async move {
+				let zero = Hash::zero();
+				let test_cases = [
+					(TWO, client.block_body(&BlockId::Hash(TWO)).unwrap()),
+					(ONE, client.block_body(&BlockId::Hash(ONE)).unwrap()), // not here
+				];
+				for (hash, expected) in &test_cases {
+					let (tx, rx) = oneshot::channel();
+
+					sender.send(FromOverseer::Communication {
+						msg: ChainApiMessage::BlockBody(*hash, tx),
+					}).await;
+
+					assert_eq!(rx.await.unwrap().unwrap(), *expected);
+				}
+
+				let (tx, rx) = oneshot::channel();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::BlockBody(zero, tx),
+				}).await;
+				assert!(rx.await.unwrap().is_err());
+
+				sender.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+			}.boxed()
+		})
+	}
+
+	#[test]
+	fn request_block_weight() {
+		test_harness(|_client, mut sender| {
+			async move {
+				let test_cases = [
+					(TWO, Some(1)),
+					(FOUR, Some(2)),
+					(ONE, None), // not here
+				];
+				for (hash, expected) in &test_cases {
+					let (tx, rx) = oneshot::channel();
+
+					sender.send(FromOverseer::Communication {
+						msg: ChainApiMessage::BlockWeight(*hash, tx),
+					}).await;
+
+					assert_eq!(rx.await.unwrap().unwrap(), *expected);
+				}
+
+				sender.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+			}.boxed()
+		})
+	}
+
+	#[test]
+	fn request_descendants() {
+		test_harness_with_client(TestClient::with_finalized_chain(), |_client, mut sender| {
+			async move {
+				let (tx, rx) = oneshot::channel();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::Descendants { hash: ONE, k: 2, response_channel: tx },
+				}).await;
+				assert_eq!(rx.await.unwrap().unwrap(), vec![TWO, THREE]);
+
+				let (tx, rx) = oneshot::channel();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::Descendants { hash: TWO, k: 4, response_channel: tx },
+				}).await;
+				assert_eq!(rx.await.unwrap().unwrap(), vec![THREE, FOUR]);
+
+				let (tx, rx) = oneshot::channel();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::Descendants { hash: ERROR_PATH, k: 2, response_channel: tx },
+				}).await;
+				assert_eq!(rx.await.unwrap().unwrap(), Vec::<Hash>::new());
+
+				sender.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+			}.boxed()
+		})
+	}
+
+	#[test]
+	fn finality_notifications() {
+		test_harness_with_client(TestClient::with_finalized_chain(), |_client, mut sender| {
+			async move {
+				// blocks finalized before subscribing aren't notified.
+				sender.send(FromOverseer::Signal(OverseerSignal::BlockFinalized(ONE))).await;
+
+				let (tx, mut rx) = mpsc::unbounded();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::FinalityNotifications(tx),
+				}).await;
+
+				let (dropped_tx, dropped_rx) = mpsc::unbounded();
+				sender.send(FromOverseer::Communication {
+					msg: ChainApiMessage::FinalityNotifications(dropped_tx),
+				}).await;
+				drop(dropped_rx);
+
+				sender.send(FromOverseer::Signal(OverseerSignal::BlockFinalized(THREE))).await;
+				sender.send(FromOverseer::Signal(OverseerSignal::BlockFinalized(FOUR))).await;
+
+				// the block finalized together with `THREE` is notified as well.
+				assert_eq!(rx.next().await, Some((TWO, 2)));
+				assert_eq!(rx.next().await, Some((THREE, 3)));
+				assert_eq!(rx.next().await, Some((FOUR, 4)));
+
+				sender.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+
+				// the subscription ends with the subsystem.
+				assert_eq!(rx.next().await, None);
+			}.boxed()
+		})
+	}
 }
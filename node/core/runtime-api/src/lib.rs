@@ -35,6 +35,7 @@ use sp_api::{ProvideRuntimeApi};
 use futures::prelude::*;
 
 /// The `RuntimeApiSubsystem`. See module docs for more details.
+#[derive(Clone)]
 pub struct RuntimeApiSubsystem<Client> {
 	client: Client,
 	metrics: Metrics,
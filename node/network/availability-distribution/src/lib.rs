@@ -16,14 +16,22 @@
 
 //! The availability distribution
 //!
-//! Transforms `AvailableData` into erasure chunks, which are distributed to peers
-//! who are interested in the relevant candidates.
-//! Gossip messages received from other peers are verified and gossiped to interested
-//! peers. Verified in this context means, the erasure chunks contained merkle proof
-//! is checked.
+//! Transforms `AvailableData` into erasure chunks, which backers of a candidate send
+//! directly to the validators owning them: chunk `i` goes to validator `i` only.
+//! Chunks received from other peers are verified, but not passed on. Verified in this
+//! context means, the erasure chunks contained merkle proof is checked.
+//!
+//! Validators missing their own chunk of a live candidate fetch it directly from interested
+//! peers, using the chunk fetching request-response protocol. Validators which are not
+//! backers of a candidate only share their own chunk of it, and only when asked.
+//!
+//! The available data of a live candidate is recovered from the systematic chunks, fetched
+//! from its backers, falling back to fetching any chunks from their owners if that fails.
 
 use codec::{Decode, Encode};
-use futures::{channel::oneshot, FutureExt};
+use futures::{
+	channel::oneshot, future::{self, BoxFuture}, stream::FuturesUnordered, Future, FutureExt, StreamExt,
+};
 
 use keystore::KeyStorePtr;
 use sp_core::{
@@ -36,7 +44,7 @@ use log::{trace, warn};
 use polkadot_erasure_coding::branch_hash;
 use polkadot_primitives::v1::{
 	PARACHAIN_KEY_TYPE_ID,
-	BlakeTwo256, CommittedCandidateReceipt, CoreState, ErasureChunk,
+	AvailableData, BlakeTwo256, CommittedCandidateReceipt, CoreState, ErasureChunk,
 	Hash as Hash, HashT, Id as ParaId,
 	ValidatorId, ValidatorIndex, SessionIndex,
 };
@@ -51,7 +59,7 @@ use polkadot_subsystem::{
 };
 use polkadot_node_network_protocol::{
 	v1 as protocol_v1, View, ReputationChange as Rep, PeerId,
-	NetworkBridgeEvent,
+	NetworkBridgeEvent, IncomingRequest,
 };
 use std::collections::{HashMap, HashSet};
 use std::io;
@@ -125,6 +133,12 @@ struct ProtocolState {
 
 	/// Track data that is specific to a candidate.
 	per_candidate: HashMap<Hash, PerCandidate>,
+
+	/// The validators behind our peers, as far as we know them.
+	validator_peers: HashMap<PeerId, ValidatorId>,
+
+	/// The validator set whose peers we keep track of.
+	known_validators: Vec<ValidatorId>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -145,6 +159,27 @@ struct PerCandidate {
 
 	/// If this node is a validator, note the index in the validator set.
 	validator_index: Option<ValidatorIndex>,
+
+	/// Whether we are fetching our own chunk from peers.
+	fetching_our_chunk: bool,
+
+	/// Whether we hold the full available data of the candidate, which is the case for
+	/// its backers. Only then we send chunks to other validators.
+	is_backer: bool,
+}
+
+impl PerCandidate {
+	/// The index of the validator behind the given peer, if known.
+	fn peer_validator_index(
+		&self,
+		validator_peers: &HashMap<PeerId, ValidatorId>,
+		peer: &PeerId,
+	) -> Option<ValidatorIndex> {
+		let validator = validator_peers.get(peer)?;
+		self.validators.iter()
+			.position(|v| v == validator)
+			.map(|index| index as ValidatorIndex)
+	}
 }
 
 #[derive(Debug, Clone, Default)]
@@ -154,6 +189,18 @@ struct PerRelayParent {
 }
 
 impl ProtocolState {
+	/// Forget the peers of validators which are not part of the given validator set anymore.
+	///
+	/// The peers of the new validators are told to us by the network bridge once the gossip
+	/// support subsystem connected to them.
+	fn note_validators(&mut self, validators: &[ValidatorId]) {
+		if self.known_validators == validators {
+			return;
+		}
+		self.known_validators = validators.to_vec();
+		self.validator_peers.retain(|_, v| validators.contains(v));
+	}
+
 	/// Collects the relay_parents ancestors including the relay parents themselfes.
 	fn extend_with_ancestors<'a>(
 		&'a self,
@@ -286,12 +333,53 @@ impl ProtocolState {
 	}
 }
 
+/// The outcome of fetching our chunk of a candidate from peers.
+struct ChunkFetchResult {
+	candidate_hash: Hash,
+	/// The chunk and the peer which provided it, if any did.
+	response: Option<(PeerId, ErasureChunk)>,
+}
+
+/// The fetches of our chunks which are in progress.
+type PendingChunkFetches = FuturesUnordered<BoxFuture<'static, ChunkFetchResult>>;
+
+/// What a recovery does next if it doesn't have all the systematic chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecoveryStep {
+	/// Fetch the systematic chunks from the backers.
+	FetchSystematic,
+	/// Fetch any chunks, from their owners and the backers, a batch at a time.
+	FetchAny,
+	/// Decode the chunks we have.
+	Decode,
+}
+
+/// A recovery of the available data of a candidate in progress.
+struct Recovery {
+	candidate_hash: Hash,
+	erasure_root: Hash,
+	/// The validators the chunks of the candidate belong to.
+	validators: Vec<ValidatorId>,
+	/// The peers of the backers of the candidate, which hold all of its chunks.
+	backers: Vec<PeerId>,
+	/// The valid chunks we have so far, by index.
+	chunks: HashMap<ValidatorIndex, ErasureChunk>,
+	/// The chunks asked for while fetching any chunks, which aren't asked for again.
+	requested: HashSet<ValidatorIndex>,
+	next_step: RecoveryStep,
+	response: oneshot::Sender<Option<AvailableData>>,
+}
+
+/// The recoveries waiting for the chunks they asked for.
+type PendingRecoveries = FuturesUnordered<BoxFuture<'static, (Recovery, Vec<Option<(PeerId, ErasureChunk)>>)>>;
+
 /// Deal with network bridge updates and track what needs to be tracked
 /// which depends on the message type received.
 async fn handle_network_msg<Context>(
 	ctx: &mut Context,
 	keystore: KeyStorePtr,
 	state: &mut ProtocolState,
+	fetches: &mut PendingChunkFetches,
 	bridge_message: NetworkBridgeEvent<protocol_v1::AvailabilityDistributionMessage>,
 ) -> Result<()>
 where
@@ -310,7 +398,7 @@ where
 			handle_peer_view_change(ctx, state, peerid, view).await?;
 		}
 		NetworkBridgeEvent::OurViewChange(view) => {
-			handle_our_view_change(ctx, keystore, state, view).await?;
+			handle_our_view_change(ctx, keystore, state, fetches, view).await?;
 		}
 		NetworkBridgeEvent::PeerMessage(remote, msg) => {
 			let gossiped_availability = match msg {
@@ -320,6 +408,9 @@ where
 
 			process_incoming_peer_message(ctx, state, remote, gossiped_availability).await?;
 		}
+		NetworkBridgeEvent::ValidatorPeers(validator_peers) => {
+			handle_validator_peers(ctx, state, validator_peers).await?;
+		}
 	}
 	Ok(())
 }
@@ -330,6 +421,7 @@ async fn handle_our_view_change<Context>(
 	ctx: &mut Context,
 	keystore: KeyStorePtr,
 	state: &mut ProtocolState,
+	fetches: &mut PendingChunkFetches,
 	view: View,
 ) -> Result<()>
 where
@@ -349,6 +441,9 @@ where
 			&validators,
 			keystore.clone(),
 		);
+		if validator_index.is_some() {
+			state.note_validators(&validators);
+		}
 		state.add_relay_parent(ctx, added, validators, validator_index).await?;
 	}
 
@@ -360,15 +455,11 @@ where
 			.or_default();
 
 		// assure the node has the validator role
-		if per_candidate.validator_index.is_none() {
-			continue;
+		let validator_index = match per_candidate.validator_index {
+			Some(validator_index) => validator_index,
+			None => continue,
 		};
 
-		// check if the availability is present in the store exists
-		if !query_data_availability(ctx, candidate_hash).await? {
-			continue;
-		}
-
 		let validator_count = per_candidate.validators.len();
 
 		// obtain interested peers in the candidate hash
@@ -385,10 +476,19 @@ where
 			.map(|(peer, _view)| peer.clone())
 			.collect();
 
-		// distribute all erasure messages to interested peers
-		for chunk_index in 0u32..(validator_count as u32) {
+		// check if the availability is present in the store exists
+		if !query_data_availability(ctx, candidate_hash).await? {
+			// we can't distribute anything, but we can make sure to obtain our own chunk
+			if !peers.is_empty() {
+				fetch_our_chunk(ctx, state, fetches, candidate_hash, validator_index, peers).await?;
+			}
+			continue;
+		}
+
+		state.per_candidate.entry(candidate_hash).or_default().is_backer = true;
 
-			// only the peers which did not receive this particular erasure chunk
+		// send each erasure chunk to the interested peer of the validator owning it
+		for chunk_index in 0u32..(validator_count as u32) {
 			let per_candidate = state
 				.per_candidate
 				.entry(candidate_hash)
@@ -407,8 +507,23 @@ where
 
 			debug_assert_eq!(erasure_chunk.index, chunk_index);
 
+			let message = AvailabilityGossipMessage {
+				candidate_hash,
+				erasure_chunk,
+			};
+
+			// keep it around for the owner to become interested later on
+			per_candidate.message_vault.insert(chunk_index, message.clone());
+
+			if chunk_index == validator_index {
+				continue;
+			}
+
 			let peers = peers
 				.iter()
+				.filter(|peer| {
+					per_candidate.peer_validator_index(&state.validator_peers, peer) == Some(chunk_index)
+				})
 				.filter(|peer| {
 					// only pick those which were not sent before
 					!per_candidate
@@ -421,10 +536,6 @@ where
 				})
 				.map(|peer| peer.clone())
 				.collect::<Vec<_>>();
-			let message = AvailabilityGossipMessage {
-				candidate_hash,
-				erasure_chunk,
-			};
 
 			send_tracked_gossip_message_to_peers(ctx, per_candidate, peers, message).await?;
 		}
@@ -508,8 +619,8 @@ where
 	Ok(())
 }
 
-// Send the difference between two views which were not sent
-// to that particular peer.
+// Send the chunks owned by the peer's validator of the candidates the peer became
+// interested in.
 async fn handle_peer_view_change<Context>(
 	ctx: &mut Context,
 	state: &mut ProtocolState,
@@ -525,46 +636,415 @@ where
 
 	*current = view;
 
+	send_chunks_owned_by_peer(ctx, state, origin, added).await
+}
+
+// Send the chunks owned by the peer's validator of the candidates of the given relay parents,
+// unless they were sent to it before. Only backers send chunks.
+async fn send_chunks_owned_by_peer<Context>(
+	ctx: &mut Context,
+	state: &mut ProtocolState,
+	peer: PeerId,
+	relay_parents: Vec<Hash>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
 	// only contains the intersection of what we are interested and
 	// the union of all relay parent's candidates.
-	let added_candidates = state.cached_live_candidates_unioned(added.iter());
+	let candidates = state.cached_live_candidates_unioned(relay_parents.iter());
 
-	// Send all messages we've seen before and the peer is now interested
-	// in to that peer.
-
-	for (candidate_hash, _receipt) in added_candidates {
+	for (candidate_hash, _receipt) in candidates {
 		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
 
-		// obtain the relevant chunk indices not sent yet
-		let messages = ((0 as ValidatorIndex)
-			..(per_candidate.validators.len() as ValidatorIndex))
-			.into_iter()
-			.filter_map(|erasure_chunk_index: ValidatorIndex| {
-				let message_id = (candidate_hash, erasure_chunk_index);
-
-				// try to pick up the message from the message vault
-				// so we send as much as we have
-				per_candidate
-					.message_vault
-					.get(&erasure_chunk_index)
-					.filter(|_| {
-						// check if that erasure chunk was already sent before
-						if let Some(sent_set) = per_candidate.sent_messages.get(&origin) {
-							if sent_set.contains(&message_id) {
-								return false;
-							}
-						}
-						true
-					})
-			})
-			.cloned()
-			.collect::<HashSet<_>>();
+		if !per_candidate.is_backer {
+			continue;
+		}
+
+		let erasure_chunk_index = match per_candidate.peer_validator_index(&state.validator_peers, &peer) {
+			Some(erasure_chunk_index) => erasure_chunk_index,
+			None => continue,
+		};
+
+		// check if that erasure chunk was already sent before
+		let message_id = (candidate_hash, erasure_chunk_index);
+		if per_candidate.sent_messages.get(&peer).map_or(false, |sent| sent.contains(&message_id)) {
+			continue;
+		}
+
+		let message = match per_candidate.message_vault.get(&erasure_chunk_index) {
+			Some(message) => message.clone(),
+			None => continue,
+		};
+
+		send_tracked_gossip_messages_to_peer(ctx, per_candidate, peer.clone(), iter::once(message)).await?;
+	}
+	Ok(())
+}
+
+/// Learn which validators are behind which peers, and send the newly known owners the chunks
+/// they are interested in.
+///
+/// The network bridge usually tells us about the peers of the validators after we went through
+/// the candidates of our new view, so chunks for peers we didn't know yet are sent here.
+async fn handle_validator_peers<Context>(
+	ctx: &mut Context,
+	state: &mut ProtocolState,
+	validator_peers: Vec<(ValidatorId, PeerId)>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	for (validator, peer) in validator_peers {
+		if state.validator_peers.get(&peer) == Some(&validator) {
+			continue;
+		}
+		state.validator_peers.insert(peer.clone(), validator);
+
+		let view = match state.peer_views.get(&peer) {
+			Some(view) => view.0.clone(),
+			None => continue,
+		};
+		send_chunks_owned_by_peer(ctx, state, peer, view).await?;
+	}
+	Ok(())
+}
+
+/// Fetch our own chunk of a candidate from the given peers, unless we have it already or are
+/// fetching it.
+async fn fetch_our_chunk<Context>(
+	ctx: &mut Context,
+	state: &mut ProtocolState,
+	fetches: &mut PendingChunkFetches,
+	candidate_hash: Hash,
+	validator_index: ValidatorIndex,
+	peers: Vec<PeerId>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+	if per_candidate.fetching_our_chunk || per_candidate.message_vault.contains_key(&validator_index) {
+		return Ok(());
+	}
+
+	if query_chunk(ctx, candidate_hash, validator_index).await?.is_some() {
+		return Ok(());
+	}
+
+	let (tx, rx) = oneshot::channel();
+	ctx.send_message(AllMessages::NetworkBridge(NetworkBridgeMessage::FetchChunk(
+		peers,
+		protocol_v1::ChunkFetchingRequest { candidate_hash, index: validator_index },
+		tx,
+	))).await?;
+
+	per_candidate.fetching_our_chunk = true;
+	fetches.push(rx.map(move |response| ChunkFetchResult {
+		candidate_hash,
+		response: response.ok().flatten(),
+	}).boxed());
+
+	Ok(())
+}
+
+/// Handle the outcome of fetching our chunk of a candidate, storing it if it is valid.
+async fn handle_chunk_fetch_result<Context>(
+	ctx: &mut Context,
+	state: &mut ProtocolState,
+	result: ChunkFetchResult,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let ChunkFetchResult { candidate_hash, response } = result;
+
+	let live_candidates = state.cached_live_candidates_unioned(state.view.0.iter());
+	let per_candidate = match state.per_candidate.get_mut(&candidate_hash) {
+		Some(per_candidate) => per_candidate,
+		// the candidate went out of view in the meantime
+		None => return Ok(()),
+	};
+	per_candidate.fetching_our_chunk = false;
+
+	let (peer, erasure_chunk) = match response {
+		Some(response) => response,
+		None => {
+			trace!(target: TARGET, "None of the peers provided our chunk of {}", candidate_hash);
+			return Ok(());
+		}
+	};
+
+	let live_candidate = match live_candidates.get(&candidate_hash) {
+		Some(live_candidate) => live_candidate,
+		None => return Ok(()),
+	};
+
+	if Some(erasure_chunk.index) != per_candidate.validator_index
+		|| !chunk_matches_root(&live_candidate.commitments.erasure_root, &erasure_chunk)
+	{
+		return modify_reputation(ctx, peer, COST_MERKLE_PROOF_INVALID).await;
+	}
+
+	per_candidate.message_vault.insert(erasure_chunk.index, AvailabilityGossipMessage {
+		candidate_hash,
+		erasure_chunk: erasure_chunk.clone(),
+	});
+
+	modify_reputation(ctx, peer, BENEFIT_VALID_MESSAGE_FIRST).await?;
+
+	if let Err(_e) = store_chunk(ctx, candidate_hash, erasure_chunk.index, erasure_chunk).await? {
+		warn!(target: TARGET, "Failed to store erasure chunk to availability store");
+	}
+
+	Ok(())
+}
+
+/// Recover the available data of a live candidate.
+///
+/// Backers hold the available data in full, so they are asked for the systematic chunks first,
+/// which the data is recovered from without decoding.
+async fn recover_available_data<Context>(
+	ctx: &mut Context,
+	state: &ProtocolState,
+	recoveries: &mut PendingRecoveries,
+	candidate_hash: Hash,
+	response: oneshot::Sender<Option<AvailableData>>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	if let Some(available_data) = query_available_data(ctx, candidate_hash).await? {
+		let _ = response.send(Some(available_data));
+		return Ok(());
+	}
+
+	let live_candidates = state.cached_live_candidates_unioned(state.view.0.iter());
+	let (receipt, per_candidate) = match (
+		live_candidates.get(&candidate_hash),
+		state.per_candidate.get(&candidate_hash),
+	) {
+		(Some(receipt), Some(per_candidate)) if !per_candidate.validators.is_empty()
+			=> (receipt, per_candidate),
+		_ => {
+			trace!(target: TARGET, "Can't recover the data of {}, which is not live", candidate_hash);
+			let _ = response.send(None);
+			return Ok(());
+		}
+	};
+
+	let recovery = Recovery {
+		candidate_hash,
+		erasure_root: receipt.commitments.erasure_root,
+		validators: per_candidate.validators.clone(),
+		// only backers send chunks.
+		backers: per_candidate.received_messages.keys().cloned().collect(),
+		chunks: per_candidate.message_vault.iter()
+			.map(|(index, message)| (*index, message.erasure_chunk.clone()))
+			.collect(),
+		requested: HashSet::new(),
+		next_step: RecoveryStep::FetchSystematic,
+		response,
+	};
+
+	advance_recovery(ctx, state, recoveries, recovery).await
+}
 
-		send_tracked_gossip_messages_to_peer(ctx, per_candidate, origin.clone(), messages).await?;
+/// Handle the chunks fetched for a recovery, keeping the valid ones.
+async fn handle_recovery_chunks<Context>(
+	ctx: &mut Context,
+	state: &ProtocolState,
+	recoveries: &mut PendingRecoveries,
+	mut recovery: Recovery,
+	responses: Vec<Option<(PeerId, ErasureChunk)>>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	for (peer, erasure_chunk) in responses.into_iter().flatten() {
+		if erasure_chunk.index as usize >= recovery.validators.len()
+			|| !chunk_matches_root(&recovery.erasure_root, &erasure_chunk)
+		{
+			modify_reputation(ctx, peer, COST_MERKLE_PROOF_INVALID).await?;
+			continue;
+		}
+
+		recovery.chunks.insert(erasure_chunk.index, erasure_chunk);
+	}
+
+	advance_recovery(ctx, state, recoveries, recovery).await
+}
+
+/// Recover the data from the systematic chunks if we have all of them. Otherwise fetch them
+/// from the backers, then fall back to fetching just enough other chunks to decode the data,
+/// asking for more only as fetches fail, and finally to decoding whatever chunks we got.
+async fn advance_recovery<Context>(
+	ctx: &mut Context,
+	state: &ProtocolState,
+	recoveries: &mut PendingRecoveries,
+	mut recovery: Recovery,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let n_validators = recovery.validators.len();
+	let threshold = polkadot_erasure_coding::systematic_recovery_threshold(n_validators)?;
+
+	let available_data: std::result::Result<AvailableData, _> = loop {
+		let systematic: Option<Vec<&[u8]>> = (0..threshold as ValidatorIndex)
+			.map(|index| recovery.chunks.get(&index).map(|chunk| &chunk.chunk[..]))
+			.collect();
+
+		if let Some(systematic) = systematic {
+			break polkadot_erasure_coding::reconstruct_from_systematic_chunks(n_validators, systematic);
+		}
+
+		let responses = match recovery.next_step {
+			RecoveryStep::FetchSystematic => {
+				recovery.next_step = RecoveryStep::FetchAny;
+				request_recovery_chunks(ctx, state, &mut recovery, threshold, threshold, false).await?
+			}
+			RecoveryStep::FetchAny if recovery.chunks.len() < threshold => {
+				let missing = threshold - recovery.chunks.len();
+				trace!(
+					target: TARGET,
+					"Missing systematic chunks of {}, fetching {} other chunks",
+					recovery.candidate_hash,
+					missing,
+				);
+				let responses = request_recovery_chunks(ctx, state, &mut recovery, n_validators, missing, true).await?;
+				if responses.is_empty() {
+					recovery.next_step = RecoveryStep::Decode;
+				}
+				responses
+			}
+			RecoveryStep::FetchAny => {
+				recovery.next_step = RecoveryStep::Decode;
+				continue;
+			}
+			RecoveryStep::Decode => break polkadot_erasure_coding::reconstruct_v1(
+				n_validators,
+				recovery.chunks.values().map(|chunk| (&chunk.chunk[..], chunk.index as usize)),
+			),
+		};
+
+		if !responses.is_empty() {
+			// the chunks of a batch are asked for at once.
+			recoveries.push(future::join_all(responses).map(move |responses| (recovery, responses)).boxed());
+			return Ok(());
+		}
+	};
+
+	if let Err(ref e) = available_data {
+		warn!(target: TARGET, "Failed to recover the data of {}: {:?}", recovery.candidate_hash, e);
 	}
+
+	let _ = recovery.response.send(available_data.ok());
+
 	Ok(())
 }
 
+/// Ask for at most `max_requests` of the chunks below `up_to` the recovery is missing, from the
+/// backers and, if `from_owners`, from the validators owning them first. Chunks asked for
+/// with `from_owners` aren't asked for again.
+async fn request_recovery_chunks<Context>(
+	ctx: &mut Context,
+	state: &ProtocolState,
+	recovery: &mut Recovery,
+	up_to: usize,
+	max_requests: usize,
+	from_owners: bool,
+) -> Result<Vec<impl Future<Output = Option<(PeerId, ErasureChunk)>> + Send>>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let mut responses = Vec::new();
+
+	for index in 0..up_to as ValidatorIndex {
+		if responses.len() == max_requests {
+			break;
+		}
+		if recovery.chunks.contains_key(&index) || (from_owners && recovery.requested.contains(&index)) {
+			continue;
+		}
+
+		let mut peers: Vec<PeerId> = Vec::new();
+		if from_owners {
+			peers.extend(
+				state.validator_peers.iter()
+					.filter(|(_, validator)| recovery.validators.get(index as usize) == Some(*validator))
+					.map(|(peer, _)| peer.clone())
+			);
+		}
+		for backer in &recovery.backers {
+			if !peers.contains(backer) {
+				peers.push(backer.clone());
+			}
+		}
+
+		if peers.is_empty() {
+			continue;
+		}
+		if from_owners {
+			recovery.requested.insert(index);
+		}
+
+		let (tx, rx) = oneshot::channel();
+		ctx.send_message(AllMessages::NetworkBridge(NetworkBridgeMessage::FetchChunk(
+			peers,
+			protocol_v1::ChunkFetchingRequest { candidate_hash: recovery.candidate_hash, index },
+			tx,
+		))).await?;
+
+		responses.push(rx.map(|response| response.ok().flatten()));
+	}
+
+	Ok(responses)
+}
+
+/// Answer a peer requesting an erasure chunk from us.
+async fn handle_chunk_request<Context>(
+	ctx: &mut Context,
+	state: &ProtocolState,
+	request: IncomingRequest<protocol_v1::ChunkFetchingRequest, protocol_v1::ChunkFetchingResponse>,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let protocol_v1::ChunkFetchingRequest { candidate_hash, index } = request.payload;
+
+	// Unless we are a backer, only our own chunk is shared, which is all that the
+	// availability store holds then.
+	let known = state.per_candidate.get(&candidate_hash)
+		.filter(|per_candidate| per_candidate.is_backer || per_candidate.validator_index == Some(index))
+		.and_then(|per_candidate| per_candidate.message_vault.get(&index))
+		.map(|message| message.erasure_chunk.clone());
+
+	let chunk = match known {
+		Some(chunk) => Some(chunk),
+		None => query_chunk(ctx, candidate_hash, index).await?,
+	};
+
+	let response = match chunk {
+		Some(chunk) => protocol_v1::ChunkFetchingResponse::Chunk(chunk),
+		None => protocol_v1::ChunkFetchingResponse::NoSuchChunk,
+	};
+
+	if request.send_response(response).is_err() {
+		trace!(target: TARGET, "Peer is no longer waiting for the requested chunk");
+	}
+
+	Ok(())
+}
+
+/// Check the merkle proof of an erasure chunk against the erasure root of its candidate.
+fn chunk_matches_root(erasure_root: &Hash, erasure_chunk: &ErasureChunk) -> bool {
+	match branch_hash(erasure_root, &erasure_chunk.proof, erasure_chunk.index as usize) {
+		Ok(anticipated_hash) => anticipated_hash == BlakeTwo256::hash(&erasure_chunk.chunk),
+		Err(_) => false,
+	}
+}
+
 /// Obtain the first key which has a signing key.
 /// Returns the index within the validator set as `ValidatorIndex`, if there exists one,
 /// otherwise, `None` is returned.
@@ -604,100 +1084,57 @@ where
 	};
 
 	// check the merkle proof
-	let root = &live_candidate.commitments.erasure_root;
-	let anticipated_hash = if let Ok(hash) = branch_hash(
-		root,
-		&message.erasure_chunk.proof,
-		message.erasure_chunk.index as usize,
-	) {
-		hash
-	} else {
-		return modify_reputation(ctx, origin, COST_MERKLE_PROOF_INVALID).await;
-	};
-
-	let erasure_chunk_hash = BlakeTwo256::hash(&message.erasure_chunk.chunk);
-	if anticipated_hash != erasure_chunk_hash {
+	if !chunk_matches_root(&live_candidate.commitments.erasure_root, &message.erasure_chunk) {
 		return modify_reputation(ctx, origin, COST_MERKLE_PROOF_INVALID).await;
 	}
 
 	// an internal unique identifier of this message
 	let message_id = (message.candidate_hash, message.erasure_chunk.index);
 
+	// chunks are sent to their owners directly, so they are not passed on
+	let per_candidate = state.per_candidate.entry(message_id.0.clone()).or_default();
+
+	// check if this particular erasure chunk was already sent by that peer before
 	{
-		let per_candidate = state.per_candidate.entry(message_id.0.clone()).or_default();
+		let received_set = per_candidate
+			.received_messages
+			.entry(origin.clone())
+			.or_default();
+		if received_set.contains(&message_id) {
+			return modify_reputation(ctx, origin, COST_PEER_DUPLICATE_MESSAGE).await;
+		} else {
+			received_set.insert(message_id.clone());
+		}
+	}
 
-		// check if this particular erasure chunk was already sent by that peer before
-		{
-			let received_set = per_candidate
-				.received_messages
-				.entry(origin.clone())
-				.or_default();
-			if received_set.contains(&message_id) {
-				return modify_reputation(ctx, origin, COST_PEER_DUPLICATE_MESSAGE).await;
-			} else {
-				received_set.insert(message_id.clone());
+	// insert into known messages and change reputation
+	if per_candidate
+		.message_vault
+		.insert(message_id.1, message.clone())
+		.is_some()
+	{
+		modify_reputation(ctx, origin, BENEFIT_VALID_MESSAGE).await
+	} else {
+		modify_reputation(ctx, origin, BENEFIT_VALID_MESSAGE_FIRST).await?;
+
+		// save the chunk for our index
+		if per_candidate.validator_index == Some(message.erasure_chunk.index) {
+			if let Err(_e) = store_chunk(
+				ctx,
+				message.candidate_hash.clone(),
+				message.erasure_chunk.index,
+				message.erasure_chunk,
+			).await? {
+				warn!(target: TARGET, "Failed to store erasure chunk to availability store");
 			}
 		}
 
-		// insert into known messages and change reputation
-		if per_candidate
-			.message_vault
-			.insert(message_id.1, message.clone())
-			.is_some()
-		{
-			modify_reputation(ctx, origin, BENEFIT_VALID_MESSAGE).await?;
-		} else {
-			modify_reputation(ctx, origin, BENEFIT_VALID_MESSAGE_FIRST).await?;
-
-			// save the chunk for our index
-			if let Some(validator_index) = per_candidate.validator_index {
-				if message.erasure_chunk.index == validator_index {
-					if let Err(_e) = store_chunk(
-						ctx,
-						message.candidate_hash.clone(),
-						message.erasure_chunk.index,
-						message.erasure_chunk.clone(),
-					).await? {
-						warn!(target: TARGET, "Failed to store erasure chunk to availability store");
-					}
-				}
-			}
-		};
+		Ok(())
 	}
-	// condense the peers to the peers with interest on the candidate
-	let peers = state
-		.peer_views
-		.clone()
-		.into_iter()
-		.filter(|(_peer, view)| {
-			// peers view must contain the candidate hash too
-			state
-				.cached_live_candidates_unioned(view.0.iter())
-				.contains_key(&message_id.0)
-		})
-		.map(|(peer, _)| -> PeerId { peer.clone() })
-		.collect::<Vec<_>>();
-
-	let per_candidate = state.per_candidate.entry(message_id.0.clone()).or_default();
-
-	let peers = peers
-		.into_iter()
-		.filter(|peer| {
-			let peer: PeerId = peer.clone();
-			// avoid sending duplicate messages
-			per_candidate
-				.sent_messages
-				.entry(peer)
-				.or_default()
-				.contains(&message_id)
-		})
-		.collect::<Vec<_>>();
-
-	// gossip that message to interested peers
-	send_tracked_gossip_message_to_peers(ctx, per_candidate, peers, message).await
 }
 
 /// The bitfield distribution subsystem.
+#[derive(Clone)]
 pub struct AvailabilityDistributionSubsystem {
 	/// Pointer to a keystore, which is required for determining this nodes validator index.
 	keystore: KeyStorePtr,
@@ -717,10 +1154,59 @@ impl AvailabilityDistributionSubsystem {
 	where
 		Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
 	{
+		enum Next {
+			FromOverseer(FromOverseer<AvailabilityDistributionMessage>),
+			Fetched(ChunkFetchResult),
+			Recovered(Recovery, Vec<Option<(PeerId, ErasureChunk)>>),
+		}
+
 		// work: process incoming messages from the overseer.
 		let mut state = ProtocolState::default();
+		let mut fetches = PendingChunkFetches::new();
+		let mut recoveries = PendingRecoveries::new();
 		loop {
-			let message = ctx.recv().await.map_err::<Error, _>(Into::into)?;
+			let next = {
+				let from_overseer = ctx.recv().fuse();
+				futures::pin_mut!(from_overseer);
+
+				futures::select! {
+					message = from_overseer => Next::FromOverseer(
+						message.map_err::<Error, _>(Into::into)?
+					),
+					result = fetches.select_next_some() => Next::Fetched(result),
+					(recovery, responses) = recoveries.select_next_some()
+						=> Next::Recovered(recovery, responses),
+				}
+			};
+
+			let message = match next {
+				Next::FromOverseer(message) => message,
+				Next::Fetched(result) => {
+					if let Err(e) = handle_chunk_fetch_result(&mut ctx, &mut state, result).await {
+						warn!(
+							target: TARGET,
+							"Failed to handle fetched erasure chunk: {:?}", e
+						);
+					}
+					continue;
+				}
+				Next::Recovered(recovery, responses) => {
+					if let Err(e) = handle_recovery_chunks(
+						&mut ctx,
+						&state,
+						&mut recoveries,
+						recovery,
+						responses,
+					).await {
+						warn!(
+							target: TARGET,
+							"Failed to recover available data: {:?}", e
+						);
+					}
+					continue;
+				}
+			};
+
 			match message {
 				FromOverseer::Communication {
 					msg: AvailabilityDistributionMessage::NetworkBridgeUpdateV1(event),
@@ -729,6 +1215,7 @@ impl AvailabilityDistributionSubsystem {
 						&mut ctx,
 						self.keystore.clone(),
 						&mut state,
+						&mut fetches,
 						event
 					).await {
 						warn!(
@@ -737,6 +1224,32 @@ impl AvailabilityDistributionSubsystem {
 						);
 					}
 				}
+				FromOverseer::Communication {
+					msg: AvailabilityDistributionMessage::ChunkFetchingRequest(request),
+				} => {
+					if let Err(e) = handle_chunk_request(&mut ctx, &state, request).await {
+						warn!(
+							target: TARGET,
+							"Failed to answer chunk request: {:?}", e
+						);
+					}
+				}
+				FromOverseer::Communication {
+					msg: AvailabilityDistributionMessage::RecoverAvailableData(candidate_hash, response),
+				} => {
+					if let Err(e) = recover_available_data(
+						&mut ctx,
+						&state,
+						&mut recoveries,
+						candidate_hash,
+						response,
+					).await {
+						warn!(
+							target: TARGET,
+							"Failed to recover available data: {:?}", e
+						);
+					}
+				}
 				FromOverseer::Signal(OverseerSignal::ActiveLeaves(ActiveLeavesUpdate {
 					activated: _,
 					deactivated: _,
@@ -921,6 +1434,20 @@ where
 	rx.await.map_err::<Error, _>(Into::into)
 }
 
+async fn query_available_data<Context>(
+	ctx: &mut Context,
+	candidate_hash: Hash,
+) -> Result<Option<AvailableData>>
+where
+	Context: SubsystemContext<Message = AvailabilityDistributionMessage>,
+{
+	let (tx, rx) = oneshot::channel();
+	ctx.send_message(AllMessages::AvailabilityStore(
+		AvailabilityStoreMessage::QueryAvailableData(candidate_hash, tx),
+	))
+	.await?;
+	rx.await.map_err::<Error, _>(Into::into)
+}
 
 async fn query_chunk<Context>(
 	ctx: &mut Context,
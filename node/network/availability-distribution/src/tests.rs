@@ -24,7 +24,7 @@ use polkadot_primitives::v1::{
 };
 use polkadot_subsystem_testhelpers::{self as test_helpers};
 use polkadot_node_subsystem_util::TimeoutExt;
-use polkadot_node_network_protocol::ObservedRole;
+use polkadot_node_network_protocol::{ObservedRole, RequestProtocol};
 
 use futures::{executor, future, Future};
 use futures_timer::Delay;
@@ -967,3 +967,618 @@ fn k_ancestors_in_session() {
 
 	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)));
 }
+
+#[test]
+fn chunk_requests_are_served_from_the_store() {
+	let test_state = TestState::default();
+
+	test_harness(test_state.keystore.clone(), |test_harness| async move {
+		let TestHarness {
+			mut virtual_overseer,
+		} = test_harness;
+
+		let pov_block = PoV {
+			block_data: BlockData(vec![42, 43, 44]),
+		};
+		let candidate_hash = Hash::repeat_byte(0xCA);
+		let chunk = make_valid_availability_gossip(&test_state, candidate_hash, 1, pov_block)
+			.erasure_chunk;
+
+		for stored in vec![Some(chunk.clone()), None] {
+			let (tx, rx) = oneshot::channel();
+			overseer_send(
+				&mut virtual_overseer,
+				AvailabilityDistributionMessage::ChunkFetchingRequest(IncomingRequest::new(
+					PeerId::random(),
+					RequestProtocol::ChunkFetching,
+					protocol_v1::ChunkFetchingRequest { candidate_hash, index: 1 },
+					tx,
+				)),
+			)
+			.await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::AvailabilityStore(
+					AvailabilityStoreMessage::QueryChunk(hash, index, tx)
+				) => {
+					assert_eq!(hash, candidate_hash);
+					assert_eq!(index, 1);
+					tx.send(stored.clone()).unwrap();
+				}
+			);
+
+			let expected = match stored {
+				Some(chunk) => protocol_v1::ChunkFetchingResponse::Chunk(chunk),
+				None => protocol_v1::ChunkFetchingResponse::NoSuchChunk,
+			};
+			assert_eq!(rx.await.unwrap(), expected.encode());
+		}
+	});
+}
+
+#[test]
+fn fetched_chunks_are_verified_and_stored() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+
+	let candidate = TestCandidateBuilder {
+		para_id: test_state.chain_ids[0],
+		relay_parent: test_state.relay_parent,
+		pov_hash: pov_block.hash(),
+		erasure_root: make_erasure_root(&test_state, pov_block.clone()),
+		..Default::default()
+	}
+	.build();
+	let candidate_hash = candidate.hash();
+
+	let our_index = 2;
+	let our_chunk = make_valid_availability_gossip(
+		&test_state,
+		candidate_hash,
+		our_index,
+		pov_block.clone(),
+	).erasure_chunk;
+	let other_chunk = make_valid_availability_gossip(
+		&test_state,
+		candidate_hash,
+		our_index + 1,
+		pov_block,
+	).erasure_chunk;
+
+	let mut state = ProtocolState::default();
+	state.view = view![test_state.relay_parent];
+	state.receipts.entry(test_state.relay_parent).or_default().insert((candidate_hash, candidate));
+	{
+		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+		per_candidate.validator_index = Some(our_index);
+		per_candidate.fetching_our_chunk = true;
+	}
+
+	let peer_a = PeerId::random();
+	let peer_b = PeerId::random();
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let test_fut = {
+		let peer_a = peer_a.clone();
+		let peer_b = peer_b.clone();
+		let our_chunk = our_chunk.clone();
+
+		async move {
+			// a valid chunk, which is not ours
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::ReportPeer(peer, rep)) => {
+					assert_eq!(peer, peer_a);
+					assert_eq!(rep, COST_MERKLE_PROOF_INVALID);
+				}
+			);
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::ReportPeer(peer, rep)) => {
+					assert_eq!(peer, peer_b);
+					assert_eq!(rep, BENEFIT_VALID_MESSAGE_FIRST);
+				}
+			);
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::AvailabilityStore(
+					AvailabilityStoreMessage::StoreChunk(hash, index, chunk, tx)
+				) => {
+					assert_eq!(hash, candidate_hash);
+					assert_eq!(index, our_index);
+					assert_eq!(chunk, our_chunk);
+					tx.send(Ok(())).unwrap();
+				}
+			);
+		}
+	};
+
+	let sut = async move {
+		handle_chunk_fetch_result(&mut ctx, &mut state, ChunkFetchResult {
+			candidate_hash,
+			response: Some((peer_a, other_chunk)),
+		}).await.unwrap();
+
+		handle_chunk_fetch_result(&mut ctx, &mut state, ChunkFetchResult {
+			candidate_hash,
+			response: Some((peer_b, our_chunk)),
+		}).await.unwrap();
+
+		let per_candidate = &state.per_candidate[&candidate_hash];
+		assert!(!per_candidate.fetching_our_chunk);
+		assert!(per_candidate.message_vault.contains_key(&our_index));
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+}
+
+#[test]
+fn backers_send_chunks_to_their_owners_only() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+
+	let candidate = TestCandidateBuilder {
+		para_id: test_state.chain_ids[0],
+		relay_parent: test_state.relay_parent,
+		pov_hash: pov_block.hash(),
+		erasure_root: make_erasure_root(&test_state, pov_block.clone()),
+		..Default::default()
+	}
+	.build();
+	let candidate_hash = candidate.hash();
+
+	let mut state = ProtocolState::default();
+	state.view = view![test_state.relay_parent];
+	state.receipts.entry(test_state.relay_parent).or_default().insert((candidate_hash, candidate));
+	{
+		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+		per_candidate.validators = test_state.validator_public.clone();
+		per_candidate.validator_index = Some(0);
+		per_candidate.is_backer = true;
+		for index in 0..test_state.validators.len() as u32 {
+			let message = make_valid_availability_gossip(
+				&test_state,
+				candidate_hash,
+				index,
+				pov_block.clone(),
+			);
+			per_candidate.message_vault.insert(index, message);
+		}
+	}
+
+	let peer_a = PeerId::random();
+	let peer_b = PeerId::random();
+	state.validator_peers.insert(peer_a.clone(), test_state.validator_public[2].clone());
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let expected = make_valid_availability_gossip(&test_state, candidate_hash, 2, pov_block);
+
+	let test_fut = {
+		let peer_a = peer_a.clone();
+
+		async move {
+			// only the peer of validator 2 gets a chunk, and only chunk 2
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendValidationMessage(
+					peers,
+					protocol_v1::ValidationProtocol::AvailabilityDistribution(message),
+				)) => {
+					assert_eq!(peers, vec![peer_a]);
+					assert_eq!(message, chunk_protocol_message(expected));
+				}
+			);
+
+			assert!(virtual_overseer.recv().timeout(TIMEOUT).await.is_none());
+		}
+	};
+
+	// borrows the context, so it is still alive while we wait for further messages
+	let sut = async {
+		let view = view![test_state.relay_parent];
+		handle_peer_view_change(&mut ctx, &mut state, peer_b, view.clone()).await.unwrap();
+		handle_peer_view_change(&mut ctx, &mut state, peer_a.clone(), view.clone()).await.unwrap();
+
+		// the chunk is not sent twice
+		handle_peer_view_change(&mut ctx, &mut state, peer_a.clone(), view![]).await.unwrap();
+		handle_peer_view_change(&mut ctx, &mut state, peer_a, view).await.unwrap();
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+}
+
+#[test]
+fn chunks_are_sent_once_the_owner_of_a_peer_is_known() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+
+	let candidate = TestCandidateBuilder {
+		para_id: test_state.chain_ids[0],
+		relay_parent: test_state.relay_parent,
+		pov_hash: pov_block.hash(),
+		erasure_root: make_erasure_root(&test_state, pov_block.clone()),
+		..Default::default()
+	}
+	.build();
+	let candidate_hash = candidate.hash();
+
+	let peer_a = PeerId::random();
+
+	let mut state = ProtocolState::default();
+	state.view = view![test_state.relay_parent];
+	state.receipts.entry(test_state.relay_parent).or_default().insert((candidate_hash, candidate));
+	state.peer_views.insert(peer_a.clone(), view![test_state.relay_parent]);
+	{
+		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+		per_candidate.validators = test_state.validator_public.clone();
+		per_candidate.validator_index = Some(0);
+		per_candidate.is_backer = true;
+		for index in 0..test_state.validators.len() as u32 {
+			let message = make_valid_availability_gossip(
+				&test_state,
+				candidate_hash,
+				index,
+				pov_block.clone(),
+			);
+			per_candidate.message_vault.insert(index, message);
+		}
+	}
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let expected = make_valid_availability_gossip(&test_state, candidate_hash, 1, pov_block);
+
+	let test_fut = {
+		let peer_a = peer_a.clone();
+
+		async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendValidationMessage(
+					peers,
+					protocol_v1::ValidationProtocol::AvailabilityDistribution(message),
+				)) => {
+					assert_eq!(peers, vec![peer_a]);
+					assert_eq!(message, chunk_protocol_message(expected));
+				}
+			);
+
+			assert!(virtual_overseer.recv().timeout(TIMEOUT).await.is_none());
+		}
+	};
+
+	// the peer's view is known before the validator behind it, as the network bridge
+	// tells us about the validators' peers later.
+	let sut = async {
+		let mut fetches = PendingChunkFetches::new();
+		let validator_peers = vec![(test_state.validator_public[1].clone(), peer_a)];
+		handle_network_msg(
+			&mut ctx,
+			test_state.keystore.clone(),
+			&mut state,
+			&mut fetches,
+			NetworkBridgeEvent::ValidatorPeers(validator_peers.clone()),
+		).await.unwrap();
+
+		// learning of the same validator again doesn't send the chunk again
+		handle_network_msg(
+			&mut ctx,
+			test_state.keystore.clone(),
+			&mut state,
+			&mut fetches,
+			NetworkBridgeEvent::ValidatorPeers(validator_peers),
+		).await.unwrap();
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+}
+
+#[test]
+fn non_backers_only_share_their_own_chunk() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+	let candidate_hash = Hash::repeat_byte(0xCA);
+	let our_index = 2;
+
+	let mut state = ProtocolState::default();
+	{
+		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+		per_candidate.validator_index = Some(our_index);
+		for index in 0..test_state.validators.len() as u32 {
+			let message = make_valid_availability_gossip(
+				&test_state,
+				candidate_hash,
+				index,
+				pov_block.clone(),
+			);
+			per_candidate.message_vault.insert(index, message);
+		}
+	}
+
+	let our_chunk = state.per_candidate[&candidate_hash].message_vault[&our_index]
+		.erasure_chunk
+		.clone();
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let (tx_ours, rx_ours) = oneshot::channel();
+	let (tx_other, rx_other) = oneshot::channel();
+
+	let test_fut = async move {
+		// the chunk which is not ours is only looked up in the store, which lacks it
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::AvailabilityStore(
+				AvailabilityStoreMessage::QueryChunk(hash, index, tx)
+			) => {
+				assert_eq!(hash, candidate_hash);
+				assert_eq!(index, our_index + 1);
+				tx.send(None).unwrap();
+			}
+		);
+	};
+
+	let sut = async move {
+		handle_chunk_request(&mut ctx, &state, IncomingRequest::new(
+			PeerId::random(),
+			RequestProtocol::ChunkFetching,
+			protocol_v1::ChunkFetchingRequest { candidate_hash, index: our_index },
+			tx_ours,
+		)).await.unwrap();
+
+		handle_chunk_request(&mut ctx, &state, IncomingRequest::new(
+			PeerId::random(),
+			RequestProtocol::ChunkFetching,
+			protocol_v1::ChunkFetchingRequest { candidate_hash, index: our_index + 1 },
+			tx_other,
+		)).await.unwrap();
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+
+	assert_eq!(
+		executor::block_on(rx_ours).unwrap(),
+		protocol_v1::ChunkFetchingResponse::Chunk(our_chunk).encode(),
+	);
+	assert_eq!(
+		executor::block_on(rx_other).unwrap(),
+		protocol_v1::ChunkFetchingResponse::NoSuchChunk.encode(),
+	);
+}
+
+fn make_recovery_state(
+	test_state: &TestState,
+	candidate: CommittedCandidateReceipt,
+	backer: PeerId,
+) -> ProtocolState {
+	let candidate_hash = candidate.hash();
+
+	let mut state = ProtocolState::default();
+	state.view = view![test_state.relay_parent];
+	state.receipts.entry(test_state.relay_parent).or_default().insert((candidate_hash, candidate));
+	{
+		let per_candidate = state.per_candidate.entry(candidate_hash).or_default();
+		per_candidate.validators = test_state.validator_public.clone();
+		per_candidate.received_messages.insert(backer, HashSet::new());
+	}
+
+	state
+}
+
+#[test]
+fn available_data_is_recovered_from_the_backers_systematic_chunks() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+
+	let candidate = TestCandidateBuilder {
+		para_id: test_state.chain_ids[0],
+		relay_parent: test_state.relay_parent,
+		pov_hash: pov_block.hash(),
+		erasure_root: make_erasure_root(&test_state, pov_block.clone()),
+		..Default::default()
+	}
+	.build();
+	let candidate_hash = candidate.hash();
+	let available_data = make_available_data(&test_state, pov_block.clone());
+
+	let backer = PeerId::random();
+	let state = make_recovery_state(&test_state, candidate, backer.clone());
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let (tx, rx) = oneshot::channel();
+
+	let test_fut = async move {
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::AvailabilityStore(
+				AvailabilityStoreMessage::QueryAvailableData(hash, tx)
+			) => {
+				assert_eq!(hash, candidate_hash);
+				tx.send(None).unwrap();
+			}
+		);
+
+		// with 5 validators, 2 chunks are systematic
+		for expected_index in 0..2 {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::FetchChunk(peers, request, tx)
+				) => {
+					assert_eq!(peers, vec![backer.clone()]);
+					assert_eq!(request.candidate_hash, candidate_hash);
+					assert_eq!(request.index, expected_index);
+					let chunk = make_valid_availability_gossip(
+						&test_state,
+						candidate_hash,
+						expected_index,
+						pov_block.clone(),
+					).erasure_chunk;
+					tx.send(Some((backer.clone(), chunk))).unwrap();
+				}
+			);
+		}
+	};
+
+	let sut = async move {
+		let mut recoveries = PendingRecoveries::new();
+		recover_available_data(&mut ctx, &state, &mut recoveries, candidate_hash, tx).await.unwrap();
+
+		let (recovery, responses) = recoveries.next().await.unwrap();
+		handle_recovery_chunks(&mut ctx, &state, &mut recoveries, recovery, responses).await.unwrap();
+		assert!(recoveries.is_empty());
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+
+	assert_eq!(executor::block_on(rx).unwrap(), Some(available_data));
+}
+
+#[test]
+fn available_data_recovery_falls_back_to_any_chunks() {
+	let test_state = TestState::default();
+
+	let pov_block = PoV {
+		block_data: BlockData(vec![42, 43, 44]),
+	};
+
+	let candidate = TestCandidateBuilder {
+		para_id: test_state.chain_ids[0],
+		relay_parent: test_state.relay_parent,
+		pov_hash: pov_block.hash(),
+		erasure_root: make_erasure_root(&test_state, pov_block.clone()),
+		..Default::default()
+	}
+	.build();
+	let candidate_hash = candidate.hash();
+	let available_data = make_available_data(&test_state, pov_block.clone());
+
+	let backer = PeerId::random();
+	let owner = PeerId::random();
+	let owner_index = 3;
+	let mut state = make_recovery_state(&test_state, candidate, backer.clone());
+	state.validator_peers.insert(owner.clone(), test_state.validator_public[owner_index as usize].clone());
+
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut ctx, mut virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+	let (tx, rx) = oneshot::channel();
+
+	let test_fut = async move {
+		let chunk = |index| make_valid_availability_gossip(
+			&test_state,
+			candidate_hash,
+			index,
+			pov_block.clone(),
+		).erasure_chunk;
+
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::AvailabilityStore(
+				AvailabilityStoreMessage::QueryAvailableData(_, tx)
+			) => {
+				tx.send(None).unwrap();
+			}
+		);
+
+		// the backer only has the first systematic chunk
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::NetworkBridge(NetworkBridgeMessage::FetchChunk(_, request, tx)) => {
+				assert_eq!(request.index, 0);
+				tx.send(Some((backer.clone(), chunk(0)))).unwrap();
+			}
+		);
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::NetworkBridge(NetworkBridgeMessage::FetchChunk(_, request, tx)) => {
+				assert_eq!(request.index, 1);
+				tx.send(None).unwrap();
+			}
+		);
+
+		// then only the one more chunk needed is asked for, from its owner first if known,
+		// and the next one every time a fetch fails. The last chunk is never asked for,
+		// since the fetch would go unanswered.
+		for expected_index in 1..4 {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::FetchChunk(peers, request, tx)
+				) => {
+					assert_eq!(request.index, expected_index);
+					if expected_index == owner_index {
+						assert_eq!(peers, vec![owner.clone(), backer.clone()]);
+						tx.send(Some((owner.clone(), chunk(expected_index)))).unwrap();
+					} else {
+						assert_eq!(peers, vec![backer.clone()]);
+						tx.send(None).unwrap();
+					}
+				}
+			);
+		}
+	};
+
+	let sut = async move {
+		let mut recoveries = PendingRecoveries::new();
+		recover_available_data(&mut ctx, &state, &mut recoveries, candidate_hash, tx).await.unwrap();
+
+		while let Some((recovery, responses)) = recoveries.next().await {
+			handle_recovery_chunks(&mut ctx, &state, &mut recoveries, recovery, responses).await.unwrap();
+		}
+	};
+
+	futures::pin_mut!(test_fut);
+	futures::pin_mut!(sut);
+
+	executor::block_on(future::join(test_fut, sut).timeout(Duration::from_millis(1000)))
+		.expect("test timed out");
+
+	assert_eq!(executor::block_on(rx).unwrap(), Some(available_data));
+}
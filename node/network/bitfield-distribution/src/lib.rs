@@ -130,6 +130,7 @@ impl PerRelayParentData {
 const TARGET: &'static str = "bitd";
 
 /// The bitfield distribution subsystem.
+#[derive(Clone)]
 pub struct BitfieldDistribution;
 
 impl BitfieldDistribution {
@@ -435,6 +436,7 @@ where
 		NetworkBridgeEvent::OurViewChange(view) => {
 			handle_our_view_change(state, view)?;
 		}
+		NetworkBridgeEvent::ValidatorPeers(_) => {}
 		NetworkBridgeEvent::PeerMessage(remote, message) => {
 			match message {
 				protocol_v1::BitfieldDistributionMessage::Bitfield(relay_parent, bitfield) => {
@@ -566,7 +568,7 @@ where
 	fn start(self, ctx: C) -> SpawnedSubsystem {
 		SpawnedSubsystem {
 			name: "bitfield-distribution-subsystem",
-			future: Box::pin(async move { Self::run(ctx) }.map(|_| ())),
+			future: Self::run(ctx).map(|_| ()).boxed(),
 		}
 	}
 }
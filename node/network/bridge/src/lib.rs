@@ -20,9 +20,11 @@ use parity_scale_codec::{Encode, Decode};
 use futures::prelude::*;
 use futures::future::BoxFuture;
 use futures::stream::BoxStream;
-use futures::channel::oneshot;
+use futures::channel::{mpsc, oneshot};
+use futures::stream::FuturesUnordered;
+use parking_lot::Mutex;
 
-use sc_network::Event as NetworkEvent;
+use sc_network::{Event as NetworkEvent, Multiaddr};
 use sp_runtime::ConsensusEngineId;
 
 use polkadot_subsystem::{
@@ -34,15 +36,26 @@ use polkadot_subsystem::messages::{
 	BitfieldDistributionMessage, PoVDistributionMessage, StatementDistributionMessage,
 	CollatorProtocolMessage,
 };
-use polkadot_primitives::v1::{Block, Hash, ValidatorId};
+use polkadot_primitives::v1::{Block, ErasureChunk, Hash, PoV, ValidatorId};
 use polkadot_node_network_protocol::{
-	ObservedRole, ReputationChange, PeerId, PeerSet, View, NetworkBridgeEvent, v1 as protocol_v1
+	ObservedRole, ReputationChange, PeerId, PeerSet, View, NetworkBridgeEvent, IncomingRequest,
+	RequestProtocol, ProtocolVersion, PROTOCOL_VERSIONS, VersionedMessage, v1 as protocol_v1
 };
+use protocol_v1::MaybeCompressed;
 
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::collections::hash_map::{HashMap, Entry as HEntry};
-use std::iter::ExactSizeIterator;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Instant;
+
+use limits::{Limiter, MaxEncodedSize, Verdict};
+use validator_discovery::ValidatorConnections;
+
+pub use validator_discovery::ValidatorDiscovery;
+
+mod limits;
+mod validator_discovery;
 
 /// The maximum amount of heads a peer is allowed to have in their view at any time.
 ///
@@ -53,10 +66,23 @@ const MAX_VIEW_HEADS: usize = 5;
 pub const VALIDATION_PROTOCOL_ID: ConsensusEngineId = *b"pvn1";
 /// The protocol name for the validation peer-set.
 pub const VALIDATION_PROTOCOL_NAME: &'static str = "/polkadot/validation/1";
+/// The engine ID of version 2 of the validation protocol.
+pub const VALIDATION_PROTOCOL_ID_V2: ConsensusEngineId = *b"pvn2";
+/// The protocol name of version 2 of the validation protocol.
+pub const VALIDATION_PROTOCOL_NAME_V2: &'static str = "/polkadot/validation/2";
 /// The engine ID of the collation protocol.
 pub const COLLATION_PROTOCOL_ID: ConsensusEngineId = *b"pcn1";
 /// The protocol name for the collation peer-set.
 pub const COLLATION_PROTOCOL_NAME: &'static str = "/polkadot/collation/1";
+/// The engine ID of version 2 of the collation protocol.
+pub const COLLATION_PROTOCOL_ID_V2: ConsensusEngineId = *b"pcn2";
+/// The protocol name of version 2 of the collation protocol.
+pub const COLLATION_PROTOCOL_NAME_V2: &'static str = "/polkadot/collation/2";
+/// The engine ID of the compressed PoV protocol. Nothing is sent on it: peers opening it
+/// announce that they understand messages carrying compressed PoVs.
+pub const COMPRESSION_PROTOCOL_ID: ConsensusEngineId = *b"pcp1";
+/// The protocol name for the compressed PoV protocol.
+pub const COMPRESSION_PROTOCOL_NAME: &'static str = "/polkadot/compressed-pov/1";
 
 const MALFORMED_MESSAGE_COST: ReputationChange
 	= ReputationChange::new(-500, "Malformed Network-bridge message");
@@ -64,10 +90,48 @@ const UNCONNECTED_PEERSET_COST: ReputationChange
 	= ReputationChange::new(-50, "Message sent to un-connected peer-set");
 const MALFORMED_VIEW_COST: ReputationChange
 	= ReputationChange::new(-500, "Malformed view");
+const MALFORMED_REQUEST_COST: ReputationChange
+	= ReputationChange::new(-500, "Malformed request");
+const MALFORMED_RESPONSE_COST: ReputationChange
+	= ReputationChange::new(-500, "Malformed response");
+const OVERSIZED_RESPONSE_COST: ReputationChange
+	= ReputationChange::new(-500, "Response exceeding the size limit");
+
+/// The maximum size of a request we accept. All requests are small and of fixed size.
+const MAX_REQUEST_SIZE: u64 = 1024;
+
+/// The number of incoming requests per protocol buffered before the network starts refusing
+/// new ones.
+const INCOMING_REQUEST_QUEUE_SIZE: usize = 100;
 
 // network bridge log target
 const TARGET: &'static str = "network_bridge";
 
+/// The notifications protocols of the peer-sets, one for every supported protocol version.
+///
+/// Peers open the protocols of all the versions they speak, so the versions open with a peer
+/// are the ones both sides support.
+const PEER_SET_PROTOCOLS: [(PeerSet, ProtocolVersion, ConsensusEngineId, &'static str); 4] = [
+	(PeerSet::Validation, 1, VALIDATION_PROTOCOL_ID, VALIDATION_PROTOCOL_NAME),
+	(PeerSet::Validation, 2, VALIDATION_PROTOCOL_ID_V2, VALIDATION_PROTOCOL_NAME_V2),
+	(PeerSet::Collation, 1, COLLATION_PROTOCOL_ID, COLLATION_PROTOCOL_NAME),
+	(PeerSet::Collation, 2, COLLATION_PROTOCOL_ID_V2, COLLATION_PROTOCOL_NAME_V2),
+];
+
+/// The peer-set and protocol version the given engine ID belongs to.
+fn peer_set_protocol(engine_id: ConsensusEngineId) -> Option<(PeerSet, ProtocolVersion)> {
+	PEER_SET_PROTOCOLS.iter()
+		.find(|(_, _, id, _)| *id == engine_id)
+		.map(|(peer_set, version, _, _)| (*peer_set, *version))
+}
+
+/// The engine ID of the given version of a peer-set's protocol.
+pub fn peer_set_engine_id(peer_set: PeerSet, version: ProtocolVersion) -> Option<ConsensusEngineId> {
+	PEER_SET_PROTOCOLS.iter()
+		.find(|(p, v, _, _)| *p == peer_set && *v == version)
+		.map(|(_, _, id, _)| *id)
+}
+
 /// Messages received on the network.
 #[derive(Debug, Encode, Decode, Clone)]
 pub enum WireMessage<M> {
@@ -79,22 +143,120 @@ pub enum WireMessage<M> {
 	ViewUpdate(View),
 }
 
+impl<M: VersionedMessage> WireMessage<M> {
+	/// Encode the message for a peer speaking the given protocol version. `None` if the message
+	/// has no counterpart in that version.
+	fn encode_versioned(&self, version: ProtocolVersion) -> Option<Vec<u8>> {
+		match (version, self) {
+			(1, message) => Some(message.encode()),
+			(2, WireMessage::ViewUpdate(view))
+				=> Some(WireMessage::<M::V2>::ViewUpdate(view.clone()).encode()),
+			(2, WireMessage::ProtocolMessage(message))
+				=> message.clone().into_v2().map(|m| WireMessage::ProtocolMessage(m).encode()),
+			_ => None,
+		}
+	}
+
+	/// Decode a message received on the given protocol version into the version subsystems
+	/// speak.
+	fn decode_versioned(version: ProtocolVersion, mut bytes: &[u8]) -> Option<Self> {
+		match version {
+			1 => Self::decode(&mut bytes).ok(),
+			2 => match WireMessage::<M::V2>::decode(&mut bytes).ok()? {
+				WireMessage::ViewUpdate(view) => Some(WireMessage::ViewUpdate(view)),
+				WireMessage::ProtocolMessage(m) => M::from_v2(m).map(WireMessage::ProtocolMessage),
+			},
+			_ => None,
+		}
+	}
+}
+
 /// Information about the notifications protocol. Should be used during network configuration
 /// or shortly after startup to register the protocol with the network service.
 pub fn notifications_protocol_info() -> Vec<(ConsensusEngineId, std::borrow::Cow<'static, str>)> {
-	vec![
-		(VALIDATION_PROTOCOL_ID, VALIDATION_PROTOCOL_NAME.into()),
-		(COLLATION_PROTOCOL_ID, COLLATION_PROTOCOL_NAME.into()),
-	]
+	PEER_SET_PROTOCOLS.iter()
+		.map(|(_, _, engine_id, name)| (*engine_id, (*name).into()))
+		.chain(std::iter::once((COMPRESSION_PROTOCOL_ID, COMPRESSION_PROTOCOL_NAME.into())))
+		.collect()
+}
+
+/// A request received on one of the request-response protocols, see
+/// [`request_response_protocol_info`](request_response_protocol_info).
+#[derive(Debug)]
+pub struct NetworkRequest {
+	/// The peer that sent the request.
+	pub peer: PeerId,
+	/// The protocol the request was received on.
+	pub protocol: RequestProtocol,
+	/// The encoded request.
+	pub payload: Vec<u8>,
+	/// Where to send the encoded response to.
+	pub pending_response: oneshot::Sender<Vec<u8>>,
+}
+
+/// The requests received on all request-response protocols, to be handed to the
+/// [`NetworkBridge`](NetworkBridge).
+pub struct IncomingRequests(BoxStream<'static, NetworkRequest>);
+
+impl IncomingRequests {
+	/// Wrap the requests received by a network other than `sc_network`, like a simulated one.
+	pub fn new(requests: impl Stream<Item = NetworkRequest> + Send + 'static) -> Self {
+		IncomingRequests(requests.boxed())
+	}
+}
+
+/// Information about the request-response protocols. Should be used during network
+/// configuration to register the protocols with the network service, while the returned
+/// incoming requests are to be handed to [`NetworkBridge::with_incoming_requests`].
+///
+/// [`NetworkBridge::with_incoming_requests`]: struct.NetworkBridge.html#method.with_incoming_requests
+pub fn request_response_protocol_info() -> (Vec<sc_network::config::RequestResponseConfig>, IncomingRequests) {
+	let mut configs = Vec::with_capacity(RequestProtocol::ALL.len());
+	let mut incoming = Vec::with_capacity(RequestProtocol::ALL.len());
+
+	for &protocol in RequestProtocol::ALL.iter() {
+		let (tx, rx) = mpsc::channel(INCOMING_REQUEST_QUEUE_SIZE);
+
+		configs.push(sc_network::config::RequestResponseConfig {
+			name: protocol.name().into(),
+			max_request_size: MAX_REQUEST_SIZE,
+			max_response_size: protocol.max_response_size(),
+			request_timeout: protocol.request_timeout(),
+			inbound_queue: Some(tx),
+		});
+
+		incoming.push(rx.map(move |request: sc_network::config::IncomingRequest| NetworkRequest {
+			peer: request.peer,
+			protocol,
+			payload: request.payload,
+			pending_response: request.pending_response,
+		}));
+	}
+
+	(configs, IncomingRequests(stream::select_all(incoming).boxed()))
 }
 
+/// The reason an outgoing request did not yield a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestFailure;
+
 /// An action to be carried out by the network.
 #[derive(Debug, PartialEq)]
 pub enum NetworkAction {
 	/// Note a change in reputation for a peer.
 	ReputationChange(PeerId, ReputationChange),
-	/// Write a notification to a given peer on the given peer-set.
-	WriteNotification(PeerId, PeerSet, Vec<u8>),
+	/// Write a notification to a given peer on the given version of the peer-set's protocol.
+	WriteNotification(PeerId, PeerSet, ProtocolVersion, Vec<u8>),
+	/// Keep up connections on the peer-set to the given addresses, each ending in a peer ID.
+	ConnectToPeers(PeerSet, Vec<Multiaddr>),
+	/// Stop keeping up the connections to the given addresses, requested with `ConnectToPeers`.
+	DisconnectFromPeers(PeerSet, Vec<Multiaddr>),
+}
+
+/// The name of the priority group the network keeps up the connections to validators of a
+/// peer-set in.
+fn validators_priority_group(peer_set: PeerSet) -> String {
+	format!("polkadot-{:?}-validators", peer_set).to_lowercase()
 }
 
 /// An abstraction over networking for the purposes of this subsystem.
@@ -119,14 +281,23 @@ pub trait Network: Send + 'static {
 		}.boxed()
 	}
 
-	/// Write a notification to a peer on the given peer-set's protocol.
-	fn write_notification(&mut self, who: PeerId, peer_set: PeerSet, message: Vec<u8>)
-		-> BoxFuture<SubsystemResult<()>>
-	{
+	/// Write a notification to a peer on the given version of the peer-set's protocol.
+	fn write_notification(
+		&mut self,
+		who: PeerId,
+		peer_set: PeerSet,
+		version: ProtocolVersion,
+		message: Vec<u8>,
+	) -> BoxFuture<SubsystemResult<()>> {
 		async move {
-			self.action_sink().send(NetworkAction::WriteNotification(who, peer_set, message)).await
+			self.action_sink().send(NetworkAction::WriteNotification(who, peer_set, version, message)).await
 		}.boxed()
 	}
+
+	/// Send a request to a peer on one of the request-response protocols. The returned future
+	/// resolves to the encoded response.
+	fn start_request(&mut self, who: PeerId, protocol: RequestProtocol, payload: Vec<u8>)
+		-> BoxFuture<'static, Result<Vec<u8>, RequestFailure>>;
 }
 
 impl Network for Arc<sc_network::NetworkService<Block, Hash>> {
@@ -155,20 +326,29 @@ impl Network for Arc<sc_network::NetworkService<Block, Hash>> {
 						peer,
 						cost_benefit,
 					),
-					NetworkAction::WriteNotification(peer, peer_set, message) => {
-						match peer_set {
-							PeerSet::Validation => self.0.write_notification(
-								peer,
-								VALIDATION_PROTOCOL_ID,
-								message,
-							),
-							PeerSet::Collation => self.0.write_notification(
-								peer,
-								COLLATION_PROTOCOL_ID,
-								message,
+					NetworkAction::WriteNotification(peer, peer_set, version, message) => {
+						match peer_set_engine_id(peer_set, version) {
+							Some(engine_id) => self.0.write_notification(peer, engine_id, message),
+							None => log::warn!(
+								target: TARGET,
+								"Dropping notification for unknown version {} of the {:?} protocol",
+								version,
+								peer_set,
 							),
 						}
 					}
+					NetworkAction::ConnectToPeers(peer_set, addresses) => {
+						let group = validators_priority_group(peer_set);
+						if let Err(e) = self.0.add_to_priority_group(group, addresses.into_iter().collect()) {
+							log::warn!(target: TARGET, "Failed to connect to validators: {}", e);
+						}
+					}
+					NetworkAction::DisconnectFromPeers(peer_set, addresses) => {
+						let group = validators_priority_group(peer_set);
+						if let Err(e) = self.0.remove_from_priority_group(group, addresses.into_iter().collect()) {
+							log::warn!(target: TARGET, "Failed to disconnect from validators: {}", e);
+						}
+					}
 				}
 
 				Ok(())
@@ -185,24 +365,108 @@ impl Network for Arc<sc_network::NetworkService<Block, Hash>> {
 
 		Box::pin(ActionSink(&**self))
 	}
+
+	fn start_request(&mut self, who: PeerId, protocol: RequestProtocol, payload: Vec<u8>)
+		-> BoxFuture<'static, Result<Vec<u8>, RequestFailure>>
+	{
+		let net = self.clone();
+		async move {
+			net.request(who, protocol.name(), payload).await.map_err(|e| {
+				log::debug!(target: TARGET, "Request on {} failed: {:?}", protocol.name(), e);
+				RequestFailure
+			})
+		}.boxed()
+	}
 }
 
 /// The network bridge subsystem.
-pub struct NetworkBridge<N>(N);
+#[derive(Clone)]
+pub struct NetworkBridge<N, D> {
+	network_service: N,
+	validator_discovery: D,
+	// Shared between the clones used to restart the subsystem, each run borrowing the requests
+	// for as long as it lives.
+	incoming_requests: Arc<Mutex<Option<BoxStream<'static, NetworkRequest>>>>,
+}
 
-impl<N> NetworkBridge<N> {
-	/// Create a new network bridge subsystem with underlying network service.
+impl<N, D> NetworkBridge<N, D> {
+	/// Create a new network bridge subsystem with underlying network service, looking up the
+	/// addresses of the validators subsystems want to be connected to with `validator_discovery`.
 	///
 	/// This assumes that the network service has had the notifications protocol for the network
 	/// bridge already registered. See [`notifications_protocol_info`](notifications_protocol_info).
-	pub fn new(net_service: N) -> Self {
-		NetworkBridge(net_service)
+	///
+	/// A bridge created like this doesn't serve requests from peers, see
+	/// [`with_incoming_requests`](#method.with_incoming_requests).
+	pub fn new(net_service: N, validator_discovery: D) -> Self {
+		NetworkBridge {
+			network_service: net_service,
+			validator_discovery,
+			incoming_requests: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	/// Create a new network bridge subsystem, which also serves the requests peers send on the
+	/// request-response protocols.
+	///
+	/// This assumes that the network service has had the request-response protocols registered as
+	/// well. See [`request_response_protocol_info`](request_response_protocol_info).
+	pub fn with_incoming_requests(
+		net_service: N,
+		validator_discovery: D,
+		incoming_requests: IncomingRequests,
+	) -> Self {
+		NetworkBridge {
+			network_service: net_service,
+			validator_discovery,
+			incoming_requests: Arc::new(Mutex::new(Some(incoming_requests.0))),
+		}
+	}
+}
+
+/// The incoming requests, lent to one run of the subsystem and handed back once it ends.
+///
+/// Never yields anything if there are no incoming requests to serve.
+struct LentRequests {
+	requests: Option<BoxStream<'static, NetworkRequest>>,
+	home: Arc<Mutex<Option<BoxStream<'static, NetworkRequest>>>>,
+}
+
+impl LentRequests {
+	fn borrow(home: &Arc<Mutex<Option<BoxStream<'static, NetworkRequest>>>>) -> Self {
+		LentRequests {
+			requests: home.lock().take(),
+			home: home.clone(),
+		}
+	}
+}
+
+impl Stream for LentRequests {
+	type Item = NetworkRequest;
+
+	fn poll_next(
+		mut self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Option<NetworkRequest>> {
+		match self.requests {
+			Some(ref mut requests) => requests.poll_next_unpin(cx),
+			None => std::task::Poll::Pending,
+		}
+	}
+}
+
+impl Drop for LentRequests {
+	fn drop(&mut self) {
+		if let Some(requests) = self.requests.take() {
+			*self.home.lock() = Some(requests);
+		}
 	}
 }
 
-impl<Net, Context> Subsystem<Context> for NetworkBridge<Net>
+impl<Net, Discovery, Context> Subsystem<Context> for NetworkBridge<Net, Discovery>
 	where
 		Net: Network,
+		Discovery: ValidatorDiscovery,
 		Context: SubsystemContext<Message=NetworkBridgeMessage>,
 {
 	type Metrics = ();
@@ -212,7 +476,12 @@ impl<Net, Context> Subsystem<Context> for NetworkBridge<Net>
 		// within `run_network`.
 		SpawnedSubsystem {
 			name: "network-bridge-subsystem",
-			future: run_network(self.0, ctx).map(|_| ()).boxed(),
+			future: run_network(
+				self.network_service,
+				self.validator_discovery,
+				ctx,
+				LentRequests::borrow(&self.incoming_requests),
+			).map(|_| ()).boxed(),
 		}
 	}
 }
@@ -220,24 +489,41 @@ impl<Net, Context> Subsystem<Context> for NetworkBridge<Net>
 struct PeerData {
 	/// Latest view sent by the peer.
 	view: View,
+	/// The protocol versions the peer has open with us. Never empty.
+	versions: BTreeSet<ProtocolVersion>,
+}
+
+impl PeerData {
+	/// The version we talk to the peer in: the highest one both sides support.
+	fn version(&self) -> ProtocolVersion {
+		self.versions.iter().next_back().copied().unwrap_or(LOWEST_PROTOCOL_VERSION)
+	}
 }
 
+/// The version peers we don't know the versions of are addressed in.
+const LOWEST_PROTOCOL_VERSION: ProtocolVersion = PROTOCOL_VERSIONS[0];
+
 #[derive(Debug)]
 enum Action {
 	SendValidationMessage(Vec<PeerId>, protocol_v1::ValidationProtocol),
 	SendCollationMessage(Vec<PeerId>, protocol_v1::CollationProtocol),
 	ConnectToValidators(PeerSet, Vec<ValidatorId>, oneshot::Sender<Vec<(ValidatorId, PeerId)>>),
+	ReleaseValidators(PeerSet, Vec<ValidatorId>),
 	ReportPeer(PeerId, ReputationChange),
+	StartRequest(OutgoingRequest),
 
 	ActiveLeaves(ActiveLeavesUpdate),
 
-	PeerConnected(PeerSet, PeerId, ObservedRole),
-	PeerDisconnected(PeerSet, PeerId),
+	PeerConnected(PeerSet, ProtocolVersion, PeerId, ObservedRole),
+	PeerDisconnected(PeerSet, ProtocolVersion, PeerId),
+	PeerSupportsCompression(PeerId, bool),
 	PeerMessages(
 		PeerId,
-		Vec<WireMessage<protocol_v1::ValidationProtocol>>,
-		Vec<WireMessage<protocol_v1::CollationProtocol>>,
+		Vec<(Option<WireMessage<protocol_v1::ValidationProtocol>>, usize)>,
+		Vec<(Option<WireMessage<protocol_v1::CollationProtocol>>, usize)>,
 	),
+	IncomingRequest(NetworkRequest),
+	RequestAttempted(OutgoingRequest, PeerId, AttemptOutcome),
 
 	Abort,
 	Nop,
@@ -258,6 +544,22 @@ fn action_from_overseer_message(
 				=> Action::SendCollationMessage(peers, msg),
 			NetworkBridgeMessage::ConnectToValidators(peer_set, validators, res)
 				=> Action::ConnectToValidators(peer_set, validators, res),
+			NetworkBridgeMessage::ReleaseValidators(peer_set, validators)
+				=> Action::ReleaseValidators(peer_set, validators),
+			NetworkBridgeMessage::FetchPoV(peers, request, res)
+				=> Action::StartRequest(OutgoingRequest::new(
+					RequestProtocol::PoVFetching,
+					request.encode(),
+					peers,
+					PendingResponse::PoV(res),
+				)),
+			NetworkBridgeMessage::FetchChunk(peers, request, res)
+				=> Action::StartRequest(OutgoingRequest::new(
+					RequestProtocol::ChunkFetching,
+					request.encode(),
+					peers,
+					PendingResponse::Chunk(res),
+				)),
 		},
 		Ok(FromOverseer::Signal(OverseerSignal::BlockFinalized(_)))
 			=> Action::Nop,
@@ -277,42 +579,30 @@ fn action_from_network_message(event: Option<NetworkEvent>) -> Action {
 		Some(NetworkEvent::Dht(_)) => Action::Nop,
 		Some(NetworkEvent::NotificationStreamOpened { remote, engine_id, role }) => {
 			let role = role.into();
-			match engine_id {
-				x if x == VALIDATION_PROTOCOL_ID
-					=> Action::PeerConnected(PeerSet::Validation, remote, role),
-				x if x == COLLATION_PROTOCOL_ID
-					=> Action::PeerConnected(PeerSet::Collation, remote, role),
-				_ => Action::Nop,
+			match peer_set_protocol(engine_id) {
+				Some((peer_set, version)) => Action::PeerConnected(peer_set, version, remote, role),
+				None if engine_id == COMPRESSION_PROTOCOL_ID
+					=> Action::PeerSupportsCompression(remote, true),
+				None => Action::Nop,
 			}
 		}
 		Some(NetworkEvent::NotificationStreamClosed { remote, engine_id }) => {
-			match engine_id {
-				x if x == VALIDATION_PROTOCOL_ID
-					=> Action::PeerDisconnected(PeerSet::Validation, remote),
-				x if x == COLLATION_PROTOCOL_ID
-					=> Action::PeerDisconnected(PeerSet::Collation, remote),
-				_ => Action::Nop,
+			match peer_set_protocol(engine_id) {
+				Some((peer_set, version)) => Action::PeerDisconnected(peer_set, version, remote),
+				None if engine_id == COMPRESSION_PROTOCOL_ID
+					=> Action::PeerSupportsCompression(remote, false),
+				None => Action::Nop,
 			}
 		}
 		Some(NetworkEvent::NotificationsReceived { remote, messages }) => {
-			let v_messages: Result<Vec<_>, _> = messages.iter()
-				.filter(|(engine_id, _)| engine_id == &VALIDATION_PROTOCOL_ID)
-				.map(|(_, msg_bytes)| WireMessage::decode(&mut msg_bytes.as_ref()))
-				.collect();
-
-			let v_messages = match v_messages {
-				Err(_) => return Action::ReportPeer(remote, MALFORMED_MESSAGE_COST),
-				Ok(v) => v,
+			let v_messages = match decode_peer_set_messages(PeerSet::Validation, &messages) {
+				None => return Action::ReportPeer(remote, MALFORMED_MESSAGE_COST),
+				Some(v) => v,
 			};
 
-			let c_messages: Result<Vec<_>, _> = messages.iter()
-				.filter(|(engine_id, _)| engine_id == &COLLATION_PROTOCOL_ID)
-				.map(|(_, msg_bytes)| WireMessage::decode(&mut msg_bytes.as_ref()))
-				.collect();
-
-			match c_messages {
-				Err(_) => Action::ReportPeer(remote, MALFORMED_MESSAGE_COST),
-				Ok(c_messages) => if v_messages.is_empty() && c_messages.is_empty() {
+			match decode_peer_set_messages(PeerSet::Collation, &messages) {
+				None => Action::ReportPeer(remote, MALFORMED_MESSAGE_COST),
+				Some(c_messages) => if v_messages.is_empty() && c_messages.is_empty() {
 					Action::Nop
 				} else {
 					Action::PeerMessages(remote, v_messages, c_messages)
@@ -322,6 +612,294 @@ fn action_from_network_message(event: Option<NetworkEvent>) -> Action {
 	}
 }
 
+/// Decode the messages received on any version of the peer-set's protocol, along with their
+/// encoded sizes. Messages too large for the peer-set are left undecoded, as `None`. `None` if any
+/// of them is malformed.
+fn decode_peer_set_messages<M: VersionedMessage>(
+	peer_set: PeerSet,
+	messages: &[(ConsensusEngineId, impl AsRef<[u8]>)],
+) -> Option<Vec<(Option<WireMessage<M>>, usize)>> {
+	messages.iter()
+		.filter_map(|(engine_id, msg_bytes)| match peer_set_protocol(*engine_id) {
+			Some((p, version)) if p == peer_set => Some((version, msg_bytes.as_ref())),
+			_ => None,
+		})
+		.map(|(version, msg_bytes)| if msg_bytes.len() > limits::max_message_size(peer_set) {
+			Some((None, msg_bytes.len()))
+		} else {
+			WireMessage::decode_versioned(version, msg_bytes).map(|m| (Some(m), msg_bytes.len()))
+		})
+		.collect()
+}
+
+/// Drop the messages received from a peer which exceed its limits, punishing it for them.
+///
+/// Messages from a peer not connected on the peer-set are all dropped, without giving the peer
+/// a budget which would outlive it.
+async fn enforce_limits<M: MaxEncodedSize>(
+	net: &mut impl Network,
+	limiter: &mut Limiter,
+	peers: &HashMap<PeerId, PeerData>,
+	peer: &PeerId,
+	peer_set: PeerSet,
+	messages: Vec<(Option<WireMessage<M>>, usize)>,
+) -> SubsystemResult<Vec<WireMessage<M>>> {
+	if messages.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	if !peers.contains_key(peer) {
+		net.report_peer(peer.clone(), UNCONNECTED_PEERSET_COST).await?;
+		return Ok(Vec::new());
+	}
+
+	let now = Instant::now();
+	let mut accepted = Vec::with_capacity(messages.len());
+
+	for (message, size) in messages {
+		let max_size = message.as_ref()
+			.map_or_else(|| limits::max_message_size(peer_set), MaxEncodedSize::max_encoded_size);
+
+		match limiter.check(peer, peer_set, max_size, size, now) {
+			Verdict::Accept => accepted.extend(message),
+			Verdict::Drop => {
+				log::debug!(
+					target: TARGET,
+					"Dropping a message of {} bytes from {}, as all peers exceed the limits of the {:?} peer-set",
+					size,
+					peer,
+					peer_set,
+				);
+			}
+			Verdict::Reject(rep) => {
+				log::debug!(
+					target: TARGET,
+					"Dropping a message of {} bytes from {} on the {:?} peer-set: {}",
+					size,
+					peer,
+					peer_set,
+					rep.reason,
+				);
+				net.report_peer(peer.clone(), rep).await?;
+			}
+		}
+	}
+
+	Ok(accepted)
+}
+
+/// A request to be sent to one peer after the other, until one of them answers it.
+#[derive(Debug)]
+struct OutgoingRequest {
+	protocol: RequestProtocol,
+	payload: Vec<u8>,
+	peers: std::vec::IntoIter<PeerId>,
+	pending_response: PendingResponse,
+}
+
+/// Where to send the answer to an outgoing request to.
+#[derive(Debug)]
+enum PendingResponse {
+	PoV(oneshot::Sender<Option<(PeerId, PoV)>>),
+	Chunk(oneshot::Sender<Option<(PeerId, ErasureChunk)>>),
+}
+
+/// What became of asking a single peer.
+#[derive(Debug)]
+enum AttemptOutcome {
+	Response(Vec<u8>),
+	Failed,
+	TimedOut,
+}
+
+enum DecodedResponse {
+	/// The requester got what it asked for.
+	Answered,
+	/// The peer doesn't have what was asked for.
+	NotFound(PendingResponse),
+	/// The peer sent garbage.
+	Malformed(PendingResponse),
+}
+
+impl PendingResponse {
+	/// Decode a response, passing it on to the requester if it contains what was asked for.
+	fn answer(self, peer: &PeerId, mut response: &[u8]) -> DecodedResponse {
+		match self {
+			PendingResponse::PoV(tx) => match protocol_v1::PoVFetchingResponse::decode(&mut response) {
+				Ok(protocol_v1::PoVFetchingResponse::PoV(pov)) => {
+					let _ = tx.send(Some((peer.clone(), pov)));
+					DecodedResponse::Answered
+				}
+				Ok(protocol_v1::PoVFetchingResponse::CompressedPoV(pov)) => match pov.decompress() {
+					Ok(pov) => {
+						let _ = tx.send(Some((peer.clone(), pov)));
+						DecodedResponse::Answered
+					}
+					Err(_) => DecodedResponse::Malformed(PendingResponse::PoV(tx)),
+				},
+				Ok(protocol_v1::PoVFetchingResponse::NoSuchPoV)
+					=> DecodedResponse::NotFound(PendingResponse::PoV(tx)),
+				Err(_) => DecodedResponse::Malformed(PendingResponse::PoV(tx)),
+			},
+			PendingResponse::Chunk(tx) => match protocol_v1::ChunkFetchingResponse::decode(&mut response) {
+				Ok(protocol_v1::ChunkFetchingResponse::Chunk(chunk)) => {
+					let _ = tx.send(Some((peer.clone(), chunk)));
+					DecodedResponse::Answered
+				}
+				Ok(protocol_v1::ChunkFetchingResponse::NoSuchChunk)
+					=> DecodedResponse::NotFound(PendingResponse::Chunk(tx)),
+				Err(_) => DecodedResponse::Malformed(PendingResponse::Chunk(tx)),
+			},
+		}
+	}
+
+	/// Let the requester know none of the peers had what it asked for.
+	fn give_up(self) {
+		match self {
+			PendingResponse::PoV(tx) => { let _ = tx.send(None); }
+			PendingResponse::Chunk(tx) => { let _ = tx.send(None); }
+		}
+	}
+
+	fn is_canceled(&self) -> bool {
+		match self {
+			PendingResponse::PoV(tx) => tx.is_canceled(),
+			PendingResponse::Chunk(tx) => tx.is_canceled(),
+		}
+	}
+}
+
+type Attempt = BoxFuture<'static, (OutgoingRequest, PeerId, AttemptOutcome)>;
+
+impl OutgoingRequest {
+	fn new(
+		protocol: RequestProtocol,
+		payload: Vec<u8>,
+		peers: Vec<PeerId>,
+		pending_response: PendingResponse,
+	) -> Self {
+		OutgoingRequest {
+			protocol,
+			payload,
+			peers: peers.into_iter(),
+			pending_response,
+		}
+	}
+
+	/// Ask the next peer, giving up if there is none left or the requester lost interest.
+	fn attempt_next(
+		mut self,
+		net: &mut impl Network,
+		compression_peers: &HashSet<PeerId>,
+	) -> Option<Attempt> {
+		if self.pending_response.is_canceled() {
+			return None;
+		}
+
+		let peer = match self.peers.next() {
+			Some(peer) => peer,
+			None => {
+				self.pending_response.give_up();
+				return None;
+			}
+		};
+
+		// Peers understanding compressed PoVs are asked for one.
+		let protocol = match self.protocol {
+			RequestProtocol::PoVFetching if compression_peers.contains(&peer)
+				=> RequestProtocol::CompressedPoVFetching,
+			protocol => protocol,
+		};
+
+		let response = net.start_request(peer.clone(), protocol, self.payload.clone());
+		let timeout = futures_timer::Delay::new(protocol.request_timeout());
+
+		Some(async move {
+			let outcome = match future::select(response, timeout).await {
+				future::Either::Left((Ok(response), _)) => AttemptOutcome::Response(response),
+				future::Either::Left((Err(RequestFailure), _)) => AttemptOutcome::Failed,
+				future::Either::Right(_) => AttemptOutcome::TimedOut,
+			};
+
+			(self, peer, outcome)
+		}.boxed())
+	}
+}
+
+/// Handle what became of asking `peer`. Returns the request if it is to be sent to the next
+/// peer.
+async fn handle_attempt_outcome(
+	net: &mut impl Network,
+	mut request: OutgoingRequest,
+	peer: PeerId,
+	outcome: AttemptOutcome,
+) -> SubsystemResult<Option<OutgoingRequest>> {
+	let response = match outcome {
+		AttemptOutcome::Response(response) => response,
+		AttemptOutcome::Failed | AttemptOutcome::TimedOut => {
+			log::debug!(
+				target: TARGET,
+				"Peer {:?} didn't answer our request on {}: {:?}",
+				peer,
+				request.protocol.name(),
+				outcome,
+			);
+
+			return Ok(Some(request));
+		}
+	};
+
+	if response.len() as u64 > request.protocol.max_response_size() {
+		net.report_peer(peer, OVERSIZED_RESPONSE_COST).await?;
+		return Ok(Some(request));
+	}
+
+	match request.pending_response.answer(&peer, &response) {
+		DecodedResponse::Answered => Ok(None),
+		DecodedResponse::NotFound(pending_response) => {
+			request.pending_response = pending_response;
+			Ok(Some(request))
+		}
+		DecodedResponse::Malformed(pending_response) => {
+			net.report_peer(peer, MALFORMED_RESPONSE_COST).await?;
+			request.pending_response = pending_response;
+			Ok(Some(request))
+		}
+	}
+}
+
+/// Decode a request from a peer and hand it to the subsystem serving it.
+async fn dispatch_incoming_request(
+	net: &mut impl Network,
+	ctx: &mut impl SubsystemContext<Message=NetworkBridgeMessage>,
+	request: NetworkRequest,
+) -> SubsystemResult<()> {
+	let NetworkRequest { peer, protocol, payload, pending_response } = request;
+
+	if payload.len() as u64 > MAX_REQUEST_SIZE {
+		return net.report_peer(peer, MALFORMED_REQUEST_COST).await;
+	}
+
+	let message = match protocol {
+		RequestProtocol::PoVFetching | RequestProtocol::CompressedPoVFetching
+			=> protocol_v1::PoVFetchingRequest::decode(&mut &payload[..])
+				.map(|request| AllMessages::PoVDistribution(PoVDistributionMessage::PoVFetchingRequest(
+					IncomingRequest::new(peer.clone(), protocol, request, pending_response),
+				))),
+		RequestProtocol::ChunkFetching => protocol_v1::ChunkFetchingRequest::decode(&mut &payload[..])
+			.map(|request| AllMessages::AvailabilityDistribution(
+				AvailabilityDistributionMessage::ChunkFetchingRequest(
+					IncomingRequest::new(peer.clone(), protocol, request, pending_response),
+				)
+			)),
+	};
+
+	match message {
+		Ok(message) => ctx.send_message(message).await,
+		Err(_) => net.report_peer(peer, MALFORMED_REQUEST_COST).await,
+	}
+}
+
 fn construct_view(live_heads: &[Hash]) -> View {
 	View(live_heads.iter().rev().take(MAX_VIEW_HEADS).cloned().collect())
 }
@@ -342,12 +920,14 @@ async fn update_view(
 	send_validation_message(
 		net,
 		validation_peers.keys().cloned(),
+		validation_peers,
 		WireMessage::ViewUpdate(new_view.clone()),
 	).await?;
 
 	send_collation_message(
 		net,
 		collation_peers.keys().cloned(),
+		collation_peers,
 		WireMessage::ViewUpdate(new_view.clone()),
 	).await?;
 
@@ -418,64 +998,125 @@ async fn handle_peer_messages<M>(
 	Ok(outgoing_messages)
 }
 
+/// Send a protocol message to peers, decompressing the PoVs it carries for the peers which don't
+/// understand compressed ones.
+async fn send_protocol_message<M>(
+	net: &mut impl Network,
+	peers: Vec<PeerId>,
+	peer_set: PeerSet,
+	peer_data: &HashMap<PeerId, PeerData>,
+	message: M,
+	compression_peers: &HashSet<PeerId>,
+) -> SubsystemResult<()>
+	where M: MaybeCompressed + VersionedMessage,
+{
+	if !message.has_compressed_pov() {
+		return send_message(net, peers, peer_set, peer_data, WireMessage::ProtocolMessage(message)).await;
+	}
+
+	let (peers, legacy_peers): (Vec<_>, Vec<_>) = peers.into_iter()
+		.partition(|peer| compression_peers.contains(peer));
+
+	if !legacy_peers.is_empty() {
+		match message.clone().decompressed() {
+			Ok(legacy_message) => send_message(
+				net,
+				legacy_peers,
+				peer_set,
+				peer_data,
+				WireMessage::ProtocolMessage(legacy_message),
+			).await?,
+			Err(e) => log::warn!(
+				target: TARGET,
+				"Not sending a message with an invalid compressed PoV: {:?}",
+				e,
+			),
+		}
+	}
+
+	send_message(net, peers, peer_set, peer_data, WireMessage::ProtocolMessage(message)).await
+}
+
 async fn send_validation_message<I>(
 	net: &mut impl Network,
 	peers: I,
+	peer_data: &HashMap<PeerId, PeerData>,
 	message: WireMessage<protocol_v1::ValidationProtocol>,
 ) -> SubsystemResult<()>
-	where
-		I: IntoIterator<Item=PeerId>,
-		I::IntoIter: ExactSizeIterator,
+	where I: IntoIterator<Item=PeerId>,
 {
-	send_message(net, peers, PeerSet::Validation, message).await
+	send_message(net, peers, PeerSet::Validation, peer_data, message).await
 }
 
 async fn send_collation_message<I>(
 	net: &mut impl Network,
 	peers: I,
+	peer_data: &HashMap<PeerId, PeerData>,
 	message: WireMessage<protocol_v1::CollationProtocol>,
 ) -> SubsystemResult<()>
-	where
-	I: IntoIterator<Item=PeerId>,
-	I::IntoIter: ExactSizeIterator,
+	where I: IntoIterator<Item=PeerId>,
 {
-	send_message(net, peers, PeerSet::Collation, message).await
+	send_message(net, peers, PeerSet::Collation, peer_data, message).await
 }
 
+/// Send a message to peers, each in the protocol version negotiated with it.
 async fn send_message<M, I>(
 	net: &mut impl Network,
 	peers: I,
 	peer_set: PeerSet,
+	peer_data: &HashMap<PeerId, PeerData>,
 	message: WireMessage<M>,
 ) -> SubsystemResult<()>
 	where
-		M: Encode + Clone,
+		M: VersionedMessage,
 		I: IntoIterator<Item=PeerId>,
-		I::IntoIter: ExactSizeIterator,
 {
-	let mut message_producer = stream::iter({
-		let peers = peers.into_iter();
-		let n_peers = peers.len();
-		let mut message = Some(message.encode());
-
-		peers.enumerate().map(move |(i, peer)| {
-			// optimization: avoid cloning the message for the last peer in the
-			// list. The message payload can be quite large. If the underlying
-			// network used `Bytes` this would not be necessary.
-			let message = if i == n_peers - 1 {
-				message.take()
-					.expect("Only taken in last iteration of loop, never afterwards; qed")
-			} else {
-				message.as_ref()
-					.expect("Only taken in last iteration of loop, we are not there yet; qed")
-					.clone()
-			};
+	let mut peers_by_version: BTreeMap<ProtocolVersion, Vec<PeerId>> = BTreeMap::new();
+	for peer in peers {
+		let version = peer_data.get(&peer).map_or(LOWEST_PROTOCOL_VERSION, PeerData::version);
+		peers_by_version.entry(version).or_default().push(peer);
+	}
 
-			Ok(NetworkAction::WriteNotification(peer, peer_set, message))
-		})
-	});
+	for (version, peers) in peers_by_version {
+		let encoded = match message.encode_versioned(version) {
+			Some(encoded) => encoded,
+			None => {
+				log::debug!(
+					target: TARGET,
+					"Not sending a message to {} peers, it has no counterpart in version {} of the {:?} protocol",
+					peers.len(),
+					version,
+					peer_set,
+				);
+				continue
+			}
+		};
+
+		let mut message_producer = stream::iter({
+			let n_peers = peers.len();
+			let mut message = Some(encoded);
+
+			peers.into_iter().enumerate().map(move |(i, peer)| {
+				// optimization: avoid cloning the message for the last peer in the
+				// list. The message payload can be quite large. If the underlying
+				// network used `Bytes` this would not be necessary.
+				let message = if i == n_peers - 1 {
+					message.take()
+						.expect("Only taken in last iteration of loop, never afterwards; qed")
+				} else {
+					message.as_ref()
+						.expect("Only taken in last iteration of loop, we are not there yet; qed")
+						.clone()
+				};
+
+				Ok(NetworkAction::WriteNotification(peer, peer_set, version, message))
+			})
+		});
+
+		net.action_sink().send_all(&mut message_producer).await?;
+	}
 
-	net.action_sink().send_all(&mut message_producer).await
+	Ok(())
 }
 
 async fn dispatch_validation_event_to_all(
@@ -540,11 +1181,57 @@ async fn dispatch_collation_events_to_all<I>(
 	ctx.send_messages(events.into_iter().flat_map(messages_for)).await
 }
 
+/// Look up the addresses of validators and ask the network to connect to them, answering with
+/// the peer IDs of the validators.
+async fn connect_to_validators(
+	net: &mut impl Network,
+	ctx: &mut impl SubsystemContext<Message=NetworkBridgeMessage>,
+	discovery: &mut impl ValidatorDiscovery,
+	connections: &mut ValidatorConnections,
+	peer_set: PeerSet,
+	validators: Vec<ValidatorId>,
+	res: oneshot::Sender<Vec<(ValidatorId, PeerId)>>,
+) -> SubsystemResult<()> {
+	let lookups = validators.iter().map(|validator| discovery.addresses_of(validator)).collect::<Vec<_>>();
+	let resolved = validators.into_iter().zip(future::join_all(lookups).await).collect();
+
+	let (changes, peers) = connections.connect(peer_set, resolved);
+	log::trace!(
+		target: TARGET,
+		"Connecting to {} new addresses of validators on the {:?} peer-set, {} validators are known",
+		changes.connect.len(),
+		peer_set,
+		peers.len(),
+	);
+
+	if !changes.disconnect.is_empty() {
+		net.action_sink().send(NetworkAction::DisconnectFromPeers(peer_set, changes.disconnect)).await?;
+	}
+	if !changes.connect.is_empty() {
+		net.action_sink().send(NetworkAction::ConnectToPeers(peer_set, changes.connect)).await?;
+	}
+
+	if !peers.is_empty() {
+		let event = NetworkBridgeEvent::ValidatorPeers(peers.clone());
+		match peer_set {
+			PeerSet::Validation => dispatch_validation_event_to_all(event, ctx).await?,
+			PeerSet::Collation => dispatch_collation_event_to_all(event, ctx).await?,
+		}
+	}
+
+	let _ = res.send(peers);
+	Ok(())
+}
+
 async fn run_network<N: Network>(
 	mut net: N,
+	mut discovery: impl ValidatorDiscovery,
 	mut ctx: impl SubsystemContext<Message=NetworkBridgeMessage>,
+	incoming_requests: impl Stream<Item = NetworkRequest> + Unpin,
 ) -> SubsystemResult<()> {
 	let mut event_stream = net.event_stream().fuse();
+	let mut incoming_requests = incoming_requests.fuse();
+	let mut attempts: FuturesUnordered<Attempt> = FuturesUnordered::new();
 
 	// Most recent heads are at the back.
 	let mut live_heads: Vec<Hash> = Vec::with_capacity(MAX_VIEW_HEADS);
@@ -552,6 +1239,9 @@ async fn run_network<N: Network>(
 
 	let mut validation_peers: HashMap<PeerId, PeerData> = HashMap::new();
 	let mut collation_peers: HashMap<PeerId, PeerData> = HashMap::new();
+	let mut compression_peers: HashSet<PeerId> = HashSet::new();
+	let mut limiter = Limiter::default();
+	let mut validator_connections = ValidatorConnections::default();
 
 	loop {
 		let action = {
@@ -562,6 +1252,9 @@ async fn run_network<N: Network>(
 			futures::select! {
 				subsystem_msg = subsystem_next => action_from_overseer_message(subsystem_msg),
 				net_event = net_event_next => action_from_network_message(net_event),
+				request = incoming_requests.select_next_some() => Action::IncomingRequest(request),
+				(request, peer, outcome) = attempts.select_next_some()
+					=> Action::RequestAttempted(request, peer, outcome),
 			}
 		};
 
@@ -569,22 +1262,67 @@ async fn run_network<N: Network>(
 			Action::Nop => {}
 			Action::Abort => return Ok(()),
 
-			Action::SendValidationMessage(peers, msg) => send_message(
+			Action::StartRequest(request) => {
+				if let Some(attempt) = request.attempt_next(&mut net, &compression_peers) {
+					attempts.push(attempt);
+				}
+			}
+
+			Action::RequestAttempted(request, peer, outcome) => {
+				let retry = handle_attempt_outcome(&mut net, request, peer, outcome).await?;
+				let attempt = retry.and_then(|request| request.attempt_next(&mut net, &compression_peers));
+				if let Some(attempt) = attempt {
+					attempts.push(attempt);
+				}
+			}
+
+			Action::PeerSupportsCompression(peer, true) => {
+				compression_peers.insert(peer);
+			}
+			Action::PeerSupportsCompression(peer, false) => {
+				compression_peers.remove(&peer);
+			}
+
+			Action::IncomingRequest(request) => {
+				if let Err(e) = dispatch_incoming_request(&mut net, &mut ctx, request).await {
+					log::warn!(target: TARGET, "Aborting - Failure to dispatch messages to overseer");
+					return Err(e)
+				}
+			}
+
+			Action::SendValidationMessage(peers, msg) => send_protocol_message(
 					&mut net,
 					peers,
 					PeerSet::Validation,
-					WireMessage::ProtocolMessage(msg),
+					&validation_peers,
+					msg,
+					&compression_peers,
 			).await?,
 
-			Action::SendCollationMessage(peers, msg) => send_message(
+			Action::SendCollationMessage(peers, msg) => send_protocol_message(
 					&mut net,
 					peers,
 					PeerSet::Collation,
-					WireMessage::ProtocolMessage(msg),
+					&collation_peers,
+					msg,
+					&compression_peers,
 			).await?,
 
-			Action::ConnectToValidators(_peer_set, _validators, _res) => {
-				// TODO: https://github.com/paritytech/polkadot/issues/1461
+			Action::ConnectToValidators(peer_set, validators, res) => connect_to_validators(
+				&mut net,
+				&mut ctx,
+				&mut discovery,
+				&mut validator_connections,
+				peer_set,
+				validators,
+				res,
+			).await?,
+
+			Action::ReleaseValidators(peer_set, validators) => {
+				let addresses = validator_connections.release(peer_set, &validators);
+				if !addresses.is_empty() {
+					net.action_sink().send(NetworkAction::DisconnectFromPeers(peer_set, addresses)).await?;
+				}
 			}
 
 			Action::ReportPeer(peer, rep) => net.report_peer(peer, rep).await?,
@@ -603,17 +1341,29 @@ async fn run_network<N: Network>(
 				).await?;
 			}
 
-			Action::PeerConnected(peer_set, peer, role) => {
+			Action::PeerConnected(peer_set, version, peer, role) => {
 				let peer_map = match peer_set {
 					PeerSet::Validation => &mut validation_peers,
 					PeerSet::Collation => &mut collation_peers,
 				};
 
 				match peer_map.entry(peer.clone()) {
-					HEntry::Occupied(_) => continue,
+					HEntry::Occupied(mut occupied) => {
+						// Another version of a protocol the peer is connected on already, which
+						// subsystems need not know about.
+						occupied.get_mut().versions.insert(version);
+						log::trace!(
+							target: TARGET,
+							"Talking to {} in version {} of the {:?} protocol",
+							peer,
+							occupied.get().version(),
+							peer_set,
+						);
+					}
 					HEntry::Vacant(vacant) => {
 						vacant.insert(PeerData {
 							view: View(Vec::new()),
+							versions: std::iter::once(version).collect(),
 						});
 
 						let res = match peer_set {
@@ -621,7 +1371,7 @@ async fn run_network<N: Network>(
 								vec![
 									NetworkBridgeEvent::PeerConnected(peer.clone(), role),
 									NetworkBridgeEvent::PeerViewChange(
-										peer,
+										peer.clone(),
 										View(Default::default()),
 									),
 								],
@@ -631,7 +1381,7 @@ async fn run_network<N: Network>(
 								vec![
 									NetworkBridgeEvent::PeerConnected(peer.clone(), role),
 									NetworkBridgeEvent::PeerViewChange(
-										peer,
+										peer.clone(),
 										View(Default::default()),
 									),
 								],
@@ -643,16 +1393,47 @@ async fn run_network<N: Network>(
 							log::warn!("Aborting - Failure to dispatch messages to overseer");
 							return Err(e);
 						}
+
+						// The peer only hears of our view when it changes, so tell it about the
+						// current one. An empty view is what it assumes anyway.
+						if !local_view.0.is_empty() {
+							match peer_set {
+								PeerSet::Validation => send_validation_message(
+									&mut net,
+									vec![peer],
+									&validation_peers,
+									WireMessage::ViewUpdate(local_view.clone()),
+								).await?,
+								PeerSet::Collation => send_collation_message(
+									&mut net,
+									vec![peer],
+									&collation_peers,
+									WireMessage::ViewUpdate(local_view.clone()),
+								).await?,
+							}
+						}
 					}
 				}
 			}
-			Action::PeerDisconnected(peer_set, peer) => {
+			Action::PeerDisconnected(peer_set, version, peer) => {
 				let peer_map = match peer_set {
 					PeerSet::Validation => &mut validation_peers,
 					PeerSet::Collation => &mut collation_peers,
 				};
 
-				if peer_map.remove(&peer).is_some() {
+				// The peer stays connected for as long as any version of the protocol is open.
+				let disconnected = match peer_map.get_mut(&peer) {
+					Some(peer_data) => {
+						peer_data.versions.remove(&version);
+						peer_data.versions.is_empty()
+					}
+					None => false,
+				};
+
+				if disconnected {
+					limiter.remove(&peer, peer_set);
+					peer_map.remove(&peer);
+
 					let res = match peer_set {
 						PeerSet::Validation => dispatch_validation_event_to_all(
 							NetworkBridgeEvent::PeerDisconnected(peer),
@@ -674,6 +1455,23 @@ async fn run_network<N: Network>(
 				}
 			},
 			Action::PeerMessages(peer, v_messages, c_messages) => {
+				let v_messages = enforce_limits(
+					&mut net,
+					&mut limiter,
+					&validation_peers,
+					&peer,
+					PeerSet::Validation,
+					v_messages,
+				).await?;
+				let c_messages = enforce_limits(
+					&mut net,
+					&mut limiter,
+					&collation_peers,
+					&peer,
+					PeerSet::Collation,
+					c_messages,
+				).await?;
+
 				if !v_messages.is_empty() {
 					let events = handle_peer_messages(
 						peer.clone(),
@@ -738,6 +1536,15 @@ mod tests {
 	struct TestNetwork {
 		net_events: Arc<Mutex<Option<SingleItemStream<NetworkEvent>>>>,
 		action_tx: mpsc::UnboundedSender<NetworkAction>,
+		request_tx: mpsc::UnboundedSender<TestRequest>,
+	}
+
+	// A request sent by the subsystem, answered by the test.
+	struct TestRequest {
+		peer: PeerId,
+		protocol: RequestProtocol,
+		payload: Vec<u8>,
+		response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
 	}
 
 	// The test's view of the network. This receives updates from the subsystem in the form
@@ -745,32 +1552,60 @@ mod tests {
 	struct TestNetworkHandle {
 		action_rx: mpsc::UnboundedReceiver<NetworkAction>,
 		net_tx: SingleItemSink<NetworkEvent>,
+		request_rx: mpsc::UnboundedReceiver<TestRequest>,
+		incoming_request_tx: mpsc::UnboundedSender<NetworkRequest>,
+		validator_addresses: TestDiscovery,
+	}
+
+	// The addresses of validators known to the subsystem, set by the test.
+	#[derive(Clone, Default)]
+	struct TestDiscovery(Arc<Mutex<HashMap<ValidatorId, Vec<Multiaddr>>>>);
+
+	impl ValidatorDiscovery for TestDiscovery {
+		fn addresses_of(&mut self, validator: &ValidatorId) -> BoxFuture<'static, Vec<Multiaddr>> {
+			future::ready(self.0.lock().get(validator).cloned().unwrap_or_default()).boxed()
+		}
+	}
+
+	fn validator_address(peer: &PeerId) -> Multiaddr {
+		Multiaddr::empty()
+			.with(sc_network::multiaddr::Protocol::Memory(1))
+			.with(sc_network::multiaddr::Protocol::P2p(peer.clone().into()))
 	}
 
 	fn new_test_network() -> (
 		TestNetwork,
 		TestNetworkHandle,
+		TestDiscovery,
+		mpsc::UnboundedReceiver<NetworkRequest>,
 	) {
 		let (net_tx, net_rx) = polkadot_node_subsystem_test_helpers::single_item_sink();
 		let (action_tx, action_rx) = mpsc::unbounded();
+		let (request_tx, request_rx) = mpsc::unbounded();
+		let (incoming_request_tx, incoming_request_rx) = mpsc::unbounded();
+		let discovery = TestDiscovery::default();
 
 		(
 			TestNetwork {
 				net_events: Arc::new(Mutex::new(Some(net_rx))),
 				action_tx,
+				request_tx,
 			},
 			TestNetworkHandle {
 				action_rx,
 				net_tx,
+				request_rx,
+				incoming_request_tx,
+				validator_addresses: discovery.clone(),
 			},
+			discovery,
+			incoming_request_rx,
 		)
 	}
 
-	fn peer_set_engine_id(peer_set: PeerSet) -> ConsensusEngineId {
-		match peer_set {
-			PeerSet::Validation => VALIDATION_PROTOCOL_ID,
-			PeerSet::Collation => COLLATION_PROTOCOL_ID,
-		}
+	// The engine ID of the oldest version of the peer-set's protocol.
+	fn v1_engine_id(peer_set: PeerSet) -> ConsensusEngineId {
+		peer_set_engine_id(peer_set, 1).expect("all peer-sets have a version 1; qed")
 	}
 
 	impl Network for TestNetwork {
@@ -786,7 +1621,16 @@ mod tests {
 		{
 			Box::pin((&mut self.action_tx).sink_map_err(Into::into))
 		}
-	}
+
+		fn start_request(&mut self, who: PeerId, protocol: RequestProtocol, payload: Vec<u8>)
+			-> BoxFuture<'static, Result<Vec<u8>, RequestFailure>>
+		{
+			let (response, response_rx) = oneshot::channel();
+			let _ = self.request_tx.unbounded_send(TestRequest { peer: who, protocol, payload, response });
+
+			response_rx.map(|res| res.unwrap_or(Err(RequestFailure))).boxed()
+		}
+	}
 
 	impl TestNetworkHandle {
 		// Get the next network action.
@@ -807,7 +1651,7 @@ mod tests {
 		async fn connect_peer(&mut self, peer: PeerId, peer_set: PeerSet, role: ObservedRole) {
 			self.send_network_event(NetworkEvent::NotificationStreamOpened {
 				remote: peer,
-				engine_id: peer_set_engine_id(peer_set),
+				engine_id: v1_engine_id(peer_set),
 				role: role.into(),
 			}).await;
 		}
@@ -815,20 +1659,48 @@ mod tests {
 		async fn disconnect_peer(&mut self, peer: PeerId, peer_set: PeerSet) {
 			self.send_network_event(NetworkEvent::NotificationStreamClosed {
 				remote: peer,
-				engine_id: peer_set_engine_id(peer_set),
+				engine_id: v1_engine_id(peer_set),
 			}).await;
 		}
 
 		async fn peer_message(&mut self, peer: PeerId, peer_set: PeerSet, message: Vec<u8>) {
 			self.send_network_event(NetworkEvent::NotificationsReceived {
 				remote: peer,
-				messages: vec![(peer_set_engine_id(peer_set), message.into())],
+				messages: vec![(v1_engine_id(peer_set), message.into())],
 			}).await;
 		}
 
 		async fn send_network_event(&mut self, event: NetworkEvent) {
 			self.net_tx.send(event).await.expect("subsystem concluded early");
 		}
+
+		// Get the next request the subsystem sent to a peer.
+		async fn next_request(&mut self) -> TestRequest {
+			self.request_rx.next().await.expect("subsystem concluded early")
+		}
+
+		// Send a request to the subsystem, returning the receiver of the response.
+		async fn incoming_request(
+			&mut self,
+			peer: PeerId,
+			protocol: RequestProtocol,
+			payload: Vec<u8>,
+		) -> oneshot::Receiver<Vec<u8>> {
+			let (pending_response, response) = oneshot::channel();
+			self.incoming_request_tx.send(NetworkRequest {
+				peer,
+				protocol,
+				payload,
+				pending_response,
+			}).await.expect("subsystem concluded early");
+
+			response
+		}
+
+		// Let the subsystem know the address of a validator.
+		fn discover_validator(&mut self, validator: ValidatorId, addresses: Vec<Multiaddr>) {
+			self.validator_addresses.0.lock().insert(validator, addresses);
+		}
 	}
 
 	// network actions are sensitive to ordering of `PeerId`s within a `HashMap`, so
@@ -844,12 +1716,14 @@ mod tests {
 
 	fn test_harness<T: Future<Output=()>>(test: impl FnOnce(TestHarness) -> T) {
 		let pool = sp_core::testing::TaskExecutor::new();
-		let (network, network_handle) = new_test_network();
+		let (network, network_handle, discovery, incoming_requests) = new_test_network();
 		let (context, virtual_overseer) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
 
 		let network_bridge = run_network(
 			network,
+			discovery,
 			context,
+			incoming_requests,
 		)
 			.map_err(|_| panic!("subsystem execution failed"))
 			.map(|_| ());
@@ -945,6 +1819,7 @@ mod tests {
 				&NetworkAction::WriteNotification(
 					peer_a,
 					PeerSet::Validation,
+					1,
 					wire_message.clone(),
 				),
 			));
@@ -954,12 +1829,40 @@ mod tests {
 				&NetworkAction::WriteNotification(
 					peer_b,
 					PeerSet::Validation,
+					1,
 					wire_message.clone(),
 				),
 			));
 		});
 	}
 
+	#[test]
+	fn peers_connecting_later_learn_our_view() {
+		test_harness(|test_harness| async move {
+			let TestHarness { mut network_handle, mut virtual_overseer } = test_harness;
+
+			let hash_a = Hash::from([1; 32]);
+
+			virtual_overseer.send(
+				FromOverseer::Signal(OverseerSignal::ActiveLeaves(ActiveLeavesUpdate::start_work(hash_a)))
+			).await;
+
+			let peer = PeerId::random();
+
+			network_handle.connect_peer(peer.clone(), PeerSet::Validation, ObservedRole::Full).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::WriteNotification(
+					peer,
+					PeerSet::Validation,
+					1,
+					WireMessage::<protocol_v1::ValidationProtocol>::ViewUpdate(View(vec![hash_a])).encode(),
+				),
+			);
+		});
+	}
+
 	#[test]
 	fn peer_view_updates_sent_via_overseer() {
 		test_harness(|test_harness| async move {
@@ -1070,6 +1973,91 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn oversized_messages_are_dropped() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let peer = PeerId::random();
+
+			network_handle.connect_peer(
+				peer.clone(),
+				PeerSet::Validation,
+				ObservedRole::Full,
+			).await;
+
+			{
+				assert_sends_validation_event_to_all(
+					NetworkBridgeEvent::PeerConnected(peer.clone(), ObservedRole::Full),
+					&mut virtual_overseer,
+				).await;
+
+				assert_sends_validation_event_to_all(
+					NetworkBridgeEvent::PeerViewChange(peer.clone(), View(Default::default())),
+					&mut virtual_overseer,
+				).await;
+			}
+
+			let oversized = protocol_v1::ValidationProtocol::PoVDistribution(
+				protocol_v1::PoVDistributionMessage::Awaiting(
+					[0; 32].into(),
+					vec![[1; 32].into(); 4096],
+				),
+			);
+
+			let pov_distribution_message = protocol_v1::PoVDistributionMessage::Awaiting(
+				[0; 32].into(),
+				vec![[1; 32].into()],
+			);
+
+			network_handle.peer_message(
+				peer.clone(),
+				PeerSet::Validation,
+				WireMessage::ProtocolMessage(oversized).encode(),
+			).await;
+
+			// Too large for any message, so it isn't even decoded.
+			network_handle.peer_message(
+				peer.clone(),
+				PeerSet::Validation,
+				vec![0xff; limits::max_message_size(PeerSet::Validation) + 1],
+			).await;
+
+			network_handle.peer_message(
+				peer.clone(),
+				PeerSet::Validation,
+				WireMessage::ProtocolMessage(
+					protocol_v1::ValidationProtocol::PoVDistribution(pov_distribution_message.clone()),
+				).encode(),
+			).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::ReputationChange(peer.clone(), limits::OVERSIZED_MESSAGE_COST),
+			);
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::ReputationChange(peer.clone(), limits::OVERSIZED_MESSAGE_COST),
+			);
+
+			// Only the message within the limits is passed on.
+			assert_matches!(
+				virtual_overseer.recv().await,
+				AllMessages::PoVDistribution(
+					PoVDistributionMessage::NetworkBridgeUpdateV1(
+						NetworkBridgeEvent::PeerMessage(p, m)
+					)
+				) => {
+					assert_eq!(p, peer);
+					assert_eq!(m, pov_distribution_message);
+				}
+			);
+		});
+	}
+
 	#[test]
 	fn peer_disconnect_from_just_one_peerset() {
 		test_harness(|test_harness| async move {
@@ -1133,6 +2121,7 @@ mod tests {
 				&NetworkAction::WriteNotification(
 					peer.clone(),
 					PeerSet::Collation,
+					1,
 					wire_message.clone(),
 				),
 			));
@@ -1352,6 +2341,7 @@ mod tests {
 					NetworkAction::WriteNotification(
 						peer.clone(),
 						PeerSet::Validation,
+						1,
 						WireMessage::ProtocolMessage(message).encode(),
 					)
 				);
@@ -1380,10 +2370,414 @@ mod tests {
 					NetworkAction::WriteNotification(
 						peer.clone(),
 						PeerSet::Collation,
+						1,
 						WireMessage::ProtocolMessage(message).encode(),
 					)
 				);
 			}
 		});
 	}
+
+	#[test]
+	fn fetch_pov_asks_peers_in_order() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let peer_a = PeerId::random();
+			let peer_b = PeerId::random();
+			let peer_c = PeerId::random();
+
+			let request = protocol_v1::PoVFetchingRequest {
+				relay_parent: [1; 32].into(),
+				pov_hash: [2; 32].into(),
+			};
+			let pov = PoV { block_data: polkadot_primitives::v1::BlockData(vec![1, 2, 3]) };
+
+			let (tx, rx) = oneshot::channel();
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::FetchPoV(
+					vec![peer_a.clone(), peer_b.clone(), peer_c.clone()],
+					request.clone(),
+					tx,
+				),
+			}).await;
+
+			// The first peer doesn't have it.
+			let attempt = network_handle.next_request().await;
+			assert_eq!(attempt.peer, peer_a);
+			assert_eq!(attempt.protocol, RequestProtocol::PoVFetching);
+			assert_eq!(attempt.payload, request.encode());
+			attempt.response.send(Ok(protocol_v1::PoVFetchingResponse::NoSuchPoV.encode())).unwrap();
+
+			// The second peer answers with garbage.
+			let attempt = network_handle.next_request().await;
+			assert_eq!(attempt.peer, peer_b);
+			attempt.response.send(Ok(vec![42; 3])).unwrap();
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::ReputationChange(peer_b.clone(), MALFORMED_RESPONSE_COST),
+			);
+
+			// The third one has it.
+			let attempt = network_handle.next_request().await;
+			assert_eq!(attempt.peer, peer_c);
+			attempt.response.send(Ok(protocol_v1::PoVFetchingResponse::PoV(pov.clone()).encode())).unwrap();
+
+			assert_eq!(rx.await.unwrap(), Some((peer_c, pov)));
+		});
+	}
+
+	#[test]
+	fn fetch_chunk_gives_up_once_peers_are_exhausted() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let peer_a = PeerId::random();
+			let peer_b = PeerId::random();
+
+			let request = protocol_v1::ChunkFetchingRequest {
+				candidate_hash: [1; 32].into(),
+				index: 3,
+			};
+
+			let (tx, rx) = oneshot::channel();
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::FetchChunk(
+					vec![peer_a.clone(), peer_b.clone()],
+					request,
+					tx,
+				),
+			}).await;
+
+			let attempt = network_handle.next_request().await;
+			assert_eq!(attempt.peer, peer_a);
+			assert_eq!(attempt.protocol, RequestProtocol::ChunkFetching);
+			attempt.response.send(Err(RequestFailure)).unwrap();
+
+			let attempt = network_handle.next_request().await;
+			assert_eq!(attempt.peer, peer_b);
+			attempt.response.send(Ok(protocol_v1::ChunkFetchingResponse::NoSuchChunk.encode())).unwrap();
+
+			assert_eq!(rx.await.unwrap(), None);
+		});
+	}
+
+	#[test]
+	fn incoming_requests_are_dispatched() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let peer = PeerId::random();
+			let request = protocol_v1::PoVFetchingRequest {
+				relay_parent: [1; 32].into(),
+				pov_hash: [2; 32].into(),
+			};
+
+			let response = network_handle.incoming_request(
+				peer.clone(),
+				RequestProtocol::PoVFetching,
+				request.encode(),
+			).await;
+
+			assert_matches!(
+				virtual_overseer.recv().await,
+				AllMessages::PoVDistribution(PoVDistributionMessage::PoVFetchingRequest(incoming)) => {
+					assert_eq!(incoming.peer, peer);
+					assert_eq!(incoming.payload, request);
+					incoming.send_response(protocol_v1::PoVFetchingResponse::NoSuchPoV).unwrap();
+				}
+			);
+
+			assert_eq!(response.await.unwrap(), protocol_v1::PoVFetchingResponse::NoSuchPoV.encode());
+
+			// Requests which can't be decoded cost the peer.
+			let _ = network_handle.incoming_request(
+				peer.clone(),
+				RequestProtocol::ChunkFetching,
+				vec![1, 2],
+			).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::ReputationChange(peer, MALFORMED_REQUEST_COST),
+			);
+		});
+	}
+
+	#[test]
+	fn compressed_povs_are_decompressed_for_legacy_peers() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let legacy_peer = PeerId::random();
+			let peer = PeerId::random();
+
+			network_handle.send_network_event(NetworkEvent::NotificationStreamOpened {
+				remote: peer.clone(),
+				engine_id: COMPRESSION_PROTOCOL_ID,
+				role: ObservedRole::Full.into(),
+			}).await;
+
+			let pov = PoV { block_data: polkadot_primitives::v1::BlockData(vec![1; 1024]) };
+			let compressed = polkadot_node_primitives::CompressedPoV::compress(&pov).unwrap();
+			let relay_parent = Hash::repeat_byte(1);
+			let pov_hash = pov.hash();
+
+			let message = protocol_v1::ValidationProtocol::PoVDistribution(
+				protocol_v1::PoVDistributionMessage::SendCompressedPoV(relay_parent, pov_hash, compressed),
+			);
+			let legacy_message = protocol_v1::ValidationProtocol::PoVDistribution(
+				protocol_v1::PoVDistributionMessage::SendPoV(relay_parent, pov_hash, pov),
+			);
+
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::SendValidationMessage(
+					vec![legacy_peer.clone(), peer.clone()],
+					message.clone(),
+				),
+			}).await;
+
+			let actions = network_handle.next_network_actions(2).await;
+			assert_eq!(
+				actions,
+				vec![
+					NetworkAction::WriteNotification(
+						legacy_peer,
+						PeerSet::Validation,
+						1,
+						WireMessage::ProtocolMessage(legacy_message).encode(),
+					),
+					NetworkAction::WriteNotification(
+						peer,
+						PeerSet::Validation,
+						1,
+						WireMessage::ProtocolMessage(message).encode(),
+					),
+				],
+			);
+		});
+	}
+
+	#[test]
+	fn peers_are_talked_to_in_the_highest_common_version() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let peer = PeerId::random();
+
+			for engine_id in vec![VALIDATION_PROTOCOL_ID, VALIDATION_PROTOCOL_ID_V2] {
+				network_handle.send_network_event(NetworkEvent::NotificationStreamOpened {
+					remote: peer.clone(),
+					engine_id,
+					role: ObservedRole::Full.into(),
+				}).await;
+			}
+
+			// Subsystems learn about the peer only once.
+			{
+				assert_sends_validation_event_to_all(
+					NetworkBridgeEvent::PeerConnected(peer.clone(), ObservedRole::Full),
+					&mut virtual_overseer,
+				).await;
+
+				assert_sends_validation_event_to_all(
+					NetworkBridgeEvent::PeerViewChange(peer.clone(), View(Default::default())),
+					&mut virtual_overseer,
+				).await;
+			}
+
+			let pov_distribution_message = protocol_v1::PoVDistributionMessage::Awaiting(
+				[0; 32].into(),
+				vec![[1; 32].into()],
+			);
+
+			let message = protocol_v1::ValidationProtocol::PoVDistribution(
+				pov_distribution_message.clone(),
+			);
+
+			// Messages from the peer on v2 reach subsystems as v1 messages.
+			network_handle.send_network_event(NetworkEvent::NotificationsReceived {
+				remote: peer.clone(),
+				messages: vec![(
+					VALIDATION_PROTOCOL_ID_V2,
+					WireMessage::ProtocolMessage(
+						polkadot_node_network_protocol::v2::ValidationProtocol::from(message.clone()),
+					).encode().into(),
+				)],
+			}).await;
+
+			assert_matches!(
+				virtual_overseer.recv().await,
+				AllMessages::PoVDistribution(
+					PoVDistributionMessage::NetworkBridgeUpdateV1(
+						NetworkBridgeEvent::PeerMessage(p, m)
+					)
+				) => {
+					assert_eq!(p, peer);
+					assert_eq!(m, pov_distribution_message);
+				}
+			);
+
+			// Messages to the peer are sent on v2.
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::SendValidationMessage(vec![peer.clone()], message.clone()),
+			}).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::WriteNotification(
+					peer.clone(),
+					PeerSet::Validation,
+					2,
+					WireMessage::ProtocolMessage(
+						polkadot_node_network_protocol::v2::ValidationProtocol::from(message.clone()),
+					).encode(),
+				),
+			);
+
+			// Closing v2 falls back to v1 without disconnecting the peer.
+			network_handle.send_network_event(NetworkEvent::NotificationStreamClosed {
+				remote: peer.clone(),
+				engine_id: VALIDATION_PROTOCOL_ID_V2,
+			}).await;
+
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::SendValidationMessage(vec![peer.clone()], message.clone()),
+			}).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::WriteNotification(
+					peer.clone(),
+					PeerSet::Validation,
+					1,
+					WireMessage::ProtocolMessage(message).encode(),
+				),
+			);
+
+			network_handle.disconnect_peer(peer.clone(), PeerSet::Validation).await;
+
+			assert_sends_validation_event_to_all(
+				NetworkBridgeEvent::PeerDisconnected(peer),
+				&mut virtual_overseer,
+			).await;
+		});
+	}
+
+	#[test]
+	fn validators_are_connected_until_released() {
+		test_harness(|test_harness| async move {
+			let TestHarness {
+				mut network_handle,
+				mut virtual_overseer,
+			} = test_harness;
+
+			let alice: ValidatorId = Sr25519Keyring::Alice.public().into();
+			let bob: ValidatorId = Sr25519Keyring::Bob.public().into();
+			let charlie: ValidatorId = Sr25519Keyring::Charlie.public().into();
+			let alice_peer = PeerId::random();
+			let bob_peer = PeerId::random();
+
+			network_handle.discover_validator(alice.clone(), vec![validator_address(&alice_peer)]);
+			network_handle.discover_validator(bob.clone(), vec![validator_address(&bob_peer)]);
+
+			let connect = |validators: Vec<ValidatorId>| {
+				let (tx, rx) = oneshot::channel();
+				let msg = NetworkBridgeMessage::ConnectToValidators(PeerSet::Validation, validators, tx);
+				(FromOverseer::Communication { msg }, rx)
+			};
+
+			// Charlie's addresses are unknown, so nothing can be done about him.
+			let (msg, peers) = connect(vec![alice.clone(), bob.clone(), charlie.clone()]);
+			virtual_overseer.send(msg).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::ConnectToPeers(
+					PeerSet::Validation,
+					vec![validator_address(&alice_peer), validator_address(&bob_peer)],
+				),
+			);
+			assert_sends_validation_event_to_all(
+				NetworkBridgeEvent::ValidatorPeers(
+					vec![(alice.clone(), alice_peer.clone()), (bob.clone(), bob_peer.clone())],
+				),
+				&mut virtual_overseer,
+			).await;
+			assert_eq!(
+				peers.await.unwrap(),
+				vec![(alice.clone(), alice_peer.clone()), (bob.clone(), bob_peer.clone())],
+			);
+
+			// Asking again doesn't lead to new connections.
+			let (msg, peers) = connect(vec![alice.clone()]);
+			virtual_overseer.send(msg).await;
+			assert_sends_validation_event_to_all(
+				NetworkBridgeEvent::ValidatorPeers(vec![(alice.clone(), alice_peer.clone())]),
+				&mut virtual_overseer,
+			).await;
+			assert_eq!(peers.await.unwrap(), vec![(alice.clone(), alice_peer.clone())]);
+
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::ReleaseValidators(PeerSet::Validation, vec![alice, charlie]),
+			}).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::DisconnectFromPeers(PeerSet::Validation, vec![validator_address(&alice_peer)]),
+			);
+
+			// Bob is still connected to, and can be released on his own.
+			virtual_overseer.send(FromOverseer::Communication {
+				msg: NetworkBridgeMessage::ReleaseValidators(PeerSet::Validation, vec![bob]),
+			}).await;
+
+			assert_eq!(
+				network_handle.next_network_action().await,
+				NetworkAction::DisconnectFromPeers(PeerSet::Validation, vec![validator_address(&bob_peer)]),
+			);
+		});
+	}
+
+	#[test]
+	fn unconnected_peers_get_no_budget() {
+		let (mut network, mut network_handle, _, _) = new_test_network();
+		let mut limiter = Limiter::default();
+		let peer = PeerId::random();
+
+		let message = WireMessage::<protocol_v1::ValidationProtocol>::ViewUpdate(View(Vec::new()));
+		let size = message.encode().len();
+
+		let accepted = executor::block_on(enforce_limits(
+			&mut network,
+			&mut limiter,
+			&HashMap::new(),
+			&peer,
+			PeerSet::Validation,
+			vec![(Some(message), size)],
+		)).unwrap();
+
+		assert!(accepted.is_empty());
+		assert!(limiter.is_empty());
+		assert_eq!(
+			executor::block_on(network_handle.next_network_actions(1)),
+			vec![NetworkAction::ReputationChange(peer, UNCONNECTED_PEERSET_COST)],
+		);
+	}
 }
@@ -0,0 +1,447 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits on what peers may send us, enforced before any message reaches a subsystem.
+//!
+//! Every message is bounded in size by its variant, and every peer has a budget of messages and
+//! bytes per peer-set which refills over time. Messages exceeding either are dropped and cost the
+//! peer reputation. A peer doing so repeatedly is disconnected. Messages larger than any message of
+//! their peer-set are dropped without being decoded at all.
+//!
+//! On top of that, all peers together have a budget per peer-set, so that many peers staying within
+//! their own budgets can't flood us either. Messages exceeding it are dropped without punishing the
+//! peer.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use polkadot_node_network_protocol::{PeerId, PeerSet, ReputationChange, v1 as protocol_v1};
+use polkadot_node_primitives::MAX_POV_SIZE;
+
+use super::WireMessage;
+
+pub(crate) const OVERSIZED_MESSAGE_COST: ReputationChange
+	= ReputationChange::new(-500, "Message exceeding the size limit");
+pub(crate) const APPARENT_FLOOD_COST: ReputationChange
+	= ReputationChange::new(-500, "Peer appears to be flooding us with messages");
+pub(crate) const REPEATED_VIOLATIONS_COST: ReputationChange
+	= ReputationChange::new_fatal("Peer repeatedly exceeded the message limits");
+
+/// The number of messages a peer may have dropped for exceeding the limits on a peer-set,
+/// before it is disconnected.
+pub(crate) const MAX_VIOLATIONS: u32 = 10;
+
+/// The slack on top of a PoV for the rest of a message carrying it.
+const POV_MESSAGE_OVERHEAD: usize = 16 * 1024;
+
+/// The maximum size of any message of a fixed or small size, such as views or advertisements.
+const MAX_SMALL_MESSAGE_SIZE: usize = 1024;
+
+/// The maximum size of a bitfield message. Enough for a few tens of thousands of cores.
+const MAX_BITFIELD_MESSAGE_SIZE: usize = 16 * 1024;
+
+/// The maximum size of a message announcing the awaited PoVs. Enough for a couple of thousand
+/// hashes.
+const MAX_AWAITING_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// The maximum size of a message carrying a PoV or a piece of it.
+const MAX_POV_MESSAGE_SIZE: usize = MAX_POV_SIZE + POV_MESSAGE_OVERHEAD;
+
+/// The maximum size of a statement. Seconded statements carry the commitments of the candidate,
+/// which may include a new validation code.
+const MAX_STATEMENT_MESSAGE_SIZE: usize = MAX_POV_SIZE;
+
+/// The maximum size of any message on a peer-set. Larger messages aren't even decoded.
+pub(crate) fn max_message_size(peer_set: PeerSet) -> usize {
+	match peer_set {
+		PeerSet::Validation => MAX_POV_MESSAGE_SIZE,
+		PeerSet::Collation => MAX_POV_MESSAGE_SIZE,
+	}
+}
+
+/// Messages with an upper bound on their encoded size.
+pub(crate) trait MaxEncodedSize {
+	/// The maximum size this message may have when encoded.
+	fn max_encoded_size(&self) -> usize;
+}
+
+impl MaxEncodedSize for protocol_v1::ValidationProtocol {
+	fn max_encoded_size(&self) -> usize {
+		use protocol_v1::{PoVDistributionMessage, ValidationProtocol};
+
+		match self {
+			ValidationProtocol::AvailabilityDistribution(_) => MAX_POV_MESSAGE_SIZE,
+			ValidationProtocol::BitfieldDistribution(_) => MAX_BITFIELD_MESSAGE_SIZE,
+			ValidationProtocol::PoVDistribution(PoVDistributionMessage::Awaiting(..))
+				=> MAX_AWAITING_MESSAGE_SIZE,
+			ValidationProtocol::PoVDistribution(_) => MAX_POV_MESSAGE_SIZE,
+			ValidationProtocol::StatementDistribution(_) => MAX_STATEMENT_MESSAGE_SIZE,
+		}
+	}
+}
+
+impl MaxEncodedSize for protocol_v1::CollationProtocol {
+	fn max_encoded_size(&self) -> usize {
+		use protocol_v1::{CollationProtocol, CollatorProtocolMessage};
+
+		match self {
+			CollationProtocol::CollatorProtocol(CollatorProtocolMessage::Collation(..))
+				| CollationProtocol::CollatorProtocol(CollatorProtocolMessage::CompressedCollation(..))
+				=> MAX_POV_MESSAGE_SIZE,
+			CollationProtocol::CollatorProtocol(_) => MAX_SMALL_MESSAGE_SIZE,
+		}
+	}
+}
+
+impl<M: MaxEncodedSize> MaxEncodedSize for WireMessage<M> {
+	fn max_encoded_size(&self) -> usize {
+		match self {
+			WireMessage::ViewUpdate(_) => MAX_SMALL_MESSAGE_SIZE,
+			WireMessage::ProtocolMessage(message) => message.max_encoded_size(),
+		}
+	}
+}
+
+/// How much a peer may send on a peer-set.
+#[derive(Debug, Clone, Copy)]
+struct RateLimit {
+	/// The number of messages a peer may send at once.
+	message_burst: u64,
+	/// The number of messages a peer may send per second, sustained.
+	messages_per_second: u64,
+	/// The number of bytes a peer may send at once.
+	byte_burst: u64,
+	/// The number of bytes a peer may send per second, sustained.
+	bytes_per_second: u64,
+}
+
+fn rate_limit(peer_set: PeerSet) -> RateLimit {
+	match peer_set {
+		PeerSet::Validation => RateLimit {
+			message_burst: 2000,
+			messages_per_second: 500,
+			byte_burst: 4 * MAX_POV_MESSAGE_SIZE as u64,
+			bytes_per_second: MAX_POV_MESSAGE_SIZE as u64,
+		},
+		PeerSet::Collation => RateLimit {
+			message_burst: 100,
+			messages_per_second: 20,
+			byte_burst: 2 * MAX_POV_MESSAGE_SIZE as u64,
+			bytes_per_second: MAX_POV_MESSAGE_SIZE as u64 / 2,
+		},
+	}
+}
+
+/// How much all peers together may send on a peer-set.
+fn total_rate_limit(peer_set: PeerSet) -> RateLimit {
+	match peer_set {
+		PeerSet::Validation => RateLimit {
+			message_burst: 20_000,
+			messages_per_second: 5000,
+			byte_burst: 16 * MAX_POV_MESSAGE_SIZE as u64,
+			bytes_per_second: 4 * MAX_POV_MESSAGE_SIZE as u64,
+		},
+		PeerSet::Collation => RateLimit {
+			message_burst: 1000,
+			messages_per_second: 200,
+			byte_burst: 8 * MAX_POV_MESSAGE_SIZE as u64,
+			bytes_per_second: 2 * MAX_POV_MESSAGE_SIZE as u64,
+		},
+	}
+}
+
+/// A bucket of tokens which refills at a constant rate, up to its capacity.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+	capacity: f64,
+	tokens: f64,
+	refill_per_second: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(capacity: u64, refill_per_second: u64, now: Instant) -> Self {
+		TokenBucket {
+			capacity: capacity as f64,
+			tokens: capacity as f64,
+			refill_per_second: refill_per_second as f64,
+			last_refill: now,
+		}
+	}
+
+	/// Take `amount` tokens out of the bucket, if there are enough.
+	fn try_take(&mut self, amount: u64, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+		self.last_refill = now;
+
+		if self.tokens >= amount as f64 {
+			self.tokens -= amount as f64;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+/// A budget of messages and bytes.
+#[derive(Debug, Clone)]
+struct Budget {
+	messages: TokenBucket,
+	bytes: TokenBucket,
+}
+
+impl Budget {
+	fn new(limit: RateLimit, now: Instant) -> Self {
+		Budget {
+			messages: TokenBucket::new(limit.message_burst, limit.messages_per_second, now),
+			bytes: TokenBucket::new(limit.byte_burst, limit.bytes_per_second, now),
+		}
+	}
+
+	/// Charge a message of `size` bytes to the budget, if it is within it.
+	fn try_take(&mut self, size: usize, now: Instant) -> bool {
+		self.messages.try_take(1, now) && self.bytes.try_take(size as u64, now)
+	}
+}
+
+/// The budget of a single peer on a single peer-set.
+#[derive(Debug, Clone)]
+struct PeerBudget {
+	budget: Budget,
+	violations: u32,
+}
+
+impl PeerBudget {
+	fn new(limit: RateLimit, now: Instant) -> Self {
+		PeerBudget {
+			budget: Budget::new(limit, now),
+			violations: 0,
+		}
+	}
+}
+
+/// Whether a message is passed on to the subsystems.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Verdict {
+	/// The message is within the limits.
+	Accept,
+	/// The message is dropped and the peer is to be punished.
+	Reject(ReputationChange),
+	/// The message is dropped, as all peers together exceed the limits of the peer-set.
+	Drop,
+}
+
+/// The budgets of all peers, both of each one and of all of them together.
+#[derive(Default)]
+pub(crate) struct Limiter {
+	budgets: HashMap<(PeerId, PeerSet), PeerBudget>,
+	totals: HashMap<PeerSet, Budget>,
+}
+
+impl Limiter {
+	/// Check a message of `size` encoded bytes, which may be at most `max_size` bytes, received from
+	/// `peer` on `peer_set` at the time `now` against the limits, charging it to the budget of the
+	/// peer and of all peers.
+	pub(crate) fn check(
+		&mut self,
+		peer: &PeerId,
+		peer_set: PeerSet,
+		max_size: usize,
+		size: usize,
+		now: Instant,
+	) -> Verdict {
+		let budget = self.budgets
+			.entry((peer.clone(), peer_set))
+			.or_insert_with(|| PeerBudget::new(rate_limit(peer_set), now));
+
+		let cost = if size > max_size {
+			OVERSIZED_MESSAGE_COST
+		} else if !budget.budget.try_take(size, now) {
+			APPARENT_FLOOD_COST
+		} else {
+			// Only messages within the budget of the peer count towards the total, so that a
+			// flooding peer can't use up the budget of everyone else.
+			let total = self.totals
+				.entry(peer_set)
+				.or_insert_with(|| Budget::new(total_rate_limit(peer_set), now));
+
+			return if total.try_take(size, now) {
+				Verdict::Accept
+			} else {
+				Verdict::Drop
+			};
+		};
+
+		budget.violations += 1;
+		if budget.violations >= MAX_VIOLATIONS {
+			Verdict::Reject(REPEATED_VIOLATIONS_COST)
+		} else {
+			Verdict::Reject(cost)
+		}
+	}
+
+	/// Forget the budget of a peer which has disconnected from a peer-set.
+	pub(crate) fn remove(&mut self, peer: &PeerId, peer_set: PeerSet) {
+		self.budgets.remove(&(peer.clone(), peer_set));
+	}
+
+	/// Whether no peer has a budget.
+	#[cfg(test)]
+	pub(crate) fn is_empty(&self) -> bool {
+		self.budgets.is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+	use polkadot_primitives::v1::Hash;
+
+	fn advertisement() -> protocol_v1::CollationProtocol {
+		protocol_v1::CollationProtocol::CollatorProtocol(
+			protocol_v1::CollatorProtocolMessage::AdvertiseCollation(Hash::repeat_byte(1), 1.into()),
+		)
+	}
+
+	const ADVERTISEMENT_SIZE: usize = MAX_SMALL_MESSAGE_SIZE;
+
+	#[test]
+	fn advertisements_are_small_messages() {
+		assert_eq!(advertisement().max_encoded_size(), ADVERTISEMENT_SIZE);
+	}
+
+	#[test]
+	fn token_bucket_refills_up_to_capacity() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(10, 5, start);
+
+		assert!(bucket.try_take(10, start));
+		assert!(!bucket.try_take(1, start));
+
+		assert!(bucket.try_take(5, start + Duration::from_secs(1)));
+		assert!(!bucket.try_take(1, start + Duration::from_secs(1)));
+
+		assert!(bucket.try_take(10, start + Duration::from_secs(100)));
+		assert!(!bucket.try_take(1, start + Duration::from_secs(100)));
+	}
+
+	#[test]
+	fn oversized_messages_are_rejected() {
+		let mut limiter = Limiter::default();
+		let peer = PeerId::random();
+		let now = Instant::now();
+
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, MAX_SMALL_MESSAGE_SIZE, now),
+			Verdict::Accept,
+		);
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, MAX_SMALL_MESSAGE_SIZE + 1, now),
+			Verdict::Reject(OVERSIZED_MESSAGE_COST),
+		);
+	}
+
+	#[test]
+	fn floods_are_rejected_per_peer_and_peer_set() {
+		let mut limiter = Limiter::default();
+		let peer = PeerId::random();
+		let other_peer = PeerId::random();
+		let now = Instant::now();
+		let burst = rate_limit(PeerSet::Collation).message_burst;
+
+		for _ in 0..burst {
+			assert_eq!(limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, 64, now), Verdict::Accept);
+		}
+
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, 64, now),
+			Verdict::Reject(APPARENT_FLOOD_COST),
+		);
+
+		// Other peers and peer-sets have budgets of their own.
+		assert_eq!(limiter.check(&other_peer, PeerSet::Collation, ADVERTISEMENT_SIZE, 64, now), Verdict::Accept);
+		assert_eq!(limiter.check(&peer, PeerSet::Validation, ADVERTISEMENT_SIZE, 64, now), Verdict::Accept);
+
+		// The budget refills over time.
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, 64, now + Duration::from_secs(1)),
+			Verdict::Accept,
+		);
+	}
+
+	#[test]
+	fn repeated_violations_disconnect() {
+		let mut limiter = Limiter::default();
+		let peer = PeerId::random();
+		let now = Instant::now();
+
+		for _ in 1..MAX_VIOLATIONS {
+			assert_eq!(
+				limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, usize::max_value(), now),
+				Verdict::Reject(OVERSIZED_MESSAGE_COST),
+			);
+		}
+
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, usize::max_value(), now),
+			Verdict::Reject(REPEATED_VIOLATIONS_COST),
+		);
+
+		// The count starts over once the peer reconnects.
+		limiter.remove(&peer, PeerSet::Collation);
+		assert_eq!(
+			limiter.check(&peer, PeerSet::Collation, ADVERTISEMENT_SIZE, usize::max_value(), now),
+			Verdict::Reject(OVERSIZED_MESSAGE_COST),
+		);
+	}
+
+	#[test]
+	fn floods_from_all_peers_together_are_dropped() {
+		let mut limiter = Limiter::default();
+		let now = Instant::now();
+		let limit = rate_limit(PeerSet::Collation);
+		let total = total_rate_limit(PeerSet::Collation);
+
+		// Enough peers staying within their own budgets to exhaust the total one.
+		let peers: Vec<PeerId> = (0..(total.message_burst / limit.message_burst) + 1)
+			.map(|_| PeerId::random())
+			.collect();
+
+		let mut accepted = 0;
+		for peer in &peers {
+			for _ in 0..limit.message_burst {
+				match limiter.check(peer, PeerSet::Collation, ADVERTISEMENT_SIZE, 64, now) {
+					Verdict::Accept => accepted += 1,
+					verdict => assert_eq!(verdict, Verdict::Drop),
+				}
+			}
+		}
+
+		assert_eq!(accepted, total.message_burst);
+
+		// Peers are not punished for that.
+		let last_peer = peers.last().unwrap();
+		assert_eq!(limiter.budgets[&(last_peer.clone(), PeerSet::Collation)].violations, 0);
+
+		// Other peer-sets have a total budget of their own.
+		assert_eq!(
+			limiter.check(last_peer, PeerSet::Validation, ADVERTISEMENT_SIZE, 64, now),
+			Verdict::Accept,
+		);
+	}
+}
@@ -0,0 +1,190 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keeping up connections to validators on request of the subsystems.
+//!
+//! The addresses of a validator are looked up when a subsystem asks to connect to it, and the
+//! network is asked to keep up connections to them until the validator is released again.
+//! Asking to connect to a validator twice is the same as asking once, so a single release is
+//! enough to drop the connections. Each set of validators is therefore connected to by a single
+//! subsystem, such as the gossip support subsystem for the validators of a session, and all
+//! subsystems of the peer-set are told about the peers the validators were found at.
+
+use std::collections::{HashMap, HashSet};
+
+use futures::future::{BoxFuture, FutureExt};
+use sc_network::{config::parse_addr, Multiaddr};
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_core::sr25519;
+
+use polkadot_node_network_protocol::{PeerId, PeerSet};
+use polkadot_primitives::v1::ValidatorId;
+
+/// Finds out the addresses validators can be reached at.
+pub trait ValidatorDiscovery: Send + 'static {
+	/// The known addresses of a validator, each ending in the peer ID of the validator. Empty if
+	/// none are known (yet).
+	fn addresses_of(&mut self, validator: &ValidatorId) -> BoxFuture<'static, Vec<Multiaddr>>;
+}
+
+/// Looks up validators on the DHT, under the authority discovery key sharing the public key of
+/// their validator key.
+impl ValidatorDiscovery for sc_authority_discovery::Service {
+	fn addresses_of(&mut self, validator: &ValidatorId) -> BoxFuture<'static, Vec<Multiaddr>> {
+		let mut service = self.clone();
+		let authority = AuthorityDiscoveryId::from(sr25519::Public::from(validator.clone()));
+
+		async move {
+			service.get_addresses_by_authority_id(authority).await.unwrap_or_default()
+		}.boxed()
+	}
+}
+
+/// What the network has to do after the requested validators changed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ConnectionChanges {
+	/// Addresses to connect to.
+	pub(crate) connect: Vec<Multiaddr>,
+	/// Addresses not to keep up connections to anymore.
+	pub(crate) disconnect: Vec<Multiaddr>,
+}
+
+/// The validators the subsystems asked to stay connected to, and the addresses we asked the
+/// network to keep up connections to on their behalf.
+#[derive(Default)]
+pub(crate) struct ValidatorConnections {
+	requested: HashMap<PeerSet, HashMap<ValidatorId, Vec<(PeerId, Multiaddr)>>>,
+}
+
+impl ValidatorConnections {
+	/// Note the addresses the requested validators were found at.
+	///
+	/// Returns the changes to the connections, and the peer IDs of the validators. Validators
+	/// without any known address keep the addresses of a previous request.
+	pub(crate) fn connect(
+		&mut self,
+		peer_set: PeerSet,
+		resolved: Vec<(ValidatorId, Vec<Multiaddr>)>,
+	) -> (ConnectionChanges, Vec<(ValidatorId, PeerId)>) {
+		let requested = self.requested.entry(peer_set).or_default();
+		let mut changes = ConnectionChanges::default();
+		let mut peers = Vec::new();
+
+		for (validator, addresses) in resolved {
+			let addresses: Vec<_> = addresses.into_iter()
+				.filter_map(|address| match parse_addr(address.clone()) {
+					Ok((peer, _)) => Some((peer, address)),
+					Err(e) => {
+						log::debug!(
+							target: super::TARGET,
+							"Ignoring address {} of validator {:?} without a peer ID: {:?}",
+							address,
+							validator,
+							e,
+						);
+						None
+					}
+				})
+				.collect();
+
+			let known = requested.entry(validator.clone()).or_default();
+			if !addresses.is_empty() {
+				changes.connect.extend(
+					addresses.iter().filter(|a| !known.contains(a)).map(|(_, address)| address.clone())
+				);
+				changes.disconnect.extend(
+					known.iter().filter(|a| !addresses.contains(a)).map(|(_, address)| address.clone())
+				);
+				*known = addresses;
+			}
+
+			let mut validator_peers = HashSet::new();
+			peers.extend(
+				known.iter()
+					.filter(|(peer, _)| validator_peers.insert(peer.clone()))
+					.map(|(peer, _)| (validator.clone(), peer.clone()))
+			);
+		}
+
+		(changes, peers)
+	}
+
+	/// Forget about validators, returning the addresses not to keep up connections to anymore.
+	pub(crate) fn release(&mut self, peer_set: PeerSet, validators: &[ValidatorId]) -> Vec<Multiaddr> {
+		let requested = match self.requested.get_mut(&peer_set) {
+			Some(requested) => requested,
+			None => return Vec::new(),
+		};
+
+		validators.iter()
+			.filter_map(|validator| requested.remove(validator))
+			.flatten()
+			.map(|(_, address)| address)
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_network::multiaddr::Protocol;
+	use sp_keyring::Sr25519Keyring;
+
+	fn address(peer: &PeerId, port: u64) -> Multiaddr {
+		Multiaddr::empty()
+			.with(Protocol::Memory(port))
+			.with(Protocol::P2p(peer.clone().into()))
+	}
+
+	#[test]
+	fn connections_are_kept_until_released() {
+		let alice: ValidatorId = Sr25519Keyring::Alice.public().into();
+		let bob: ValidatorId = Sr25519Keyring::Bob.public().into();
+		let alice_peer = PeerId::random();
+		let bob_peer = PeerId::random();
+
+		let mut connections = ValidatorConnections::default();
+
+		let (changes, peers) = connections.connect(PeerSet::Validation, vec![
+			(alice.clone(), vec![address(&alice_peer, 1)]),
+			(bob.clone(), vec![]),
+		]);
+		assert_eq!(changes.connect, vec![address(&alice_peer, 1)]);
+		assert!(changes.disconnect.is_empty());
+		assert_eq!(peers, vec![(alice.clone(), alice_peer.clone())]);
+
+		// asking again doesn't connect again, but picks up new and changed addresses.
+		let (changes, peers) = connections.connect(PeerSet::Validation, vec![
+			(alice.clone(), vec![address(&alice_peer, 2)]),
+			(bob.clone(), vec![address(&bob_peer, 1)]),
+		]);
+		assert_eq!(changes.connect, vec![address(&alice_peer, 2), address(&bob_peer, 1)]);
+		assert_eq!(changes.disconnect, vec![address(&alice_peer, 1)]);
+		assert_eq!(peers, vec![(alice.clone(), alice_peer.clone()), (bob.clone(), bob_peer.clone())]);
+
+		// addresses which can't be found anymore are kept.
+		let (changes, peers) = connections.connect(PeerSet::Validation, vec![(alice.clone(), vec![])]);
+		assert_eq!(changes, ConnectionChanges::default());
+		assert_eq!(peers, vec![(alice.clone(), alice_peer.clone())]);
+
+		// peer-sets are separate.
+		assert!(connections.release(PeerSet::Collation, &[alice.clone()]).is_empty());
+
+		assert_eq!(connections.release(PeerSet::Validation, &[alice.clone()]), vec![address(&alice_peer, 2)]);
+		assert!(connections.release(PeerSet::Validation, &[alice]).is_empty());
+		assert_eq!(connections.release(PeerSet::Validation, &[bob]), vec![address(&bob_peer, 1)]);
+	}
+}
@@ -37,6 +37,7 @@ use polkadot_node_subsystem_util::{
 	request_validators_ctx,
 	request_validator_groups_ctx,
 };
+use polkadot_node_primitives::CompressedPoV;
 
 #[derive(Default)]
 struct State {
@@ -109,7 +110,7 @@ where
 
 	// Determine which core the para collated-on is assigned to.
 	// If it is not scheduled then ignore the message.
-	let (our_core, num_cores) = match determine_core(ctx, id, relay_parent).await? {
+	let (our_core, num_cores) = match determine_core(ctx, &state.our_id, id, relay_parent).await? {
 	    Some(core) => core,
 	    None => {
 			warn!(
@@ -140,13 +141,35 @@ where
 
 	state.collations.insert(relay_parent, (receipt, pov));
 
+	// Advertise just-in-time to the validators which are already connected and interested in the
+	// relay parent. The others are advertised to as soon as their view includes it.
+	let interested: Vec<PeerId> = state.peer_views.iter()
+		.filter(|(peer, view)| view.contains(&relay_parent) && is_our_validator(state, peer, &relay_parent))
+		.map(|(peer, _)| peer.clone())
+		.collect();
+
+	if !interested.is_empty() {
+		advertise_collation(ctx, state, relay_parent, interested).await?;
+	}
+
 	Ok(())
 }
 
+/// Whether a peer is one of the validators we collate for at the relay parent.
+fn is_our_validator(state: &State, peer: &PeerId, relay_parent: &Hash) -> bool {
+	match (state.known_validators.get(peer), state.our_validators_groups.get(relay_parent)) {
+		(Some(validator), Some(group)) => group.contains(validator),
+		_ => false,
+	}
+}
+
 /// Get the Id of the Core that is assigned to the para being collated on if any
 /// and the total number of cores.
+///
+/// Parathread cores which were claimed by another collator are not ours to collate on.
 async fn determine_core<Context>(
 	ctx: &mut Context,
+	our_id: &CollatorId,
 	para_id: ParaId,
 	relay_parent: Hash,
 ) -> Result<Option<(CoreIndex, usize)>>
@@ -165,8 +188,10 @@ where
 	let cores = rx.await??;
 
 	for (idx, core) in cores.iter().enumerate() {
-		if let CoreState::Scheduled(occupied) = core {
-			if occupied.para_id == para_id {
+		if let CoreState::Scheduled(scheduled) = core {
+			let claimed_by_us = scheduled.collator.as_ref().map_or(true, |c| c == our_id);
+
+			if scheduled.para_id == para_id && claimed_by_us {
 				return Ok(Some(((idx as u32).into(), cores.len())));
 			}
 		}
@@ -375,11 +400,26 @@ async fn send_collation<Context>(
 where
 	Context: SubsystemContext<Message = CollatorProtocolMessage>
 {
-	let wire_message = protocol_v1::CollatorProtocolMessage::Collation(
-		request_id,
-		receipt,
-		pov,
-	);
+	let wire_message = match CompressedPoV::compress(&pov) {
+		Ok(pov) => protocol_v1::CollatorProtocolMessage::CompressedCollation(
+			request_id,
+			receipt,
+			pov,
+		),
+		Err(e) => {
+			warn!(
+				target: TARGET,
+				"Failed to compress PoV for collation response: {:?}",
+				e,
+			);
+
+			protocol_v1::CollatorProtocolMessage::Collation(
+				request_id,
+				receipt,
+				pov,
+			)
+		}
+	};
 
 	ctx.send_message(AllMessages::NetworkBridge(
 		NetworkBridgeMessage::SendCollationMessage(
@@ -440,7 +480,7 @@ where
 				}
 			}
 		}
-	    Collation(_, _, _) => {
+	    Collation(_, _, _) | CompressedCollation(_, _, _) => {
 			warn!(
 				target: TARGET,
 				"Collation message is not expected on the collator side of the protocol",
@@ -468,7 +508,8 @@ where
 	*current = view;
 
 	for added in added.into_iter() {
-		if state.collations.contains_key(&added) {
+		// we only hold collations for relay parents we are scheduled, or claimed the core, at.
+		if state.collations.contains_key(&added) && is_our_validator(state, &peer_id, &added) {
 			advertise_collation(ctx, state, added.clone(), vec![peer_id.clone()]).await?;
 		}
 	}
@@ -525,6 +566,7 @@ where
 		OurViewChange(view) => {
 			handle_our_view_change(state, view).await?;
 		}
+		ValidatorPeers(_) => {}
 	    PeerMessage(remote, msg) => {
 			handle_incoming_peer_message(ctx, state, remote, msg).await?;
 		}
@@ -981,10 +1023,10 @@ mod tests {
 					assert_eq!(to, vec![test_state.validator_peer_id[2].clone()]);
 					assert_matches!(
 						wire_message,
-						protocol_v1::CollatorProtocolMessage::Collation(req_id, receipt, pov) => {
+						protocol_v1::CollatorProtocolMessage::CompressedCollation(req_id, receipt, pov) => {
 							assert_eq!(req_id, request_id);
 							assert_eq!(receipt, candidate);
-							assert_eq!(pov, pov_block);
+							assert_eq!(pov.decompress().unwrap(), pov_block);
 						}
 					);
 				}
@@ -1020,4 +1062,187 @@ mod tests {
 			assert!(overseer_recv_with_timeout(&mut virtual_overseer, TIMEOUT).await.is_none());
 		});
 	}
+
+	// answer the requests for the group of a core and connect to its first validator.
+	async fn connect_to_group(
+		virtual_overseer: &mut test_helpers::TestSubsystemContextHandle<CollatorProtocolMessage>,
+		test_state: &TestState,
+	) {
+		assert_matches!(
+			overseer_recv(virtual_overseer).await,
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+				_,
+				RuntimeApiRequest::ValidatorGroups(tx),
+			)) => {
+				tx.send(Ok(test_state.validator_groups.clone())).unwrap();
+			}
+		);
+
+		assert_matches!(
+			overseer_recv(virtual_overseer).await,
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+				_,
+				RuntimeApiRequest::Validators(tx),
+			)) => {
+				tx.send(Ok(test_state.validator_public.clone())).unwrap();
+			}
+		);
+
+		assert_matches!(
+			overseer_recv(virtual_overseer).await,
+			AllMessages::NetworkBridge(NetworkBridgeMessage::ConnectToValidators(_, _, tx)) => {
+				tx.send(vec![
+					(test_state.validator_public[2].clone(), test_state.validator_peer_id[2].clone()),
+				]).unwrap();
+			}
+		);
+	}
+
+	#[test]
+	fn advertises_parathread_collation_just_in_time_once_claimed() {
+		let test_state = TestState::default();
+		let our_id = test_state.our_collator_pair.public();
+		let other_id = CollatorPair::generate().0.public();
+
+		test_harness(our_id.clone(), |test_harness| async move {
+			let TestHarness {
+				mut virtual_overseer,
+			} = test_harness;
+
+			let para_id = test_state.chain_ids[0];
+			let first = Hash::repeat_byte(0x01);
+			let claimed_by_other = Hash::repeat_byte(0x02);
+			let claimed_by_us = Hash::repeat_byte(0x03);
+			let peer = test_state.validator_peer_id[2].clone();
+
+			let collation = |relay_parent: Hash| {
+				let pov = PoV { block_data: BlockData(vec![relay_parent.as_ref()[0]]) };
+				let candidate = TestCandidateBuilder {
+					para_id,
+					relay_parent,
+					pov_hash: pov.hash(),
+					..Default::default()
+				}.build();
+
+				CollatorProtocolMessage::DistributeCollation(candidate, pov)
+			};
+
+			let parathread_core = |collator: &CollatorId| vec![CoreState::Scheduled(ScheduledCore {
+				para_id,
+				collator: Some(collator.clone()),
+			})];
+
+			overseer_send(&mut virtual_overseer, CollatorProtocolMessage::CollateOn(para_id)).await;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::OurViewChange(View(vec![first, claimed_by_other, claimed_by_us])),
+				),
+			).await;
+
+			overseer_send(&mut virtual_overseer, collation(first)).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+					relay_parent,
+					RuntimeApiRequest::AvailabilityCores(tx),
+				)) => {
+					assert_eq!(relay_parent, first);
+					tx.send(Ok(parathread_core(&our_id))).unwrap();
+				}
+			);
+
+			connect_to_group(&mut virtual_overseer, &test_state).await;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::PeerConnected(peer.clone(), ObservedRole::Authority),
+				),
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendCollationMessage(
+					_,
+					protocol_v1::CollationProtocol::CollatorProtocol(
+						protocol_v1::CollatorProtocolMessage::Declare(_),
+					),
+				))
+			);
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::PeerViewChange(
+						peer.clone(),
+						View(vec![first, claimed_by_other, claimed_by_us]),
+					),
+				),
+			).await;
+
+			// only the collation on the core claimed by us so far is advertised.
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendCollationMessage(
+					to,
+					protocol_v1::CollationProtocol::CollatorProtocol(
+						protocol_v1::CollatorProtocolMessage::AdvertiseCollation(relay_parent, _),
+					),
+				)) => {
+					assert_eq!(to, vec![peer.clone()]);
+					assert_eq!(relay_parent, first);
+				}
+			);
+
+			// a core claimed by another collator is not ours to advertise on.
+			overseer_send(&mut virtual_overseer, collation(claimed_by_other)).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+					relay_parent,
+					RuntimeApiRequest::AvailabilityCores(tx),
+				)) => {
+					assert_eq!(relay_parent, claimed_by_other);
+					tx.send(Ok(parathread_core(&other_id))).unwrap();
+				}
+			);
+
+			// once we claimed the core, the collation is advertised right away to the validators
+			// which are already waiting for it.
+			overseer_send(&mut virtual_overseer, collation(claimed_by_us)).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+					relay_parent,
+					RuntimeApiRequest::AvailabilityCores(tx),
+				)) => {
+					assert_eq!(relay_parent, claimed_by_us);
+					tx.send(Ok(parathread_core(&our_id))).unwrap();
+				}
+			);
+
+			connect_to_group(&mut virtual_overseer, &test_state).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendCollationMessage(
+					to,
+					protocol_v1::CollationProtocol::CollatorProtocol(
+						protocol_v1::CollatorProtocolMessage::AdvertiseCollation(relay_parent, collating_on),
+					),
+				)) => {
+					assert_eq!(to, vec![peer.clone()]);
+					assert_eq!(relay_parent, claimed_by_us);
+					assert_eq!(collating_on, para_id);
+				}
+			);
+
+			assert!(overseer_recv_with_timeout(&mut virtual_overseer, TIMEOUT).await.is_none());
+		});
+	}
 }
@@ -19,6 +19,10 @@
 
 #![deny(missing_docs)]
 
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use futures::{channel::oneshot, FutureExt};
 use log::trace;
@@ -36,8 +40,12 @@ use polkadot_node_network_protocol::{
 };
 use polkadot_primitives::v1::CollatorId;
 use polkadot_node_subsystem_util as util;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+
+use reputation::ReputationTable;
 
 mod collator_side;
+mod reputation;
 mod validator_side;
 
 const TARGET: &'static str = "colp";
@@ -57,12 +65,22 @@ enum Error {
 
 type Result<T> = std::result::Result<T, Error>;
 
+#[derive(Clone)]
 enum ProtocolSide {
-	Validator,
+	Validator(ReputationTable),
 	Collator(CollatorId),
 }
 
+/// Configuration of the database collator reputations are persisted in.
+pub struct ReputationConfig {
+	/// Total cache size in megabytes. If `None` the default is used.
+	pub cache_size: Option<usize>,
+	/// Path to the database.
+	pub path: PathBuf,
+}
+
 /// The collator protocol subsystem.
+#[derive(Clone)]
 pub struct CollatorProtocolSubsystem {
 	protocol_side: ProtocolSide, 
 }
@@ -71,10 +89,13 @@ impl CollatorProtocolSubsystem {
 	/// Start the collator protocol.
 	/// If `id` is `Some` this is a collator side of the protocol.
 	/// If `id` is `None` this is a validator side of the protocol. 
+	///
+	/// The validator side started this way keeps the reputations of collators in memory only,
+	/// see `new_validator_on_disk` for persisting them.
 	pub fn new(id: Option<CollatorId>) -> Self {
 		let protocol_side = match id {
 			Some(id) => ProtocolSide::Collator(id),
-			None => ProtocolSide::Validator,
+			None => ProtocolSide::Validator(ReputationTable::default()),
 		};
 
 		Self {
@@ -82,12 +103,40 @@ impl CollatorProtocolSubsystem {
 		}
 	}
 
+	/// Start the validator side of the protocol, persisting the reputations of collators
+	/// in the database described by `config`.
+	pub fn new_validator_on_disk(config: ReputationConfig) -> io::Result<Self> {
+		let mut db_config = DatabaseConfig::with_columns(reputation::columns::NUM_COLUMNS);
+
+		if let Some(cache_size) = config.cache_size {
+			let mut memory_budget = HashMap::new();
+
+			for i in 0..reputation::columns::NUM_COLUMNS {
+				memory_budget.insert(i, cache_size / reputation::columns::NUM_COLUMNS as usize);
+			}
+			db_config.memory_budget = memory_budget;
+		}
+
+		let path = config.path.to_str().ok_or_else(|| io::Error::new(
+			io::ErrorKind::Other,
+			format!("Bad database path: {:?}", config.path),
+		))?;
+
+		let db = Database::open(&db_config, &path)?;
+
+		Ok(Self {
+			protocol_side: ProtocolSide::Validator(ReputationTable::new(Arc::new(db))),
+		})
+	}
+
 	async fn run<Context>(self, ctx: Context) -> Result<()>
 	where
 		Context: SubsystemContext<Message = CollatorProtocolMessage>,
 	{
 		match self.protocol_side {
-		    ProtocolSide::Validator => validator_side::run(ctx, REQUEST_TIMEOUT).await,
+		    ProtocolSide::Validator(reputations) => {
+				validator_side::run(ctx, REQUEST_TIMEOUT, reputations).await
+			}
 		    ProtocolSide::Collator(id) => collator_side::run(ctx, id).await,
 		}
 	}
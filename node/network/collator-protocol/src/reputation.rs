@@ -0,0 +1,218 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reputations of collators, kept per para and persisted across restarts.
+//!
+//! Unlike the reputation of a peer, which the peer set manager forgets as soon as the peer
+//! disconnects, these are keyed by the `CollatorId` a collator declares itself with, so a
+//! misbehaving collator cannot shed its reputation by reconnecting. Scores decay towards zero
+//! with a half-life of `HALF_LIFE`, so old behavior is forgiven eventually.
+
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use codec::{Encode, Decode};
+use kvdb::{KeyValueDB, DBTransaction};
+
+use polkadot_primitives::v1::{Id as ParaId, CollatorId};
+
+pub(crate) mod columns {
+	pub const REPUTATIONS: u32 = 0;
+	pub const NUM_COLUMNS: u32 = 1;
+}
+
+/// The time after which a score has decayed to half its value.
+const HALF_LIFE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Scores are kept within `-MAX_SCORE..=MAX_SCORE`, so a collator can neither bank an unbounded
+/// credit for later misbehavior nor be punished forever.
+const MAX_SCORE: i32 = 10_000;
+
+/// Collators with a score below this are refused.
+pub(crate) const BAN_THRESHOLD: i32 = -500;
+
+/// The score of a collator, as stored in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+struct StoredScore {
+	score: i32,
+	/// Seconds since the UNIX epoch at which `score` was last updated.
+	updated_at: u64,
+}
+
+impl StoredScore {
+	/// The score with the decay since its last update applied.
+	fn decayed(&self, now: u64) -> i32 {
+		let elapsed = now.saturating_sub(self.updated_at) as f64;
+		let factor = 0.5f64.powf(elapsed / HALF_LIFE.as_secs() as f64);
+
+		(self.score as f64 * factor).round() as i32
+	}
+}
+
+/// The current time in seconds since the UNIX epoch.
+pub(crate) fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default()
+}
+
+/// Keys are the collator followed by the para, so all the scores of a collator can be iterated
+/// by prefix.
+fn score_key(collator: &CollatorId, para_id: ParaId) -> Vec<u8> {
+	(collator, para_id).encode()
+}
+
+/// Per-para reputations of collators.
+#[derive(Clone)]
+pub(crate) struct ReputationTable {
+	db: Arc<dyn KeyValueDB>,
+}
+
+impl Default for ReputationTable {
+	/// A table which is kept in memory only.
+	fn default() -> Self {
+		Self::new(Arc::new(kvdb_memorydb::create(columns::NUM_COLUMNS)))
+	}
+}
+
+impl ReputationTable {
+	/// Create a table on top of `db`, which must have `columns::NUM_COLUMNS` columns.
+	pub(crate) fn new(db: Arc<dyn KeyValueDB>) -> Self {
+		Self { db }
+	}
+
+	fn stored_score(&self, collator: &CollatorId, para_id: ParaId) -> io::Result<Option<StoredScore>> {
+		let raw = self.db.get(columns::REPUTATIONS, &score_key(collator, para_id))?;
+
+		Ok(raw.and_then(|raw| StoredScore::decode(&mut &raw[..]).ok()))
+	}
+
+	/// The score of `collator` on `para_id` at the time `now`.
+	pub(crate) fn score(&self, collator: &CollatorId, para_id: ParaId, now: u64) -> io::Result<i32> {
+		Ok(self.stored_score(collator, para_id)?.map_or(0, |stored| stored.decayed(now)))
+	}
+
+	/// Change the score of `collator` on `para_id` by `delta` at the time `now`, returning the
+	/// new score.
+	pub(crate) fn modify(
+		&self,
+		collator: &CollatorId,
+		para_id: ParaId,
+		delta: i32,
+		now: u64,
+	) -> io::Result<i32> {
+		let score = self.score(collator, para_id, now)?
+			.saturating_add(delta)
+			.max(-MAX_SCORE)
+			.min(MAX_SCORE);
+
+		let mut tx = DBTransaction::new();
+		tx.put_vec(
+			columns::REPUTATIONS,
+			&score_key(collator, para_id),
+			StoredScore { score, updated_at: now }.encode(),
+		);
+		self.db.write(tx)?;
+
+		Ok(score)
+	}
+
+	/// Whether `collator` is to be refused on `para_id` at the time `now`.
+	pub(crate) fn is_banned(&self, collator: &CollatorId, para_id: ParaId, now: u64) -> io::Result<bool> {
+		Ok(self.score(collator, para_id, now)? < BAN_THRESHOLD)
+	}
+
+	/// Whether `collator` is to be refused on any of the paras it has a score for, at the time `now`.
+	pub(crate) fn is_banned_anywhere(&self, collator: &CollatorId, now: u64) -> bool {
+		self.db
+			.iter_with_prefix(columns::REPUTATIONS, &collator.encode())
+			.filter_map(|(_, raw)| StoredScore::decode(&mut &raw[..]).ok())
+			.any(|stored| stored.decayed(now) < BAN_THRESHOLD)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::Pair;
+	use polkadot_primitives::v1::CollatorPair;
+
+	#[test]
+	fn scores_are_kept_per_para() {
+		let table = ReputationTable::default();
+		let collator = CollatorPair::generate().0.public();
+
+		assert_eq!(table.modify(&collator, 1.into(), -100, 0).unwrap(), -100);
+		assert_eq!(table.modify(&collator, 2.into(), 50, 0).unwrap(), 50);
+
+		assert_eq!(table.score(&collator, 1.into(), 0).unwrap(), -100);
+		assert_eq!(table.score(&collator, 2.into(), 0).unwrap(), 50);
+		assert_eq!(table.score(&collator, 3.into(), 0).unwrap(), 0);
+	}
+
+	#[test]
+	fn scores_decay_over_time() {
+		let table = ReputationTable::default();
+		let collator = CollatorPair::generate().0.public();
+		let half_life = HALF_LIFE.as_secs();
+
+		table.modify(&collator, 1.into(), -800, 0).unwrap();
+
+		assert_eq!(table.score(&collator, 1.into(), half_life).unwrap(), -400);
+		assert_eq!(table.score(&collator, 1.into(), 2 * half_life).unwrap(), -200);
+
+		// The decay is applied before a modification.
+		assert_eq!(table.modify(&collator, 1.into(), -100, half_life).unwrap(), -500);
+		assert_eq!(table.score(&collator, 1.into(), 2 * half_life).unwrap(), -250);
+	}
+
+	#[test]
+	fn collators_below_threshold_are_banned_until_decayed() {
+		let table = ReputationTable::default();
+		let collator = CollatorPair::generate().0.public();
+
+		table.modify(&collator, 1.into(), 2 * BAN_THRESHOLD, 0).unwrap();
+
+		assert!(table.is_banned(&collator, 1.into(), 0).unwrap());
+		assert!(!table.is_banned(&collator, 2.into(), 0).unwrap());
+		assert!(table.is_banned_anywhere(&collator, 0));
+
+		let later = 2 * HALF_LIFE.as_secs();
+		assert!(!table.is_banned(&collator, 1.into(), later).unwrap());
+		assert!(!table.is_banned_anywhere(&collator, later));
+	}
+
+	#[test]
+	fn scores_are_bounded() {
+		let table = ReputationTable::default();
+		let collator = CollatorPair::generate().0.public();
+
+		assert_eq!(table.modify(&collator, 1.into(), i32::max_value(), 0).unwrap(), MAX_SCORE);
+		assert_eq!(table.modify(&collator, 1.into(), i32::min_value(), 0).unwrap(), -MAX_SCORE);
+	}
+
+	#[test]
+	fn scores_survive_reopening() {
+		let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let collator = CollatorPair::generate().0.public();
+
+		ReputationTable::new(db.clone()).modify(&collator, 1.into(), -100, 0).unwrap();
+
+		assert_eq!(ReputationTable::new(db).score(&collator, 1.into(), 0).unwrap(), -100);
+	}
+}
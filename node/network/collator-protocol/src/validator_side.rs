@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::time::Duration;
 use std::task::Poll;
 
@@ -42,11 +43,14 @@ use polkadot_node_network_protocol::{
 use polkadot_node_subsystem_util::TimeoutExt;
 
 use super::{modify_reputation, TARGET, Result};
+use super::reputation::{self, ReputationTable};
 
 const COST_UNEXPECTED_MESSAGE: Rep = Rep::new(-10, "An unexpected message");
 const COST_REQUEST_TIMED_OUT: Rep = Rep::new(-20, "A collation request has timed out");
+const COST_MALFORMED_COLLATION: Rep = Rep::new(-500, "A collation which could not be decompressed");
 const COST_REPORT_BAD: Rep = Rep::new(-50, "A collator was reported by another subsystem");
 const BENEFIT_NOTIFY_GOOD: Rep = Rep::new(50, "A collator was noted good by another subsystem");
+const COST_BANNED_COLLATOR: Rep = Rep::new_fatal("A collator whose reputation is below the threshold");
 
 #[derive(Debug)]
 enum CollationRequestResult {
@@ -95,6 +99,31 @@ struct PerRequest {
 	result: oneshot::Sender<(CandidateReceipt, PoV)>,
 }
 
+/// An advertisment candidate selection has yet to be notified of.
+#[derive(PartialEq, Eq)]
+struct PendingAdvertisment {
+	/// The reputation of the collator on the para when the advertisment arrived.
+	score: i32,
+	/// When the advertisment arrived, relative to the others.
+	arrival: u64,
+	collator: CollatorId,
+	relay_parent: Hash,
+	para_id: ParaId,
+}
+
+/// Better reputed collators come first, equally reputed ones in order of arrival.
+impl Ord for PendingAdvertisment {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.score.cmp(&other.score).then_with(|| other.arrival.cmp(&self.arrival))
+	}
+}
+
+impl PartialOrd for PendingAdvertisment {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
 /// All state relevant for the validator side of the protocol lives here.
 #[derive(Default)]
 struct State {
@@ -134,6 +163,20 @@ struct State {
 
 	/// Possessed collations.
 	collations: HashMap<(Hash, ParaId), Vec<(CollatorId, CandidateReceipt, PoV)>>,
+
+	/// The paras we fetched collations of each collator on. Kept once the collations are
+	/// pruned, so that reports arriving after we moved on still apply to the right paras.
+	collator_paras: HashMap<CollatorId, HashSet<ParaId>>,
+
+	/// Reputations of collators, which outlive their connections.
+	reputations: ReputationTable,
+
+	/// Advertisments candidate selection has yet to be notified of, ordered by the reputation
+	/// of the collators.
+	pending_advertisments: BinaryHeap<PendingAdvertisment>,
+
+	/// The arrival of the next advertisment.
+	next_arrival: u64,
 }
 
 /// Another subsystem has requested to fetch collations on a particular leaf for some para.
@@ -186,6 +229,57 @@ where
 	Ok(())
 }
 
+/// Apply `rep` to the persistent reputation of a collator on all paras we fetched its
+/// collations on. Returns whether the collator is below the threshold on any of them afterwards.
+///
+/// The paras are recorded along with the collations, so they don't depend on whatever the peers
+/// of the collator advertised since, or whether they are still connected.
+fn modify_collator_reputation(state: &State, id: &CollatorId, rep: &Rep) -> bool {
+	let now = reputation::now();
+	let mut banned = false;
+
+	for para_id in state.collator_paras.get(id).into_iter().flatten() {
+		match state.reputations.modify(id, *para_id, rep.value, now) {
+			Ok(score) => banned |= score < reputation::BAN_THRESHOLD,
+			Err(e) => warn!(
+				target: TARGET,
+				"Failed to persist the reputation of collator {:?}: {:?}", id, e,
+			),
+		}
+	}
+
+	banned
+}
+
+/// Whether a collator is to be refused on a para.
+fn is_banned(state: &State, id: &CollatorId, para_id: ParaId) -> bool {
+	match state.reputations.is_banned(id, para_id, reputation::now()) {
+		Ok(banned) => banned,
+		Err(e) => {
+			warn!(
+				target: TARGET,
+				"Failed to read the reputation of collator {:?}: {:?}", id, e,
+			);
+			false
+		}
+	}
+}
+
+/// Forget a peer whose collator is below the reputation threshold and have it disconnected.
+async fn refuse_collator<Context>(
+	ctx: &mut Context,
+	state: &mut State,
+	peer_id: PeerId,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = CollatorProtocolMessage>
+{
+	state.known_collators.remove(&peer_id);
+	state.advertisments.remove(&peer_id);
+
+	modify_reputation(ctx, peer_id, COST_BANNED_COLLATOR).await
+}
+
 /// Report a collator for some malicious actions.
 async fn report_collator<Context>(
 	ctx: &mut Context,
@@ -195,12 +289,22 @@ async fn report_collator<Context>(
 where
 	Context: SubsystemContext<Message = CollatorProtocolMessage>
 {
+	let banned = modify_collator_reputation(state, &id, &COST_REPORT_BAD);
+
 	// Since we have a one way map of PeerId -> CollatorId we have to
 	// iterate here. Since a huge amount of peers is not expected this
 	// is a tolerable thing to do.
-	for (k, v) in state.known_collators.iter() {
-		if *v == id {
-			modify_reputation(ctx, k.clone(), COST_REPORT_BAD).await?;
+	let peers: Vec<_> = state.known_collators
+		.iter()
+		.filter(|(_, v)| **v == id)
+		.map(|(k, _)| k.clone())
+		.collect();
+
+	for peer_id in peers {
+		if banned {
+			refuse_collator(ctx, state, peer_id).await?;
+		} else {
+			modify_reputation(ctx, peer_id, COST_REPORT_BAD).await?;
 		}
 	}
 
@@ -216,6 +320,8 @@ async fn note_good_collation<Context>(
 where
 	Context: SubsystemContext<Message = CollatorProtocolMessage>
 {
+	modify_collator_reputation(state, &id, &BENEFIT_NOTIFY_GOOD);
+
 	for (peer_id, collator_id) in state.known_collators.iter() {
 		if id == *collator_id {
 			modify_reputation(ctx, peer_id.clone(), BENEFIT_NOTIFY_GOOD).await?;
@@ -292,6 +398,10 @@ where
 				if let Some(collator_id) = state.known_collators.get(&origin) {
 					let _ = per_request.result.send((receipt.clone(), pov.clone()));
 
+					state.collator_paras
+						.entry(collator_id.clone())
+						.or_default()
+						.insert(para_id);
 					state.collations
 						.entry((relay_parent, para_id))
 						.or_default()
@@ -406,6 +516,56 @@ where
 	Ok(())
 }
 
+/// Queue an advertisment for candidate selection to be notified of.
+fn queue_advertisment(state: &mut State, collator: CollatorId, relay_parent: Hash, para_id: ParaId) {
+	let score = match state.reputations.score(&collator, para_id, reputation::now()) {
+		Ok(score) => score,
+		Err(e) => {
+			warn!(
+				target: TARGET,
+				"Failed to read the reputation of collator {:?}: {:?}", collator, e,
+			);
+			0
+		}
+	};
+
+	let arrival = state.next_arrival;
+	state.next_arrival += 1;
+
+	state.pending_advertisments.push(PendingAdvertisment {
+		score,
+		arrival,
+		collator,
+		relay_parent,
+		para_id,
+	});
+}
+
+/// Notify `CandidateSelectionSubsystem` of the pending advertisments which are still in our
+/// view, those by collators with a better reputation first.
+async fn notify_pending_advertisments<Context>(
+	ctx: &mut Context,
+	state: &mut State,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = CollatorProtocolMessage>
+{
+	while let Some(advertisment) = state.pending_advertisments.pop() {
+		if !state.view.contains(&advertisment.relay_parent) {
+			continue;
+		}
+
+		notify_candidate_selection(
+			ctx,
+			advertisment.collator,
+			advertisment.relay_parent,
+			advertisment.para_id,
+		).await?;
+	}
+
+	Ok(())
+}
+
 /// Networking message has been received.
 async fn process_incoming_peer_message<Context>(
 	ctx: &mut Context,
@@ -420,15 +580,31 @@ where
 
 	match msg {
 	    Declare(id) => {
+			if state.reputations.is_banned_anywhere(&id, reputation::now()) {
+				trace!(
+					target: TARGET,
+					"Refusing collator {:?} on {:?} for its reputation", id, origin,
+				);
+				return refuse_collator(ctx, state, origin).await;
+			}
+
 			state.known_collators.insert(origin.clone(), id);
 			state.peer_views.entry(origin).or_default();
 		}
 	    AdvertiseCollation(relay_parent, para_id) => {
-			state.advertisments.entry(origin.clone()).or_default().insert((para_id, relay_parent));
+			if let Some(collator) = state.known_collators.get(&origin).cloned() {
+				if is_banned(state, &collator, para_id) {
+					trace!(
+						target: TARGET,
+						"Refusing advertisment by collator {:?} on {} for its reputation", collator, para_id,
+					);
+					return refuse_collator(ctx, state, origin).await;
+				}
 
-			if let Some(collator) = state.known_collators.get(&origin) {
-				notify_candidate_selection(ctx, collator.clone(), relay_parent, para_id).await?;
+				queue_advertisment(state, collator, relay_parent, para_id);
 			}
+
+			state.advertisments.entry(origin.clone()).or_default().insert((para_id, relay_parent));
 		}
 	    RequestCollation(_, _, _) => {
 			// This is a validator side of the protocol, collation requests are not expected here.
@@ -437,6 +613,22 @@ where
 	    Collation(request_id, receipt, pov) => {
 			received_collation(ctx, state, origin, request_id, receipt, pov).await?;
 		}
+	    CompressedCollation(request_id, receipt, pov) => {
+			match pov.decompress() {
+				Ok(pov) => {
+					received_collation(ctx, state, origin, request_id, receipt, pov).await?;
+				}
+				Err(e) => {
+					warn!(
+						target: TARGET,
+						"Failed to decompress collation {} from {:?}: {:?}",
+						request_id, origin, e,
+					);
+
+					return modify_reputation(ctx, origin, COST_MALFORMED_COLLATION).await;
+				}
+			}
+		}
 	}
 
 	Ok(())
@@ -536,6 +728,7 @@ where
 		OurViewChange(view) => {
 			handle_our_view_change(state, view).await?;
 		},
+		ValidatorPeers(_) => {},
 		PeerMessage(remote, msg) => {
 			process_incoming_peer_message(ctx, state, remote, msg).await?;
 		}
@@ -595,7 +788,11 @@ where
 }
 
 /// The main run loop.
-pub(crate) async fn run<Context>(mut ctx: Context, request_timeout: Duration) -> Result<()>
+pub(crate) async fn run<Context>(
+	mut ctx: Context,
+	request_timeout: Duration,
+	reputations: ReputationTable,
+) -> Result<()>
 where
 	Context: SubsystemContext<Message = CollatorProtocolMessage>
 {
@@ -604,6 +801,7 @@ where
 
 	let mut state = State {
 		request_timeout,
+		reputations,
 		..Default::default()
 	};
 
@@ -621,6 +819,10 @@ where
 			continue;
 		}
 
+		// All the messages which have arrived are processed, so the advertisments among
+		// them can be ordered.
+		notify_pending_advertisments(&mut ctx, &mut state).await?;
+
 		while let Poll::Ready(Some(request)) = futures::poll!(state.requests_in_progress.next()) {
 			// Request has timed out, we need to penalize the collator and re-send the request
 			// if the chain has not moved on yet.
@@ -691,6 +893,13 @@ mod tests {
 	}
 
 	fn test_harness<T: Future<Output = ()>>(test: impl FnOnce(TestHarness) -> T) {
+		test_harness_with_reputations(ReputationTable::default(), test)
+	}
+
+	fn test_harness_with_reputations<T: Future<Output = ()>>(
+		reputations: ReputationTable,
+		test: impl FnOnce(TestHarness) -> T,
+	) {
 		let _ = env_logger::builder()
 			.is_test(true)
 			.filter(
@@ -707,7 +916,7 @@ mod tests {
 
 		let (context, virtual_overseer) = test_helpers::make_subsystem_context(pool.clone());
 
-		let subsystem = run(context, Duration::from_millis(50));
+		let subsystem = run(context, Duration::from_millis(50), reputations);
 
 		let test_fut = test(TestHarness { virtual_overseer });
 
@@ -1221,4 +1430,310 @@ mod tests {
 			assert_eq!(collation_1.0, candidate_b);
 		});
 	}
+
+	async fn connect_and_declare(
+		virtual_overseer: &mut test_helpers::TestSubsystemContextHandle<CollatorProtocolMessage>,
+		peer: PeerId,
+		collator: CollatorId,
+	) {
+		overseer_send(
+			virtual_overseer,
+			CollatorProtocolMessage::NetworkBridgeUpdateV1(
+				NetworkBridgeEvent::PeerMessage(
+					peer,
+					protocol_v1::CollatorProtocolMessage::Declare(collator),
+				)
+			)
+		).await;
+	}
+
+	async fn advertise(
+		virtual_overseer: &mut test_helpers::TestSubsystemContextHandle<CollatorProtocolMessage>,
+		peer: PeerId,
+		relay_parent: Hash,
+		para_id: ParaId,
+	) {
+		overseer_send(
+			virtual_overseer,
+			CollatorProtocolMessage::NetworkBridgeUpdateV1(
+				NetworkBridgeEvent::PeerMessage(
+					peer,
+					protocol_v1::CollatorProtocolMessage::AdvertiseCollation(relay_parent, para_id),
+				)
+			)
+		).await;
+	}
+
+	// Collators below the reputation threshold are disconnected and their advertisments ignored.
+	#[test]
+	fn collators_below_threshold_are_refused() {
+		let test_state = TestState::default();
+		let reputations = ReputationTable::default();
+
+		reputations.modify(
+			&test_state.collators[0].public(),
+			test_state.chain_ids[0],
+			2 * reputation::BAN_THRESHOLD,
+			reputation::now(),
+		).unwrap();
+
+		test_harness_with_reputations(reputations, |test_harness| async move {
+			let TestHarness {
+				mut virtual_overseer,
+			} = test_harness;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::OurViewChange(View(vec![test_state.relay_parent]))
+				)
+			).await;
+
+			let peer_b = PeerId::random();
+
+			connect_and_declare(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.collators[0].public(),
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, peer_b);
+					assert_eq!(rep, COST_BANNED_COLLATOR);
+				}
+			);
+
+			advertise(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.relay_parent,
+				test_state.chain_ids[0],
+			).await;
+
+			assert!(
+				overseer_recv_with_timeout(&mut virtual_overseer, TIMEOUT).await.is_none()
+			);
+		});
+	}
+
+	// Advertisments arriving together are passed on best reputed collator first.
+	#[test]
+	fn advertisments_are_ordered_by_reputation() {
+		let test_state = TestState::default();
+		let reputations = ReputationTable::default();
+
+		reputations.modify(
+			&test_state.collators[1].public(),
+			test_state.chain_ids[0],
+			100,
+			reputation::now(),
+		).unwrap();
+
+		test_harness_with_reputations(reputations, |test_harness| async move {
+			let TestHarness {
+				mut virtual_overseer,
+			} = test_harness;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::OurViewChange(View(vec![test_state.relay_parent]))
+				)
+			).await;
+
+			let peer_b = PeerId::random();
+			let peer_c = PeerId::random();
+
+			connect_and_declare(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.collators[0].public(),
+			).await;
+
+			connect_and_declare(
+				&mut virtual_overseer,
+				peer_c.clone(),
+				test_state.collators[1].public(),
+			).await;
+
+			advertise(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.relay_parent,
+				test_state.chain_ids[0],
+			).await;
+
+			advertise(
+				&mut virtual_overseer,
+				peer_c.clone(),
+				test_state.relay_parent,
+				test_state.chain_ids[0],
+			).await;
+
+			for expected in &[test_state.collators[1].public(), test_state.collators[0].public()] {
+				assert_matches!(
+					overseer_recv(&mut virtual_overseer).await,
+					AllMessages::CandidateSelection(CandidateSelectionMessage::Collation(
+						relay_parent,
+						para_id,
+						collator,
+					)) => {
+						assert_eq!(relay_parent, test_state.relay_parent);
+						assert_eq!(para_id, test_state.chain_ids[0]);
+						assert_eq!(&collator, expected);
+					}
+				);
+			}
+		});
+	}
+
+	// Reports outlive the connection of a collator and are kept per para.
+	#[test]
+	fn reports_are_persisted_per_para() {
+		let test_state = TestState::default();
+		let reputations = ReputationTable::default();
+		let collator = test_state.collators[0].public();
+		let chain_ids = test_state.chain_ids.clone();
+		let reported = collator.clone();
+
+		test_harness_with_reputations(reputations.clone(), |test_harness| async move {
+			let TestHarness {
+				mut virtual_overseer,
+			} = test_harness;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::OurViewChange(View(vec![test_state.relay_parent]))
+				)
+			).await;
+
+			let peer_b = PeerId::random();
+
+			connect_and_declare(&mut virtual_overseer, peer_b.clone(), collator.clone()).await;
+
+			advertise(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.relay_parent,
+				test_state.chain_ids[0],
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::CandidateSelection(CandidateSelectionMessage::Collation(..))
+			);
+
+			// the collator is reported on the para we fetched its collation on, not on the
+			// others its peer advertises.
+			let (tx, rx) = oneshot::channel();
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::FetchCollation(
+					test_state.relay_parent,
+					collator.clone(),
+					test_state.chain_ids[0],
+					tx,
+				),
+			).await;
+
+			let request_id = assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendCollationMessage(
+					_,
+					protocol_v1::CollationProtocol::CollatorProtocol(
+						protocol_v1::CollatorProtocolMessage::RequestCollation(id, _, _),
+					),
+				)) => id
+			);
+
+			let mut candidate = CandidateReceipt::default();
+			candidate.descriptor.para_id = test_state.chain_ids[0];
+			candidate.descriptor.relay_parent = test_state.relay_parent;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::PeerMessage(
+						peer_b.clone(),
+						protocol_v1::CollatorProtocolMessage::Collation(
+							request_id,
+							candidate,
+							PoV {
+								block_data: BlockData(vec![]),
+							},
+						),
+					)
+				)
+			).await;
+
+			rx.await.unwrap();
+
+			advertise(
+				&mut virtual_overseer,
+				peer_b.clone(),
+				test_state.relay_parent,
+				test_state.chain_ids[1],
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::CandidateSelection(CandidateSelectionMessage::Collation(..))
+			);
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::ReportCollator(collator.clone()),
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, peer_b);
+					assert_eq!(rep, COST_REPORT_BAD);
+				}
+			);
+
+			// the collation is pruned once we move on, but reports still apply to its para.
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::OurViewChange(View(vec![Hash::repeat_byte(0x42)]))
+				)
+			).await;
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::ReportCollator(collator.clone()),
+			).await;
+
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, peer_b);
+					assert_eq!(rep, COST_REPORT_BAD);
+				}
+			);
+
+			overseer_send(
+				&mut virtual_overseer,
+				CollatorProtocolMessage::NetworkBridgeUpdateV1(
+					NetworkBridgeEvent::PeerDisconnected(peer_b),
+				)
+			).await;
+		});
+
+		let now = reputation::now();
+
+		assert_eq!(reputations.score(&reported, chain_ids[0], now).unwrap(), 2 * COST_REPORT_BAD.value);
+		assert_eq!(reputations.score(&reported, chain_ids[1], now).unwrap(), 0);
+	}
 }
@@ -0,0 +1,47 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use std::fmt::Debug;
+
+use libfuzzer_sys::fuzz_target;
+use parity_scale_codec::{Decode, Encode};
+use polkadot_node_network_protocol::{v1, v2, View};
+
+/// Decode `data` as a `T`, checking that whatever decodes re-encodes to a value decoding the
+/// same.
+fn roundtrip<T: Decode + Encode + PartialEq + Debug>(data: &[u8]) -> Option<T> {
+	let decoded = T::decode(&mut &data[..]).ok()?;
+	let reencoded = decoded.encode();
+	assert_eq!(T::decode(&mut &reencoded[..]).ok().as_ref(), Some(&decoded));
+
+	Some(decoded)
+}
+
+fuzz_target!(|data: &[u8]| {
+	if let Some(v1::ValidationProtocol::PoVDistribution(
+		v1::PoVDistributionMessage::SendCompressedPoV(_, _, pov),
+	)) = roundtrip::<v1::ValidationProtocol>(data) {
+		// Decompression is bounded by `MAX_POV_SIZE`, whatever the input.
+		let _ = pov.decompress();
+	}
+
+	roundtrip::<v1::CollationProtocol>(data);
+	roundtrip::<v2::ValidationProtocol>(data);
+	roundtrip::<v2::CollationProtocol>(data);
+	roundtrip::<View>(data);
+});
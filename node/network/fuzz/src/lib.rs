@@ -0,0 +1,337 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fuzz harnesses for the networking subsystems.
+//!
+//! The `decode_messages` target decodes arbitrary bytes as the messages peers send us. The
+//! distribution targets drive a subsystem with an arbitrary sequence of peers connecting,
+//! disconnecting, changing views, sending messages and being learned to be validators,
+//! interleaved with our own view changing, and check that
+//!   - the subsystem never panics,
+//!   - a single input never makes it send more than `MAX_OUTPUTS_PER_INPUT` messages,
+//!   - it never sends the same message to the same peer twice while neither side's view changed, and
+//!   - it never connects to validators itself, which is left to gossip support.
+//!
+//! Memory use is bounded by running the targets under libFuzzer's `-rss_limit_mb`.
+//!
+//! Run with `cargo fuzz run <target>` from this directory, using a nightly toolchain.
+
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use arbitrary::Arbitrary;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use futures::FutureExt;
+use parity_scale_codec::{Decode, Encode};
+use sc_network::config::identity::{self, ed25519};
+use sp_core::testing::TaskExecutor;
+use sp_keyring::Sr25519Keyring;
+
+use polkadot_node_network_protocol::{NetworkBridgeEvent, ObservedRole, PeerId, View};
+use polkadot_node_subsystem_test_helpers::{make_subsystem_context, TestSubsystemContext};
+use polkadot_primitives::v1::{GroupRotationInfo, Hash, ValidatorId, ValidatorPair};
+use polkadot_subsystem::{
+	ActiveLeavesUpdate, FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem,
+	messages::{
+		AllMessages, AvailabilityStoreMessage, ChainApiMessage, NetworkBridgeMessage,
+		RuntimeApiMessage, RuntimeApiRequest,
+	},
+};
+
+/// The maximum number of operations of a single run.
+pub const MAX_OPS: usize = 256;
+
+/// The maximum number of messages a subsystem may send in reaction to a single input.
+pub const MAX_OUTPUTS_PER_INPUT: usize = 1024;
+
+/// The number of distinct peers operations refer to.
+const NUM_PEERS: u8 = 8;
+
+/// The number of distinct relay-parents operations refer to.
+const NUM_HEADS: u8 = 4;
+
+/// The validators of the session every relay-parent is in. We are the first of them.
+const VALIDATORS: [Sr25519Keyring; 6] = [
+	Sr25519Keyring::Alice,
+	Sr25519Keyring::Bob,
+	Sr25519Keyring::Charlie,
+	Sr25519Keyring::Dave,
+	Sr25519Keyring::Eve,
+	Sr25519Keyring::Ferdie,
+];
+
+// Derived from fixed keys rather than random ones, so that replaying an input behaves the same.
+thread_local! {
+	static PEERS: Vec<PeerId> = (0..NUM_PEERS).map(|index| {
+		let secret = ed25519::SecretKey::from_bytes([index + 1; 32])
+			.expect("any 32 bytes are a valid ed25519 secret key; qed");
+		PeerId::from_public_key(identity::PublicKey::Ed25519(ed25519::Keypair::from(secret).public()))
+	}).collect();
+}
+
+fn peer(index: u8) -> PeerId {
+	PEERS.with(|peers| peers[(index % NUM_PEERS) as usize].clone())
+}
+
+fn validator(index: u8) -> ValidatorId {
+	VALIDATORS[index as usize % VALIDATORS.len()].public().into()
+}
+
+fn head(index: u8) -> Hash {
+	Hash::repeat_byte(index % NUM_HEADS)
+}
+
+fn view(heads: &[u8]) -> View {
+	View(heads.iter().take(NUM_HEADS as usize).copied().map(head).collect())
+}
+
+/// Something happening to the subsystem under test.
+#[derive(Debug, Clone, Arbitrary)]
+pub enum Op {
+	/// A peer connects.
+	Connect(u8),
+	/// A peer disconnects.
+	Disconnect(u8),
+	/// A peer changes its view to the given heads.
+	PeerView(u8, Vec<u8>),
+	/// Our view changes to the given heads.
+	OurView(Vec<u8>),
+	/// A head is activated.
+	ActivateLeaf(u8),
+	/// A head is deactivated.
+	DeactivateLeaf(u8),
+	/// A peer sends the message encoded as the given bytes. Ignored unless they decode.
+	Message(u8, Vec<u8>),
+	/// The bridge connected to the given validators, as the given peers.
+	ValidatorPeers(Vec<(u8, u8)>),
+}
+
+impl Op {
+	fn into_input<W: Decode>(self) -> Option<FromOverseer<NetworkBridgeEvent<W>>> {
+		let event = match self {
+			Op::Connect(p) => NetworkBridgeEvent::PeerConnected(peer(p), ObservedRole::Full),
+			Op::Disconnect(p) => NetworkBridgeEvent::PeerDisconnected(peer(p)),
+			Op::PeerView(p, heads) => NetworkBridgeEvent::PeerViewChange(peer(p), view(&heads)),
+			Op::OurView(heads) => NetworkBridgeEvent::OurViewChange(view(&heads)),
+			Op::ActivateLeaf(h) => return Some(FromOverseer::Signal(
+				OverseerSignal::ActiveLeaves(ActiveLeavesUpdate::start_work(head(h))),
+			)),
+			Op::DeactivateLeaf(h) => return Some(FromOverseer::Signal(
+				OverseerSignal::ActiveLeaves(ActiveLeavesUpdate::stop_work(head(h))),
+			)),
+			Op::Message(p, bytes) => {
+				let message = W::decode(&mut &bytes[..]).ok()?;
+				NetworkBridgeEvent::PeerMessage(peer(p), message)
+			}
+			Op::ValidatorPeers(validator_peers) => NetworkBridgeEvent::ValidatorPeers(
+				validator_peers.into_iter().map(|(v, p)| (validator(v), peer(p))).collect(),
+			),
+		};
+
+		Some(FromOverseer::Communication { msg: event })
+	}
+}
+
+/// A keystore holding the key of the first validator.
+pub fn keystore() -> sc_keystore::KeyStorePtr {
+	let keystore = sc_keystore::Store::new_in_memory();
+	keystore.write()
+		.insert_ephemeral_from_seed::<ValidatorPair>(&VALIDATORS[0].to_seed())
+		.expect("inserting into an in-memory keystore never fails; qed");
+
+	keystore
+}
+
+/// Checks the invariants on what a subsystem sends, and answers its requests.
+#[derive(Default)]
+struct Checker {
+	/// The messages sent to each peer since the last view change.
+	sent: HashSet<(PeerId, Vec<u8>)>,
+}
+
+impl Checker {
+	fn note_op(&mut self, op: &Op) {
+		match op {
+			Op::Connect(p) | Op::Disconnect(p) | Op::PeerView(p, _) => {
+				let p = peer(*p);
+				self.sent.retain(|(to, _)| to != &p);
+			}
+			Op::OurView(_) | Op::ActivateLeaf(_) | Op::DeactivateLeaf(_) => self.sent.clear(),
+			Op::Message(..) | Op::ValidatorPeers(_) => {}
+		}
+	}
+
+	fn note_sent(&mut self, peers: Vec<PeerId>, message: Vec<u8>) {
+		for peer in peers {
+			if !self.sent.insert((peer.clone(), message.clone())) {
+				panic!("Message {:?} sent to {} twice", message, peer);
+			}
+		}
+	}
+
+	fn on_output(&mut self, message: AllMessages) {
+		match message {
+			AllMessages::NetworkBridge(NetworkBridgeMessage::SendValidationMessage(peers, message))
+				=> self.note_sent(peers, message.encode()),
+			AllMessages::NetworkBridge(NetworkBridgeMessage::SendCollationMessage(peers, message))
+				=> self.note_sent(peers, message.encode()),
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(_, request)) => answer_runtime_api(request),
+			AllMessages::AvailabilityStore(message) => answer_availability_store(message),
+			AllMessages::ChainApi(message) => answer_chain_api(message),
+			// The bridge tells every subsystem which peers validators are once gossip support
+			// connected to them, so no other subsystem needs to connect.
+			AllMessages::NetworkBridge(NetworkBridgeMessage::ConnectToValidators(..))
+				| AllMessages::NetworkBridge(NetworkBridgeMessage::ReleaseValidators(..))
+				=> panic!("Connections to validators requested by a distribution subsystem"),
+			// Anything else is either not a request, or one left unanswered, such as fetches
+			// from peers, which then fail.
+			_ => {}
+		}
+	}
+}
+
+fn answer_runtime_api(request: RuntimeApiRequest) {
+	match request {
+		RuntimeApiRequest::Validators(tx) => {
+			let validators: Vec<ValidatorId> = VALIDATORS.iter().map(|v| v.public().into()).collect();
+			let _ = tx.send(Ok(validators));
+		}
+		RuntimeApiRequest::ValidatorGroups(tx) => {
+			let groups = vec![vec![0, 1, 2], vec![3, 4, 5]];
+			let rotation = GroupRotationInfo {
+				session_start_block: 0,
+				group_rotation_frequency: 100,
+				now: 1,
+			};
+			let _ = tx.send(Ok((groups, rotation)));
+		}
+		RuntimeApiRequest::AvailabilityCores(tx) => { let _ = tx.send(Ok(Vec::new())); }
+		RuntimeApiRequest::SessionIndexForChild(tx) => { let _ = tx.send(Ok(1)); }
+		RuntimeApiRequest::CandidateEvents(tx) => { let _ = tx.send(Ok(Vec::new())); }
+		RuntimeApiRequest::CandidatePendingAvailability(_, tx) => { let _ = tx.send(Ok(None)); }
+		RuntimeApiRequest::PersistedValidationData(_, _, tx) => { let _ = tx.send(Ok(None)); }
+		RuntimeApiRequest::FullValidationData(_, _, tx) => { let _ = tx.send(Ok(None)); }
+		RuntimeApiRequest::ValidationCode(_, _, tx) => { let _ = tx.send(Ok(None)); }
+	}
+}
+
+fn answer_availability_store(message: AvailabilityStoreMessage) {
+	match message {
+		AvailabilityStoreMessage::QueryAvailableData(_, tx) => { let _ = tx.send(None); }
+		AvailabilityStoreMessage::QueryDataAvailability(_, tx) => { let _ = tx.send(false); }
+		AvailabilityStoreMessage::QueryChunk(_, _, tx) => { let _ = tx.send(None); }
+		AvailabilityStoreMessage::QueryChunkAvailability(_, _, tx) => { let _ = tx.send(false); }
+		AvailabilityStoreMessage::StoreChunk(_, _, _, tx) => { let _ = tx.send(Ok(())); }
+		AvailabilityStoreMessage::StoreAvailableData(_, _, _, _, tx) => { let _ = tx.send(Ok(())); }
+	}
+}
+
+fn answer_chain_api(message: ChainApiMessage) {
+	match message {
+		ChainApiMessage::BlockNumber(_, tx) => { let _ = tx.send(Ok(None)); }
+		ChainApiMessage::FinalizedBlockHash(_, tx) => { let _ = tx.send(Ok(None)); }
+		ChainApiMessage::FinalizedBlockNumber(tx) => { let _ = tx.send(Ok(0)); }
+		ChainApiMessage::Ancestors { response_channel, .. } => { let _ = response_channel.send(Ok(Vec::new())); }
+		ChainApiMessage::BlockHeader(_, tx) => { let _ = tx.send(Ok(None)); }
+		ChainApiMessage::BlockBody(_, tx) => { let _ = tx.send(Ok(None)); }
+		ChainApiMessage::BlockWeight(_, tx) => { let _ = tx.send(Ok(None)); }
+		ChainApiMessage::Descendants { response_channel, .. } => { let _ = response_channel.send(Ok(Vec::new())); }
+		// no block is ever finalized.
+		ChainApiMessage::FinalityNotifications(_) => {}
+	}
+}
+
+/// Drive `subsystem` with `ops`, checking the invariants on what it sends.
+///
+/// `wrap` turns the network events of the wire message type `W` into messages of the subsystem.
+/// Every input is only fed once the subsystem is idle, with all of its requests answered.
+pub fn drive<M, W, S>(subsystem: S, ops: Vec<Op>, wrap: impl Fn(NetworkBridgeEvent<W>) -> M)
+where
+	M: Send + 'static,
+	W: Decode,
+	S: Subsystem<TestSubsystemContext<M, TaskExecutor>>,
+{
+	let mut pool = LocalPool::new();
+	let (context, mut handle) = make_subsystem_context(TaskExecutor::new());
+	let SpawnedSubsystem { future, .. } = subsystem.start(context);
+
+	let concluded = Rc::new(Cell::new(false));
+	let subsystem_concluded = concluded.clone();
+	pool.spawner()
+		.spawn_local(async move {
+			future.await;
+			subsystem_concluded.set(true);
+		})
+		.expect("the pool is alive; qed");
+
+	let mut checker = Checker::default();
+	let inputs = ops.into_iter()
+		.take(MAX_OPS)
+		.map(Some)
+		.chain(std::iter::once(None));
+
+	for op in inputs {
+		pool.run_until_stalled();
+		if concluded.get() {
+			break;
+		}
+
+		let input = match op {
+			Some(op) => {
+				checker.note_op(&op);
+				match op.into_input::<W>() {
+					Some(FromOverseer::Communication { msg }) => FromOverseer::Communication { msg: wrap(msg) },
+					Some(FromOverseer::Signal(signal)) => FromOverseer::Signal(signal),
+					None => continue,
+				}
+			}
+			None => FromOverseer::Signal(OverseerSignal::Conclude),
+		};
+
+		// The subsystem is idle, so either it takes the input now or it never will.
+		{
+			let mut send = Box::pin(handle.send(input));
+			if send.as_mut().now_or_never().is_none() {
+				pool.run_until_stalled();
+				assert!(
+					send.as_mut().now_or_never().is_some(),
+					"Subsystem stopped receiving while idle",
+				);
+			}
+		}
+
+		let mut outputs = 0;
+		loop {
+			pool.run_until_stalled();
+
+			match handle.try_recv().now_or_never() {
+				Some(Some(message)) => {
+					outputs += 1;
+					assert!(
+						outputs <= MAX_OUTPUTS_PER_INPUT,
+						"More than {} messages sent in reaction to a single input",
+						MAX_OUTPUTS_PER_INPUT,
+					);
+
+					checker.on_output(message);
+				}
+				_ => break,
+			}
+		}
+	}
+}
@@ -0,0 +1,520 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Gossip Support subsystem.
+//!
+//! Keeps track of session changes and asks the network bridge to connect to the validators of
+//! every new session, so that the gossiping subsystems are able to reach them. The connections
+//! of a past session are released once a grace period after the session change has passed.
+//!
+//! This is the only subsystem connecting to the validators of a session. The other subsystems
+//! learn about the peers of the validators from the network bridge.
+
+#![deny(missing_docs)]
+
+use std::time::Duration;
+
+use futures::{
+	channel::oneshot,
+	future::BoxFuture,
+	prelude::*,
+	stream::FuturesUnordered,
+};
+use futures_timer::Delay;
+use log::{debug, trace};
+use sc_keystore::KeyStorePtr;
+use sp_core::Pair;
+
+use polkadot_node_network_protocol::PeerSet;
+use polkadot_node_subsystem_util::{
+	self as util,
+	request_session_index_for_child_ctx,
+	request_validator_groups_ctx,
+	request_validators_ctx,
+};
+use polkadot_primitives::v1::{Hash, SessionIndex, ValidatorId, ValidatorIndex};
+use polkadot_subsystem::{
+	messages::{AllMessages, GossipSupportMessage, NetworkBridgeMessage},
+	ActiveLeavesUpdate, FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem,
+	SubsystemContext,
+};
+
+const LOG_TARGET: &str = "gossip_support";
+
+/// How long the connections of a past session are kept up after a new session started, so
+/// that the gossip concerning the end of the past session still reaches everyone.
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// How often the connection requests of the current session are re-issued, as the addresses
+/// of some validators might only have been discovered after the previous request.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+type Result<T> = std::result::Result<T, util::Error>;
+
+/// The validators we asked the network bridge to connect to for one session.
+#[derive(Debug, Clone, PartialEq)]
+struct SessionConnections {
+	session_index: SessionIndex,
+	/// All validators of the session but ourselves.
+	validators: Vec<ValidatorId>,
+	/// The other validators of our backing group, empty if we are not a validator in the
+	/// session.
+	our_group: Vec<ValidatorId>,
+}
+
+impl SessionConnections {
+	fn contains(&self, validator: &ValidatorId) -> bool {
+		self.validators.contains(validator) || self.our_group.contains(validator)
+	}
+}
+
+#[derive(Default)]
+struct State {
+	/// The connections of the most recent session we have seen.
+	current: Option<SessionConnections>,
+	/// The connections of past sessions which are still within their grace period.
+	retiring: Vec<SessionConnections>,
+}
+
+/// The Gossip Support subsystem.
+#[derive(Clone)]
+pub struct GossipSupport {
+	keystore: KeyStorePtr,
+	grace_period: Duration,
+	refresh_interval: Duration,
+}
+
+impl GossipSupport {
+	/// Create a new instance of the gossip support subsystem.
+	pub fn new(keystore: KeyStorePtr) -> Self {
+		Self {
+			keystore,
+			grace_period: DEFAULT_GRACE_PERIOD,
+			refresh_interval: DEFAULT_REFRESH_INTERVAL,
+		}
+	}
+
+	/// Keep the connections of a past session up for `grace_period` after a session change,
+	/// instead of ten minutes.
+	pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+		self.grace_period = grace_period;
+		self
+	}
+
+	async fn run<Context>(self, mut ctx: Context)
+	where
+		Context: SubsystemContext<Message = GossipSupportMessage>,
+	{
+		let mut state = State::default();
+		// Resolve to the index of a retiring session once its grace period is over.
+		let mut grace_periods: FuturesUnordered<BoxFuture<'static, SessionIndex>> = FuturesUnordered::new();
+		let mut refresh = Delay::new(self.refresh_interval).fuse();
+
+		loop {
+			let result = futures::select! {
+				message = ctx.recv().fuse() => match message {
+					Ok(FromOverseer::Signal(OverseerSignal::ActiveLeaves(ActiveLeavesUpdate {
+						activated,
+						..
+					}))) => {
+						let mut result = Ok(());
+						for leaf in activated {
+							result = self.handle_active_leaf(&mut ctx, &mut state, &mut grace_periods, leaf).await;
+							if result.is_err() {
+								break;
+							}
+						}
+						result
+					}
+					Ok(FromOverseer::Signal(OverseerSignal::BlockFinalized(_))) => Ok(()),
+					Ok(FromOverseer::Signal(OverseerSignal::Conclude)) => return,
+					Ok(FromOverseer::Communication { msg }) => match msg {},
+					Err(e) => Err(e.into()),
+				},
+				session_index = grace_periods.select_next_some() => {
+					release_session(&mut ctx, &mut state, session_index).await
+				}
+				_ = refresh => {
+					refresh = Delay::new(self.refresh_interval).fuse();
+					match state.current {
+						Some(ref current) => connect_to_session(&mut ctx, current).await,
+						None => Ok(()),
+					}
+				}
+			};
+
+			match result {
+				Ok(()) => {}
+				Err(util::Error::Subsystem(e)) => {
+					debug!(target: LOG_TARGET, "Shutting down: {:?}", e);
+					return;
+				}
+				Err(e) => debug!(target: LOG_TARGET, "Error while handling an event: {:?}", e),
+			}
+		}
+	}
+
+	/// Issue the connection requests for the session of the child of `leaf`, if it is a new one,
+	/// and start the grace period of the session before.
+	async fn handle_active_leaf<Context>(
+		&self,
+		ctx: &mut Context,
+		state: &mut State,
+		grace_periods: &mut FuturesUnordered<BoxFuture<'static, SessionIndex>>,
+		leaf: Hash,
+	) -> Result<()>
+	where
+		Context: SubsystemContext<Message = GossipSupportMessage>,
+	{
+		let session_index = request_session_index_for_child_ctx(leaf, ctx).await?.await??;
+
+		if state.current.as_ref().map_or(false, |current| current.session_index >= session_index) {
+			return Ok(());
+		}
+
+		let validators = request_validators_ctx(leaf, ctx).await?.await??;
+		let (groups, _) = request_validator_groups_ctx(leaf, ctx).await?.await??;
+
+		let connections = session_connections(session_index, validators, &groups, &self.keystore);
+
+		trace!(
+			target: LOG_TARGET,
+			"New session {}, connecting to {} validators ({} in our group)",
+			session_index,
+			connections.validators.len(),
+			connections.our_group.len(),
+		);
+
+		connect_to_session(ctx, &connections).await?;
+
+		if let Some(previous) = state.current.replace(connections) {
+			let previous_index = previous.session_index;
+			state.retiring.push(previous);
+			grace_periods.push(Delay::new(self.grace_period).map(move |_| previous_index).boxed());
+		}
+
+		Ok(())
+	}
+}
+
+/// Determine the validators to connect to in a session: everyone but ourselves, and in
+/// particular the other members of our backing group.
+fn session_connections(
+	session_index: SessionIndex,
+	validators: Vec<ValidatorId>,
+	groups: &[Vec<ValidatorIndex>],
+	keystore: &KeyStorePtr,
+) -> SessionConnections {
+	let our_index = util::signing_key(&validators, keystore)
+		.and_then(|key| validators.iter().position(|v| *v == key.public()));
+
+	let our_group = our_index
+		.and_then(|index| groups.iter().find(|group| group.contains(&(index as ValidatorIndex))))
+		.map(|group| group
+			.iter()
+			.map(|&v| v as usize)
+			.filter(|&v| Some(v) != our_index)
+			.filter_map(|v| validators.get(v).cloned())
+			.collect()
+		)
+		.unwrap_or_default();
+
+	let validators = validators
+		.into_iter()
+		.enumerate()
+		.filter(|(i, _)| Some(*i) != our_index)
+		.map(|(_, v)| v)
+		.collect();
+
+	SessionConnections {
+		session_index,
+		validators,
+		our_group,
+	}
+}
+
+/// Ask the network bridge to connect to the validators of a session.
+///
+/// Our backing group is requested on its own, so that those connections are kept up
+/// even if the bridge is not able to serve the request for the whole validator set.
+async fn connect_to_session<Context>(
+	ctx: &mut Context,
+	connections: &SessionConnections,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = GossipSupportMessage>,
+{
+	for validators in &[&connections.validators, &connections.our_group] {
+		if validators.is_empty() {
+			continue;
+		}
+
+		// We are not interested in the peer ids, so the receiver is dropped right away.
+		let (tx, _) = oneshot::channel();
+		ctx.send_message(AllMessages::NetworkBridge(NetworkBridgeMessage::ConnectToValidators(
+			PeerSet::Validation,
+			(*validators).clone(),
+			tx,
+		))).await?;
+	}
+
+	Ok(())
+}
+
+/// Release the connections of a past session whose grace period is over, apart from those
+/// still needed by the current session or other sessions within their grace period.
+async fn release_session<Context>(
+	ctx: &mut Context,
+	state: &mut State,
+	session_index: SessionIndex,
+) -> Result<()>
+where
+	Context: SubsystemContext<Message = GossipSupportMessage>,
+{
+	let position = match state.retiring.iter().position(|s| s.session_index == session_index) {
+		Some(position) => position,
+		None => return Ok(()),
+	};
+	let retired = state.retiring.remove(position);
+
+	let still_needed = |v: &ValidatorId| {
+		state.current.iter().chain(state.retiring.iter()).any(|s| s.contains(v))
+	};
+
+	// Our group is part of the whole validator set, so releasing the latter is enough.
+	let validators: Vec<_> = retired.validators
+		.into_iter()
+		.filter(|v| !still_needed(v))
+		.collect();
+
+	trace!(
+		target: LOG_TARGET,
+		"Grace period of session {} is over, releasing {} validators",
+		session_index,
+		validators.len(),
+	);
+
+	if !validators.is_empty() {
+		ctx.send_message(AllMessages::NetworkBridge(NetworkBridgeMessage::ReleaseValidators(
+			PeerSet::Validation,
+			validators,
+		))).await?;
+	}
+
+	Ok(())
+}
+
+impl<Context> Subsystem<Context> for GossipSupport
+where
+	Context: SubsystemContext<Message = GossipSupportMessage> + Sync + Send,
+{
+	type Metrics = ();
+
+	fn start(self, ctx: Context) -> SpawnedSubsystem {
+		SpawnedSubsystem {
+			name: "gossip-support-subsystem",
+			future: self.run(ctx).boxed(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use assert_matches::assert_matches;
+	use futures::executor;
+	use polkadot_node_subsystem_test_helpers as test_helpers;
+	use polkadot_primitives::v1::{GroupRotationInfo, ValidatorPair};
+	use polkadot_subsystem::messages::RuntimeApiMessage;
+	use polkadot_subsystem::messages::RuntimeApiRequest;
+	use sc_keystore as keystore;
+	use sp_keyring::Sr25519Keyring;
+
+	type VirtualOverseer = test_helpers::TestSubsystemContextHandle<GossipSupportMessage>;
+
+	fn validator_id(keyring: Sr25519Keyring) -> ValidatorId {
+		keyring.public().into()
+	}
+
+	fn test_harness<T: Future<Output = ()>>(
+		grace_period: Duration,
+		test: impl FnOnce(VirtualOverseer) -> T,
+	) {
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (context, virtual_overseer) = test_helpers::make_subsystem_context(pool);
+
+		let keystore = keystore::Store::new_in_memory();
+		keystore
+			.write()
+			.insert_ephemeral_from_seed::<ValidatorPair>(&Sr25519Keyring::Alice.to_seed())
+			.expect("Insert key into keystore");
+
+		let subsystem = GossipSupport {
+			keystore,
+			grace_period,
+			refresh_interval: Duration::from_secs(60 * 60),
+		};
+
+		let subsystem = subsystem.run(context);
+		let test_fut = test(virtual_overseer);
+
+		futures::pin_mut!(test_fut);
+		futures::pin_mut!(subsystem);
+
+		executor::block_on(future::select(test_fut, subsystem));
+	}
+
+	async fn activate_leaf(virtual_overseer: &mut VirtualOverseer, leaf: Hash) {
+		virtual_overseer.send(FromOverseer::Signal(OverseerSignal::ActiveLeaves(
+			ActiveLeavesUpdate::start_work(leaf),
+		))).await;
+	}
+
+	async fn answer_session_index(virtual_overseer: &mut VirtualOverseer, leaf: Hash, index: SessionIndex) {
+		assert_matches!(
+			virtual_overseer.recv().await,
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+				relay_parent,
+				RuntimeApiRequest::SessionIndexForChild(tx),
+			)) => {
+				assert_eq!(relay_parent, leaf);
+				tx.send(Ok(index)).unwrap();
+			}
+		);
+	}
+
+	async fn answer_session_info(
+		virtual_overseer: &mut VirtualOverseer,
+		leaf: Hash,
+		validators: Vec<ValidatorId>,
+		groups: Vec<Vec<ValidatorIndex>>,
+	) {
+		assert_matches!(
+			virtual_overseer.recv().await,
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+				relay_parent,
+				RuntimeApiRequest::Validators(tx),
+			)) => {
+				assert_eq!(relay_parent, leaf);
+				tx.send(Ok(validators)).unwrap();
+			}
+		);
+
+		assert_matches!(
+			virtual_overseer.recv().await,
+			AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+				relay_parent,
+				RuntimeApiRequest::ValidatorGroups(tx),
+			)) => {
+				assert_eq!(relay_parent, leaf);
+				let rotation_info = GroupRotationInfo {
+					session_start_block: 0,
+					group_rotation_frequency: 100,
+					now: 1,
+				};
+				tx.send(Ok((groups, rotation_info))).unwrap();
+			}
+		);
+	}
+
+	async fn expect_connect(virtual_overseer: &mut VirtualOverseer, expected: Vec<ValidatorId>) {
+		assert_matches!(
+			virtual_overseer.recv().await,
+			AllMessages::NetworkBridge(NetworkBridgeMessage::ConnectToValidators(
+				PeerSet::Validation,
+				validators,
+				_,
+			)) => {
+				assert_eq!(validators, expected);
+			}
+		);
+	}
+
+	#[test]
+	fn new_session_connects_to_validators_and_our_group() {
+		test_harness(DEFAULT_GRACE_PERIOD, |mut virtual_overseer| async move {
+			let leaf = Hash::repeat_byte(1);
+			let validators = vec![
+				validator_id(Sr25519Keyring::Alice),
+				validator_id(Sr25519Keyring::Bob),
+				validator_id(Sr25519Keyring::Charlie),
+				validator_id(Sr25519Keyring::Dave),
+			];
+
+			activate_leaf(&mut virtual_overseer, leaf).await;
+			answer_session_index(&mut virtual_overseer, leaf, 1).await;
+			answer_session_info(&mut virtual_overseer, leaf, validators.clone(), vec![vec![0, 2], vec![1, 3]]).await;
+
+			expect_connect(&mut virtual_overseer, validators[1..].to_vec()).await;
+			expect_connect(&mut virtual_overseer, vec![validators[2].clone()]).await;
+
+			// A leaf of the same session does not cause new connection requests.
+			let next_leaf = Hash::repeat_byte(2);
+			activate_leaf(&mut virtual_overseer, next_leaf).await;
+			answer_session_index(&mut virtual_overseer, next_leaf, 1).await;
+
+			let last_leaf = Hash::repeat_byte(3);
+			activate_leaf(&mut virtual_overseer, last_leaf).await;
+			answer_session_index(&mut virtual_overseer, last_leaf, 1).await;
+		});
+	}
+
+	#[test]
+	fn past_session_is_released_after_grace_period() {
+		test_harness(Duration::from_millis(10), |mut virtual_overseer| async move {
+			let alice = validator_id(Sr25519Keyring::Alice);
+			let bob = validator_id(Sr25519Keyring::Bob);
+			let charlie = validator_id(Sr25519Keyring::Charlie);
+			let dave = validator_id(Sr25519Keyring::Dave);
+			let eve = validator_id(Sr25519Keyring::Eve);
+
+			let leaf = Hash::repeat_byte(1);
+			activate_leaf(&mut virtual_overseer, leaf).await;
+			answer_session_index(&mut virtual_overseer, leaf, 1).await;
+			answer_session_info(
+				&mut virtual_overseer,
+				leaf,
+				vec![alice.clone(), bob.clone(), charlie.clone(), dave.clone()],
+				vec![vec![0, 2], vec![1, 3]],
+			).await;
+			expect_connect(&mut virtual_overseer, vec![bob.clone(), charlie.clone(), dave.clone()]).await;
+			expect_connect(&mut virtual_overseer, vec![charlie.clone()]).await;
+
+			let leaf = Hash::repeat_byte(2);
+			activate_leaf(&mut virtual_overseer, leaf).await;
+			answer_session_index(&mut virtual_overseer, leaf, 2).await;
+			answer_session_info(
+				&mut virtual_overseer,
+				leaf,
+				vec![alice, bob.clone(), eve.clone()],
+				vec![vec![0, 1], vec![2]],
+			).await;
+			expect_connect(&mut virtual_overseer, vec![bob.clone(), eve]).await;
+			expect_connect(&mut virtual_overseer, vec![bob]).await;
+
+			// Bob is still a validator in the new session, so only Charlie and Dave are released.
+			assert_matches!(
+				virtual_overseer.recv().await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::ReleaseValidators(
+					PeerSet::Validation,
+					validators,
+				)) => {
+					assert_eq!(validators, vec![charlie, dave]);
+				}
+			);
+		});
+	}
+}
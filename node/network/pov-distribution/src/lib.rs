@@ -16,10 +16,11 @@
 
 //! PoV Distribution Subsystem of Polkadot.
 //!
-//! This is a gossip implementation of code that is responsible for distributing PoVs
-//! among validators.
+//! This is responsible for distributing PoVs among validators. PoVs are fetched from the
+//! backing group of the candidate over a request-response protocol, falling back to gossip if
+//! none of them answers.
 
-use polkadot_primitives::v1::{Hash, PoV, CandidateDescriptor};
+use polkadot_primitives::v1::{Hash, PoV, CandidateDescriptor, Id as ParaId, ValidatorId};
 use polkadot_subsystem::{
 	ActiveLeavesUpdate, OverseerSignal, SubsystemContext, Subsystem, SubsystemResult, FromOverseer, SpawnedSubsystem,
 };
@@ -28,24 +29,37 @@ use polkadot_subsystem::messages::{
 };
 use polkadot_node_network_protocol::{
 	v1 as protocol_v1, ReputationChange as Rep, NetworkBridgeEvent, PeerId, View,
+	IncomingRequest, RequestProtocol,
 };
+use node_primitives::CompressedPoV;
 
 use futures::prelude::*;
 use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
 
 use std::collections::{hash_map::{Entry, HashMap}, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 const COST_APPARENT_FLOOD: Rep = Rep::new(-500, "Peer appears to be flooding us with PoV requests");
 const COST_UNEXPECTED_POV: Rep = Rep::new(-500, "Peer sent us an unexpected PoV");
 const COST_AWAITED_NOT_IN_VIEW: Rep
 	= Rep::new(-100, "Peer claims to be awaiting something outside of its view");
+const COST_MALFORMED_POV: Rep = Rep::new(-500, "Peer sent a PoV which could not be decompressed");
 
 const BENEFIT_FRESH_POV: Rep = Rep::new(25, "Peer supplied us with an awaited PoV");
 const BENEFIT_LATE_POV: Rep = Rep::new(10, "Peer supplied us with an awaited PoV, \
 	but was not the first to do so");
 
+/// The most backers we ask for a PoV at once.
+const MAX_FETCH_ATTEMPTS: usize = 5;
+
+/// How long we wait for any of the backers to send us a PoV before falling back to gossip.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// The PoV Distribution Subsystem.
+#[derive(Clone)]
 pub struct PoVDistribution;
 
 impl<C> Subsystem<C> for PoVDistribution
@@ -67,6 +81,10 @@ struct State {
 	relay_parent_state: HashMap<Hash, BlockBasedState>,
 	peer_state: HashMap<PeerId, PeerState>,
 	our_view: View,
+	/// The validator set whose peers we keep track of.
+	known_validators: Vec<ValidatorId>,
+	/// The validators behind the peers we know of.
+	validator_peers: HashMap<PeerId, ValidatorId>,
 }
 
 struct BlockBasedState {
@@ -77,6 +95,8 @@ struct BlockBasedState {
 	/// received it already.
 	fetching: HashMap<Hash, Vec<oneshot::Sender<Arc<PoV>>>>,
 	n_validators: usize,
+	/// The validators backing candidates of each para scheduled or occupying a core.
+	backing_groups: HashMap<ParaId, Vec<ValidatorId>>,
 }
 
 #[derive(Default)]
@@ -85,6 +105,19 @@ struct PeerState {
 	awaited: HashMap<Hash, HashSet<Hash>>,
 }
 
+/// The outcome of fetching a PoV over the request-response protocol.
+struct FetchResult {
+	relay_parent: Hash,
+	pov_hash: Hash,
+	/// The PoV and the peer which provided it, if any did.
+	response: Option<(PeerId, PoV)>,
+	/// The peers which answered with a PoV other than the one we asked for.
+	unexpected: Vec<PeerId>,
+}
+
+/// The fetches of PoVs from peers which are in progress.
+type PendingFetches = FuturesUnordered<BoxFuture<'static, FetchResult>>;
+
 fn awaiting_message(relay_parent: Hash, awaiting: Vec<Hash>)
 	-> protocol_v1::ValidationProtocol
 {
@@ -93,12 +126,20 @@ fn awaiting_message(relay_parent: Hash, awaiting: Vec<Hash>)
 	)
 }
 
+/// The message sending a PoV. The PoV is compressed; the network bridge decompresses it for
+/// peers which don't support compressed PoVs.
 fn send_pov_message(relay_parent: Hash, pov_hash: Hash, pov: PoV)
 	-> protocol_v1::ValidationProtocol
 {
-	protocol_v1::ValidationProtocol::PoVDistribution(
-		protocol_v1::PoVDistributionMessage::SendPoV(relay_parent, pov_hash, pov)
-	)
+	let message = match CompressedPoV::compress(&pov) {
+		Ok(pov) => protocol_v1::PoVDistributionMessage::SendCompressedPoV(relay_parent, pov_hash, pov),
+		Err(e) => {
+			log::warn!(target: "pov_distribution", "Failed to compress PoV {}: {:?}", pov_hash, e);
+			protocol_v1::PoVDistributionMessage::SendPoV(relay_parent, pov_hash, pov)
+		}
+	};
+
+	protocol_v1::ValidationProtocol::PoVDistribution(message)
 }
 
 /// Handles the signal. If successful, returns `true` if the subsystem should conclude,
@@ -113,16 +154,33 @@ async fn handle_signal(
 		OverseerSignal::ActiveLeaves(ActiveLeavesUpdate { activated, deactivated }) => {
 			for relay_parent in activated {
 				let (vals_tx, vals_rx) = oneshot::channel();
-				ctx.send_message(AllMessages::RuntimeApi(RuntimeApiMessage::Request(
-					relay_parent,
-					RuntimeApiRequest::Validators(vals_tx),
-				))).await?;
-
-				let n_validators = match vals_rx.await? {
-					Ok(v) => v.len(),
-					Err(e) => {
+				let (groups_tx, groups_rx) = oneshot::channel();
+				let (cores_tx, cores_rx) = oneshot::channel();
+
+				ctx.send_messages(vec![
+					AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+						relay_parent,
+						RuntimeApiRequest::Validators(vals_tx),
+					)),
+					AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+						relay_parent,
+						RuntimeApiRequest::ValidatorGroups(groups_tx),
+					)),
+					AllMessages::RuntimeApi(RuntimeApiMessage::Request(
+						relay_parent,
+						RuntimeApiRequest::AvailabilityCores(cores_tx),
+					)),
+				].into_iter()).await?;
+
+				let (validators, (groups, rotation_info), cores) = match (
+					vals_rx.await?,
+					groups_rx.await?,
+					cores_rx.await?,
+				) {
+					(Ok(v), Ok(g), Ok(c)) => (v, g, c),
+					(Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
 						log::warn!(target: "pov_distribution",
-							"Error fetching validators from runtime API for active leaf: {:?}",
+							"Error fetching runtime API data for active leaf: {:?}",
 							e
 						);
 
@@ -135,10 +193,29 @@ async fn handle_signal(
 					}
 				};
 
+				let backing_groups = cores.iter()
+					.enumerate()
+					.filter_map(|(core_index, core)| {
+						let group_index = rotation_info.group_for_core(
+							(core_index as u32).into(),
+							cores.len(),
+						);
+						let group = groups.get(group_index.0 as usize)?
+							.iter()
+							.filter_map(|index| validators.get(*index as usize).cloned())
+							.collect();
+
+						Some((core.para_id()?, group))
+					})
+					.collect();
+
+				note_validators(state, &validators);
+
 				state.relay_parent_state.insert(relay_parent, BlockBasedState {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
-					n_validators: n_validators,
+					n_validators: validators.len(),
+					backing_groups,
 				});
 			}
 
@@ -152,6 +229,16 @@ async fn handle_signal(
 	}
 }
 
+/// Keep track of the peers of the given validator set only. The network bridge tells us about the
+/// peers of new validators, which we need to know in order to fetch PoVs from the backers.
+fn note_validators(state: &mut State, validators: &[ValidatorId]) {
+	if state.known_validators == validators {
+		return;
+	}
+	state.known_validators = validators.to_vec();
+	state.validator_peers.retain(|_, v| validators.contains(v));
+}
+
 /// Notify peers that we are awaiting a given PoV hash.
 ///
 /// This only notifies peers who have the relay parent in their view.
@@ -239,6 +326,7 @@ async fn distribute_to_awaiting(
 async fn handle_fetch(
 	state: &mut State,
 	ctx: &mut impl SubsystemContext<Message = PoVDistributionMessage>,
+	fetches: &mut PendingFetches,
 	relay_parent: Hash,
 	descriptor: CandidateDescriptor,
 	response_sender: oneshot::Sender<Arc<PoV>>,
@@ -272,15 +360,168 @@ async fn handle_fetch(
 		return Ok(());
 	}
 
-	// Issue an `Awaiting` message to all peers with this in their view.
-	notify_all_we_are_awaiting(
+	let pov_hash = descriptor.pov_hash;
+
+	// Only the backers of the candidate are bound to have its PoV. We use `awaited` as a proxy
+	// for which heads are in the peer's view.
+	let backers = relay_parent_state.backing_groups.get(&descriptor.para_id);
+	let peers: Vec<_> = state.peer_state.iter()
+		.filter(|(_, peer_state)| peer_state.awaited.contains_key(&relay_parent))
+		.filter(|(peer, _)| match (state.validator_peers.get(peer), backers) {
+			(Some(validator), Some(backers)) => backers.contains(validator),
+			_ => false,
+		})
+		.map(|(peer, _)| peer.clone())
+		.take(MAX_FETCH_ATTEMPTS)
+		.collect();
+
+	if peers.is_empty() {
+		return notify_all_we_are_awaiting(&mut state.peer_state, ctx, relay_parent, pov_hash).await;
+	}
+
+	// All the backers are asked at once, each of them by a request of its own.
+	let mut responses = FuturesUnordered::new();
+	for peer in peers {
+		let (tx, rx) = oneshot::channel();
+		ctx.send_message(AllMessages::NetworkBridge(NetworkBridgeMessage::FetchPoV(
+			vec![peer],
+			protocol_v1::PoVFetchingRequest { relay_parent, pov_hash },
+			tx,
+		))).await?;
+
+		responses.push(rx.map(|response| response.ok().flatten()));
+	}
+
+	fetches.push(first_expected_pov(relay_parent, pov_hash, responses).boxed());
+
+	Ok(())
+}
+
+/// Wait for the first of the responses carrying the PoV we asked for, up to `FETCH_TIMEOUT`.
+async fn first_expected_pov(
+	relay_parent: Hash,
+	pov_hash: Hash,
+	mut responses: FuturesUnordered<impl Future<Output = Option<(PeerId, PoV)>>>,
+) -> FetchResult {
+	let mut unexpected = Vec::new();
+	let mut timeout = futures_timer::Delay::new(FETCH_TIMEOUT).fuse();
+
+	let response = loop {
+		futures::select! {
+			response = responses.next() => match response {
+				Some(Some((peer, pov))) if pov.hash() == pov_hash => break Some((peer, pov)),
+				Some(Some((peer, _))) => unexpected.push(peer),
+				Some(None) => {}
+				None => break None,
+			},
+			_ = timeout => break None,
+		}
+	};
+
+	// Peers which have answered with something else by now are reported as well.
+	while let Some(Some(response)) = responses.next().now_or_never() {
+		match response {
+			Some((peer, pov)) if pov.hash() != pov_hash => unexpected.push(peer),
+			_ => {}
+		}
+	}
+
+	FetchResult { relay_parent, pov_hash, response, unexpected }
+}
+
+/// Handles the outcome of fetching a PoV from peers.
+///
+/// If none of them provided it, we fall back to letting everyone know we are awaiting it.
+async fn handle_fetch_result(
+	state: &mut State,
+	ctx: &mut impl SubsystemContext<Message = PoVDistributionMessage>,
+	result: FetchResult,
+) -> SubsystemResult<()> {
+	let FetchResult { relay_parent, pov_hash, response, unexpected } = result;
+
+	for peer in unexpected {
+		report_peer(ctx, peer, COST_UNEXPECTED_POV).await?;
+	}
+
+	let relay_parent_state = match state.relay_parent_state.get_mut(&relay_parent) {
+		None => return Ok(()),
+		Some(s) => s,
+	};
+
+	// Gossip may have delivered the PoV in the meantime, in which case it was distributed then.
+	if relay_parent_state.known.contains_key(&pov_hash) {
+		return Ok(());
+	}
+
+	let pov = match response {
+		Some((peer, pov)) => {
+			report_peer(ctx, peer, BENEFIT_FRESH_POV).await?;
+			Some(Arc::new(pov))
+		}
+		None => None,
+	};
+
+	let pov = match pov {
+		Some(pov) => pov,
+		None => {
+			let still_awaited = relay_parent_state.fetching.get(&pov_hash)
+				.map_or(false, |senders| !senders.is_empty());
+
+			if !still_awaited {
+				return Ok(());
+			}
+
+			// Issue an `Awaiting` message to all peers with this in their view.
+			return notify_all_we_are_awaiting(
+				&mut state.peer_state,
+				ctx,
+				relay_parent,
+				pov_hash,
+			).await;
+		}
+	};
+
+	if let Some(our_awaited) = relay_parent_state.fetching.get_mut(&pov_hash) {
+		for response_sender in our_awaited.drain(..) {
+			let _ = response_sender.send(pov.clone());
+		}
+	}
+
+	relay_parent_state.known.insert(pov_hash, pov.clone());
+
+	distribute_to_awaiting(
 		&mut state.peer_state,
 		ctx,
 		relay_parent,
-		descriptor.pov_hash
+		pov_hash,
+		&*pov,
 	).await
 }
 
+/// Answers a peer requesting a PoV from us.
+fn handle_pov_request(
+	state: &State,
+	request: IncomingRequest<protocol_v1::PoVFetchingRequest, protocol_v1::PoVFetchingResponse>,
+) {
+	let pov = state.relay_parent_state.get(&request.payload.relay_parent)
+		.and_then(|s| s.known.get(&request.payload.pov_hash));
+
+	let response = match pov {
+		Some(pov) if request.protocol == RequestProtocol::CompressedPoVFetching => {
+			match CompressedPoV::compress(pov) {
+				Ok(pov) => protocol_v1::PoVFetchingResponse::CompressedPoV(pov),
+				Err(_) => protocol_v1::PoVFetchingResponse::PoV((&**pov).clone()),
+			}
+		}
+		Some(pov) => protocol_v1::PoVFetchingResponse::PoV((&**pov).clone()),
+		None => protocol_v1::PoVFetchingResponse::NoSuchPoV,
+	};
+
+	if request.send_response(response).is_err() {
+		log::debug!(target: "pov_distribution", "Peer is no longer waiting for the requested PoV");
+	}
+}
+
 /// Handles a `DistributePoV` message.
 async fn handle_distribute(
 	state: &mut State,
@@ -501,12 +742,30 @@ async fn handle_network_update(
 						pov_hash,
 						pov,
 					).await,
+				protocol_v1::PoVDistributionMessage::SendCompressedPoV(relay_parent, pov_hash, pov)
+					=> match pov.decompress() {
+						Ok(pov) => handle_incoming_pov(
+							state,
+							ctx,
+							peer,
+							relay_parent,
+							pov_hash,
+							pov,
+						).await,
+						Err(_) => report_peer(ctx, peer, COST_MALFORMED_POV).await,
+					},
 			}
 		}
 		NetworkBridgeEvent::OurViewChange(view) => {
 			state.our_view = view;
 			Ok(())
 		}
+		NetworkBridgeEvent::ValidatorPeers(validator_peers) => {
+			state.validator_peers.extend(
+				validator_peers.into_iter().map(|(validator, peer)| (peer, validator))
+			);
+			Ok(())
+		}
 	}
 }
 
@@ -517,10 +776,37 @@ async fn run(
 		relay_parent_state: HashMap::new(),
 		peer_state: HashMap::new(),
 		our_view: View(Vec::new()),
+		known_validators: Vec::new(),
+		validator_peers: HashMap::new(),
 	};
 
+	enum Next {
+		FromOverseer(FromOverseer<PoVDistributionMessage>),
+		Fetched(FetchResult),
+	}
+
+	let mut fetches = PendingFetches::new();
+
 	loop {
-		match ctx.recv().await? {
+		let next = {
+			let from_overseer = ctx.recv().fuse();
+			futures::pin_mut!(from_overseer);
+
+			futures::select! {
+				msg = from_overseer => Next::FromOverseer(msg?),
+				result = fetches.select_next_some() => Next::Fetched(result),
+			}
+		};
+
+		let msg = match next {
+			Next::FromOverseer(msg) => msg,
+			Next::Fetched(result) => {
+				handle_fetch_result(&mut state, &mut ctx, result).await?;
+				continue;
+			}
+		};
+
+		match msg {
 			FromOverseer::Signal(signal) => if handle_signal(&mut state, &mut ctx, signal).await? {
 				return Ok(());
 			},
@@ -529,6 +815,7 @@ async fn run(
 					handle_fetch(
 						&mut state,
 						&mut ctx,
+						&mut fetches,
 						relay_parent,
 						descriptor,
 						response_sender,
@@ -547,6 +834,8 @@ async fn run(
 						&mut ctx,
 						event,
 					).await?,
+				PoVDistributionMessage::PoVFetchingRequest(request) =>
+					handle_pov_request(&state, request),
 			},
 		}
 	}
@@ -558,11 +847,16 @@ mod tests {
 	use futures::executor;
 	use polkadot_primitives::v1::BlockData;
 	use assert_matches::assert_matches;
+	use parity_scale_codec::{Decode, Encode};
 
 	fn make_pov(data: Vec<u8>) -> PoV {
 		PoV { block_data: BlockData(data) }
 	}
 
+	fn make_validator(n: u8) -> ValidatorId {
+		sp_core::sr25519::Public::from_raw([n; 32]).into()
+	}
+
 	fn make_peer_state(awaited: Vec<(Hash, Vec<Hash>)>)
 		-> PeerState
 	{
@@ -591,6 +885,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				b.fetching.insert(pov_hash, vec![pov_send]);
@@ -621,6 +916,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a, hash_b]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -665,6 +962,7 @@ mod tests {
 
 		let peer_a = PeerId::random();
 		let peer_b = PeerId::random();
+		let peer_c = PeerId::random();
 
 		let (pov_send, _) = oneshot::channel();
 		let pov = make_pov(vec![1, 2, 3]);
@@ -677,6 +975,9 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: vec![
+						(ParaId::from(1), vec![make_validator(0), make_validator(1)]),
+					].into_iter().collect(),
 				};
 
 				s.insert(hash_a, b);
@@ -697,20 +998,36 @@ mod tests {
 					make_peer_state(vec![(hash_b, vec![])]),
 				);
 
+				// peer C has hash_a in its view, but is not a backer.
+				s.insert(
+					peer_c.clone(),
+					make_peer_state(vec![(hash_a, vec![])]),
+				);
+
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: vec![
+				(peer_a.clone(), make_validator(0)),
+				(peer_b.clone(), make_validator(1)),
+				(peer_c.clone(), make_validator(2)),
+			].into_iter().collect(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
 		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
 		let mut descriptor = CandidateDescriptor::default();
 		descriptor.pov_hash = pov_hash;
+		descriptor.para_id = 1.into();
 
 		executor::block_on(async move {
+			let mut fetches = PendingFetches::new();
+
 			handle_fetch(
 				&mut state,
 				&mut ctx,
+				&mut fetches,
 				hash_a,
 				descriptor,
 				pov_send,
@@ -718,12 +1035,29 @@ mod tests {
 
 			assert_eq!(state.relay_parent_state[&hash_a].fetching[&pov_hash].len(), 1);
 
+			// We first ask the backers with the relay-parent in view directly.
 			assert_matches!(
 				handle.recv().await,
 				AllMessages::NetworkBridge(
-					NetworkBridgeMessage::SendValidationMessage(peers, message)
+					NetworkBridgeMessage::FetchPoV(peers, request, response)
 				) => {
 					assert_eq!(peers, vec![peer_a.clone()]);
+					assert_eq!(request, protocol_v1::PoVFetchingRequest { relay_parent: hash_a, pov_hash });
+					let _ = response.send(None);
+				}
+			);
+
+			// None of them had it, so we let them know we are awaiting it.
+			let result = fetches.next().await.unwrap();
+			handle_fetch_result(&mut state, &mut ctx, result).await.unwrap();
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::SendValidationMessage(peers, message)
+				) => {
+					let peers: HashSet<_> = peers.into_iter().collect();
+					assert_eq!(peers, vec![peer_a.clone(), peer_c.clone()].into_iter().collect());
 					assert_eq!(
 						message,
 						awaiting_message(hash_a, vec![pov_hash]),
@@ -733,6 +1067,338 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn fetched_pov_completes_local_and_awaiting_peers() {
+		let hash_a: Hash = [0; 32].into();
+
+		let peer_a = PeerId::random();
+		let peer_b = PeerId::random();
+
+		let (pov_send, pov_recv) = oneshot::channel();
+		let pov = make_pov(vec![1, 2, 3]);
+		let pov_hash = pov.hash();
+
+		let mut state = State {
+			relay_parent_state: {
+				let mut s = HashMap::new();
+				let b = BlockBasedState {
+					known: HashMap::new(),
+					fetching: HashMap::new(),
+					n_validators: 10,
+					backing_groups: vec![
+						(ParaId::from(1), vec![make_validator(0), make_validator(1)]),
+					].into_iter().collect(),
+				};
+
+				s.insert(hash_a, b);
+				s
+			},
+			peer_state: {
+				let mut s = HashMap::new();
+
+				// peer A has hash_a in its view.
+				s.insert(
+					peer_a.clone(),
+					make_peer_state(vec![(hash_a, vec![])]),
+				);
+
+				// peer B has hash_a in its view and is awaiting the PoV.
+				s.insert(
+					peer_b.clone(),
+					make_peer_state(vec![(hash_a, vec![pov_hash])]),
+				);
+
+				s
+			},
+			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: vec![
+				(peer_a.clone(), make_validator(0)),
+				(peer_b.clone(), make_validator(1)),
+			].into_iter().collect(),
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+		let mut descriptor = CandidateDescriptor::default();
+		descriptor.pov_hash = pov_hash;
+		descriptor.para_id = 1.into();
+
+		executor::block_on(async move {
+			let mut fetches = PendingFetches::new();
+
+			handle_fetch(
+				&mut state,
+				&mut ctx,
+				&mut fetches,
+				hash_a,
+				descriptor,
+				pov_send,
+			).await.unwrap();
+
+			// Both backers are asked at once.
+			let mut responses = HashMap::new();
+			for _ in 0..2 {
+				assert_matches!(
+					handle.recv().await,
+					AllMessages::NetworkBridge(
+						NetworkBridgeMessage::FetchPoV(mut peers, _, response)
+					) => {
+						assert_eq!(peers.len(), 1);
+						responses.insert(peers.pop().unwrap(), response);
+					}
+				);
+			}
+
+			let _ = responses.remove(&peer_a).unwrap().send(Some((peer_a.clone(), pov.clone())));
+			assert!(responses.contains_key(&peer_b));
+
+			let result = fetches.next().await.unwrap();
+			handle_fetch_result(&mut state, &mut ctx, result).await.unwrap();
+
+			assert_eq!(&*pov_recv.await.unwrap(), &pov);
+			assert!(state.relay_parent_state[&hash_a].known.contains_key(&pov_hash));
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, peer_a);
+					assert_eq!(rep, BENEFIT_FRESH_POV);
+				}
+			);
+
+			// The peer awaiting it through gossip gets it as well.
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::SendValidationMessage(peers, message)
+				) => {
+					assert_eq!(peers, vec![peer_b.clone()]);
+					assert_eq!(message, send_pov_message(hash_a, pov_hash, pov.clone()));
+				}
+			);
+		});
+	}
+
+	#[test]
+	fn fetches_from_a_limited_number_of_backers() {
+		let hash_a: Hash = [0; 32].into();
+
+		let peers: Vec<_> = (0..MAX_FETCH_ATTEMPTS + 2).map(|_| PeerId::random()).collect();
+
+		let (pov_send, pov_recv) = oneshot::channel();
+		let pov = make_pov(vec![1, 2, 3]);
+		let pov_hash = pov.hash();
+
+		let mut state = State {
+			relay_parent_state: {
+				let mut s = HashMap::new();
+				let b = BlockBasedState {
+					known: HashMap::new(),
+					fetching: HashMap::new(),
+					n_validators: 10,
+					backing_groups: vec![
+						(ParaId::from(1), (0..peers.len() as u8).map(make_validator).collect()),
+					].into_iter().collect(),
+				};
+
+				s.insert(hash_a, b);
+				s
+			},
+			peer_state: peers.iter()
+				.map(|peer| (peer.clone(), make_peer_state(vec![(hash_a, vec![])])))
+				.collect(),
+			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: peers.iter()
+				.enumerate()
+				.map(|(i, peer)| (peer.clone(), make_validator(i as u8)))
+				.collect(),
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+		let mut descriptor = CandidateDescriptor::default();
+		descriptor.pov_hash = pov_hash;
+		descriptor.para_id = 1.into();
+
+		executor::block_on(async move {
+			let mut fetches = PendingFetches::new();
+
+			handle_fetch(
+				&mut state,
+				&mut ctx,
+				&mut fetches,
+				hash_a,
+				descriptor,
+				pov_send,
+			).await.unwrap();
+
+			let mut responses = Vec::new();
+			for _ in 0..MAX_FETCH_ATTEMPTS {
+				assert_matches!(
+					handle.recv().await,
+					AllMessages::NetworkBridge(
+						NetworkBridgeMessage::FetchPoV(mut peers, _, response)
+					) => {
+						assert_eq!(peers.len(), 1);
+						responses.push((peers.pop().unwrap(), response));
+					}
+				);
+			}
+
+			// one backer sends something else, another one what we asked for.
+			let (bad_peer, response) = responses.remove(0);
+			let _ = response.send(Some((bad_peer.clone(), make_pov(vec![4, 5, 6]))));
+			let (good_peer, response) = responses.pop().unwrap();
+			let _ = response.send(Some((good_peer.clone(), pov.clone())));
+
+			let result = fetches.next().await.unwrap();
+			handle_fetch_result(&mut state, &mut ctx, result).await.unwrap();
+
+			assert_eq!(&*pov_recv.await.unwrap(), &pov);
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, bad_peer);
+					assert_eq!(rep, COST_UNEXPECTED_POV);
+				}
+			);
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, good_peer);
+					assert_eq!(rep, BENEFIT_FRESH_POV);
+				}
+			);
+
+			// the other backers were never asked.
+			drop(ctx);
+			assert!(handle.try_recv().await.is_none());
+		});
+	}
+
+	#[test]
+	fn fetched_pov_already_gossiped_is_ignored() {
+		let hash_a: Hash = [0; 32].into();
+		let peer = PeerId::random();
+
+		let pov = make_pov(vec![1, 2, 3]);
+		let pov_hash = pov.hash();
+
+		let mut state = State {
+			relay_parent_state: {
+				let mut s = HashMap::new();
+				let mut b = BlockBasedState {
+					known: HashMap::new(),
+					fetching: HashMap::new(),
+					n_validators: 10,
+					backing_groups: HashMap::new(),
+				};
+
+				b.known.insert(pov_hash, Arc::new(pov.clone()));
+				s.insert(hash_a, b);
+				s
+			},
+			peer_state: vec![
+				(peer.clone(), make_peer_state(vec![(hash_a, vec![pov_hash])])),
+			].into_iter().collect(),
+			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+
+		executor::block_on(async move {
+			handle_fetch_result(&mut state, &mut ctx, FetchResult {
+				relay_parent: hash_a,
+				pov_hash,
+				response: Some((peer, pov)),
+				unexpected: Vec::new(),
+			}).await.unwrap();
+
+			// the peer is neither rewarded nor sent the PoV again.
+			drop(ctx);
+			assert!(handle.try_recv().await.is_none());
+		});
+	}
+
+	#[test]
+	fn pov_requests_are_served_from_known() {
+		let hash_a: Hash = [0; 32].into();
+
+		let pov = make_pov(vec![1, 2, 3]);
+		let pov_hash = pov.hash();
+
+		let state = State {
+			relay_parent_state: {
+				let mut s = HashMap::new();
+				let mut b = BlockBasedState {
+					known: HashMap::new(),
+					fetching: HashMap::new(),
+					n_validators: 10,
+					backing_groups: HashMap::new(),
+				};
+
+				b.known.insert(pov_hash, Arc::new(pov.clone()));
+				s.insert(hash_a, b);
+				s
+			},
+			peer_state: HashMap::new(),
+			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
+		};
+
+		let request = |protocol, pov_hash| {
+			let (tx, rx) = oneshot::channel();
+			let request = IncomingRequest::new(
+				PeerId::random(),
+				protocol,
+				protocol_v1::PoVFetchingRequest { relay_parent: hash_a, pov_hash },
+				tx,
+			);
+
+			(request, rx)
+		};
+
+		executor::block_on(async move {
+			let (known, known_response) = request(RequestProtocol::PoVFetching, pov_hash);
+			handle_pov_request(&state, known);
+			assert_eq!(
+				known_response.await.unwrap(),
+				protocol_v1::PoVFetchingResponse::PoV(pov.clone()).encode(),
+			);
+
+			// Peers asking on the compressed protocol get a compressed PoV.
+			let (compressed, compressed_response) = request(RequestProtocol::CompressedPoVFetching, pov_hash);
+			handle_pov_request(&state, compressed);
+			assert_matches!(
+				protocol_v1::PoVFetchingResponse::decode(&mut &compressed_response.await.unwrap()[..]),
+				Ok(protocol_v1::PoVFetchingResponse::CompressedPoV(compressed)) => {
+					assert_eq!(compressed.decompress(), Ok(pov));
+				}
+			);
+
+			let (unknown, unknown_response) = request(RequestProtocol::PoVFetching, [1; 32].into());
+			handle_pov_request(&state, unknown);
+			assert_eq!(
+				unknown_response.await.unwrap(),
+				protocol_v1::PoVFetchingResponse::NoSuchPoV.encode(),
+			);
+		});
+	}
+
 	#[test]
 	fn peer_view_change_leads_to_us_informing() {
 		let hash_a: Hash = [0; 32].into();
@@ -755,6 +1421,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				// pov_a is still being fetched, whereas the fetch of pov_b has already
@@ -777,6 +1444,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -823,6 +1492,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				// pov is being fetched.
@@ -848,6 +1518,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -917,6 +1589,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				// pov is being fetched.
@@ -936,6 +1609,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -967,6 +1642,76 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn peer_punished_for_sending_malformed_compressed_pov() {
+		let hash_a: Hash = [0; 32].into();
+
+		let peer_a = PeerId::random();
+
+		let (pov_send, _) = oneshot::channel();
+		let pov_hash = make_pov(vec![1, 2, 3]).hash();
+
+		// not zstd at all.
+		let garbage = CompressedPoV::decode(&mut &vec![6u8, 6, 6].encode()[..]).unwrap();
+
+		let mut state = State {
+			relay_parent_state: {
+				let mut s = HashMap::new();
+				let mut b = BlockBasedState {
+					known: HashMap::new(),
+					fetching: HashMap::new(),
+					n_validators: 10,
+					backing_groups: HashMap::new(),
+				};
+
+				b.fetching.insert(pov_hash, vec![pov_send]);
+
+				s.insert(hash_a, b);
+				s
+			},
+			peer_state: {
+				let mut s = HashMap::new();
+
+				s.insert(
+					peer_a.clone(),
+					make_peer_state(vec![(hash_a, vec![])]),
+				);
+
+				s
+			},
+			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+
+		executor::block_on(async move {
+			handle_network_update(
+				&mut state,
+				&mut ctx,
+				NetworkBridgeEvent::PeerMessage(
+					peer_a.clone(),
+					protocol_v1::PoVDistributionMessage::SendCompressedPoV(hash_a, pov_hash, garbage),
+				),
+			).await.unwrap();
+
+			// didn't complete our sender.
+			assert_eq!(state.relay_parent_state[&hash_a].fetching[&pov_hash].len(), 1);
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(
+					NetworkBridgeMessage::ReportPeer(peer, rep)
+				) => {
+					assert_eq!(peer, peer_a);
+					assert_eq!(rep, COST_MALFORMED_POV);
+				}
+			);
+		});
+	}
+
 	#[test]
 	fn peer_punished_for_sending_unexpected_pov() {
 		let hash_a: Hash = [0; 32].into();
@@ -983,6 +1728,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				s.insert(hash_a, b);
@@ -999,6 +1745,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1044,6 +1792,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				s.insert(hash_a, b);
@@ -1060,6 +1809,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1102,6 +1853,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators,
+					backing_groups: HashMap::new(),
 				};
 
 				s.insert(hash_a, b);
@@ -1118,6 +1870,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1181,12 +1935,14 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				});
 
 				s.insert(hash_b, BlockBasedState {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				});
 
 				s
@@ -1203,6 +1959,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a, hash_b]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1249,6 +2007,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				});
 
 				s
@@ -1265,6 +2024,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1317,6 +2078,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				// pov is being fetched.
@@ -1342,6 +2104,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1404,6 +2168,7 @@ mod tests {
 					known: HashMap::new(),
 					fetching: HashMap::new(),
 					n_validators: 10,
+					backing_groups: HashMap::new(),
 				};
 
 				// pov is being fetched.
@@ -1424,6 +2189,8 @@ mod tests {
 				s
 			},
 			our_view: View(vec![hash_a]),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
 		};
 
 		let pool = sp_core::testing::TaskExecutor::new();
@@ -1455,4 +2222,45 @@ mod tests {
 			assert!(!state.peer_state[&peer_a].awaited[&hash_a].contains(&pov_hash));
 		});
 	}
+
+	#[test]
+	fn validator_peers_are_learned_from_the_bridge() {
+		let peer_a = PeerId::random();
+		let peer_b = PeerId::random();
+		let validator_a = make_validator(1);
+		let validator_b = make_validator(2);
+
+		let mut state = State {
+			relay_parent_state: HashMap::new(),
+			peer_state: HashMap::new(),
+			our_view: View(Vec::new()),
+			known_validators: Vec::new(),
+			validator_peers: HashMap::new(),
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+
+		executor::block_on(async move {
+			handle_network_update(
+				&mut state,
+				&mut ctx,
+				NetworkBridgeEvent::ValidatorPeers(vec![
+					(validator_a.clone(), peer_a.clone()),
+					(validator_b.clone(), peer_b.clone()),
+				]),
+			).await.unwrap();
+
+			assert_eq!(state.validator_peers.get(&peer_a), Some(&validator_a));
+			assert_eq!(state.validator_peers.get(&peer_b), Some(&validator_b));
+
+			// validators leaving the set are forgotten.
+			note_validators(&mut state, &[validator_a.clone()]);
+			assert_eq!(state.validator_peers.get(&peer_a), Some(&validator_a));
+			assert!(state.validator_peers.get(&peer_b).is_none());
+
+			drop(ctx);
+			assert!(handle.try_recv().await.is_none());
+		});
+	}
 }
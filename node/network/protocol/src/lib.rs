@@ -16,9 +16,13 @@
 
 //! Network protocol types for parachains.
 
-use polkadot_primitives::v1::Hash;
+use polkadot_primitives::v1::{Hash, ValidatorId};
 use parity_scale_codec::{Encode, Decode};
+use futures::channel::oneshot;
 use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
 
 pub use sc_network::{ReputationChange, PeerId};
 
@@ -28,12 +32,16 @@ pub type RequestId = u64;
 /// A version of the protocol.
 pub type ProtocolVersion = u32;
 
+/// The versions of the peer-set protocols we speak, oldest first. Each peer is talked to in the
+/// highest version both sides support.
+pub const PROTOCOL_VERSIONS: [ProtocolVersion; 2] = [1, 2];
+
 /// An error indicating that this the over-arching message type had the wrong variant
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WrongVariant;
 
 /// The peer-sets that the network manages. Different subsystems will use different peer-sets.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PeerSet {
 	/// The validation peer-set is responsible for all messages related to candidate validation and communication among validators.
 	Validation,
@@ -75,6 +83,107 @@ impl Into<sc_network::ObservedRole> for ObservedRole {
 	}
 }
 
+/// The request-response protocols the network manages, next to the notification protocols
+/// of the [`PeerSet`]s.
+///
+/// [`PeerSet`]: enum.PeerSet.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestProtocol {
+	/// Fetching a PoV from a peer, see `v1::PoVFetchingRequest`.
+	PoVFetching,
+	/// Fetching an erasure chunk from a peer, see `v1::ChunkFetchingRequest`.
+	ChunkFetching,
+	/// Fetching a PoV from a peer, which may answer with a compressed PoV.
+	CompressedPoVFetching,
+}
+
+/// The upper bound on the size of a PoV we are willing to fetch.
+const MAX_POV_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+
+impl RequestProtocol {
+	/// All the request-response protocols.
+	pub const ALL: [RequestProtocol; 3] = [
+		RequestProtocol::PoVFetching,
+		RequestProtocol::ChunkFetching,
+		RequestProtocol::CompressedPoVFetching,
+	];
+
+	/// The name the protocol is registered under with the network.
+	pub fn name(&self) -> &'static str {
+		match self {
+			RequestProtocol::PoVFetching => "/polkadot/req_pov/1",
+			RequestProtocol::ChunkFetching => "/polkadot/req_chunk/1",
+			RequestProtocol::CompressedPoVFetching => "/polkadot/req_pov/2",
+		}
+	}
+
+	/// Responses larger than this are rejected.
+	pub fn max_response_size(&self) -> u64 {
+		match self {
+			RequestProtocol::PoVFetching | RequestProtocol::CompressedPoVFetching => MAX_POV_RESPONSE_SIZE,
+			// A chunk together with its proof is never larger than the data it is a piece of.
+			RequestProtocol::ChunkFetching => MAX_POV_RESPONSE_SIZE,
+		}
+	}
+
+	/// How long to wait for a response from a peer before giving up on it.
+	pub fn request_timeout(&self) -> Duration {
+		match self {
+			RequestProtocol::PoVFetching | RequestProtocol::CompressedPoVFetching => Duration::from_secs(3),
+			RequestProtocol::ChunkFetching => Duration::from_secs(1),
+		}
+	}
+}
+
+/// A request received from a peer on one of the [`RequestProtocol`]s, handed to the subsystem
+/// which is supposed to answer it.
+///
+/// [`RequestProtocol`]: enum.RequestProtocol.html
+pub struct IncomingRequest<Req, Resp> {
+	/// The peer that sent the request.
+	pub peer: PeerId,
+	/// The protocol the request was received on.
+	pub protocol: RequestProtocol,
+	/// The request itself.
+	pub payload: Req,
+	pending_response: oneshot::Sender<Vec<u8>>,
+	_response: PhantomData<fn() -> Resp>,
+}
+
+impl<Req, Resp: Encode> IncomingRequest<Req, Resp> {
+	/// Wrap a decoded request together with the channel the encoded response is expected on.
+	pub fn new(
+		peer: PeerId,
+		protocol: RequestProtocol,
+		payload: Req,
+		pending_response: oneshot::Sender<Vec<u8>>,
+	) -> Self {
+		IncomingRequest {
+			peer,
+			protocol,
+			payload,
+			pending_response,
+			_response: PhantomData,
+		}
+	}
+
+	/// Answer the request. Fails if the network is no longer interested in the response,
+	/// e.g. because the request timed out.
+	pub fn send_response(self, response: Resp) -> Result<(), ()> {
+		self.pending_response.send(response.encode()).map_err(|_| ())
+	}
+}
+
+impl<Req: fmt::Debug, Resp> fmt::Debug for IncomingRequest<Req, Resp> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("IncomingRequest")
+			.field("peer", &self.peer)
+			.field("protocol", &self.protocol)
+			.field("payload", &self.payload)
+			.finish()
+	}
+}
+
 /// Events from network.
 #[derive(Debug, Clone, PartialEq)]
 pub enum NetworkBridgeEvent<M> {
@@ -92,6 +201,9 @@ pub enum NetworkBridgeEvent<M> {
 
 	/// Our `View` has changed.
 	OurViewChange(View),
+
+	/// The peers of validators the network bridge was asked to connect to have become known.
+	ValidatorPeers(Vec<(ValidatorId, PeerId)>),
 }
 
 macro_rules! impl_try_from {
@@ -142,6 +254,8 @@ impl<M> NetworkBridgeEvent<M> {
 				=> NetworkBridgeEvent::PeerViewChange(peer.clone(), view.clone()),
 			NetworkBridgeEvent::OurViewChange(ref view)
 				=> NetworkBridgeEvent::OurViewChange(view.clone()),
+			NetworkBridgeEvent::ValidatorPeers(ref validator_peers)
+				=> NetworkBridgeEvent::ValidatorPeers(validator_peers.clone()),
 		})
 	}
 }
@@ -173,13 +287,57 @@ impl View {
 pub mod v1 {
 	use polkadot_primitives::v1::{
 		Hash, CollatorId, Id as ParaId, ErasureChunk, CandidateReceipt,
-		SignedAvailabilityBitfield, PoV,
+		SignedAvailabilityBitfield, PoV, ValidatorIndex,
 	};
-	use polkadot_node_primitives::SignedFullStatement;
+	use polkadot_node_primitives::{SignedFullStatement, CompressedPoV, CompressedPoVError};
 	use parity_scale_codec::{Encode, Decode};
 	use std::convert::TryFrom;
 	use super::RequestId;
 
+	/// A request for the PoV with the given hash, backed under the given relay-parent.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub struct PoVFetchingRequest {
+		/// The relay-parent the candidate was backed under.
+		pub relay_parent: Hash,
+		/// The hash of the requested PoV.
+		pub pov_hash: Hash,
+	}
+
+	/// The response to a `PoVFetchingRequest`.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub enum PoVFetchingResponse {
+		/// The requested PoV.
+		#[codec(index = "0")]
+		PoV(PoV),
+		/// The peer doesn't have the requested PoV.
+		#[codec(index = "1")]
+		NoSuchPoV,
+		/// The requested PoV, compressed. Only sent in response to requests on
+		/// `RequestProtocol::CompressedPoVFetching`.
+		#[codec(index = "2")]
+		CompressedPoV(CompressedPoV),
+	}
+
+	/// A request for the erasure chunk of the given validator of the given candidate.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub struct ChunkFetchingRequest {
+		/// The hash of the candidate the chunk belongs to.
+		pub candidate_hash: Hash,
+		/// The index of the validator the chunk is meant for.
+		pub index: ValidatorIndex,
+	}
+
+	/// The response to a `ChunkFetchingRequest`.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub enum ChunkFetchingResponse {
+		/// The requested chunk.
+		#[codec(index = "0")]
+		Chunk(ErasureChunk),
+		/// The peer doesn't have the requested chunk.
+		#[codec(index = "1")]
+		NoSuchChunk,
+	}
+
 	/// Network messages used by the availability distribution subsystem
 	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
 	pub enum AvailabilityDistributionMessage {
@@ -207,6 +365,10 @@ pub mod v1 {
 		/// (relay_parent, pov_hash, pov)
 		#[codec(index = "1")]
 		SendPoV(Hash, Hash, PoV),
+		/// Like `SendPoV`, with the PoV compressed. Only understood by peers supporting
+		/// compressed PoVs.
+		#[codec(index = "2")]
+		SendCompressedPoV(Hash, Hash, CompressedPoV),
 	}
 
 	/// Network messages used by the statement distribution subsystem.
@@ -233,8 +395,109 @@ pub mod v1 {
 		/// A requested collation.
 		#[codec(index = "3")]
 		Collation(RequestId, CandidateReceipt, PoV),
+		/// A requested collation, with the PoV compressed. Only understood by peers supporting
+		/// compressed PoVs.
+		#[codec(index = "4")]
+		CompressedCollation(RequestId, CandidateReceipt, CompressedPoV),
+	}
+
+	/// All network messages on the validation peer-set.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub enum ValidationProtocol {
+		/// Availability distribution messages
+		#[codec(index = "0")]
+		AvailabilityDistribution(AvailabilityDistributionMessage),
+		/// Bitfield distribution messages
+		#[codec(index = "1")]
+		BitfieldDistribution(BitfieldDistributionMessage),
+		/// PoV Distribution messages
+		#[codec(index = "2")]
+		PoVDistribution(PoVDistributionMessage),
+		/// Statement distribution messages
+		#[codec(index = "3")]
+		StatementDistribution(StatementDistributionMessage),
+	}
+
+	impl_try_from!(ValidationProtocol, AvailabilityDistribution, AvailabilityDistributionMessage);
+	impl_try_from!(ValidationProtocol, BitfieldDistribution, BitfieldDistributionMessage);
+	impl_try_from!(ValidationProtocol, PoVDistribution, PoVDistributionMessage);
+	impl_try_from!(ValidationProtocol, StatementDistribution, StatementDistributionMessage);
+
+	/// All network messages on the collation peer-set.
+	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+	pub enum CollationProtocol {
+		/// Collator protocol messages
+		#[codec(index = "0")]
+		CollatorProtocol(CollatorProtocolMessage),
 	}
 
+	impl_try_from!(CollationProtocol, CollatorProtocol, CollatorProtocolMessage);
+
+	/// Network messages which may carry compressed PoVs, which not all peers understand.
+	pub trait MaybeCompressed: Sized {
+		/// Whether the message carries a compressed PoV.
+		fn has_compressed_pov(&self) -> bool;
+
+		/// The message in the form understood by peers without support for compressed PoVs.
+		fn decompressed(self) -> Result<Self, CompressedPoVError>;
+	}
+
+	impl MaybeCompressed for ValidationProtocol {
+		fn has_compressed_pov(&self) -> bool {
+			match self {
+				ValidationProtocol::PoVDistribution(PoVDistributionMessage::SendCompressedPoV(..)) => true,
+				_ => false,
+			}
+		}
+
+		fn decompressed(self) -> Result<Self, CompressedPoVError> {
+			match self {
+				ValidationProtocol::PoVDistribution(
+					PoVDistributionMessage::SendCompressedPoV(relay_parent, pov_hash, pov),
+				) => Ok(ValidationProtocol::PoVDistribution(
+					PoVDistributionMessage::SendPoV(relay_parent, pov_hash, pov.decompress()?),
+				)),
+				other => Ok(other),
+			}
+		}
+	}
+
+	impl MaybeCompressed for CollationProtocol {
+		fn has_compressed_pov(&self) -> bool {
+			match self {
+				CollationProtocol::CollatorProtocol(CollatorProtocolMessage::CompressedCollation(..)) => true,
+				_ => false,
+			}
+		}
+
+		fn decompressed(self) -> Result<Self, CompressedPoVError> {
+			match self {
+				CollationProtocol::CollatorProtocol(
+					CollatorProtocolMessage::CompressedCollation(request_id, receipt, pov),
+				) => Ok(CollationProtocol::CollatorProtocol(
+					CollatorProtocolMessage::Collation(request_id, receipt, pov.decompress()?),
+				)),
+				other => Ok(other),
+			}
+		}
+	}
+}
+
+/// v2 protocol types.
+///
+/// Scaffolding for the next version of the peer-set protocols. Messages which are the same as in
+/// v1 are re-exported from there; changed ones get defined here, together with their conversion
+/// to and from v1. Subsystems speak v1, the network bridge converts messages from and to v2 peers.
+pub mod v2 {
+	use std::convert::TryFrom;
+	use parity_scale_codec::{Encode, Decode};
+	use super::v1;
+
+	pub use super::v1::{
+		AvailabilityDistributionMessage, BitfieldDistributionMessage, PoVDistributionMessage,
+		StatementDistributionMessage, CollatorProtocolMessage,
+	};
+
 	/// All network messages on the validation peer-set.
 	#[derive(Debug, Clone, Encode, Decode, PartialEq)]
 	pub enum ValidationProtocol {
@@ -266,4 +529,89 @@ pub mod v1 {
 	}
 
 	impl_try_from!(CollationProtocol, CollatorProtocol, CollatorProtocolMessage);
+
+	impl From<v1::ValidationProtocol> for ValidationProtocol {
+		fn from(message: v1::ValidationProtocol) -> Self {
+			match message {
+				v1::ValidationProtocol::AvailabilityDistribution(m)
+					=> ValidationProtocol::AvailabilityDistribution(m),
+				v1::ValidationProtocol::BitfieldDistribution(m)
+					=> ValidationProtocol::BitfieldDistribution(m),
+				v1::ValidationProtocol::PoVDistribution(m)
+					=> ValidationProtocol::PoVDistribution(m),
+				v1::ValidationProtocol::StatementDistribution(m)
+					=> ValidationProtocol::StatementDistribution(m),
+			}
+		}
+	}
+
+	impl From<ValidationProtocol> for v1::ValidationProtocol {
+		fn from(message: ValidationProtocol) -> Self {
+			match message {
+				ValidationProtocol::AvailabilityDistribution(m)
+					=> v1::ValidationProtocol::AvailabilityDistribution(m),
+				ValidationProtocol::BitfieldDistribution(m)
+					=> v1::ValidationProtocol::BitfieldDistribution(m),
+				ValidationProtocol::PoVDistribution(m)
+					=> v1::ValidationProtocol::PoVDistribution(m),
+				ValidationProtocol::StatementDistribution(m)
+					=> v1::ValidationProtocol::StatementDistribution(m),
+			}
+		}
+	}
+
+	impl From<v1::CollationProtocol> for CollationProtocol {
+		fn from(message: v1::CollationProtocol) -> Self {
+			match message {
+				v1::CollationProtocol::CollatorProtocol(m) => CollationProtocol::CollatorProtocol(m),
+			}
+		}
+	}
+
+	impl From<CollationProtocol> for v1::CollationProtocol {
+		fn from(message: CollationProtocol) -> Self {
+			match message {
+				CollationProtocol::CollatorProtocol(m) => v1::CollationProtocol::CollatorProtocol(m),
+			}
+		}
+	}
+}
+
+/// Messages on a peer-set in the version the subsystems speak, which can be translated to and
+/// from every supported version of the peer-set's protocol.
+///
+/// Conversions are fallible: a message of one version need not have a counterpart in another.
+pub trait VersionedMessage: Encode + Decode + Clone + Sized {
+	/// The message type of version 2 of the protocol.
+	type V2: Encode + Decode;
+
+	/// Convert into a version 2 message.
+	fn into_v2(self) -> Option<Self::V2>;
+
+	/// Convert from a version 2 message.
+	fn from_v2(message: Self::V2) -> Option<Self>;
+}
+
+impl VersionedMessage for v1::ValidationProtocol {
+	type V2 = v2::ValidationProtocol;
+
+	fn into_v2(self) -> Option<Self::V2> {
+		TryFrom::try_from(self).ok()
+	}
+
+	fn from_v2(message: Self::V2) -> Option<Self> {
+		TryFrom::try_from(message).ok()
+	}
+}
+
+impl VersionedMessage for v1::CollationProtocol {
+	type V2 = v2::CollationProtocol;
+
+	fn into_v2(self) -> Option<Self::V2> {
+		TryFrom::try_from(self).ok()
+	}
+
+	fn from_v2(message: Self::V2) -> Option<Self> {
+		TryFrom::try_from(message).ok()
+	}
 }
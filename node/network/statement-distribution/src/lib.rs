@@ -18,15 +18,20 @@
 //!
 //! This is responsible for distributing signed statements about candidate
 //! validity amongst validators.
+//!
+//! Validators route statements along a 2-D grid topology, see the `grid` module. Nodes which
+//! are not validators, or do not know the validators' peers, fall back to full gossip.
 
 use polkadot_subsystem::{
 	Subsystem, SubsystemResult, SubsystemContext, SpawnedSubsystem,
 	ActiveLeavesUpdate, FromOverseer, OverseerSignal,
+	metrics::{self, prometheus},
 };
 use polkadot_subsystem::messages::{
 	AllMessages, NetworkBridgeMessage, StatementDistributionMessage, CandidateBackingMessage,
 	RuntimeApiMessage, RuntimeApiRequest,
 };
+use polkadot_node_subsystem_util::signing_key;
 use node_primitives::SignedFullStatement;
 use polkadot_primitives::v1::{
 	Hash, CompactStatement, ValidatorIndex, ValidatorId, SigningContext, ValidatorSignature,
@@ -34,6 +39,8 @@ use polkadot_primitives::v1::{
 use polkadot_node_network_protocol::{
 	v1 as protocol_v1, View, PeerId, ReputationChange as Rep, NetworkBridgeEvent,
 };
+use sc_keystore::KeyStorePtr;
+use sp_core::Pair;
 
 use futures::prelude::*;
 use futures::channel::oneshot;
@@ -41,6 +48,10 @@ use indexmap::IndexSet;
 
 use std::collections::{HashMap, HashSet};
 
+mod grid;
+
+use grid::Topology;
+
 const COST_UNEXPECTED_STATEMENT: Rep = Rep::new(-100, "Unexpected Statement");
 const COST_INVALID_SIGNATURE: Rep = Rep::new(-500, "Invalid Statement Signature");
 const COST_DUPLICATE_STATEMENT: Rep = Rep::new(-250, "Statement sent more than once by peer");
@@ -60,19 +71,30 @@ const BENEFIT_VALID_STATEMENT_FIRST: Rep = Rep::new(
 const VC_THRESHOLD: usize = 2;
 
 /// The statement distribution subsystem.
-pub struct StatementDistribution;
+#[derive(Clone)]
+pub struct StatementDistribution {
+	keystore: KeyStorePtr,
+	metrics: Metrics,
+}
+
+impl StatementDistribution {
+	/// Create a new instance of the `StatementDistribution`.
+	pub fn new(keystore: KeyStorePtr, metrics: Metrics) -> Self {
+		StatementDistribution { keystore, metrics }
+	}
+}
 
 impl<C> Subsystem<C> for StatementDistribution
 	where C: SubsystemContext<Message=StatementDistributionMessage>
 {
-	type Metrics = ();
+	type Metrics = Metrics;
 
 	fn start(self, ctx: C) -> SpawnedSubsystem {
 		// Swallow error because failure is fatal to the node and we log with more precision
 		// within `run`.
 		SpawnedSubsystem {
 			name: "statement-distribution-subsystem",
-			future: run(ctx).map(|_| ()).boxed(),
+			future: run(ctx, self.keystore, self.metrics).map(|_| ()).boxed(),
 		}
 	}
 }
@@ -373,19 +395,77 @@ struct ActiveHeadData {
 	session_index: sp_staking::SessionIndex,
 	/// How many `Seconded` statements we've seen per validator.
 	seconded_counts: HashMap<ValidatorIndex, usize>,
+	/// Our place in the grid topology at this head, if we are a validator.
+	///
+	/// Without a topology, statements are gossiped to all peers.
+	topology: Option<Topology>,
+	/// The index of each validator at this head.
+	validator_indices: HashMap<ValidatorId, ValidatorIndex>,
 }
 
 impl ActiveHeadData {
-	fn new(validators: Vec<ValidatorId>, session_index: sp_staking::SessionIndex) -> Self {
+	fn new(
+		validators: Vec<ValidatorId>,
+		session_index: sp_staking::SessionIndex,
+		topology: Option<Topology>,
+	) -> Self {
+		let validator_indices = validators.iter()
+			.enumerate()
+			.map(|(i, v)| (v.clone(), i as ValidatorIndex))
+			.collect();
+
 		ActiveHeadData {
 			candidates: Default::default(),
 			statements: Default::default(),
 			validators,
 			session_index,
 			seconded_counts: Default::default(),
+			topology,
+			validator_indices,
 		}
 	}
 
+	/// The index at this head of the validator behind `peer`, if known.
+	fn peer_validator_index(
+		&self,
+		authorities: &HashMap<PeerId, ValidatorId>,
+		peer: &PeerId,
+	) -> Option<ValidatorIndex> {
+		authorities.get(peer).and_then(|v| self.validator_indices.get(v)).copied()
+	}
+
+	/// Whether statements at this head are to be sent to `peer`, if the topology routes them
+	/// to `targets`.
+	///
+	/// Peers which can't be placed in the topology are served as with full gossip, apart from
+	/// the statements we `forward`: with full gossip those reach them from their originator.
+	fn routes_to(
+		&self,
+		authorities: &HashMap<PeerId, ValidatorId>,
+		peer: &PeerId,
+		targets: Option<&HashSet<ValidatorIndex>>,
+		forward: bool,
+	) -> bool {
+		match (targets, self.peer_validator_index(authorities, peer)) {
+			(Some(targets), Some(index)) => targets.contains(&index),
+			(_, None) => !forward,
+			(None, Some(_)) => true,
+		}
+	}
+
+	/// Whether we exchange statements at this head with `peer` at all.
+	fn is_neighbor(&self, authorities: &HashMap<PeerId, ValidatorId>, peer: &PeerId) -> bool {
+		match (&self.topology, self.peer_validator_index(authorities, peer)) {
+			(Some(topology), Some(index)) => topology.is_neighbor(index),
+			_ => true,
+		}
+	}
+
+	/// The name of the topology statements at this head are routed with, for the metrics.
+	fn routing(&self) -> &'static str {
+		if self.topology.is_some() { "grid" } else { "gossip" }
+	}
+
 	/// Note the given statement.
 	///
 	/// If it was not already known and can be accepted,  returns `NotedStatement::Fresh`,
@@ -482,41 +562,82 @@ fn check_statement_signature(
 /// them but now can.
 async fn circulate_statement_and_dependents(
 	peers: &mut HashMap<PeerId, PeerData>,
+	authorities: &HashMap<PeerId, ValidatorId>,
 	active_heads: &mut HashMap<Hash, ActiveHeadData>,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	relay_parent: Hash,
 	statement: SignedFullStatement,
+	metrics: &Metrics,
 ) -> SubsystemResult<()> {
 	if let Some(active_head)= active_heads.get_mut(&relay_parent) {
-
-		// First circulate the statement directly to all peers needing it.
 		// The borrow of `active_head` needs to encompass only this (Rust) statement.
-		let outputs: Option<(Hash, Vec<PeerId>)> = {
-			match active_head.note_statement(statement) {
-				NotedStatement::Fresh(stored) => Some((
-					stored.compact().candidate_hash().clone(),
-					circulate_statement(peers, ctx, relay_parent, stored).await?,
-				)),
-				_ => None,
-			}
+		let comparator = match active_head.note_statement(statement) {
+			NotedStatement::Fresh(stored) => stored.comparator.clone(),
+			_ => return Ok(()),
 		};
 
-		// Now send dependent statements to all peers needing them, if any.
-		if let Some((candidate_hash, peers_needing_dependents)) = outputs {
-			for peer in peers_needing_dependents {
-				if let Some(peer_data) = peers.get_mut(&peer) {
-					// defensive: the peer data should always be some because the iterator
-					// of peers is derived from the set of peers.
-					send_statements_about(
-						peer,
-						peer_data,
-						ctx,
-						relay_parent,
-						candidate_hash,
-						&*active_head
-					).await?;
-				}
-			}
+		circulate_stored_and_dependents(
+			peers,
+			authorities,
+			&*active_head,
+			ctx,
+			relay_parent,
+			&comparator,
+			None,
+			metrics,
+		).await?;
+	}
+
+	Ok(())
+}
+
+/// Circulates an already stored statement to all peers who have not seen it yet, and
+/// sends all statements dependent on that statement to peers who could previously not receive
+/// them but now can.
+///
+/// `origin` is the validator we received the statement from, or `None` if it is our own.
+async fn circulate_stored_and_dependents(
+	peers: &mut HashMap<PeerId, PeerData>,
+	authorities: &HashMap<PeerId, ValidatorId>,
+	active_head: &ActiveHeadData,
+	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
+	relay_parent: Hash,
+	comparator: &StoredStatementComparator,
+	origin: Option<ValidatorIndex>,
+	metrics: &Metrics,
+) -> SubsystemResult<()> {
+	let stored = match active_head.statements.get(comparator) {
+		Some(stored) => stored,
+		None => return Ok(()),
+	};
+
+	// First circulate the statement directly to all peers needing it.
+	let candidate_hash = stored.compact().candidate_hash().clone();
+	let peers_needing_dependents = circulate_statement(
+		peers,
+		authorities,
+		ctx,
+		relay_parent,
+		active_head,
+		stored,
+		origin,
+		metrics,
+	).await?;
+
+	// Now send dependent statements to all peers needing them, if any.
+	for peer in peers_needing_dependents {
+		if let Some(peer_data) = peers.get_mut(&peer) {
+			// defensive: the peer data should always be some because the iterator
+			// of peers is derived from the set of peers.
+			send_statements_about(
+				peer,
+				peer_data,
+				ctx,
+				relay_parent,
+				candidate_hash,
+				active_head,
+				metrics,
+			).await?;
 		}
 	}
 
@@ -531,19 +652,31 @@ fn statement_message(relay_parent: Hash, statement: SignedFullStatement)
 	)
 }
 
-/// Circulates a statement to all peers who have not seen it yet, and returns
-/// an iterator over peers who need to have dependent statements sent.
+/// Circulates a statement to all peers who have not seen it yet and which the topology of the
+/// active head routes it to, and returns an iterator over peers who need to have dependent
+/// statements sent.
+///
+/// `origin` is the validator we received the statement from, or `None` if it is our own.
 async fn circulate_statement(
 	peers: &mut HashMap<PeerId, PeerData>,
+	authorities: &HashMap<PeerId, ValidatorId>,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	relay_parent: Hash,
+	active_head: &ActiveHeadData,
 	stored: &StoredStatement,
+	origin: Option<ValidatorIndex>,
+	metrics: &Metrics,
 ) -> SubsystemResult<Vec<PeerId>> {
 	let fingerprint = stored.fingerprint();
+	let targets = active_head.topology.as_ref().map(|topology| topology.targets(origin));
 
 	let mut peers_to_send = HashMap::new();
 
 	for (peer, data) in peers.iter_mut() {
+		if !active_head.routes_to(authorities, peer, targets.as_ref(), origin.is_some()) {
+			continue;
+		}
+
 		if let Some(new_known) = data.send(&relay_parent, &fingerprint) {
 			peers_to_send.insert(peer.clone(), new_known);
 		}
@@ -556,6 +689,8 @@ async fn circulate_statement(
 			peers_to_send.keys().cloned().collect(),
 			payload,
 		))).await?;
+
+		metrics.on_statements_distributed(peers_to_send.len(), active_head.routing());
 	}
 
 	Ok(peers_to_send.into_iter().filter_map(|(peer, needs_dependent)| if needs_dependent {
@@ -573,6 +708,7 @@ async fn send_statements_about(
 	relay_parent: Hash,
 	candidate_hash: Hash,
 	active_head: &ActiveHeadData,
+	metrics: &Metrics,
 ) -> SubsystemResult<()> {
 	for statement in active_head.statements_about(candidate_hash) {
 		if peer_data.send(&relay_parent, &statement.fingerprint()).is_some() {
@@ -584,6 +720,8 @@ async fn send_statements_about(
 			ctx.send_message(AllMessages::NetworkBridge(
 				NetworkBridgeMessage::SendValidationMessage(vec![peer.clone()], payload)
 			)).await?;
+
+			metrics.on_statements_distributed(1, active_head.routing());
 		}
 	}
 
@@ -596,7 +734,8 @@ async fn send_statements(
 	peer_data: &mut PeerData,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	relay_parent: Hash,
-	active_head: &ActiveHeadData
+	active_head: &ActiveHeadData,
+	metrics: &Metrics,
 ) -> SubsystemResult<()> {
 	for statement in active_head.statements() {
 		if peer_data.send(&relay_parent, &statement.fingerprint()).is_some() {
@@ -608,6 +747,8 @@ async fn send_statements(
 			ctx.send_message(AllMessages::NetworkBridge(
 				NetworkBridgeMessage::SendValidationMessage(vec![peer.clone()], payload)
 			)).await?;
+
+			metrics.on_statements_distributed(1, active_head.routing());
 		}
 	}
 
@@ -636,11 +777,14 @@ async fn handle_incoming_message<'a>(
 	active_heads: &'a mut HashMap<Hash, ActiveHeadData>,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	message: protocol_v1::StatementDistributionMessage,
+	metrics: &Metrics,
 ) -> SubsystemResult<Option<(Hash, &'a StoredStatement)>> {
 	let (relay_parent, statement) = match message {
 		protocol_v1::StatementDistributionMessage::Statement(r, s) => (r, s),
 	};
 
+	metrics.on_statement_received();
+
 	if !our_view.contains(&relay_parent) {
 		return report_peer(ctx, peer, COST_UNEXPECTED_STATEMENT).await.map(|_| None);
 	}
@@ -681,6 +825,7 @@ async fn handle_incoming_message<'a>(
 				relay_parent,
 				fingerprint.0.candidate_hash().clone(),
 				&*active_head,
+				metrics,
 			).await?
 		}
 		Ok(false) => {}
@@ -707,7 +852,9 @@ async fn update_peer_view_and_send_unlocked(
 	peer_data: &mut PeerData,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	active_heads: &HashMap<Hash, ActiveHeadData>,
+	authorities: &HashMap<PeerId, ValidatorId>,
 	new_view: View,
+	metrics: &Metrics,
 ) -> SubsystemResult<()> {
 	let old_view = std::mem::replace(&mut peer_data.view, new_view);
 
@@ -723,13 +870,17 @@ async fn update_peer_view_and_send_unlocked(
 		peer_data.view_knowledge.insert(new, Default::default());
 
 		if let Some(active_head) = active_heads.get(&new) {
-			send_statements(
-				peer.clone(),
-				peer_data,
-				ctx,
-				new,
-				active_head,
-			).await?;
+			// Peers outside of our neighbourhood in the topology get the statements from others.
+			if active_head.is_neighbor(authorities, &peer) {
+				send_statements(
+					peer.clone(),
+					peer_data,
+					ctx,
+					new,
+					active_head,
+					metrics,
+				).await?;
+			}
 		}
 	}
 
@@ -738,10 +889,12 @@ async fn update_peer_view_and_send_unlocked(
 
 async fn handle_network_update(
 	peers: &mut HashMap<PeerId, PeerData>,
+	authorities: &mut HashMap<PeerId, ValidatorId>,
 	active_heads: &mut HashMap<Hash, ActiveHeadData>,
 	ctx: &mut impl SubsystemContext<Message = StatementDistributionMessage>,
 	our_view: &mut View,
 	update: NetworkBridgeEvent<protocol_v1::StatementDistributionMessage>,
+	metrics: &Metrics,
 ) -> SubsystemResult<()> {
 	match update {
 		NetworkBridgeEvent::PeerConnected(peer, _role) => {
@@ -757,31 +910,49 @@ async fn handle_network_update(
 			Ok(())
 		}
 		NetworkBridgeEvent::PeerMessage(peer, message) => {
-			match peers.get_mut(&peer) {
-				Some(data) => {
-					let new_stored = handle_incoming_message(
-						peer,
-						data,
-						&*our_view,
-						active_heads,
-						ctx,
-						message,
-					).await?;
-
-					if let Some((relay_parent, new)) = new_stored {
-						// When we receive a new message from a peer, we forward it to the
-						// candidate backing subsystem.
-						let message = AllMessages::CandidateBacking(
-							CandidateBackingMessage::Statement(relay_parent, new.statement.clone())
-						);
-						ctx.send_message(message).await?;
-					}
+			let new_stored = match peers.get_mut(&peer) {
+				Some(data) => handle_incoming_message(
+					peer.clone(),
+					data,
+					&*our_view,
+					active_heads,
+					ctx,
+					message,
+					metrics,
+				).await?,
+				None => return Ok(()),
+			};
 
-					Ok(())
+			if let Some((relay_parent, new)) = new_stored {
+				// When we receive a new message from a peer, we forward it to the
+				// candidate backing subsystem.
+				let comparator = new.comparator.clone();
+				let message = AllMessages::CandidateBacking(
+					CandidateBackingMessage::Statement(relay_parent, new.statement.clone())
+				);
+				ctx.send_message(message).await?;
+
+				// With full gossip the originator sends its statements to all peers itself,
+				// but along the grid we have to pass them on. That is only possible if we
+				// know where in the grid the statement came from.
+				if let Some(active_head) = active_heads.get(&relay_parent) {
+					let origin = active_head.peer_validator_index(authorities, &peer);
+					if let (Some(_), Some(origin)) = (&active_head.topology, origin) {
+						circulate_stored_and_dependents(
+							peers,
+							authorities,
+							active_head,
+							ctx,
+							relay_parent,
+							&comparator,
+							Some(origin),
+							metrics,
+						).await?;
+					}
 				}
-				None => Ok(()),
 			}
 
+			Ok(())
 		}
 		NetworkBridgeEvent::PeerViewChange(peer, view) => {
 			match peers.get_mut(&peer) {
@@ -791,7 +962,9 @@ async fn handle_network_update(
 						data,
 						ctx,
 						&*active_heads,
+						authorities,
 						view,
+						metrics,
 					).await
 				}
 				None => Ok(()),
@@ -811,25 +984,36 @@ async fn handle_network_update(
 
 			Ok(())
 		}
+		NetworkBridgeEvent::ValidatorPeers(validator_peers) => {
+			authorities.extend(validator_peers.into_iter().map(|(v, p)| (p, v)));
+			Ok(())
+		}
 	}
 
 }
 
 async fn run(
 	mut ctx: impl SubsystemContext<Message = StatementDistributionMessage>,
+	keystore: KeyStorePtr,
+	metrics: Metrics,
 ) -> SubsystemResult<()> {
 	let mut peers: HashMap<PeerId, PeerData> = HashMap::new();
 	let mut our_view = View::default();
 	let mut active_heads: HashMap<Hash, ActiveHeadData> = HashMap::new();
+	// The validators behind our peers, as far as the network bridge told us.
+	let mut authorities: HashMap<PeerId, ValidatorId> = HashMap::new();
+	// The last session we know the validators of.
+	let mut known_session: Option<sp_staking::SessionIndex> = None;
 
 	loop {
 		let message = ctx.recv().await?;
 		match message {
 			FromOverseer::Signal(OverseerSignal::ActiveLeaves(ActiveLeavesUpdate { activated, .. })) => {
 				for relay_parent in activated {
-					let (validators, session_index) = {
+					let (validators, session_index, groups) = {
 						let (val_tx, val_rx) = oneshot::channel();
 						let (session_tx, session_rx) = oneshot::channel();
+						let (groups_tx, groups_rx) = oneshot::channel();
 
 						let val_message = AllMessages::RuntimeApi(
 							RuntimeApiMessage::Request(
@@ -843,14 +1027,22 @@ async fn run(
 								RuntimeApiRequest::SessionIndexForChild(session_tx),
 							),
 						);
+						let groups_message = AllMessages::RuntimeApi(
+							RuntimeApiMessage::Request(
+								relay_parent,
+								RuntimeApiRequest::ValidatorGroups(groups_tx),
+							),
+						);
 
 						ctx.send_messages(
-							std::iter::once(val_message).chain(std::iter::once(session_message))
+							std::iter::once(val_message)
+								.chain(std::iter::once(session_message))
+								.chain(std::iter::once(groups_message))
 						).await?;
 
-						match (val_rx.await?, session_rx.await?) {
-							(Ok(v), Ok(s)) => (v, s),
-							(Err(e), _) | (_, Err(e)) => {
+						match (val_rx.await?, session_rx.await?, groups_rx.await?) {
+							(Ok(v), Ok(s), Ok((g, _))) => (v, s, g),
+							(Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
 								log::warn!(
 									target: "statement_distribution",
 									"Failed to fetch runtime API data for active leaf: {:?}",
@@ -865,8 +1057,25 @@ async fn run(
 						}
 					};
 
+					// Only validators route statements along the grid.
+					let topology = signing_key(&validators, &keystore)
+						.and_then(|key| validators.iter().position(|v| *v == key.public()))
+						.map(|our_index| Topology::new(
+							validators.len(),
+							our_index as ValidatorIndex,
+							&groups,
+							&relay_parent,
+						));
+
+					let new_session = known_session.map_or(true, |known| known < session_index);
+
+					if new_session {
+						known_session = Some(session_index);
+						authorities.retain(|_, v| validators.contains(v));
+					}
+
 					active_heads.entry(relay_parent)
-						.or_insert(ActiveHeadData::new(validators, session_index));
+						.or_insert(ActiveHeadData::new(validators, session_index, topology));
 				}
 			}
 			FromOverseer::Signal(OverseerSignal::BlockFinalized(_block_hash)) => {
@@ -877,18 +1086,22 @@ async fn run(
 				StatementDistributionMessage::Share(relay_parent, statement) =>
 					circulate_statement_and_dependents(
 						&mut peers,
+						&authorities,
 						&mut active_heads,
 						&mut ctx,
 						relay_parent,
 						statement,
+						&metrics,
 					).await?,
 				StatementDistributionMessage::NetworkBridgeUpdateV1(event) =>
 					handle_network_update(
 						&mut peers,
+						&mut authorities,
 						&mut active_heads,
 						&mut ctx,
 						&mut our_view,
 						event,
+						&metrics,
 					).await?,
 			}
 		}
@@ -896,6 +1109,58 @@ async fn run(
 	Ok(())
 }
 
+#[derive(Clone)]
+struct MetricsInner {
+	statements_distributed_total: prometheus::CounterVec<prometheus::U64>,
+	statements_received_total: prometheus::Counter<prometheus::U64>,
+}
+
+/// Statement Distribution metrics.
+#[derive(Default, Clone)]
+pub struct Metrics(Option<MetricsInner>);
+
+impl Metrics {
+	fn on_statements_distributed(&self, count: usize, routing: &'static str) {
+		if let Some(metrics) = &self.0 {
+			use core::convert::TryFrom as _;
+			// assume usize fits into u64
+			let by = u64::try_from(count).unwrap_or_default();
+			metrics.statements_distributed_total.with_label_values(&[routing]).inc_by(by);
+		}
+	}
+
+	fn on_statement_received(&self) {
+		if let Some(metrics) = &self.0 {
+			metrics.statements_received_total.inc();
+		}
+	}
+}
+
+impl metrics::Metrics for Metrics {
+	fn try_register(registry: &prometheus::Registry) -> Result<Self, prometheus::PrometheusError> {
+		let metrics = MetricsInner {
+			statements_distributed_total: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_statements_distributed_total",
+						"Number of statements sent to peers, by the topology they were routed with.",
+					),
+					&["topology"],
+				)?,
+				registry,
+			)?,
+			statements_received_total: prometheus::register(
+				prometheus::Counter::new(
+					"parachain_statements_received_total",
+					"Number of statements received from peers.",
+				)?,
+				registry,
+			)?,
+		};
+		Ok(Metrics(Some(metrics)))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -941,7 +1206,7 @@ mod tests {
 			c
 		};
 
-		let mut head_data = ActiveHeadData::new(validators, session_index);
+		let mut head_data = ActiveHeadData::new(validators, session_index, None);
 
 		// note A
 		let a_seconded_val_0 = SignedFullStatement::sign(
@@ -1174,7 +1439,7 @@ mod tests {
 		};
 
 		let new_head_data = {
-			let mut data = ActiveHeadData::new(validators, session_index);
+			let mut data = ActiveHeadData::new(validators, session_index, None);
 
 			let noted = data.note_statement(SignedFullStatement::sign(
 				Statement::Seconded(candidate.clone()),
@@ -1230,7 +1495,9 @@ mod tests {
 				&mut peer_data,
 				&mut ctx,
 				&active_heads,
+				&HashMap::new(),
 				new_view.clone(),
+				&Metrics::default(),
 			).await.unwrap();
 
 			assert_eq!(peer_data.view, new_view);
@@ -1275,6 +1542,38 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn validator_peers_are_learned_from_the_bridge() {
+		let validator: ValidatorId = Sr25519Keyring::Alice.public().into();
+		let peer = PeerId::random();
+
+		let mut peers = HashMap::new();
+		let mut authorities = HashMap::new();
+		let mut active_heads = HashMap::new();
+		let mut our_view = View::default();
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+
+		executor::block_on(async move {
+			handle_network_update(
+				&mut peers,
+				&mut authorities,
+				&mut active_heads,
+				&mut ctx,
+				&mut our_view,
+				NetworkBridgeEvent::ValidatorPeers(vec![(validator.clone(), peer.clone())]),
+				&Metrics::default(),
+			).await.unwrap();
+
+			assert_eq!(authorities.get(&peer), Some(&validator));
+
+			// nothing is asked of the network bridge.
+			drop(ctx);
+			assert!(handle.try_recv().await.is_none());
+		});
+	}
+
 	#[test]
 	fn circulated_statement_goes_to_all_peers_with_view() {
 		let hash_a = [1; 32].into();
@@ -1336,11 +1635,17 @@ mod tests {
 				}
 			};
 
+			let active_head = ActiveHeadData::new(Vec::new(), session_index, None);
+
 			let needs_dependents = circulate_statement(
 				&mut peer_data,
+				&HashMap::new(),
 				&mut ctx,
 				hash_b,
+				&active_head,
 				&statement,
+				None,
+				&Metrics::default(),
 			).await.unwrap();
 
 			{
@@ -1382,4 +1687,137 @@ mod tests {
 			)
 		});
 	}
+
+	#[test]
+	fn circulated_statement_follows_grid_topology() {
+		let hash = [1; 32].into();
+		let session_index = 1;
+
+		let candidate = {
+			let mut c = CommittedCandidateReceipt::default();
+			c.descriptor.relay_parent = hash;
+			c.descriptor.para_id = 1.into();
+			c
+		};
+		let candidate_hash = candidate.hash();
+
+		let keyrings = [
+			Sr25519Keyring::Alice, // <- this node
+			Sr25519Keyring::Bob,
+			Sr25519Keyring::Charlie,
+			Sr25519Keyring::Dave,
+		];
+		let validators: Vec<ValidatorId> = keyrings.iter().map(|k| k.public().into()).collect();
+
+		// Bob shares our row and Charlie our column, Dave neither.
+		let topology = Topology {
+			row: vec![1].into_iter().collect(),
+			column: vec![2].into_iter().collect(),
+			local_group: Default::default(),
+		};
+		let mut active_head = ActiveHeadData::new(validators.clone(), session_index, Some(topology));
+
+		let peer_bob = PeerId::random();
+		let peer_charlie = PeerId::random();
+		let peer_dave = PeerId::random();
+		let peer_unknown = PeerId::random();
+
+		let authorities: HashMap<_, _> = vec![
+			(peer_bob.clone(), validators[1].clone()),
+			(peer_charlie.clone(), validators[2].clone()),
+			(peer_dave.clone(), validators[3].clone()),
+		].into_iter().collect();
+
+		let mut peer_data: HashMap<_, _> = vec![
+			peer_bob.clone(),
+			peer_charlie.clone(),
+			peer_dave.clone(),
+			peer_unknown.clone(),
+		].into_iter().map(|peer| (peer, PeerData {
+			view: View(vec![hash]),
+			view_knowledge: vec![(hash, Default::default())].into_iter().collect(),
+		})).collect();
+
+		let signing_context = SigningContext {
+			parent_hash: hash,
+			session_index,
+		};
+
+		let pool = sp_core::testing::TaskExecutor::new();
+		let (mut ctx, mut handle) = polkadot_node_subsystem_test_helpers::make_subsystem_context(pool);
+
+		executor::block_on(async move {
+			// Our own statement goes along our row and column.
+			let seconded = SignedFullStatement::sign(
+				Statement::Seconded(candidate),
+				&signing_context,
+				0,
+				&keyrings[0].pair().into(),
+			);
+			let comparator = match active_head.note_statement(seconded.clone()) {
+				NotedStatement::Fresh(stored) => stored.comparator.clone(),
+				_ => panic!("statement is fresh"),
+			};
+
+			circulate_statement(
+				&mut peer_data,
+				&authorities,
+				&mut ctx,
+				hash,
+				&active_head,
+				active_head.statements.get(&comparator).unwrap(),
+				None,
+				&Metrics::default(),
+			).await.unwrap();
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendValidationMessage(
+					to,
+					payload,
+				)) => {
+					assert_eq!(to.len(), 3);
+					assert!(to.contains(&peer_bob));
+					assert!(to.contains(&peer_charlie));
+					assert!(to.contains(&peer_unknown));
+					assert_eq!(payload, statement_message(hash, seconded));
+				}
+			);
+
+			// A statement received from our row is passed on along our column only, to the
+			// validators we can place in the grid.
+			let valid = SignedFullStatement::sign(
+				Statement::Valid(candidate_hash),
+				&signing_context,
+				1,
+				&keyrings[1].pair().into(),
+			);
+			let comparator = match active_head.note_statement(valid.clone()) {
+				NotedStatement::Fresh(stored) => stored.comparator.clone(),
+				_ => panic!("statement is fresh"),
+			};
+
+			circulate_statement(
+				&mut peer_data,
+				&authorities,
+				&mut ctx,
+				hash,
+				&active_head,
+				active_head.statements.get(&comparator).unwrap(),
+				Some(1),
+				&Metrics::default(),
+			).await.unwrap();
+
+			assert_matches!(
+				handle.recv().await,
+				AllMessages::NetworkBridge(NetworkBridgeMessage::SendValidationMessage(
+					to,
+					payload,
+				)) => {
+					assert_eq!(to, vec![peer_charlie.clone()]);
+					assert_eq!(payload, statement_message(hash, valid));
+				}
+			);
+		});
+	}
 }
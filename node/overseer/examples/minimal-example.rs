@@ -38,6 +38,7 @@ use polkadot_subsystem::messages::{
 	CandidateValidationMessage, CandidateBackingMessage, AllMessages,
 };
 
+#[derive(Clone)]
 struct Subsystem1;
 
 impl Subsystem1 {
@@ -90,6 +91,7 @@ impl<C> Subsystem<C> for Subsystem1
 	}
 }
 
+#[derive(Clone)]
 struct Subsystem2;
 
 impl Subsystem2 {
@@ -161,10 +163,13 @@ fn main() {
 			chain_api: DummySubsystem,
 			collation_generation: DummySubsystem,
 			collator_protocol: DummySubsystem,
+			gossip_support: DummySubsystem,
 		};
 		let (overseer, _handler) = Overseer::new(
 			vec![],
 			all_subsystems,
+			Default::default(),
+			Default::default(),
 			None,
 			spawner,
 		).unwrap();
@@ -55,10 +55,13 @@
 //! ```
 
 use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::collections::{hash_map, HashMap};
 
 use futures::channel::{mpsc, oneshot};
@@ -69,6 +72,7 @@ use futures::{
 	Future, FutureExt, SinkExt, StreamExt,
 };
 use futures_timer::Delay;
+use parity_scale_codec::Encode;
 use streamunordered::{StreamYield, StreamUnordered};
 
 use polkadot_primitives::v1::{Block, BlockNumber, Hash};
@@ -80,7 +84,9 @@ use polkadot_subsystem::messages::{
 	AvailabilityDistributionMessage, BitfieldSigningMessage, BitfieldDistributionMessage,
 	ProvisionerMessage, PoVDistributionMessage, RuntimeApiMessage,
 	AvailabilityStoreMessage, NetworkBridgeMessage, AllMessages, CollationGenerationMessage, CollatorProtocolMessage,
+	GossipSupportMessage, SubsystemKind,
 };
+use polkadot_subsystem::record::{RecordEntry, RecordedEvent, RecordedMessage, RecordedSignal};
 pub use polkadot_subsystem::{
 	Subsystem, SubsystemContext, OverseerSignal, FromOverseer, SubsystemError, SubsystemResult,
 	SpawnedSubsystem, ActiveLeavesUpdate,
@@ -88,6 +94,10 @@ pub use polkadot_subsystem::{
 };
 use polkadot_node_primitives::SpawnNamed;
 
+mod queue;
+
+use queue::{QueueReceiver, QueueSender};
+
 
 // A capacity of bounded channels inside the overseer.
 const CHANNEL_CAPACITY: usize = 1024;
@@ -221,6 +231,47 @@ impl OverseerHandler {
 	}
 }
 
+/// Writes the traffic routed by the [`Overseer`] to a recording.
+///
+/// See [`Overseer::record_to`] and the `polkadot_subsystem::record` module for the format.
+///
+/// [`Overseer`]: struct.Overseer.html
+/// [`Overseer::record_to`]: struct.Overseer.html#method.record_to
+pub struct Recorder {
+	writer: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+	/// Create a `Recorder` writing into the given writer.
+	pub fn new(writer: impl Write + Send + 'static) -> Self {
+		Recorder {
+			writer: Box::new(writer),
+		}
+	}
+
+	/// Create a `Recorder` writing into a new file at `path`, truncating it if it already exists.
+	pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+		Ok(Self::new(io::BufWriter::new(File::create(path)?)))
+	}
+
+	fn record(&mut self, event: RecordedEvent) {
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_millis() as u64)
+			.unwrap_or_default();
+
+		if let Err(e) = self.writer.write_all(&RecordEntry { timestamp, event }.encode()) {
+			log::warn!(target: LOG_TARGET, "Failed to write to the recording: {:?}", e);
+		}
+	}
+
+	fn flush(&mut self) {
+		if let Err(e) = self.writer.flush() {
+			log::warn!(target: LOG_TARGET, "Failed to flush the recording: {:?}", e);
+		}
+	}
+}
+
 /// Glues together the [`Overseer`] and `BlockchainEvents` by forwarding
 /// import and finality notifications into the [`OverseerHandler`].
 ///
@@ -274,7 +325,7 @@ impl Debug for ToOverseer {
 ///
 /// [`Subsystem`]: trait.Subsystem.html
 struct SubsystemInstance<M> {
-	tx: mpsc::Sender<FromOverseer<M>>,
+	tx: QueueSender<M>,
 }
 
 /// A context type that is given to the [`Subsystem`] upon spawning.
@@ -286,7 +337,7 @@ struct SubsystemInstance<M> {
 /// [`SubsystemJob`]: trait.SubsystemJob.html
 #[derive(Debug)]
 pub struct OverseerSubsystemContext<M>{
-	rx: mpsc::Receiver<FromOverseer<M>>,
+	rx: QueueReceiver<M>,
 	tx: mpsc::Sender<ToOverseer>,
 }
 
@@ -345,6 +396,316 @@ impl<M: Send + 'static> SubsystemContext for OverseerSubsystemContext<M> {
 /// [`Subsystem`]: trait.Subsystem.html
 struct OverseenSubsystem<M> {
 	instance: Option<SubsystemInstance<M>>,
+	/// Starts a fresh copy of the [`Subsystem`], used both initially and on restarts.
+	///
+	/// [`Subsystem`]: trait.Subsystem.html
+	starter: Box<dyn FnMut(OverseerSubsystemContext<M>) -> SpawnedSubsystem + Send>,
+	policy: SupervisionPolicy,
+	overflow: OverflowPolicy,
+	restarts: u32,
+	name: &'static str,
+}
+
+impl<M: Send + 'static> OverseenSubsystem<M> {
+	fn new<S>(subsystem: S, policy: SupervisionPolicy, overflow: OverflowPolicy) -> Self
+	where
+		S: Subsystem<OverseerSubsystemContext<M>> + Clone + Send + 'static,
+	{
+		OverseenSubsystem {
+			instance: None,
+			starter: Box::new(move |ctx| subsystem.clone().start(ctx)),
+			policy,
+			overflow,
+			restarts: 0,
+			name: "",
+		}
+	}
+
+	/// Forget about the instance that has just exited.
+	fn exited(&mut self) -> (&'static str, SupervisionPolicy, u32) {
+		self.instance = None;
+		(self.name, self.policy, self.restarts)
+	}
+
+	/// Send a signal to the running instance, if any.
+	///
+	/// Failing to reach an instance that has already exited is only an error
+	/// if its exit is going to bring down the `Overseer` anyway.
+	async fn send_signal(&mut self, signal: OverseerSignal) -> SubsystemResult<()> {
+		if let Some(ref mut s) = self.instance {
+			if let Err(e) = s.tx.send(FromOverseer::Signal(signal)).await {
+				if self.policy == SupervisionPolicy::Escalate {
+					return Err(e.into());
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// What the [`Overseer`] does when one of its subsystems exits or fails.
+///
+/// [`Overseer`]: struct.Overseer.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupervisionPolicy {
+	/// Stop the `Overseer` along with all other subsystems.
+	Escalate,
+	/// Start the subsystem again after waiting `initial_backoff`. The delay doubles
+	/// with every restart of the same subsystem, but never exceeds `max_backoff`.
+	Restart {
+		/// Delay before the first restart.
+		initial_backoff: Duration,
+		/// Upper bound on the delay between restarts.
+		max_backoff: Duration,
+	},
+	/// Log the exit and carry on without the subsystem.
+	Ignore,
+}
+
+impl Default for SupervisionPolicy {
+	fn default() -> Self {
+		SupervisionPolicy::Escalate
+	}
+}
+
+impl SupervisionPolicy {
+	/// How long to wait before restarting a subsystem that was already restarted `restarts` times.
+	fn backoff(&self, restarts: u32) -> Option<Duration> {
+		match *self {
+			SupervisionPolicy::Restart { initial_backoff, max_backoff } => {
+				let factor = 1u32.checked_shl(restarts).unwrap_or(u32::max_value());
+				Some(initial_backoff.checked_mul(factor).map_or(max_backoff, |d| d.min(max_backoff)))
+			}
+			SupervisionPolicy::Escalate | SupervisionPolicy::Ignore => None,
+		}
+	}
+}
+
+/// Per-subsystem [`SupervisionPolicy`]s given to [`Overseer::new`].
+///
+/// Every subsystem escalates by default, which matches the behavior of an
+/// `Overseer` that shuts down as soon as any subsystem exits.
+///
+/// [`SupervisionPolicy`]: enum.SupervisionPolicy.html
+/// [`Overseer::new`]: struct.Overseer.html#method.new
+#[derive(Clone, Debug, Default)]
+pub struct SupervisionPolicies {
+	/// Policy of the candidate validation subsystem.
+	pub candidate_validation: SupervisionPolicy,
+	/// Policy of the candidate backing subsystem.
+	pub candidate_backing: SupervisionPolicy,
+	/// Policy of the candidate selection subsystem.
+	pub candidate_selection: SupervisionPolicy,
+	/// Policy of the statement distribution subsystem.
+	pub statement_distribution: SupervisionPolicy,
+	/// Policy of the availability distribution subsystem.
+	pub availability_distribution: SupervisionPolicy,
+	/// Policy of the bitfield signing subsystem.
+	pub bitfield_signing: SupervisionPolicy,
+	/// Policy of the bitfield distribution subsystem.
+	pub bitfield_distribution: SupervisionPolicy,
+	/// Policy of the provisioner subsystem.
+	pub provisioner: SupervisionPolicy,
+	/// Policy of the PoV distribution subsystem.
+	pub pov_distribution: SupervisionPolicy,
+	/// Policy of the runtime API subsystem.
+	pub runtime_api: SupervisionPolicy,
+	/// Policy of the availability store subsystem.
+	pub availability_store: SupervisionPolicy,
+	/// Policy of the network bridge subsystem.
+	pub network_bridge: SupervisionPolicy,
+	/// Policy of the Chain API subsystem.
+	pub chain_api: SupervisionPolicy,
+	/// Policy of the Collation Generation subsystem.
+	pub collation_generation: SupervisionPolicy,
+	/// Policy of the Collator Protocol subsystem.
+	pub collator_protocol: SupervisionPolicy,
+	/// Policy of the Gossip Support subsystem.
+	pub gossip_support: SupervisionPolicy,
+}
+
+impl SupervisionPolicies {
+	/// Use the same policy for every subsystem.
+	pub fn all(policy: SupervisionPolicy) -> Self {
+		SupervisionPolicies {
+			candidate_validation: policy,
+			candidate_backing: policy,
+			candidate_selection: policy,
+			statement_distribution: policy,
+			availability_distribution: policy,
+			bitfield_signing: policy,
+			bitfield_distribution: policy,
+			provisioner: policy,
+			pov_distribution: policy,
+			runtime_api: policy,
+			availability_store: policy,
+			network_bridge: policy,
+			chain_api: policy,
+			collation_generation: policy,
+			collator_protocol: policy,
+			gossip_support: policy,
+		}
+	}
+}
+
+/// What the [`Overseer`] does when it routes a message to a subsystem whose queue is full.
+///
+/// [`Overseer`]: struct.Overseer.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+	/// Wait until the subsystem makes room, stalling the `Overseer` and everyone sending through it.
+	Block,
+	/// Drop the oldest message waiting in the queue to make room. Signals are never dropped.
+	DropOldest,
+}
+
+impl Default for OverflowPolicy {
+	fn default() -> Self {
+		OverflowPolicy::Block
+	}
+}
+
+/// Per-subsystem [`OverflowPolicy`]s given to [`Overseer::new`].
+///
+/// Every subsystem blocks by default, which matches the behavior of plain bounded channels.
+///
+/// [`OverflowPolicy`]: enum.OverflowPolicy.html
+/// [`Overseer::new`]: struct.Overseer.html#method.new
+#[derive(Clone, Debug, Default)]
+pub struct OverflowPolicies {
+	/// Policy of the candidate validation subsystem.
+	pub candidate_validation: OverflowPolicy,
+	/// Policy of the candidate backing subsystem.
+	pub candidate_backing: OverflowPolicy,
+	/// Policy of the candidate selection subsystem.
+	pub candidate_selection: OverflowPolicy,
+	/// Policy of the statement distribution subsystem.
+	pub statement_distribution: OverflowPolicy,
+	/// Policy of the availability distribution subsystem.
+	pub availability_distribution: OverflowPolicy,
+	/// Policy of the bitfield signing subsystem.
+	pub bitfield_signing: OverflowPolicy,
+	/// Policy of the bitfield distribution subsystem.
+	pub bitfield_distribution: OverflowPolicy,
+	/// Policy of the provisioner subsystem.
+	pub provisioner: OverflowPolicy,
+	/// Policy of the PoV distribution subsystem.
+	pub pov_distribution: OverflowPolicy,
+	/// Policy of the runtime API subsystem.
+	pub runtime_api: OverflowPolicy,
+	/// Policy of the availability store subsystem.
+	pub availability_store: OverflowPolicy,
+	/// Policy of the network bridge subsystem.
+	pub network_bridge: OverflowPolicy,
+	/// Policy of the Chain API subsystem.
+	pub chain_api: OverflowPolicy,
+	/// Policy of the Collation Generation subsystem.
+	pub collation_generation: OverflowPolicy,
+	/// Policy of the Collator Protocol subsystem.
+	pub collator_protocol: OverflowPolicy,
+	/// Policy of the Gossip Support subsystem.
+	pub gossip_support: OverflowPolicy,
+}
+
+impl OverflowPolicies {
+	/// Drop the oldest messages to the gossip subsystems, for which a stale message is worth
+	/// less than a fresh one, and block on all others.
+	pub fn drop_oldest_gossip() -> Self {
+		OverflowPolicies {
+			candidate_validation: OverflowPolicy::Block,
+			candidate_backing: OverflowPolicy::Block,
+			candidate_selection: OverflowPolicy::Block,
+			statement_distribution: OverflowPolicy::DropOldest,
+			availability_distribution: OverflowPolicy::DropOldest,
+			bitfield_signing: OverflowPolicy::Block,
+			bitfield_distribution: OverflowPolicy::DropOldest,
+			provisioner: OverflowPolicy::Block,
+			pov_distribution: OverflowPolicy::DropOldest,
+			runtime_api: OverflowPolicy::Block,
+			availability_store: OverflowPolicy::Block,
+			network_bridge: OverflowPolicy::Block,
+			chain_api: OverflowPolicy::Block,
+			collation_generation: OverflowPolicy::Block,
+			collator_protocol: OverflowPolicy::Block,
+			gossip_support: OverflowPolicy::Block,
+		}
+	}
+}
+
+/// Evaluate `$body` with `$s` bound to the `OverseenSubsystem` of the given kind.
+///
+/// The subsystems all have different message types, so this expands to a `match`
+/// with a copy of `$body` for each of them.
+macro_rules! with_subsystem {
+	($overseer:ident, $id:expr, |$s:ident| $body:expr) => {
+		match $id {
+			SubsystemKind::CandidateValidation => {
+				let $s = &mut $overseer.candidate_validation_subsystem;
+				$body
+			}
+			SubsystemKind::CandidateBacking => {
+				let $s = &mut $overseer.candidate_backing_subsystem;
+				$body
+			}
+			SubsystemKind::CandidateSelection => {
+				let $s = &mut $overseer.candidate_selection_subsystem;
+				$body
+			}
+			SubsystemKind::StatementDistribution => {
+				let $s = &mut $overseer.statement_distribution_subsystem;
+				$body
+			}
+			SubsystemKind::AvailabilityDistribution => {
+				let $s = &mut $overseer.availability_distribution_subsystem;
+				$body
+			}
+			SubsystemKind::BitfieldSigning => {
+				let $s = &mut $overseer.bitfield_signing_subsystem;
+				$body
+			}
+			SubsystemKind::BitfieldDistribution => {
+				let $s = &mut $overseer.bitfield_distribution_subsystem;
+				$body
+			}
+			SubsystemKind::Provisioner => {
+				let $s = &mut $overseer.provisioner_subsystem;
+				$body
+			}
+			SubsystemKind::PoVDistribution => {
+				let $s = &mut $overseer.pov_distribution_subsystem;
+				$body
+			}
+			SubsystemKind::RuntimeApi => {
+				let $s = &mut $overseer.runtime_api_subsystem;
+				$body
+			}
+			SubsystemKind::AvailabilityStore => {
+				let $s = &mut $overseer.availability_store_subsystem;
+				$body
+			}
+			SubsystemKind::NetworkBridge => {
+				let $s = &mut $overseer.network_bridge_subsystem;
+				$body
+			}
+			SubsystemKind::ChainApi => {
+				let $s = &mut $overseer.chain_api_subsystem;
+				$body
+			}
+			SubsystemKind::CollationGeneration => {
+				let $s = &mut $overseer.collation_generation_subsystem;
+				$body
+			}
+			SubsystemKind::CollatorProtocol => {
+				let $s = &mut $overseer.collator_protocol_subsystem;
+				$body
+			}
+			SubsystemKind::GossipSupport => {
+				let $s = &mut $overseer.gossip_support_subsystem;
+				$body
+			}
+		}
+	};
 }
 
 /// The `Overseer` itself.
@@ -394,15 +755,27 @@ pub struct Overseer<S: SpawnNamed> {
 	/// A Collator Protocol subsystem.
 	collator_protocol_subsystem: OverseenSubsystem<CollatorProtocolMessage>,
 
+	/// A Gossip Support subsystem.
+	gossip_support_subsystem: OverseenSubsystem<GossipSupportMessage>,
+
 	/// Spawner to spawn tasks to.
 	s: S,
 
 	/// Here we keep handles to spawned subsystems to be notified when they terminate.
-	running_subsystems: FuturesUnordered<BoxFuture<'static, ()>>,
+	running_subsystems: FuturesUnordered<BoxFuture<'static, SubsystemKind>>,
+
+	/// Subsystems waiting out their backoff delay before being restarted.
+	pending_restarts: FuturesUnordered<BoxFuture<'static, SubsystemKind>>,
 
 	/// Gather running subsystms' outbound streams into one.
 	running_subsystems_rx: StreamUnordered<mpsc::Receiver<ToOverseer>>,
 
+	/// Which subsystem each of the streams in `running_subsystems_rx` belongs to.
+	stream_origins: HashMap<usize, SubsystemKind>,
+
+	/// Records the routed traffic, if enabled.
+	recorder: Option<Recorder>,
+
 	/// Events that are sent to the overseer from the outside world
 	events_rx: mpsc::Receiver<Event>,
 
@@ -432,7 +805,7 @@ pub struct Overseer<S: SpawnNamed> {
 ///
 /// [`Subsystem`]: trait.Subsystem.html
 /// [`DummySubsystem`]: struct.DummySubsystem.html
-pub struct AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP> {
+pub struct AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP, GS> {
 	/// A candidate validation subsystem.
 	pub candidate_validation: CV,
 	/// A candidate backing subsystem.
@@ -463,6 +836,8 @@ pub struct AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG
 	pub collation_generation: CG,
 	/// A Collator Protocol subsystem.
 	pub collator_protocol: CP,
+	/// A Gossip Support subsystem.
+	pub gossip_support: GS,
 }
 
 /// Overseer Prometheus metrics.
@@ -470,6 +845,12 @@ pub struct AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG
 struct MetricsInner {
 	activated_heads_total: prometheus::Counter<prometheus::U64>,
 	deactivated_heads_total: prometheus::Counter<prometheus::U64>,
+	subsystem_restarts_total: prometheus::CounterVec<prometheus::U64>,
+	subsystem_queue_depth: prometheus::GaugeVec<prometheus::U64>,
+	subsystem_queue_time: prometheus::HistogramVec,
+	messages_sent_total: prometheus::CounterVec<prometheus::U64>,
+	messages_received_total: prometheus::CounterVec<prometheus::U64>,
+	messages_dropped_total: prometheus::CounterVec<prometheus::U64>,
 }
 
 #[derive(Default, Clone)]
@@ -487,6 +868,40 @@ impl Metrics {
 			metrics.deactivated_heads_total.inc();
 		}
 	}
+
+	fn on_subsystem_restarted(&self, name: &'static str) {
+		if let Some(metrics) = &self.0 {
+			metrics.subsystem_restarts_total.with_label_values(&[name]).inc();
+		}
+	}
+
+	fn on_message_sent(&self, destination: SubsystemKind) {
+		if let Some(metrics) = &self.0 {
+			metrics.messages_sent_total.with_label_values(&[destination.name()]).inc();
+		}
+	}
+
+	fn on_message_dropped(&self, subsystem: SubsystemKind) {
+		if let Some(metrics) = &self.0 {
+			metrics.messages_dropped_total.with_label_values(&[subsystem.name()]).inc();
+		}
+	}
+
+	fn on_queue_depth(&self, subsystem: SubsystemKind, depth: usize) {
+		if let Some(metrics) = &self.0 {
+			metrics.subsystem_queue_depth.with_label_values(&[subsystem.name()]).set(depth as u64);
+		}
+	}
+
+	/// A message or signal was taken out of a subsystem's queue after waiting there for `queued`.
+	fn on_dequeued(&self, subsystem: SubsystemKind, queued: Duration, is_message: bool) {
+		if let Some(metrics) = &self.0 {
+			metrics.subsystem_queue_time.with_label_values(&[subsystem.name()]).observe(queued.as_secs_f64());
+			if is_message {
+				metrics.messages_received_total.with_label_values(&[subsystem.name()]).inc();
+			}
+		}
+	}
 }
 
 impl metrics::Metrics for Metrics {
@@ -506,6 +921,66 @@ impl metrics::Metrics for Metrics {
 				)?,
 				registry,
 			)?,
+			subsystem_restarts_total: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_subsystem_restarts_total",
+						"Number of times a subsystem was restarted after exiting.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
+			subsystem_queue_depth: prometheus::register(
+				prometheus::GaugeVec::new(
+					prometheus::Opts::new(
+						"parachain_subsystem_queue_depth",
+						"Number of messages and signals waiting in the queue of a subsystem.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
+			subsystem_queue_time: prometheus::register(
+				prometheus::HistogramVec::new(
+					prometheus::HistogramOpts::new(
+						"parachain_subsystem_queue_time_seconds",
+						"Time the messages and signals taken by a subsystem spent in its queue.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
+			messages_sent_total: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_messages_sent_total",
+						"Number of messages routed by the overseer, by destination subsystem.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
+			messages_received_total: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_messages_received_total",
+						"Number of messages taken by subsystems from their queues.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
+			messages_dropped_total: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_messages_dropped_total",
+						"Number of messages dropped from the full queue of a subsystem.",
+					),
+					&["subsystem"],
+				)?,
+				registry,
+			)?,
 		};
 		Ok(Metrics(Some(metrics)))
 	}
@@ -538,7 +1013,15 @@ where
 	///
 	/// ```
 	///
+	/// What happens when a subsystem exits is decided by its [`SupervisionPolicy`] in `supervision`.
+	/// Subsystems have to be `Clone`, since a fresh copy is started every time one is restarted.
+	///
+	/// What happens when a message is routed to a subsystem with a full queue is decided by
+	/// its [`OverflowPolicy`] in `overflow`.
+	///
 	/// [`Subsystem`]: trait.Subsystem.html
+	/// [`SupervisionPolicy`]: enum.SupervisionPolicy.html
+	/// [`OverflowPolicy`]: enum.OverflowPolicy.html
 	///
 	/// # Example
 	///
@@ -555,6 +1038,7 @@ where
 	/// #     messages::CandidateValidationMessage,
 	/// # };
 	///
+	/// #[derive(Clone)]
 	/// struct ValidationSubsystem;
 	///
 	/// impl<C> Subsystem<C> for ValidationSubsystem
@@ -595,10 +1079,13 @@ where
 	///     chain_api: DummySubsystem,
 	///     collation_generation: DummySubsystem,
 	///     collator_protocol: DummySubsystem,
+	///     gossip_support: DummySubsystem,
 	/// };
 	/// let (overseer, _handler) = Overseer::new(
 	///     vec![],
 	///     all_subsystems,
+	///     Default::default(),
+	///     Default::default(),
 	///     None,
 	///     spawner,
 	/// ).unwrap();
@@ -616,28 +1103,31 @@ where
 	/// #
 	/// # }); }
 	/// ```
-	pub fn new<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP>(
+	pub fn new<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP, GS>(
 		leaves: impl IntoIterator<Item = BlockInfo>,
-		all_subsystems: AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP>,
+		all_subsystems: AllSubsystems<CV, CB, CS, SD, AD, BS, BD, P, PoVD, RA, AS, NB, CA, CG, CP, GS>,
+		supervision: SupervisionPolicies,
+		overflow: OverflowPolicies,
 		prometheus_registry: Option<&prometheus::Registry>,
 		mut s: S,
 	) -> SubsystemResult<(Self, OverseerHandler)>
 	where
-		CV: Subsystem<OverseerSubsystemContext<CandidateValidationMessage>> + Send,
-		CB: Subsystem<OverseerSubsystemContext<CandidateBackingMessage>> + Send,
-		CS: Subsystem<OverseerSubsystemContext<CandidateSelectionMessage>> + Send,
-		SD: Subsystem<OverseerSubsystemContext<StatementDistributionMessage>> + Send,
-		AD: Subsystem<OverseerSubsystemContext<AvailabilityDistributionMessage>> + Send,
-		BS: Subsystem<OverseerSubsystemContext<BitfieldSigningMessage>> + Send,
-		BD: Subsystem<OverseerSubsystemContext<BitfieldDistributionMessage>> + Send,
-		P: Subsystem<OverseerSubsystemContext<ProvisionerMessage>> + Send,
-		PoVD: Subsystem<OverseerSubsystemContext<PoVDistributionMessage>> + Send,
-		RA: Subsystem<OverseerSubsystemContext<RuntimeApiMessage>> + Send,
-		AS: Subsystem<OverseerSubsystemContext<AvailabilityStoreMessage>> + Send,
-		NB: Subsystem<OverseerSubsystemContext<NetworkBridgeMessage>> + Send,
-		CA: Subsystem<OverseerSubsystemContext<ChainApiMessage>> + Send,
-		CG: Subsystem<OverseerSubsystemContext<CollationGenerationMessage>> + Send,
-		CP: Subsystem<OverseerSubsystemContext<CollatorProtocolMessage>> + Send,
+		CV: Subsystem<OverseerSubsystemContext<CandidateValidationMessage>> + Clone + Send + 'static,
+		CB: Subsystem<OverseerSubsystemContext<CandidateBackingMessage>> + Clone + Send + 'static,
+		CS: Subsystem<OverseerSubsystemContext<CandidateSelectionMessage>> + Clone + Send + 'static,
+		SD: Subsystem<OverseerSubsystemContext<StatementDistributionMessage>> + Clone + Send + 'static,
+		AD: Subsystem<OverseerSubsystemContext<AvailabilityDistributionMessage>> + Clone + Send + 'static,
+		BS: Subsystem<OverseerSubsystemContext<BitfieldSigningMessage>> + Clone + Send + 'static,
+		BD: Subsystem<OverseerSubsystemContext<BitfieldDistributionMessage>> + Clone + Send + 'static,
+		P: Subsystem<OverseerSubsystemContext<ProvisionerMessage>> + Clone + Send + 'static,
+		PoVD: Subsystem<OverseerSubsystemContext<PoVDistributionMessage>> + Clone + Send + 'static,
+		RA: Subsystem<OverseerSubsystemContext<RuntimeApiMessage>> + Clone + Send + 'static,
+		AS: Subsystem<OverseerSubsystemContext<AvailabilityStoreMessage>> + Clone + Send + 'static,
+		NB: Subsystem<OverseerSubsystemContext<NetworkBridgeMessage>> + Clone + Send + 'static,
+		CA: Subsystem<OverseerSubsystemContext<ChainApiMessage>> + Clone + Send + 'static,
+		CG: Subsystem<OverseerSubsystemContext<CollationGenerationMessage>> + Clone + Send + 'static,
+		CP: Subsystem<OverseerSubsystemContext<CollatorProtocolMessage>> + Clone + Send + 'static,
+		GS: Subsystem<OverseerSubsystemContext<GossipSupportMessage>> + Clone + Send + 'static,
 	{
 		let (events_tx, events_rx) = mpsc::channel(CHANNEL_CAPACITY);
 
@@ -647,111 +1137,247 @@ where
 
 		let mut running_subsystems_rx = StreamUnordered::new();
 		let mut running_subsystems = FuturesUnordered::new();
+		let mut stream_origins = HashMap::new();
+		let metrics = <Metrics as metrics::Metrics>::register(prometheus_registry);
 
-		let candidate_validation_subsystem = spawn(
+		let mut candidate_validation_subsystem = OverseenSubsystem::new(
+			all_subsystems.candidate_validation,
+			supervision.candidate_validation,
+			overflow.candidate_validation,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.candidate_validation,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::CandidateValidation,
+			&mut candidate_validation_subsystem,
 		)?;
 
-		let candidate_backing_subsystem = spawn(
+		let mut candidate_backing_subsystem = OverseenSubsystem::new(
+			all_subsystems.candidate_backing,
+			supervision.candidate_backing,
+			overflow.candidate_backing,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.candidate_backing,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::CandidateBacking,
+			&mut candidate_backing_subsystem,
 		)?;
 
-		let candidate_selection_subsystem = spawn(
+		let mut candidate_selection_subsystem = OverseenSubsystem::new(
+			all_subsystems.candidate_selection,
+			supervision.candidate_selection,
+			overflow.candidate_selection,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.candidate_selection,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::CandidateSelection,
+			&mut candidate_selection_subsystem,
 		)?;
 
-		let statement_distribution_subsystem = spawn(
+		let mut statement_distribution_subsystem = OverseenSubsystem::new(
+			all_subsystems.statement_distribution,
+			supervision.statement_distribution,
+			overflow.statement_distribution,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.statement_distribution,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::StatementDistribution,
+			&mut statement_distribution_subsystem,
 		)?;
 
-		let availability_distribution_subsystem = spawn(
+		let mut availability_distribution_subsystem = OverseenSubsystem::new(
+			all_subsystems.availability_distribution,
+			supervision.availability_distribution,
+			overflow.availability_distribution,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.availability_distribution,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::AvailabilityDistribution,
+			&mut availability_distribution_subsystem,
 		)?;
 
-		let bitfield_signing_subsystem = spawn(
+		let mut bitfield_signing_subsystem = OverseenSubsystem::new(
+			all_subsystems.bitfield_signing,
+			supervision.bitfield_signing,
+			overflow.bitfield_signing,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.bitfield_signing,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::BitfieldSigning,
+			&mut bitfield_signing_subsystem,
 		)?;
 
-		let bitfield_distribution_subsystem = spawn(
+		let mut bitfield_distribution_subsystem = OverseenSubsystem::new(
+			all_subsystems.bitfield_distribution,
+			supervision.bitfield_distribution,
+			overflow.bitfield_distribution,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.bitfield_distribution,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::BitfieldDistribution,
+			&mut bitfield_distribution_subsystem,
 		)?;
 
-		let provisioner_subsystem = spawn(
+		let mut provisioner_subsystem = OverseenSubsystem::new(
+			all_subsystems.provisioner,
+			supervision.provisioner,
+			overflow.provisioner,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.provisioner,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::Provisioner,
+			&mut provisioner_subsystem,
 		)?;
 
-		let pov_distribution_subsystem = spawn(
+		let mut pov_distribution_subsystem = OverseenSubsystem::new(
+			all_subsystems.pov_distribution,
+			supervision.pov_distribution,
+			overflow.pov_distribution,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.pov_distribution,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::PoVDistribution,
+			&mut pov_distribution_subsystem,
 		)?;
 
-		let runtime_api_subsystem = spawn(
+		let mut runtime_api_subsystem = OverseenSubsystem::new(
+			all_subsystems.runtime_api,
+			supervision.runtime_api,
+			overflow.runtime_api,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.runtime_api,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::RuntimeApi,
+			&mut runtime_api_subsystem,
 		)?;
 
-		let availability_store_subsystem = spawn(
+		let mut availability_store_subsystem = OverseenSubsystem::new(
+			all_subsystems.availability_store,
+			supervision.availability_store,
+			overflow.availability_store,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.availability_store,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::AvailabilityStore,
+			&mut availability_store_subsystem,
 		)?;
 
-		let network_bridge_subsystem = spawn(
+		let mut network_bridge_subsystem = OverseenSubsystem::new(
+			all_subsystems.network_bridge,
+			supervision.network_bridge,
+			overflow.network_bridge,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.network_bridge,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::NetworkBridge,
+			&mut network_bridge_subsystem,
 		)?;
 
-		let chain_api_subsystem = spawn(
+		let mut chain_api_subsystem = OverseenSubsystem::new(
+			all_subsystems.chain_api,
+			supervision.chain_api,
+			overflow.chain_api,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.chain_api,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::ChainApi,
+			&mut chain_api_subsystem,
 		)?;
 
-		let collation_generation_subsystem = spawn(
+		let mut collation_generation_subsystem = OverseenSubsystem::new(
+			all_subsystems.collation_generation,
+			supervision.collation_generation,
+			overflow.collation_generation,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.collation_generation,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::CollationGeneration,
+			&mut collation_generation_subsystem,
 		)?;
 
+		let mut collator_protocol_subsystem = OverseenSubsystem::new(
+			all_subsystems.collator_protocol,
+			supervision.collator_protocol,
+			overflow.collator_protocol,
+		);
+		spawn(
+			&mut s,
+			&mut running_subsystems,
+			&mut running_subsystems_rx,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::CollatorProtocol,
+			&mut collator_protocol_subsystem,
+		)?;
 
-		let collator_protocol_subsystem = spawn(
+		let mut gossip_support_subsystem = OverseenSubsystem::new(
+			all_subsystems.gossip_support,
+			supervision.gossip_support,
+			overflow.gossip_support,
+		);
+		spawn(
 			&mut s,
 			&mut running_subsystems,
 			&mut running_subsystems_rx,
-			all_subsystems.collator_protocol,
+			&mut stream_origins,
+			&metrics,
+			SubsystemKind::GossipSupport,
+			&mut gossip_support_subsystem,
 		)?;
 
 		let leaves = leaves
@@ -761,7 +1387,6 @@ where
 
 		let active_leaves = HashMap::new();
 
-		let metrics = <Metrics as metrics::Metrics>::register(prometheus_registry);
 		let activation_external_listeners = HashMap::new();
 
 		let this = Self {
@@ -780,9 +1405,13 @@ where
 			chain_api_subsystem,
 			collation_generation_subsystem,
 			collator_protocol_subsystem,
+			gossip_support_subsystem,
 			s,
 			running_subsystems,
+			pending_restarts: FuturesUnordered::new(),
 			running_subsystems_rx,
+			stream_origins,
+			recorder: None,
 			events_rx,
 			activation_external_listeners,
 			leaves,
@@ -793,8 +1422,20 @@ where
 		Ok((this, handler))
 	}
 
+	/// Record every message routed and every signal broadcast by the `Overseer` from now on.
+	///
+	/// The recording is written on the `Overseer`'s own task, so this is meant for debugging only.
+	pub fn record_to(&mut self, recorder: Recorder) {
+		self.recorder = Some(recorder);
+	}
+
 	// Stop the overseer.
 	async fn stop(mut self) {
+		if let Some(ref mut recorder) = self.recorder {
+			recorder.record(RecordedEvent::Signal(RecordedSignal::Conclude));
+			recorder.flush();
+		}
+
 		if let Some(ref mut s) = self.candidate_validation_subsystem.instance {
 			let _ = s.tx.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
 		}
@@ -855,6 +1496,10 @@ where
 			let _ = s.tx.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
 		}
 
+		if let Some(ref mut s) = self.gossip_support_subsystem.instance {
+			let _ = s.tx.send(FromOverseer::Signal(OverseerSignal::Conclude)).await;
+		}
+
 		let mut stop_delay = Delay::new(Duration::from_secs(STOP_DELAY)).fuse();
 
 		loop {
@@ -887,7 +1532,7 @@ where
 			while let Poll::Ready(Some(msg)) = poll!(&mut self.events_rx.next()) {
 				match msg {
 					Event::MsgToSubsystem(msg) => {
-						self.route_message(msg).await;
+						self.route_message(None, msg).await;
 					}
 					Event::Stop => {
 						self.stop().await;
@@ -905,11 +1550,23 @@ where
 				}
 			}
 
-			while let Poll::Ready(Some((StreamYield::Item(msg), _))) = poll!(
-				&mut self.running_subsystems_rx.next()
-			) {
+			loop {
+				let (msg, token) = match poll!(&mut self.running_subsystems_rx.next()) {
+					Poll::Ready(Some((StreamYield::Item(msg), token))) => (msg, token),
+					Poll::Ready(Some((StreamYield::Finished(finished), token))) => {
+						// The subsystem instance is gone, a restarted one gets a new stream.
+						self.stream_origins.remove(&token);
+						finished.remove(Pin::new(&mut self.running_subsystems_rx));
+						continue;
+					}
+					_ => break,
+				};
+
 				match msg {
-					ToOverseer::SubsystemMessage(msg) => self.route_message(msg).await,
+					ToOverseer::SubsystemMessage(msg) => {
+						let origin = self.stream_origins.get(&token).cloned();
+						self.route_message(origin, msg).await
+					}
 					ToOverseer::SpawnJob { name, s } => {
 						self.spawn_job(name, s);
 					}
@@ -919,11 +1576,18 @@ where
 				}
 			}
 
-			// Some subsystem exited? It's time to panic.
-			if let Poll::Ready(Some(finished)) = poll!(self.running_subsystems.next()) {
-				log::error!(target: LOG_TARGET, "Subsystem finished unexpectedly {:?}", finished);
-				self.stop().await;
-				return Err(SubsystemError);
+			// Some subsystems exited? Let their supervision policies decide what happens next.
+			while let Poll::Ready(Some(finished)) = poll!(self.running_subsystems.next()) {
+				if let Err(e) = self.supervise(finished) {
+					self.stop().await;
+					return Err(e);
+				}
+			}
+
+			while let Poll::Ready(Some(id)) = poll!(self.pending_restarts.next()) {
+				if let Err(e) = self.restart(id).await {
+					log::error!(target: LOG_TARGET, "Failed to restart subsystem {:?}: {:?}", id, e);
+				}
 			}
 
 			// Looks like nothing is left to be polled, let's take a break.
@@ -984,70 +1648,37 @@ where
 	}
 
 	async fn broadcast_signal(&mut self, signal: OverseerSignal) -> SubsystemResult<()> {
-		if let Some(ref mut s) = self.candidate_validation_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
+		if let Some(ref mut recorder) = self.recorder {
+			recorder.record(RecordedEvent::Signal((&signal).into()));
 		}
 
-		if let Some(ref mut s) = self.candidate_backing_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.candidate_selection_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.statement_distribution_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.availability_distribution_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.bitfield_distribution_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.bitfield_signing_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.provisioner_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.pov_distribution_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.runtime_api_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.availability_store_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.network_bridge_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
+		self.candidate_validation_subsystem.send_signal(signal.clone()).await?;
+		self.candidate_backing_subsystem.send_signal(signal.clone()).await?;
+		self.candidate_selection_subsystem.send_signal(signal.clone()).await?;
+		self.statement_distribution_subsystem.send_signal(signal.clone()).await?;
+		self.availability_distribution_subsystem.send_signal(signal.clone()).await?;
+		self.bitfield_distribution_subsystem.send_signal(signal.clone()).await?;
+		self.bitfield_signing_subsystem.send_signal(signal.clone()).await?;
+		self.provisioner_subsystem.send_signal(signal.clone()).await?;
+		self.pov_distribution_subsystem.send_signal(signal.clone()).await?;
+		self.runtime_api_subsystem.send_signal(signal.clone()).await?;
+		self.availability_store_subsystem.send_signal(signal.clone()).await?;
+		self.network_bridge_subsystem.send_signal(signal.clone()).await?;
+		self.chain_api_subsystem.send_signal(signal.clone()).await?;
+		self.collator_protocol_subsystem.send_signal(signal.clone()).await?;
+		self.collation_generation_subsystem.send_signal(signal.clone()).await?;
+		self.gossip_support_subsystem.send_signal(signal.clone()).await?;
 
-		if let Some(ref mut s) = self.chain_api_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
-
-		if let Some(ref mut s) = self.collator_protocol_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
-		}
+		Ok(())
+	}
 
-		if let Some(ref mut s) = self.collation_generation_subsystem.instance {
-			s.tx.send(FromOverseer::Signal(signal.clone())).await?;
+	async fn route_message(&mut self, origin: Option<SubsystemKind>, msg: AllMessages) {
+		if let Some(ref mut recorder) = self.recorder {
+			recorder.record(RecordedEvent::Message(RecordedMessage::new(origin, &msg)));
 		}
 
-		Ok(())
-	}
+		self.metrics.on_message_sent(msg.destination());
 
-	async fn route_message(&mut self, msg: AllMessages) {
 		match msg {
 			AllMessages::CandidateValidation(msg) => {
 				if let Some(ref mut s) = self.candidate_validation_subsystem.instance {
@@ -1124,9 +1755,61 @@ where
 					let _ = s.tx.send(FromOverseer::Communication { msg }).await;
 				}
 			}
+			AllMessages::GossipSupport(msg) => match msg {},
+		}
+	}
+
+	fn supervise(&mut self, id: SubsystemKind) -> SubsystemResult<()> {
+		let (name, policy, restarts) = with_subsystem!(self, id, |s| s.exited());
+
+		match policy {
+			SupervisionPolicy::Escalate => {
+				log::error!(target: LOG_TARGET, "Subsystem {} finished unexpectedly", name);
+				Err(SubsystemError)
+			}
+			SupervisionPolicy::Ignore => {
+				log::warn!(target: LOG_TARGET, "Subsystem {} finished, continuing without it", name);
+				Ok(())
+			}
+			SupervisionPolicy::Restart { .. } => {
+				let backoff = policy.backoff(restarts).unwrap_or_default();
+				log::warn!(
+					target: LOG_TARGET,
+					"Subsystem {} finished, restarting in {:?} (restarted {} times so far)",
+					name,
+					backoff,
+					restarts,
+				);
+				self.pending_restarts.push(Box::pin(Delay::new(backoff).map(move |_| id)));
+				Ok(())
+			}
 		}
 	}
 
+	async fn restart(&mut self, id: SubsystemKind) -> SubsystemResult<()> {
+		// The restarted subsystem has lost all of its state, so bring it up to date
+		// with the full set of leaves rather than the latest delta.
+		let signal = OverseerSignal::ActiveLeaves(ActiveLeavesUpdate {
+			activated: self.active_leaves.keys().cloned().collect(),
+			..Default::default()
+		});
+
+		let name = with_subsystem!(self, id, |s| respawn(
+			&mut self.s,
+			&mut self.running_subsystems,
+			&mut self.running_subsystems_rx,
+			&mut self.stream_origins,
+			&self.metrics,
+			id,
+			s,
+			signal,
+		).await?);
+
+		self.metrics.on_subsystem_restarted(name);
+
+		Ok(())
+	}
+
 	fn on_head_activated(&mut self, hash: &Hash) {
 		self.metrics.on_head_activated();
 		if let Some(listeners) = self.activation_external_listeners.remove(hash) {
@@ -1177,14 +1860,17 @@ where
 
 fn spawn<S: SpawnNamed, M: Send + 'static>(
 	spawner: &mut S,
-	futures: &mut FuturesUnordered<BoxFuture<'static, ()>>,
+	futures: &mut FuturesUnordered<BoxFuture<'static, SubsystemKind>>,
 	streams: &mut StreamUnordered<mpsc::Receiver<ToOverseer>>,
-	s: impl Subsystem<OverseerSubsystemContext<M>>,
-) -> SubsystemResult<OverseenSubsystem<M>> {
-	let (to_tx, to_rx) = mpsc::channel(CHANNEL_CAPACITY);
+	origins: &mut HashMap<usize, SubsystemKind>,
+	metrics: &Metrics,
+	id: SubsystemKind,
+	s: &mut OverseenSubsystem<M>,
+) -> SubsystemResult<()> {
+	let (to_tx, to_rx) = queue::queue(CHANNEL_CAPACITY, s.overflow, id, metrics.clone());
 	let (from_tx, from_rx) = mpsc::channel(CHANNEL_CAPACITY);
 	let ctx = OverseerSubsystemContext { rx: to_rx, tx: from_tx };
-	let SpawnedSubsystem { future, name } = s.start(ctx);
+	let SpawnedSubsystem { future, name } = (s.starter)(ctx);
 
 	let (tx, rx) = oneshot::channel();
 
@@ -1195,16 +1881,34 @@ fn spawn<S: SpawnNamed, M: Send + 'static>(
 
 	spawner.spawn(name, fut);
 
-	streams.push(from_rx);
-	futures.push(Box::pin(rx.map(|_| ())));
+	origins.insert(streams.push(from_rx), id);
+	// A panicking subsystem drops `tx`, so this resolves on any kind of exit.
+	futures.push(Box::pin(rx.map(move |_| id)));
 
-	let instance = Some(SubsystemInstance {
+	s.instance = Some(SubsystemInstance {
 		tx: to_tx,
 	});
+	s.name = name;
+
+	Ok(())
+}
+
 
-	Ok(OverseenSubsystem {
-		instance,
-	})
+async fn respawn<S: SpawnNamed, M: Send + 'static>(
+	spawner: &mut S,
+	futures: &mut FuturesUnordered<BoxFuture<'static, SubsystemKind>>,
+	streams: &mut StreamUnordered<mpsc::Receiver<ToOverseer>>,
+	origins: &mut HashMap<usize, SubsystemKind>,
+	metrics: &Metrics,
+	id: SubsystemKind,
+	s: &mut OverseenSubsystem<M>,
+	signal: OverseerSignal,
+) -> SubsystemResult<&'static str> {
+	spawn(spawner, futures, streams, origins, metrics, id, s)?;
+	s.restarts += 1;
+	s.send_signal(signal).await?;
+
+	Ok(s.name)
 }
 
 
@@ -1224,6 +1928,7 @@ mod tests {
 	use super::*;
 
 
+	#[derive(Clone)]
 	struct TestSubsystem1(mpsc::Sender<usize>);
 
 	impl<C> Subsystem<C> for TestSubsystem1
@@ -1254,6 +1959,7 @@ mod tests {
 		}
 	}
 
+	#[derive(Clone)]
 	struct TestSubsystem2(mpsc::Sender<usize>);
 
 	impl<C> Subsystem<C> for TestSubsystem2
@@ -1302,6 +2008,7 @@ mod tests {
 		}
 	}
 
+	#[derive(Clone)]
 	struct TestSubsystem4;
 
 	impl<C> Subsystem<C> for TestSubsystem4
@@ -1345,10 +2052,13 @@ mod tests {
 				chain_api: DummySubsystem,
 				collation_generation: DummySubsystem,
 				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
 			};
 			let (overseer, mut handler) = Overseer::new(
 				vec![],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				None,
 				spawner,
 			).unwrap();
@@ -1387,6 +2097,341 @@ mod tests {
 		});
 	}
 
+	#[derive(Clone, Default)]
+	struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+	impl Write for SharedBuffer {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	// Checks that the routed messages and broadcast signals end up in the recording.
+	#[test]
+	fn overseer_records_traffic() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let buffer = SharedBuffer::default();
+
+		executor::block_on(async move {
+			let (s1_tx, mut s1_rx) = mpsc::channel(64);
+			let (s2_tx, _s2_rx) = mpsc::channel(64);
+
+			let all_subsystems = AllSubsystems {
+				candidate_validation: TestSubsystem1(s1_tx),
+				candidate_backing: TestSubsystem2(s2_tx),
+				candidate_selection: DummySubsystem,
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
+			};
+			let (mut overseer, mut handler) = Overseer::new(
+				vec![],
+				all_subsystems,
+				Default::default(),
+				Default::default(),
+				None,
+				spawner,
+			).unwrap();
+			overseer.record_to(Recorder::new(buffer.clone()));
+			let overseer_fut = overseer.run().fuse();
+
+			pin_mut!(overseer_fut);
+
+			let mut s1_results = 0;
+
+			loop {
+				select! {
+					a = overseer_fut => break,
+					s1_next = s1_rx.next() => {
+						match s1_next {
+							Some(_) => {
+								s1_results += 1;
+								if s1_results == 10 {
+									handler.stop().await.unwrap();
+								}
+							}
+							None => break,
+						}
+					},
+					complete => break,
+				}
+			}
+
+			let recording = buffer.0.lock().unwrap().clone();
+			let entries = polkadot_subsystem::record::decode_recording(&recording).unwrap();
+
+			let messages: Vec<_> = entries.iter().filter_map(|entry| match entry.event {
+				RecordedEvent::Message(ref msg) => Some(msg),
+				_ => None,
+			}).collect();
+
+			assert_eq!(messages.len(), 10);
+			for msg in messages {
+				assert_eq!(msg.origin, Some(SubsystemKind::CandidateBacking));
+				assert_eq!(msg.destination, SubsystemKind::CandidateValidation);
+				assert!(msg.debug.starts_with("CandidateValidation(ValidateFromChainState"));
+				assert!(msg.payload.is_none());
+			}
+
+			assert_eq!(
+				entries.last().map(|entry| &entry.event),
+				Some(&RecordedEvent::Signal(RecordedSignal::Conclude)),
+			);
+		});
+	}
+
+	// Asks the collator protocol to collate on a few paras, and counts the answers.
+	#[derive(Clone)]
+	struct CollateOnSource(mpsc::Sender<()>);
+
+	impl<C> Subsystem<C> for CollateOnSource
+		where C: SubsystemContext<Message=CandidateSelectionMessage>
+	{
+		type Metrics = ();
+
+		fn start(self, mut ctx: C) -> SpawnedSubsystem {
+			let mut sender = self.0;
+			SpawnedSubsystem {
+				name: "collate-on-source",
+				future: Box::pin(async move {
+					for para in 1..=3u32 {
+						ctx.send_message(AllMessages::CollatorProtocol(
+							CollatorProtocolMessage::CollateOn(para.into()),
+						)).await.unwrap();
+					}
+
+					loop {
+						match ctx.recv().await {
+							Ok(FromOverseer::Communication { .. }) => {
+								let _ = sender.send(()).await;
+							}
+							Ok(FromOverseer::Signal(OverseerSignal::Conclude)) | Err(_) => return,
+							_ => (),
+						}
+					}
+				}),
+			}
+		}
+	}
+
+	// Answers every `CollateOn` with a message to candidate selection.
+	#[derive(Clone)]
+	struct CollateOnEcho;
+
+	impl<C> Subsystem<C> for CollateOnEcho
+		where C: SubsystemContext<Message=CollatorProtocolMessage>
+	{
+		type Metrics = ();
+
+		fn start(self, mut ctx: C) -> SpawnedSubsystem {
+			SpawnedSubsystem {
+				name: "collate-on-echo",
+				future: Box::pin(async move {
+					loop {
+						match ctx.recv().await {
+							Ok(FromOverseer::Communication { msg: CollatorProtocolMessage::CollateOn(para) }) => {
+								ctx.send_message(AllMessages::CandidateSelection(
+									CandidateSelectionMessage::Invalid(
+										Hash::repeat_byte(u32::from(para) as u8),
+										Default::default(),
+									),
+								)).await.unwrap();
+							}
+							Ok(FromOverseer::Signal(OverseerSignal::Conclude)) | Err(_) => return,
+							_ => (),
+						}
+					}
+				}),
+			}
+		}
+	}
+
+	// Checks that a subsystem replayed against a recording of the overseer behaves as it did.
+	#[test]
+	fn recording_replays_subsystem() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let buffer = SharedBuffer::default();
+		let (answers_tx, mut answers_rx) = mpsc::channel(64);
+
+		executor::block_on(async move {
+			let all_subsystems = AllSubsystems {
+				candidate_validation: DummySubsystem,
+				candidate_backing: DummySubsystem,
+				candidate_selection: CollateOnSource(answers_tx),
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: CollateOnEcho,
+				gossip_support: DummySubsystem,
+			};
+			let (mut overseer, mut handler) = Overseer::new(
+				vec![],
+				all_subsystems,
+				Default::default(),
+				Default::default(),
+				None,
+				spawner,
+			).unwrap();
+			overseer.record_to(Recorder::new(buffer.clone()));
+			let overseer_fut = overseer.run().fuse();
+
+			pin_mut!(overseer_fut);
+
+			let mut answers = 0;
+
+			loop {
+				select! {
+					_ = overseer_fut => break,
+					answer = answers_rx.next() => match answer {
+						Some(()) => {
+							answers += 1;
+							if answers == 3 {
+								handler.stop().await.unwrap();
+							}
+						}
+						None => break,
+					},
+					complete => break,
+				}
+			}
+		});
+
+		let recording = buffer.0.lock().unwrap().clone();
+		let entries = polkadot_subsystem::record::decode_recording(&recording).unwrap();
+
+		let report = polkadot_node_subsystem_test_helpers::replay_recording::<CollatorProtocolMessage, _>(
+			&entries,
+			SubsystemKind::CollatorProtocol,
+			CollateOnEcho,
+		);
+
+		assert_eq!(report.expected.len(), 3);
+		assert_eq!(report.skipped, 0);
+		assert!(report.matches(), "replay diverged: {:?}", report);
+		assert_eq!(report.first_divergence(), None);
+
+		// a subsystem which doesn't answer diverges right away.
+		let report = polkadot_node_subsystem_test_helpers::replay_recording::<CollatorProtocolMessage, _>(
+			&entries,
+			SubsystemKind::CollatorProtocol,
+			DummySubsystem,
+		);
+
+		assert_eq!(report.expected.len(), 3);
+		assert!(report.actual.is_empty());
+		assert_eq!(report.first_divergence(), Some(0));
+	}
+
+	// Checks that routed and received messages are counted per message type.
+	#[test]
+	fn overseer_counts_messages() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+
+		executor::block_on(async move {
+			let (s1_tx, mut s1_rx) = mpsc::channel(64);
+			let (s2_tx, _s2_rx) = mpsc::channel(64);
+
+			let all_subsystems = AllSubsystems {
+				candidate_validation: TestSubsystem1(s1_tx),
+				candidate_backing: TestSubsystem2(s2_tx),
+				candidate_selection: DummySubsystem,
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
+			};
+			let registry = prometheus::Registry::new();
+			let (overseer, mut handler) = Overseer::new(
+				vec![],
+				all_subsystems,
+				Default::default(),
+				OverflowPolicies::drop_oldest_gossip(),
+				Some(&registry),
+				spawner,
+			).unwrap();
+			let overseer_fut = overseer.run().fuse();
+
+			pin_mut!(overseer_fut);
+
+			let mut s1_results = 0;
+
+			loop {
+				select! {
+					a = overseer_fut => break,
+					s1_next = s1_rx.next() => {
+						match s1_next {
+							Some(_) => {
+								s1_results += 1;
+								if s1_results == 10 {
+									handler.stop().await.unwrap();
+								}
+							}
+							None => break,
+						}
+					},
+					complete => break,
+				}
+			}
+
+			let counter = |name: &str| registry.gather()
+				.into_iter()
+				.find(|m| m.get_name() == name)
+				.map(|m| {
+					let metric = &m.get_metric()[0];
+					assert_eq!(metric.get_label()[0].get_value(), "CandidateValidation");
+					metric.get_counter().get_value() as u64
+				});
+
+			assert_eq!(counter("parachain_messages_sent_total"), Some(10));
+			assert_eq!(counter("parachain_messages_received_total"), Some(10));
+			assert_eq!(counter("parachain_messages_dropped_total"), None);
+
+			let queue_time = registry.gather()
+				.into_iter()
+				.find(|m| m.get_name() == "parachain_subsystem_queue_time_seconds")
+				.expect("queue times were observed");
+			let candidate_validation = queue_time.get_metric()
+				.iter()
+				.find(|m| m.get_label()[0].get_value() == "CandidateValidation")
+				.expect("candidate validation took messages");
+			assert_eq!(candidate_validation.get_label()[0].get_name(), "subsystem");
+			// the messages, and maybe the `Conclude` signal.
+			assert!(candidate_validation.get_histogram().get_sample_count() >= 10);
+		});
+	}
+
 	// Checks activated/deactivated metrics are updated properly.
 	#[test]
 	fn overseer_metrics_work() {
@@ -1419,6 +2464,7 @@ mod tests {
 				candidate_backing: DummySubsystem,
 				candidate_selection: DummySubsystem,
 				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
 				statement_distribution: DummySubsystem,
 				availability_distribution: DummySubsystem,
 				bitfield_signing: DummySubsystem,
@@ -1434,6 +2480,8 @@ mod tests {
 			let (overseer, mut handler) = Overseer::new(
 				vec![first_block],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				Some(&registry),
 				spawner,
 			).unwrap();
@@ -1491,10 +2539,13 @@ mod tests {
 				chain_api: DummySubsystem,
 				collation_generation: DummySubsystem,
 				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
 			};
 			let (overseer, _handle) = Overseer::new(
 				vec![],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				None,
 				spawner,
 			).unwrap();
@@ -1508,6 +2559,316 @@ mod tests {
 		})
 	}
 
+	// Spawn a subsystem that immediately exits, but is allowed to.
+	//
+	// The overseer should keep running without it.
+	#[test]
+	fn overseer_ignores_subsystem_exit() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+
+		executor::block_on(async move {
+			let (s1_tx, _) = mpsc::channel(64);
+			let all_subsystems = AllSubsystems {
+				candidate_validation: TestSubsystem1(s1_tx),
+				candidate_backing: TestSubsystem4,
+				candidate_selection: DummySubsystem,
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
+			};
+			let supervision = SupervisionPolicies {
+				candidate_backing: SupervisionPolicy::Ignore,
+				..Default::default()
+			};
+			let (overseer, mut handler) = Overseer::new(
+				vec![],
+				all_subsystems,
+				supervision,
+				Default::default(),
+				None,
+				spawner,
+			).unwrap();
+			let overseer_fut = overseer.run().fuse();
+			pin_mut!(overseer_fut);
+
+			let timer = Delay::new(Duration::from_millis(50)).fuse();
+			pin_mut!(timer);
+
+			select! {
+				_ = overseer_fut => panic!("overseer should not conclude on an ignored exit"),
+				_ = timer => (),
+			}
+
+			// Messages to the exited subsystem are dropped.
+			handler.send_msg(AllMessages::CandidateBacking(test_candidate_backing_msg())).await.unwrap();
+			handler.stop().await.unwrap();
+
+			assert!(overseer_fut.await.is_ok());
+		})
+	}
+
+	#[derive(Clone)]
+	struct ExitOnFirstStart {
+		starts: Arc<atomic::AtomicUsize>,
+		signals: mpsc::Sender<OverseerSignal>,
+	}
+
+	impl<C, M> Subsystem<C> for ExitOnFirstStart
+		where
+			C: SubsystemContext<Message=M>,
+			M: Send,
+	{
+		type Metrics = ();
+
+		fn start(self, mut ctx: C) -> SpawnedSubsystem {
+			let first_start = self.starts.fetch_add(1, atomic::Ordering::SeqCst) == 0;
+			let mut sender = self.signals;
+
+			SpawnedSubsystem {
+				name: "exit-on-first-start",
+				future: Box::pin(async move {
+					if first_start {
+						return;
+					}
+
+					loop {
+						match ctx.recv().await {
+							Ok(FromOverseer::Signal(OverseerSignal::Conclude)) => return,
+							Ok(FromOverseer::Signal(s)) => {
+								let _ = sender.send(s).await;
+							}
+							Ok(_) => (),
+							Err(_) => return,
+						}
+					}
+				}),
+			}
+		}
+	}
+
+	// Spawn a subsystem that exits the first time it is started.
+	//
+	// The overseer should start it again and bring it up to date with the active leaves.
+	#[test]
+	fn overseer_restarts_subsystem() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+
+		executor::block_on(async move {
+			let first_block_hash = [1; 32].into();
+			let first_block = BlockInfo {
+				hash: first_block_hash,
+				parent_hash: [0; 32].into(),
+				number: 1,
+			};
+
+			let starts = Arc::new(atomic::AtomicUsize::new(0));
+			let (signals_tx, mut signals_rx) = mpsc::channel(64);
+
+			let all_subsystems = AllSubsystems {
+				candidate_validation: DummySubsystem,
+				candidate_backing: ExitOnFirstStart {
+					starts: starts.clone(),
+					signals: signals_tx,
+				},
+				candidate_selection: DummySubsystem,
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
+			};
+			let supervision = SupervisionPolicies {
+				candidate_backing: SupervisionPolicy::Restart {
+					initial_backoff: Duration::from_millis(10),
+					max_backoff: Duration::from_millis(100),
+				},
+				..Default::default()
+			};
+			let registry = prometheus::Registry::new();
+			let (overseer, mut handler) = Overseer::new(
+				vec![first_block],
+				all_subsystems,
+				supervision,
+				Default::default(),
+				Some(&registry),
+				spawner,
+			).unwrap();
+			let overseer_fut = overseer.run().fuse();
+			pin_mut!(overseer_fut);
+
+			let mut signals = Vec::new();
+
+			loop {
+				select! {
+					res = overseer_fut => {
+						assert!(res.is_ok());
+						break;
+					},
+					res = signals_rx.next() => {
+						if let Some(res) = res {
+							signals.push(res);
+							if signals.len() == 1 {
+								handler.stop().await.unwrap();
+							}
+						}
+					},
+					complete => break,
+				}
+			}
+
+			assert_eq!(starts.load(atomic::Ordering::SeqCst), 2);
+			assert_eq!(
+				signals[0],
+				OverseerSignal::ActiveLeaves(ActiveLeavesUpdate::start_work(first_block_hash)),
+			);
+
+			let restarts = registry.gather()
+				.into_iter()
+				.find(|m| m.get_name() == "parachain_subsystem_restarts_total")
+				.expect("a restart was recorded");
+			assert_eq!(restarts.get_metric()[0].get_counter().get_value() as u64, 1);
+		})
+	}
+
+	// Spawn two subsystems that both exit right away the first time they are started.
+	//
+	// The overseer should restart both, not only the first one to be noticed.
+	#[test]
+	fn overseer_restarts_subsystems_exiting_together() {
+		let spawner = sp_core::testing::TaskExecutor::new();
+
+		executor::block_on(async move {
+			let first_block_hash = [1; 32].into();
+			let first_block = BlockInfo {
+				hash: first_block_hash,
+				parent_hash: [0; 32].into(),
+				number: 1,
+			};
+
+			let backing_starts = Arc::new(atomic::AtomicUsize::new(0));
+			let selection_starts = Arc::new(atomic::AtomicUsize::new(0));
+			let (signals_tx, mut signals_rx) = mpsc::channel(64);
+
+			let all_subsystems = AllSubsystems {
+				candidate_validation: DummySubsystem,
+				candidate_backing: ExitOnFirstStart {
+					starts: backing_starts.clone(),
+					signals: signals_tx.clone(),
+				},
+				candidate_selection: ExitOnFirstStart {
+					starts: selection_starts.clone(),
+					signals: signals_tx,
+				},
+				statement_distribution: DummySubsystem,
+				availability_distribution: DummySubsystem,
+				bitfield_signing: DummySubsystem,
+				bitfield_distribution: DummySubsystem,
+				provisioner: DummySubsystem,
+				pov_distribution: DummySubsystem,
+				runtime_api: DummySubsystem,
+				availability_store: DummySubsystem,
+				network_bridge: DummySubsystem,
+				chain_api: DummySubsystem,
+				collation_generation: DummySubsystem,
+				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
+			};
+			let restart = SupervisionPolicy::Restart {
+				initial_backoff: Duration::from_millis(10),
+				max_backoff: Duration::from_millis(100),
+			};
+			let supervision = SupervisionPolicies {
+				candidate_backing: restart,
+				candidate_selection: restart,
+				..Default::default()
+			};
+			let registry = prometheus::Registry::new();
+			let (overseer, mut handler) = Overseer::new(
+				vec![first_block],
+				all_subsystems,
+				supervision,
+				Default::default(),
+				Some(&registry),
+				spawner,
+			).unwrap();
+			let overseer_fut = overseer.run().fuse();
+			pin_mut!(overseer_fut);
+
+			let mut signals = Vec::new();
+
+			loop {
+				select! {
+					res = overseer_fut => {
+						assert!(res.is_ok());
+						break;
+					},
+					res = signals_rx.next() => {
+						if let Some(res) = res {
+							signals.push(res);
+							if signals.len() == 2 {
+								handler.stop().await.unwrap();
+							}
+						}
+					},
+					complete => break,
+				}
+			}
+
+			assert_eq!(backing_starts.load(atomic::Ordering::SeqCst), 2);
+			assert_eq!(selection_starts.load(atomic::Ordering::SeqCst), 2);
+			for signal in &signals[..2] {
+				assert_eq!(
+					*signal,
+					OverseerSignal::ActiveLeaves(ActiveLeavesUpdate::start_work(first_block_hash)),
+				);
+			}
+
+			let restarts: u64 = registry.gather()
+				.into_iter()
+				.find(|m| m.get_name() == "parachain_subsystem_restarts_total")
+				.expect("restarts were recorded")
+				.get_metric()
+				.iter()
+				.map(|m| m.get_counter().get_value() as u64)
+				.sum();
+			assert_eq!(restarts, 2);
+		})
+	}
+
+	#[test]
+	fn restart_backoff_doubles_up_to_max() {
+		let policy = SupervisionPolicy::Restart {
+			initial_backoff: Duration::from_millis(10),
+			max_backoff: Duration::from_millis(50),
+		};
+
+		assert_eq!(policy.backoff(0), Some(Duration::from_millis(10)));
+		assert_eq!(policy.backoff(1), Some(Duration::from_millis(20)));
+		assert_eq!(policy.backoff(2), Some(Duration::from_millis(40)));
+		assert_eq!(policy.backoff(3), Some(Duration::from_millis(50)));
+		assert_eq!(policy.backoff(100), Some(Duration::from_millis(50)));
+		assert_eq!(SupervisionPolicy::Ignore.backoff(0), None);
+	}
+
+	#[derive(Clone)]
 	struct TestSubsystem5(mpsc::Sender<OverseerSignal>);
 
 	impl<C> Subsystem<C> for TestSubsystem5
@@ -1539,6 +2900,7 @@ mod tests {
 		}
 	}
 
+	#[derive(Clone)]
 	struct TestSubsystem6(mpsc::Sender<OverseerSignal>);
 
 	impl<C> Subsystem<C> for TestSubsystem6
@@ -1615,10 +2977,13 @@ mod tests {
 				chain_api: DummySubsystem,
 				collation_generation: DummySubsystem,
 				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
 			};
 			let (overseer, mut handler) = Overseer::new(
 				vec![first_block],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				None,
 				spawner,
 			).unwrap();
@@ -1720,11 +3085,14 @@ mod tests {
 				chain_api: DummySubsystem,
 				collation_generation: DummySubsystem,
 				collator_protocol: DummySubsystem,
+				gossip_support: DummySubsystem,
 			};
 			// start with two forks of different height.
 			let (overseer, mut handler) = Overseer::new(
 				vec![first_block, second_block],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				None,
 				spawner,
 			).unwrap();
@@ -1957,10 +3325,13 @@ mod tests {
 				availability_store: subsystem.clone(),
 				network_bridge: subsystem.clone(),
 				chain_api: subsystem.clone(),
+				gossip_support: subsystem.clone(),
 			};
 			let (overseer, mut handler) = Overseer::new(
 				vec![],
 				all_subsystems,
+				Default::default(),
+				Default::default(),
 				None,
 				spawner,
 			).unwrap();
@@ -1998,13 +3369,13 @@ mod tests {
 
 			select! {
 				res = overseer_fut => {
-					const NUM_SUBSYSTEMS: usize = 15;
+					const NUM_SUBSYSTEMS: usize = 16;
 
 					assert_eq!(stop_signals_received.load(atomic::Ordering::SeqCst), NUM_SUBSYSTEMS);
 					// x2 because of broadcast_signal on startup
 					assert_eq!(signals_received.load(atomic::Ordering::SeqCst), 2 * NUM_SUBSYSTEMS);
-					// -1 for BitfieldSigning
-					assert_eq!(msgs_received.load(atomic::Ordering::SeqCst), NUM_SUBSYSTEMS - 1);
+					// -2 for BitfieldSigning and GossipSupport
+					assert_eq!(msgs_received.load(atomic::Ordering::SeqCst), NUM_SUBSYSTEMS - 2);
 
 					assert!(res.is_ok());
 				},
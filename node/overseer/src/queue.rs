@@ -0,0 +1,258 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The bounded queues carrying messages and signals from the `Overseer` to its subsystems.
+//!
+//! Unlike a plain `mpsc` channel, a queue knows what to do when it is full (see [`OverflowPolicy`])
+//! and reports its depth and the time its items spend waiting to the `Overseer`'s metrics.
+//!
+//! [`OverflowPolicy`]: ../enum.OverflowPolicy.html
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+
+use futures::{future, Stream};
+use parking_lot::Mutex;
+
+use polkadot_subsystem::{FromOverseer, SubsystemError, SubsystemResult};
+use polkadot_subsystem::messages::SubsystemKind;
+
+use crate::{Metrics, OverflowPolicy};
+
+struct Queue<M> {
+	items: VecDeque<(Instant, FromOverseer<M>)>,
+	/// The receiver waiting for an item to arrive.
+	recv_waker: Option<Waker>,
+	/// The sender waiting for room in a full queue.
+	send_waker: Option<Waker>,
+	sender_dropped: bool,
+	receiver_dropped: bool,
+}
+
+struct Shared<M> {
+	queue: Mutex<Queue<M>>,
+	capacity: usize,
+	policy: OverflowPolicy,
+	subsystem: SubsystemKind,
+	metrics: Metrics,
+}
+
+/// The `Overseer`'s end of a subsystem queue.
+pub(crate) struct QueueSender<M>(Arc<Shared<M>>);
+
+/// The subsystem's end of its queue.
+pub(crate) struct QueueReceiver<M>(Arc<Shared<M>>);
+
+/// Create the queue of a subsystem, holding up to `capacity` items before `policy` kicks in.
+pub(crate) fn queue<M>(
+	capacity: usize,
+	policy: OverflowPolicy,
+	subsystem: SubsystemKind,
+	metrics: Metrics,
+) -> (QueueSender<M>, QueueReceiver<M>) {
+	let shared = Arc::new(Shared {
+		queue: Mutex::new(Queue {
+			items: VecDeque::new(),
+			recv_waker: None,
+			send_waker: None,
+			sender_dropped: false,
+			receiver_dropped: false,
+		}),
+		capacity,
+		policy,
+		subsystem,
+		metrics,
+	});
+
+	(QueueSender(shared.clone()), QueueReceiver(shared))
+}
+
+impl<M> QueueSender<M> {
+	/// Send a message or a signal, dealing with a full queue according to its policy.
+	///
+	/// Fails if the receiving subsystem is gone.
+	pub(crate) async fn send(&mut self, item: FromOverseer<M>) -> SubsystemResult<()> {
+		let mut item = Some(item);
+		future::poll_fn(|cx| self.poll_send(cx, &mut item)).await
+	}
+
+	fn poll_send(&self, cx: &mut Context, item: &mut Option<FromOverseer<M>>) -> Poll<SubsystemResult<()>> {
+		let shared = &*self.0;
+		let mut queue = shared.queue.lock();
+
+		if queue.receiver_dropped {
+			return Poll::Ready(Err(SubsystemError));
+		}
+
+		if queue.items.len() >= shared.capacity {
+			match shared.policy {
+				OverflowPolicy::Block => {
+					queue.send_waker = Some(cx.waker().clone());
+					return Poll::Pending;
+				}
+				OverflowPolicy::DropOldest => {
+					// Signals are never dropped, so a queue full of them simply grows.
+					let oldest_message = queue.items.iter()
+						.position(|(_, item)| matches!(item, FromOverseer::Communication { .. }));
+
+					if let Some(position) = oldest_message {
+						queue.items.remove(position);
+						shared.metrics.on_message_dropped(shared.subsystem);
+					}
+				}
+			}
+		}
+
+		let item = item.take().expect("`poll_send` is not called again once it is ready; qed");
+		queue.items.push_back((Instant::now(), item));
+		shared.metrics.on_queue_depth(shared.subsystem, queue.items.len());
+
+		if let Some(waker) = queue.recv_waker.take() {
+			waker.wake();
+		}
+
+		Poll::Ready(Ok(()))
+	}
+}
+
+impl<M> Drop for QueueSender<M> {
+	fn drop(&mut self) {
+		let mut queue = self.0.queue.lock();
+		queue.sender_dropped = true;
+
+		if let Some(waker) = queue.recv_waker.take() {
+			waker.wake();
+		}
+	}
+}
+
+impl<M> Stream for QueueReceiver<M> {
+	type Item = FromOverseer<M>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let shared = &*self.0;
+		let mut queue = shared.queue.lock();
+
+		match queue.items.pop_front() {
+			Some((queued_at, item)) => {
+				let is_message = matches!(item, FromOverseer::Communication { .. });
+				shared.metrics.on_queue_depth(shared.subsystem, queue.items.len());
+				shared.metrics.on_dequeued(shared.subsystem, queued_at.elapsed(), is_message);
+
+				if let Some(waker) = queue.send_waker.take() {
+					waker.wake();
+				}
+
+				Poll::Ready(Some(item))
+			}
+			None if queue.sender_dropped => Poll::Ready(None),
+			None => {
+				queue.recv_waker = Some(cx.waker().clone());
+				Poll::Pending
+			}
+		}
+	}
+}
+
+impl<M> Drop for QueueReceiver<M> {
+	fn drop(&mut self) {
+		let mut queue = self.0.queue.lock();
+		queue.receiver_dropped = true;
+		queue.items.clear();
+		self.0.metrics.on_queue_depth(self.0.subsystem, 0);
+
+		if let Some(waker) = queue.send_waker.take() {
+			waker.wake();
+		}
+	}
+}
+
+impl<M> fmt::Debug for QueueReceiver<M> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("QueueReceiver")
+			.field("subsystem", &self.0.subsystem)
+			.field("policy", &self.0.policy)
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{executor, StreamExt};
+
+	fn message(n: usize) -> FromOverseer<usize> {
+		FromOverseer::Communication { msg: n }
+	}
+
+	fn received(item: Option<FromOverseer<usize>>) -> Option<usize> {
+		match item {
+			Some(FromOverseer::Communication { msg }) => Some(msg),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn drop_oldest_keeps_newest_messages_and_all_signals() {
+		let (mut tx, mut rx) = queue(
+			2,
+			OverflowPolicy::DropOldest,
+			SubsystemKind::StatementDistribution,
+			Metrics::default(),
+		);
+
+		executor::block_on(async move {
+			tx.send(FromOverseer::Signal(polkadot_subsystem::OverseerSignal::Conclude)).await.unwrap();
+			for n in 0..5 {
+				tx.send(message(n)).await.unwrap();
+			}
+
+			assert!(matches!(
+				rx.next().await,
+				Some(FromOverseer::Signal(polkadot_subsystem::OverseerSignal::Conclude))
+			));
+			assert_eq!(received(rx.next().await), Some(4));
+
+			drop(tx);
+			assert!(rx.next().await.is_none());
+		});
+	}
+
+	#[test]
+	fn block_waits_for_room() {
+		let (mut tx, mut rx) = queue(1, OverflowPolicy::Block, SubsystemKind::CandidateBacking, Metrics::default());
+
+		executor::block_on(async move {
+			tx.send(message(0)).await.unwrap();
+
+			let mut item = Some(message(1));
+			let waker = futures::task::noop_waker();
+			let mut cx = Context::from_waker(&waker);
+			assert!(tx.poll_send(&mut cx, &mut item).is_pending());
+
+			assert_eq!(received(rx.next().await), Some(0));
+			assert!(tx.poll_send(&mut cx, &mut item).is_ready());
+			assert_eq!(received(rx.next().await), Some(1));
+
+			drop(rx);
+			assert!(tx.send(message(2)).await.is_err());
+		});
+	}
+}
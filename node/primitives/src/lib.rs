@@ -280,6 +280,71 @@ pub struct Collation {
 	pub proof_of_validity: PoV,
 }
 
+/// The maximum size of a decompressed PoV.
+///
+/// This bounds the memory a small compressed PoV can make us allocate when it is decompressed.
+pub const MAX_POV_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compression level used for PoVs; zstd's default, favouring speed over size.
+const POV_COMPRESSION_LEVEL: i32 = 3;
+
+/// A SCALE-encoded `PoV`, compressed with zstd.
+#[derive(Clone, Encode, Decode, PartialEq, Eq)]
+pub struct CompressedPoV(Vec<u8>);
+
+/// Errors compressing or decompressing a `PoV`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedPoVError {
+	/// The PoV could not be compressed.
+	Compress,
+	/// The compressed data is not valid zstd.
+	Decompress,
+	/// The decompressed data exceeds `MAX_POV_SIZE`.
+	TooLarge,
+	/// The decompressed data is not a SCALE-encoded PoV.
+	Decode,
+}
+
+impl CompressedPoV {
+	/// Compress the given PoV.
+	pub fn compress(pov: &PoV) -> Result<Self, CompressedPoVError> {
+		zstd::encode_all(&pov.encode()[..], POV_COMPRESSION_LEVEL)
+			.map(CompressedPoV)
+			.map_err(|_| CompressedPoVError::Compress)
+	}
+
+	/// Decompress the PoV, refusing to produce more than `MAX_POV_SIZE` bytes of encoded PoV.
+	pub fn decompress(&self) -> Result<PoV, CompressedPoVError> {
+		use std::io::Read;
+
+		let decoder = zstd::stream::read::Decoder::new(&self.0[..])
+			.map_err(|_| CompressedPoVError::Decompress)?;
+
+		// Read a byte more than allowed, to tell a PoV of the maximum size from a larger one.
+		let mut encoded = Vec::new();
+		decoder.take(MAX_POV_SIZE as u64 + 1)
+			.read_to_end(&mut encoded)
+			.map_err(|_| CompressedPoVError::Decompress)?;
+
+		if encoded.len() > MAX_POV_SIZE {
+			return Err(CompressedPoVError::TooLarge);
+		}
+
+		PoV::decode(&mut &encoded[..]).map_err(|_| CompressedPoVError::Decode)
+	}
+
+	/// The size of the compressed PoV in bytes.
+	pub fn compressed_size(&self) -> usize {
+		self.0.len()
+	}
+}
+
+impl std::fmt::Debug for CompressedPoV {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "CompressedPoV({} bytes)", self.0.len())
+	}
+}
+
 /// Configuration for the collation generator
 pub struct CollationGenerationConfig {
 	/// Collator's authentication key, so it can sign things.
@@ -295,3 +360,34 @@ impl std::fmt::Debug for CollationGenerationConfig {
 		write!(f, "CollationGenerationConfig {{ ... }}")
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use polkadot_primitives::v1::BlockData;
+
+	#[test]
+	fn compressed_pov_roundtrip() {
+		let pov = PoV { block_data: BlockData(vec![7; 64 * 1024]) };
+		let compressed = CompressedPoV::compress(&pov).unwrap();
+
+		assert!(compressed.compressed_size() < pov.encoded_size());
+		assert_eq!(compressed.decompress(), Ok(pov));
+	}
+
+	#[test]
+	fn decompression_bomb_is_refused() {
+		let pov = PoV { block_data: BlockData(vec![0; MAX_POV_SIZE + 1]) };
+		let compressed = CompressedPoV::compress(&pov).unwrap();
+
+		assert_eq!(compressed.decompress(), Err(CompressedPoVError::TooLarge));
+	}
+
+	#[test]
+	fn garbage_is_refused() {
+		assert_eq!(
+			CompressedPoV(vec![1, 2, 3]).decompress(),
+			Err(CompressedPoVError::Decompress),
+		);
+	}
+}
@@ -0,0 +1,404 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A relay chain whose blocks are scripted by the test, and the subsystems serving requests
+//! about it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::{channel::mpsc, prelude::*};
+use parking_lot::Mutex;
+use sp_runtime::traits::Header as HeaderT;
+
+use polkadot_overseer::BlockInfo;
+use polkadot_primitives::v1::{BlockNumber, Hash, Header};
+use polkadot_subsystem::{
+	FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem, SubsystemContext, SubsystemResult,
+	messages::{ChainApiMessage, RuntimeApiMessage, RuntimeApiRequest},
+};
+
+#[derive(Default)]
+struct ChainState {
+	headers: HashMap<Hash, Header>,
+	children: HashMap<Hash, Vec<Hash>>,
+	// The finalized chain, indexed by block number.
+	finalized: Vec<Hash>,
+	best: Hash,
+}
+
+impl ChainState {
+	fn number(&self, hash: &Hash) -> Option<BlockNumber> {
+		self.headers.get(hash).map(|header| header.number)
+	}
+
+	fn ancestors(&self, hash: &Hash, k: usize) -> Vec<Hash> {
+		let mut ancestors = Vec::with_capacity(k);
+		let mut current = self.headers.get(hash);
+
+		while ancestors.len() < k {
+			let parent = match current {
+				Some(header) if header.number > 0 => header.parent_hash,
+				_ => break,
+			};
+
+			ancestors.push(parent);
+			current = self.headers.get(&parent);
+		}
+
+		ancestors
+	}
+
+	fn is_ancestor(&self, ancestor: &Hash, of: &Hash) -> bool {
+		let number = match self.number(ancestor) {
+			Some(number) => number,
+			None => return false,
+		};
+
+		let distance = match self.number(of) {
+			Some(of_number) if of_number >= number => (of_number - number) as usize,
+			_ => return false,
+		};
+
+		(distance == 0 && ancestor == of) || self.ancestors(of, distance).last() == Some(ancestor)
+	}
+}
+
+/// A relay chain built block by block by the test.
+///
+/// Blocks carry no extrinsics, and are only told apart by their parent and by the order in which
+/// siblings were added. The best block is the first block added at the greatest height.
+#[derive(Clone)]
+pub struct SimulatedChain(Arc<Mutex<ChainState>>);
+
+impl Default for SimulatedChain {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SimulatedChain {
+	/// Create a chain made of the finalized genesis block.
+	pub fn new() -> Self {
+		let genesis = Header {
+			parent_hash: Hash::zero(),
+			number: 0,
+			state_root: Hash::zero(),
+			extrinsics_root: Hash::zero(),
+			digest: Default::default(),
+		};
+		let hash = genesis.hash();
+
+		let mut state = ChainState::default();
+		state.headers.insert(hash, genesis);
+		state.finalized.push(hash);
+		state.best = hash;
+
+		SimulatedChain(Arc::new(Mutex::new(state)))
+	}
+
+	/// The hash of the genesis block.
+	pub fn genesis(&self) -> Hash {
+		self.0.lock().finalized[0]
+	}
+
+	/// The hash of the best block.
+	pub fn best(&self) -> Hash {
+		self.0.lock().best
+	}
+
+	/// The number of the last finalized block.
+	pub fn finalized_number(&self) -> BlockNumber {
+		(self.0.lock().finalized.len() - 1) as BlockNumber
+	}
+
+	/// Add a child to the given block, returning its hash.
+	///
+	/// Panics if the parent is unknown.
+	pub fn add_block(&self, parent: Hash) -> Hash {
+		let mut state = self.0.lock();
+		let number = state.number(&parent).expect("blocks can only be added on top of known ones") + 1;
+		let siblings = state.children.get(&parent).map_or(0, Vec::len);
+
+		let header = Header {
+			parent_hash: parent,
+			number,
+			state_root: Hash::zero(),
+			extrinsics_root: Hash::from_low_u64_be(siblings as u64),
+			digest: Default::default(),
+		};
+		let hash = header.hash();
+
+		state.headers.insert(hash, header);
+		state.children.entry(parent).or_default().push(hash);
+
+		let best_number = state.number(&state.best).unwrap_or(0);
+		if number > best_number {
+			state.best = hash;
+		}
+
+		hash
+	}
+
+	/// Finalize the given block along with its ancestors.
+	///
+	/// Panics if the block is unknown, or not a descendant of the last finalized block.
+	pub fn finalize(&self, hash: Hash) {
+		let mut state = self.0.lock();
+		let last_finalized = *state.finalized.last().expect("genesis is always finalized; qed");
+		assert!(
+			state.is_ancestor(&last_finalized, &hash),
+			"Only descendants of the last finalized block can be finalized",
+		);
+
+		let number = state.number(&hash).expect("known because descendant of a known block; qed");
+		let newly_finalized = number as usize + 1 - state.finalized.len();
+
+		let mut route = state.ancestors(&hash, newly_finalized.saturating_sub(1));
+		route.reverse();
+		route.push(hash);
+
+		state.finalized.extend(route.into_iter().skip_while(|h| *h == last_finalized));
+	}
+
+	/// The header of the given block.
+	pub fn header(&self, hash: &Hash) -> Option<Header> {
+		self.0.lock().headers.get(hash).cloned()
+	}
+
+	/// Information about the given block, as the overseer expects it.
+	///
+	/// Panics if the block is unknown.
+	pub fn block_info(&self, hash: Hash) -> BlockInfo {
+		let header = self.header(&hash).expect("block info can only be built for known blocks");
+
+		BlockInfo {
+			hash,
+			parent_hash: header.parent_hash,
+			number: header.number,
+		}
+	}
+
+	fn descendants_on_best_chain(&self, hash: &Hash, k: usize) -> Vec<Hash> {
+		let state = self.0.lock();
+		let number = match state.number(hash) {
+			Some(number) => number,
+			None => return Vec::new(),
+		};
+
+		let best = state.best;
+		let best_number = state.number(&best).unwrap_or(0);
+		if best_number <= number || !state.is_ancestor(hash, &best) {
+			return Vec::new();
+		}
+
+		// ancestors of the best block down to, but excluding, `hash`.
+		let mut descendants = state.ancestors(&best, (best_number - number - 1) as usize);
+		descendants.reverse();
+		descendants.push(best);
+		descendants.truncate(k);
+
+		descendants
+	}
+}
+
+/// A Chain API subsystem serving the blocks of a [`SimulatedChain`].
+///
+/// The weight of a block is its number, as if every block claimed a primary slot.
+#[derive(Clone)]
+pub struct SimulatedChainApi {
+	chain: SimulatedChain,
+}
+
+impl SimulatedChainApi {
+	/// Create a Chain API subsystem serving the given chain.
+	pub fn new(chain: SimulatedChain) -> Self {
+		SimulatedChainApi { chain }
+	}
+}
+
+impl<Context> Subsystem<Context> for SimulatedChainApi
+	where Context: SubsystemContext<Message = ChainApiMessage>
+{
+	type Metrics = ();
+
+	fn start(self, ctx: Context) -> SpawnedSubsystem {
+		SpawnedSubsystem {
+			name: "simulated-chain-api-subsystem",
+			future: run_chain_api(ctx, self.chain).map(|_| ()).boxed(),
+		}
+	}
+}
+
+async fn run_chain_api(
+	mut ctx: impl SubsystemContext<Message = ChainApiMessage>,
+	chain: SimulatedChain,
+) -> SubsystemResult<()> {
+	let mut finality_subscribers: Vec<mpsc::UnboundedSender<(Hash, BlockNumber)>> = Vec::new();
+	let mut last_finalized = None;
+
+	loop {
+		let msg = match ctx.recv().await? {
+			FromOverseer::Signal(OverseerSignal::Conclude) => return Ok(()),
+			FromOverseer::Signal(OverseerSignal::BlockFinalized(hash)) => {
+				let state = chain.0.lock();
+				let number = match state.number(&hash) {
+					Some(number) => number,
+					None => continue,
+				};
+
+				// every block finalized since the last signal, as the real Chain API does.
+				let first = last_finalized.map_or(number, |last: BlockNumber| last + 1);
+				let finalized: Vec<_> = (first..=number)
+					.filter_map(|n| state.finalized.get(n as usize).map(|hash| (*hash, n)))
+					.collect();
+				drop(state);
+
+				last_finalized = Some(number);
+				finality_subscribers.retain(|s| {
+					finalized.iter().all(|block| s.unbounded_send(*block).is_ok())
+				});
+				continue;
+			}
+			FromOverseer::Signal(_) => continue,
+			FromOverseer::Communication { msg } => msg,
+		};
+
+		match msg {
+			ChainApiMessage::BlockNumber(hash, response_channel) => {
+				let _ = response_channel.send(Ok(chain.0.lock().number(&hash)));
+			}
+			ChainApiMessage::BlockHeader(hash, response_channel) => {
+				let _ = response_channel.send(Ok(chain.header(&hash)));
+			}
+			ChainApiMessage::BlockBody(hash, response_channel) => {
+				let body = chain.header(&hash).map(|_| Vec::new());
+				let _ = response_channel.send(Ok(body));
+			}
+			ChainApiMessage::BlockWeight(hash, response_channel) => {
+				let _ = response_channel.send(Ok(chain.0.lock().number(&hash)));
+			}
+			ChainApiMessage::FinalizedBlockHash(number, response_channel) => {
+				let hash = chain.0.lock().finalized.get(number as usize).cloned();
+				let _ = response_channel.send(Ok(hash));
+			}
+			ChainApiMessage::FinalizedBlockNumber(response_channel) => {
+				let _ = response_channel.send(Ok(chain.finalized_number()));
+			}
+			ChainApiMessage::Ancestors { hash, k, response_channel } => {
+				let _ = response_channel.send(Ok(chain.0.lock().ancestors(&hash, k)));
+			}
+			ChainApiMessage::Descendants { hash, k, response_channel } => {
+				let _ = response_channel.send(Ok(chain.descendants_on_best_chain(&hash, k)));
+			}
+			ChainApiMessage::FinalityNotifications(subscriber) => {
+				finality_subscribers.push(subscriber);
+			}
+		}
+	}
+}
+
+/// A Runtime API subsystem answering every request with the given function.
+///
+/// The function is given the relay parent of every request, and is responsible for answering
+/// it on the channel it carries. Requests it drops the channel of fail.
+pub struct MockRuntimeApi<F>(Arc<F>);
+
+impl<F> Clone for MockRuntimeApi<F> {
+	fn clone(&self) -> Self {
+		MockRuntimeApi(self.0.clone())
+	}
+}
+
+impl<F> MockRuntimeApi<F>
+	where F: Fn(Hash, RuntimeApiRequest) + Send + Sync + 'static
+{
+	/// Create a Runtime API subsystem answering requests with `answer`.
+	pub fn new(answer: F) -> Self {
+		MockRuntimeApi(Arc::new(answer))
+	}
+}
+
+impl<F, Context> Subsystem<Context> for MockRuntimeApi<F>
+	where
+		F: Fn(Hash, RuntimeApiRequest) + Send + Sync + 'static,
+		Context: SubsystemContext<Message = RuntimeApiMessage>,
+{
+	type Metrics = ();
+
+	fn start(self, mut ctx: Context) -> SpawnedSubsystem {
+		let answer = self.0;
+		let future = async move {
+			loop {
+				match ctx.recv().await? {
+					FromOverseer::Signal(OverseerSignal::Conclude) => return Ok(()),
+					FromOverseer::Signal(_) => {}
+					FromOverseer::Communication { msg: RuntimeApiMessage::Request(relay_parent, request) }
+						=> answer(relay_parent, request),
+				}
+			}
+		};
+
+		SpawnedSubsystem {
+			name: "mock-runtime-api-subsystem",
+			future: future.map(|_: SubsystemResult<()>| ()).boxed(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn best_block_and_finality_follow_the_script() {
+		let chain = SimulatedChain::new();
+		let genesis = chain.genesis();
+
+		let a1 = chain.add_block(genesis);
+		let b1 = chain.add_block(genesis);
+		assert_ne!(a1, b1);
+		assert_eq!(chain.best(), a1);
+
+		let b2 = chain.add_block(b1);
+		let b3 = chain.add_block(b2);
+		assert_eq!(chain.best(), b3);
+
+		assert_eq!(chain.0.lock().ancestors(&b3, 10), vec![b2, b1, genesis]);
+		assert_eq!(chain.descendants_on_best_chain(&genesis, 2), vec![b1, b2]);
+		assert!(chain.descendants_on_best_chain(&a1, 2).is_empty());
+
+		chain.finalize(b2);
+		assert_eq!(chain.finalized_number(), 2);
+		assert_eq!(chain.0.lock().finalized, vec![genesis, b1, b2]);
+
+		chain.finalize(b3);
+		assert_eq!(chain.0.lock().finalized, vec![genesis, b1, b2, b3]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn finalizing_a_sibling_of_a_finalized_block_panics() {
+		let chain = SimulatedChain::new();
+		let genesis = chain.genesis();
+
+		let a1 = chain.add_block(genesis);
+		let b1 = chain.add_block(genesis);
+
+		chain.finalize(a1);
+		chain.finalize(b1);
+	}
+}
@@ -0,0 +1,264 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deterministic simulation of several validators, each running real subsystems under a real
+//! [`Overseer`].
+//!
+//! A [`Simulation`] runs every task of every node on a single-threaded [`Scheduler`] driven by a
+//! simulated [`Clock`]. The nodes talk to each other through real network bridges on top of a
+//! [`SimulatedNetwork`], with a configurable latency and packet loss drawn from a seeded random
+//! number generator. The relay chain is a [`SimulatedChain`] the test builds and finalizes block
+//! by block, and which the nodes learn about through their overseers.
+//!
+//! What the nodes run is up to the test: it builds the overseer of every node from the pieces in
+//! [`NodeSetup`], picking real subsystems, mocks such as [`MockRuntimeApi`], or
+//! [`DummySubsystem`](polkadot_subsystem::DummySubsystem)s.
+//!
+//! Runs are reproducible as long as the subsystems only wait on each other, on the network and on
+//! the simulated clock.
+//!
+//! # Limitations
+//!
+//! Subsystems don't take a clock, so only the network and whatever waits on the [`Clock`] run on
+//! simulated time. The timeouts and delays of the subsystems themselves, such as the grace period
+//! of gossip support or the delay before signing bitfields, are wall-clock timers, and work they
+//! hand off to other threads, such as erasure coding when backing, completes in real time too.
+//! Simulated time may jump past either while it is pending, so tests waiting on them have to keep
+//! running the simulation while real time passes, and can't assert on their timing. Routing them
+//! through the simulated clock needs every subsystem to take a timer source, which is out of scope.
+//!
+//! [`Overseer`]: polkadot_overseer::Overseer
+
+#![warn(missing_docs)]
+
+use std::time::Duration;
+
+use futures::prelude::*;
+
+use polkadot_network_bridge::{IncomingRequests, NetworkBridge};
+use polkadot_overseer::{BlockInfo, Overseer, OverseerHandler};
+use polkadot_primitives::v1::Hash;
+use polkadot_subsystem::{messages::AllMessages, SubsystemResult};
+
+mod chain;
+mod network;
+mod scheduler;
+
+#[cfg(test)]
+mod tests;
+
+pub use chain::{MockRuntimeApi, SimulatedChain, SimulatedChainApi};
+pub use network::{NetworkConfig, NetworkStats, Report, SimulatedNetwork, SimulatedNodeNetwork};
+pub use scheduler::{Clock, Scheduler, Sleep, Spawner};
+
+const LOG_TARGET: &str = "simulation";
+
+/// Everything a simulated node's overseer is built from.
+pub struct NodeSetup {
+	index: usize,
+	network: SimulatedNodeNetwork,
+	incoming_requests: Option<IncomingRequests>,
+	chain: SimulatedChain,
+	spawner: Spawner,
+}
+
+impl NodeSetup {
+	/// The index of the node in the simulation.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// The node's view of the simulated network.
+	pub fn network(&self) -> &SimulatedNodeNetwork {
+		&self.network
+	}
+
+	/// A network bridge on top of the simulated network, serving the requests of peers.
+	///
+	/// Panics if called more than once, since only one bridge can serve the node's requests.
+	pub fn network_bridge(&mut self) -> NetworkBridge<SimulatedNodeNetwork, SimulatedNodeNetwork> {
+		let incoming_requests = self.incoming_requests.take()
+			.expect("A simulated node can only have one network bridge");
+
+		NetworkBridge::with_incoming_requests(self.network.clone(), self.network.clone(), incoming_requests)
+	}
+
+	/// A Chain API subsystem serving the simulated chain.
+	pub fn chain_api(&self) -> SimulatedChainApi {
+		SimulatedChainApi::new(self.chain.clone())
+	}
+
+	/// The simulated chain.
+	pub fn chain(&self) -> &SimulatedChain {
+		&self.chain
+	}
+
+	/// The spawner the overseer has to be created with, so that it runs on the simulation's
+	/// scheduler.
+	pub fn spawner(&self) -> Spawner {
+		self.spawner.clone()
+	}
+
+	/// The leaves the overseer starts with: the current best block of the simulated chain.
+	pub fn leaves(&self) -> Vec<BlockInfo> {
+		vec![self.chain.block_info(self.chain.best())]
+	}
+}
+
+/// A set of simulated nodes, along with the network connecting them and the chain they follow.
+pub struct Simulation {
+	scheduler: Scheduler,
+	network: SimulatedNetwork,
+	chain: SimulatedChain,
+	handlers: Vec<OverseerHandler>,
+}
+
+impl Simulation {
+	/// Create a simulation without any nodes yet. The same `seed` leads to the same network delays
+	/// and losses.
+	pub fn new(network_config: NetworkConfig, seed: u64) -> Self {
+		let scheduler = Scheduler::new();
+		let network = SimulatedNetwork::new(
+			network_config,
+			seed,
+			scheduler.clock().clone(),
+			scheduler.spawner(),
+		);
+
+		Simulation {
+			scheduler,
+			network,
+			chain: SimulatedChain::new(),
+			handlers: Vec::new(),
+		}
+	}
+
+	/// Add a node, whose overseer is built by `build` from the given [`NodeSetup`]. The node isn't
+	/// connected to the others yet, see [`SimulatedNetwork::connect`].
+	///
+	/// Returns the index of the node.
+	pub fn add_node<F>(&mut self, build: F) -> SubsystemResult<usize>
+		where F: FnOnce(NodeSetup) -> SubsystemResult<(Overseer<Spawner>, OverseerHandler)>
+	{
+		let (network, incoming_requests) = self.network.add_node();
+		let index = network.index();
+
+		let (overseer, handler) = build(NodeSetup {
+			index,
+			network,
+			incoming_requests: Some(incoming_requests),
+			chain: self.chain.clone(),
+			spawner: self.scheduler.spawner(),
+		})?;
+
+		self.scheduler.spawn("overseer", overseer.run().map(move |res| if let Err(e) = res {
+			log::warn!(target: LOG_TARGET, "Overseer of node {} failed: {:?}", index, e);
+		}));
+		self.handlers.push(handler);
+
+		Ok(index)
+	}
+
+	/// The number of nodes.
+	pub fn node_count(&self) -> usize {
+		self.handlers.len()
+	}
+
+	/// The scheduler running the nodes.
+	pub fn scheduler(&mut self) -> &mut Scheduler {
+		&mut self.scheduler
+	}
+
+	/// The network connecting the nodes.
+	pub fn network(&self) -> &SimulatedNetwork {
+		&self.network
+	}
+
+	/// The chain followed by the nodes.
+	pub fn chain(&self) -> &SimulatedChain {
+		&self.chain
+	}
+
+	/// Add a block on top of `parent` and let all nodes know it was imported, in order.
+	pub fn import_block(&mut self, parent: Hash) -> Hash {
+		let hash = self.chain.add_block(parent);
+		for node in 0..self.node_count() {
+			self.announce_block(node, hash);
+		}
+
+		hash
+	}
+
+	/// Let a single node know a block of the simulated chain was imported, for scripting nodes
+	/// that see blocks at different times. See [`SimulatedChain::add_block`].
+	pub fn announce_block(&mut self, node: usize, hash: Hash) {
+		let block = self.chain.block_info(hash);
+		let mut handler = self.handlers[node].clone();
+
+		self.scheduler.spawn("simulation-block-import", async move {
+			let _ = handler.block_imported(block).await;
+		});
+	}
+
+	/// Finalize a block and let all nodes know, in order.
+	pub fn finalize_block(&mut self, hash: Hash) {
+		self.chain.finalize(hash);
+		for node in 0..self.node_count() {
+			let block = self.chain.block_info(hash);
+			let mut handler = self.handlers[node].clone();
+
+			self.scheduler.spawn("simulation-block-finalized", async move {
+				let _ = handler.block_finalized(block).await;
+			});
+		}
+	}
+
+	/// Send a message to one of the subsystems of a node.
+	pub fn send_message(&mut self, node: usize, msg: AllMessages) {
+		let mut handler = self.handlers[node].clone();
+
+		self.scheduler.spawn("simulation-message", async move {
+			let _ = handler.send_msg(msg).await;
+		});
+	}
+
+	/// Run the nodes for `duration` of simulated time.
+	pub fn run_for(&mut self, duration: Duration) {
+		self.scheduler.run_for(duration);
+	}
+
+	/// Run the nodes until `future` resolves, or `timeout` of simulated time has passed. `None`
+	/// in case of the latter.
+	pub fn run_until<T: Send + 'static>(
+		&mut self,
+		future: impl Future<Output = T> + Send + 'static,
+		timeout: Duration,
+	) -> Option<T> {
+		self.scheduler.run_until(future, timeout)
+	}
+
+	/// Stop the overseers of all nodes, and run until their subsystems concluded.
+	pub fn stop(mut self) {
+		for handler in &self.handlers {
+			let mut handler = handler.clone();
+			self.scheduler.spawn("simulation-stop", async move {
+				let _ = handler.stop().await;
+			});
+		}
+
+		self.scheduler.run_until_stalled();
+	}
+}
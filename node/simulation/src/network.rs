@@ -0,0 +1,518 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A simulated network, connecting the network bridges of the simulated nodes.
+
+use std::collections::{BTreeSet, HashMap};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::channel::{mpsc, oneshot};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, Sink, StreamExt};
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sc_network::{
+	config::{identity, parse_addr},
+	multiaddr::Protocol,
+	Event as NetworkEvent, Multiaddr, ObservedRole, PeerId,
+};
+use sp_runtime::ConsensusEngineId;
+
+use polkadot_network_bridge::{
+	peer_set_engine_id, IncomingRequests, Network, NetworkAction, NetworkRequest, RequestFailure,
+	ValidatorDiscovery, COMPRESSION_PROTOCOL_ID,
+};
+use polkadot_node_network_protocol::{
+	PeerSet, ReputationChange, RequestProtocol, PROTOCOL_VERSIONS,
+};
+use polkadot_primitives::v1::ValidatorId;
+use polkadot_subsystem::SubsystemError;
+
+use crate::scheduler::{Clock, Spawner};
+use polkadot_node_primitives::SpawnNamed;
+
+/// How notifications and requests travel through a [`SimulatedNetwork`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkConfig {
+	/// The time any notification or request takes to reach its destination, at least.
+	pub latency: Duration,
+	/// An additional delay, chosen uniformly at random up to this for every notification and
+	/// request.
+	pub jitter: Duration,
+	/// The probability for a notification or request to be lost, from `0.0` to `1.0`.
+	pub packet_loss: f64,
+}
+
+impl Default for NetworkConfig {
+	fn default() -> Self {
+		NetworkConfig {
+			latency: Duration::from_millis(50),
+			jitter: Duration::from_millis(0),
+			packet_loss: 0.0,
+		}
+	}
+}
+
+/// What became of the traffic on a [`SimulatedNetwork`] so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkStats {
+	/// Notifications and requests which reached their destination.
+	pub delivered: usize,
+	/// Notifications and requests lost to the configured packet loss.
+	pub lost: usize,
+	/// Notifications and requests sent to peers the sender isn't connected to.
+	pub unroutable: usize,
+}
+
+/// A reputation change a node reported for one of its peers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+	/// The index of the reporting node.
+	pub reporter: usize,
+	/// The index of the reported node, `None` if no simulated node has that peer ID.
+	pub reported: Option<usize>,
+	/// The reputation change.
+	pub change: ReputationChange,
+}
+
+struct Endpoint {
+	peer_id: PeerId,
+	events: mpsc::UnboundedSender<NetworkEvent>,
+	requests: mpsc::UnboundedSender<NetworkRequest>,
+}
+
+struct Inner {
+	config: NetworkConfig,
+	rng: ChaCha8Rng,
+	endpoints: Vec<Endpoint>,
+	// Connected pairs of nodes, lower index first.
+	connections: BTreeSet<(usize, usize)>,
+	// When the last notification sent on each directed link arrives, to keep them in order.
+	last_arrival: HashMap<(usize, usize), Duration>,
+	stats: NetworkStats,
+	reports: Vec<Report>,
+	// The nodes validators run on, for the nodes to discover.
+	validators: HashMap<ValidatorId, usize>,
+}
+
+impl Inner {
+	fn index_of(&self, peer: &PeerId) -> Option<usize> {
+		self.endpoints.iter().position(|endpoint| &endpoint.peer_id == peer)
+	}
+
+	fn is_connected(&self, a: usize, b: usize) -> bool {
+		self.connections.contains(&(a.min(b), a.max(b)))
+	}
+
+	/// Decide whether something sent from `from` to `to` now arrives, and after which delay.
+	fn route(&mut self, from: usize, to: &PeerId) -> Option<(usize, Duration)> {
+		let to = match self.index_of(to) {
+			Some(to) if self.is_connected(from, to) => to,
+			_ => {
+				self.stats.unroutable += 1;
+				return None;
+			}
+		};
+
+		// always draw the delay, so that changing the packet loss doesn't change the delays.
+		let lost = self.rng.gen::<f64>() < self.config.packet_loss;
+		let delay = self.delay();
+
+		if lost {
+			self.stats.lost += 1;
+			return None;
+		}
+
+		self.stats.delivered += 1;
+		Some((to, delay))
+	}
+
+	fn delay(&mut self) -> Duration {
+		let jitter = self.rng.gen_range(0, self.config.jitter.as_micros() as u64 + 1);
+		self.config.latency + Duration::from_micros(jitter)
+	}
+
+	fn report(&mut self, reporter: usize, peer: &PeerId, change: ReputationChange) {
+		let reported = self.index_of(peer);
+		self.reports.push(Report { reporter, reported, change });
+	}
+}
+
+/// The engine IDs of all the notification protocols simulated nodes open with each other.
+fn engine_ids() -> Vec<ConsensusEngineId> {
+	[PeerSet::Validation, PeerSet::Collation].iter()
+		.flat_map(|&peer_set| PROTOCOL_VERSIONS.iter().filter_map(move |&v| peer_set_engine_id(peer_set, v)))
+		.chain(std::iter::once(COMPRESSION_PROTOCOL_ID))
+		.collect()
+}
+
+/// A network of simulated nodes, delivering their notifications and requests after a simulated
+/// delay, or losing them.
+///
+/// Delays and losses are drawn from a random number generator seeded at creation, so a network
+/// created with the same seed and used in the same way behaves the same way. Notifications on a
+/// link arrive in the order they were sent, like on a real notification stream.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+	inner: Arc<Mutex<Inner>>,
+	clock: Clock,
+	spawner: Spawner,
+}
+
+impl SimulatedNetwork {
+	/// Create a network without any nodes.
+	pub fn new(config: NetworkConfig, seed: u64, clock: Clock, spawner: Spawner) -> Self {
+		SimulatedNetwork {
+			inner: Arc::new(Mutex::new(Inner {
+				config,
+				rng: ChaCha8Rng::seed_from_u64(seed),
+				endpoints: Vec::new(),
+				connections: BTreeSet::new(),
+				last_arrival: HashMap::new(),
+				stats: NetworkStats::default(),
+				reports: Vec::new(),
+				validators: HashMap::new(),
+			})),
+			clock,
+			spawner,
+		}
+	}
+
+	/// Add a node to the network, not connected to anyone yet.
+	///
+	/// Returns the node's view of the network, along with the requests peers send it, both to be
+	/// handed to a [`NetworkBridge`](polkadot_network_bridge::NetworkBridge).
+	pub fn add_node(&self) -> (SimulatedNodeNetwork, IncomingRequests) {
+		let (events_tx, events_rx) = mpsc::unbounded();
+		let (requests_tx, requests_rx) = mpsc::unbounded();
+
+		let mut inner = self.inner.lock();
+		let index = inner.endpoints.len();
+		let peer_id = peer_id_for(index);
+
+		inner.endpoints.push(Endpoint {
+			peer_id: peer_id.clone(),
+			events: events_tx,
+			requests: requests_tx,
+		});
+
+		let node_network = SimulatedNodeNetwork {
+			index,
+			peer_id,
+			network: self.clone(),
+			events: Arc::new(Mutex::new(Some(events_rx))),
+		};
+
+		(node_network, IncomingRequests::new(requests_rx))
+	}
+
+	/// The number of nodes in the network.
+	pub fn node_count(&self) -> usize {
+		self.inner.lock().endpoints.len()
+	}
+
+	/// The peer ID of the node with the given index.
+	pub fn peer_id(&self, index: usize) -> PeerId {
+		self.inner.lock().endpoints[index].peer_id.clone()
+	}
+
+	/// Let the nodes know that a validator runs on the node with the given index, so that their
+	/// network bridges are able to connect to it on request.
+	pub fn register_validator(&self, validator: ValidatorId, index: usize) {
+		self.inner.lock().validators.insert(validator, index);
+	}
+
+	/// The address of the node with the given index, as nodes discover it.
+	pub fn address(&self, index: usize) -> Multiaddr {
+		Multiaddr::empty()
+			.with(Protocol::Memory(index as u64))
+			.with(Protocol::P2p(self.peer_id(index).into()))
+	}
+
+	/// Whether two nodes are connected.
+	pub fn is_connected(&self, a: usize, b: usize) -> bool {
+		self.inner.lock().is_connected(a, b)
+	}
+
+	/// Change how notifications and requests sent from now on travel.
+	pub fn set_config(&self, config: NetworkConfig) {
+		self.inner.lock().config = config;
+	}
+
+	/// What became of the traffic so far.
+	pub fn stats(&self) -> NetworkStats {
+		self.inner.lock().stats.clone()
+	}
+
+	/// All the reputation changes reported by the nodes so far, in the order they were reported.
+	pub fn reports(&self) -> Vec<Report> {
+		self.inner.lock().reports.clone()
+	}
+
+	/// Connect two nodes, opening all the notification protocols between them. Does nothing if
+	/// they are connected already.
+	pub fn connect(&self, a: usize, b: usize) {
+		assert_ne!(a, b, "a node can't be connected to itself");
+
+		let mut inner = self.inner.lock();
+		if !inner.connections.insert((a.min(b), a.max(b))) {
+			return;
+		}
+
+		for engine_id in engine_ids() {
+			for &(local, remote) in &[(a, b), (b, a)] {
+				let _ = inner.endpoints[local].events.unbounded_send(NetworkEvent::NotificationStreamOpened {
+					remote: inner.endpoints[remote].peer_id.clone(),
+					engine_id,
+					role: ObservedRole::Authority,
+				});
+			}
+		}
+	}
+
+	/// Connect every node to every other one.
+	pub fn connect_all(&self) {
+		let n = self.node_count();
+		for a in 0..n {
+			for b in (a + 1)..n {
+				self.connect(a, b);
+			}
+		}
+	}
+
+	/// Disconnect two nodes, closing all the notification protocols between them. Notifications
+	/// in flight are lost. Does nothing if they aren't connected.
+	pub fn disconnect(&self, a: usize, b: usize) {
+		let mut inner = self.inner.lock();
+		if !inner.connections.remove(&(a.min(b), a.max(b))) {
+			return;
+		}
+
+		for engine_id in engine_ids() {
+			for &(local, remote) in &[(a, b), (b, a)] {
+				let _ = inner.endpoints[local].events.unbounded_send(NetworkEvent::NotificationStreamClosed {
+					remote: inner.endpoints[remote].peer_id.clone(),
+					engine_id,
+				});
+			}
+		}
+	}
+
+	fn send_notification(&self, from: usize, to: &PeerId, engine_id: ConsensusEngineId, message: Vec<u8>) {
+		let mut inner = self.inner.lock();
+		let (to, delay) = match inner.route(from, to) {
+			Some(route) => route,
+			None => return,
+		};
+
+		let now = self.clock.now();
+		let last_arrival = inner.last_arrival.entry((from, to)).or_insert(now);
+		let arrival = std::cmp::max(now + delay, *last_arrival);
+		*last_arrival = arrival;
+
+		let remote = inner.endpoints[from].peer_id.clone();
+		let events = inner.endpoints[to].events.clone();
+		let network = self.clone();
+		let sleep = self.clock.sleep(arrival - now);
+
+		self.spawner.spawn("simulated-notification", async move {
+			sleep.await;
+
+			// the connection may have been closed in the meantime.
+			if network.inner.lock().is_connected(from, to) {
+				let _ = events.unbounded_send(NetworkEvent::NotificationsReceived {
+					remote,
+					messages: vec![(engine_id, message.into())],
+				});
+			}
+		}.boxed());
+	}
+
+	fn start_request(
+		&self,
+		from: usize,
+		to: &PeerId,
+		protocol: RequestProtocol,
+		payload: Vec<u8>,
+	) -> BoxFuture<'static, Result<Vec<u8>, RequestFailure>> {
+		let (route, failure_delay, peer) = {
+			let mut inner = self.inner.lock();
+			let route = inner.route(from, to).map(|(to, request_delay)| {
+				let response_delay = inner.delay();
+				(inner.endpoints[to].requests.clone(), request_delay, response_delay)
+			});
+			let failure_delay = inner.delay();
+
+			(route, failure_delay, inner.endpoints[from].peer_id.clone())
+		};
+
+		let clock = self.clock.clone();
+
+		async move {
+			let (target, request_delay, response_delay) = match route {
+				Some(route) => route,
+				None => {
+					// lost requests fail after a simulated delay, instead of waiting for the
+					// bridge's timeout which runs on the wall-clock.
+					clock.sleep(failure_delay).await;
+					return Err(RequestFailure);
+				}
+			};
+
+			clock.sleep(request_delay).await;
+
+			let (pending_response, response) = oneshot::channel();
+			target.unbounded_send(NetworkRequest { peer, protocol, payload, pending_response })
+				.map_err(|_| RequestFailure)?;
+
+			let response = response.await.map_err(|_| RequestFailure)?;
+			clock.sleep(response_delay).await;
+
+			Ok(response)
+		}.boxed()
+	}
+}
+
+/// A deterministic peer ID for the node with the given index.
+fn peer_id_for(index: usize) -> PeerId {
+	let mut seed = [0u8; 32];
+	seed[..8].copy_from_slice(&(index as u64).to_le_bytes());
+	seed[31] = 1;
+
+	let secret = identity::ed25519::SecretKey::from_bytes(&mut seed)
+		.expect("any 32 bytes are a valid ed25519 secret key; qed");
+
+	identity::Keypair::Ed25519(secret.into()).public().into_peer_id()
+}
+
+/// A simulated node's view of a [`SimulatedNetwork`].
+#[derive(Clone)]
+pub struct SimulatedNodeNetwork {
+	index: usize,
+	peer_id: PeerId,
+	network: SimulatedNetwork,
+	events: Arc<Mutex<Option<mpsc::UnboundedReceiver<NetworkEvent>>>>,
+}
+
+impl SimulatedNodeNetwork {
+	/// The index of the node in the network.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// The peer ID of the node.
+	pub fn peer_id(&self) -> &PeerId {
+		&self.peer_id
+	}
+
+	// The indices of the other nodes at the given addresses.
+	fn peers_at(&self, addresses: Vec<Multiaddr>) -> Vec<usize> {
+		let inner = self.network.inner.lock();
+		addresses.into_iter()
+			.filter_map(|address| parse_addr(address).ok())
+			.filter_map(|(peer, _)| inner.index_of(&peer))
+			.filter(|&peer| peer != self.index)
+			.collect()
+	}
+}
+
+impl Network for SimulatedNodeNetwork {
+	fn event_stream(&mut self) -> BoxStream<'static, NetworkEvent> {
+		self.events.lock()
+			.take()
+			.expect("The network bridge of a simulated node may only be started once")
+			.boxed()
+	}
+
+	fn action_sink<'a>(&'a mut self)
+		-> Pin<Box<dyn Sink<NetworkAction, Error = SubsystemError> + Send + 'a>>
+	{
+		Box::pin(ActionSink(self))
+	}
+
+	fn start_request(&mut self, who: PeerId, protocol: RequestProtocol, payload: Vec<u8>)
+		-> BoxFuture<'static, Result<Vec<u8>, RequestFailure>>
+	{
+		self.network.start_request(self.index, &who, protocol, payload)
+	}
+}
+
+impl ValidatorDiscovery for SimulatedNodeNetwork {
+	fn addresses_of(&mut self, validator: &ValidatorId) -> BoxFuture<'static, Vec<Multiaddr>> {
+		let index = self.network.inner.lock().validators.get(validator).copied();
+		let addresses = index.map(|index| self.network.address(index)).into_iter().collect();
+
+		futures::future::ready(addresses).boxed()
+	}
+}
+
+// Carries out the actions of a node's network bridge right away.
+struct ActionSink<'a>(&'a SimulatedNodeNetwork);
+
+impl<'a> Sink<NetworkAction> for ActionSink<'a> {
+	type Error = SubsystemError;
+
+	fn poll_ready(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), SubsystemError>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn start_send(self: Pin<&mut Self>, action: NetworkAction) -> Result<(), SubsystemError> {
+		let node = self.0;
+		match action {
+			NetworkAction::ReputationChange(peer, change) => {
+				node.network.inner.lock().report(node.index, &peer, change);
+			}
+			NetworkAction::WriteNotification(peer, peer_set, version, message) => {
+				match peer_set_engine_id(peer_set, version) {
+					Some(engine_id) => node.network.send_notification(node.index, &peer, engine_id, message),
+					None => log::warn!(
+						target: crate::LOG_TARGET,
+						"Dropping notification for unknown version {} of the {:?} protocol",
+						version,
+						peer_set,
+					),
+				}
+			}
+			// The simulated nodes talk on all peer-sets once connected, and there is a single
+			// connection between any two nodes.
+			NetworkAction::ConnectToPeers(_, addresses) => {
+				for peer in node.peers_at(addresses) {
+					node.network.connect(node.index, peer);
+				}
+			}
+			NetworkAction::DisconnectFromPeers(_, addresses) => {
+				for peer in node.peers_at(addresses) {
+					node.network.disconnect(node.index, peer);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), SubsystemError>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), SubsystemError>> {
+		Poll::Ready(Ok(()))
+	}
+}
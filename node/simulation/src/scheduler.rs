@@ -0,0 +1,404 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A single-threaded executor polling its tasks in a reproducible order, along with the
+//! simulated clock driving it.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::task::{self as future_task, ArcWake};
+use futures::{Future, FutureExt};
+use parking_lot::Mutex;
+use polkadot_node_primitives::SpawnNamed;
+
+/// Polls after which a single call to `run_until_stalled` is considered a livelock.
+const MAX_POLLS_PER_RUN: usize = 10_000_000;
+
+/// The simulated time, shared by everything running in a simulation.
+///
+/// Time only advances when the [`Scheduler`] is told to, and then jumps straight to the next
+/// deadline, so simulated seconds cost no wall-clock time.
+#[derive(Clone, Default)]
+pub struct Clock(Arc<Mutex<ClockState>>);
+
+#[derive(Default)]
+struct ClockState {
+	now: Duration,
+	next_timer: u64,
+	// Pending timers ordered by deadline, then by creation. The waker is missing until the timer
+	// is first polled.
+	timers: BTreeMap<(Duration, u64), Option<Waker>>,
+}
+
+impl Clock {
+	/// The time elapsed since the start of the simulation.
+	pub fn now(&self) -> Duration {
+		self.0.lock().now
+	}
+
+	/// A future resolving once the simulated time has advanced by `duration`.
+	pub fn sleep(&self, duration: Duration) -> Sleep {
+		let mut state = self.0.lock();
+		let key = (state.now + duration, state.next_timer);
+		state.next_timer += 1;
+		state.timers.insert(key, None);
+
+		Sleep { clock: self.clone(), key }
+	}
+
+	/// The deadline of the earliest pending timer, if any.
+	pub fn next_deadline(&self) -> Option<Duration> {
+		self.0.lock().timers.keys().next().map(|&(deadline, _)| deadline)
+	}
+
+	/// Advance the time to `to`, waking the timers due by then in the order of their deadlines.
+	/// The time never goes backwards.
+	pub fn advance_to(&self, to: Duration) {
+		let wakers = {
+			let mut state = self.0.lock();
+			if to > state.now {
+				state.now = to;
+			}
+
+			let pending = state.timers.split_off(&(state.now, u64::max_value()));
+			std::mem::replace(&mut state.timers, pending)
+		};
+
+		// wake outside of the lock, so woken timers can be polled right away.
+		for waker in wakers.into_iter().filter_map(|(_, waker)| waker) {
+			waker.wake();
+		}
+	}
+}
+
+/// A future returned by [`Clock::sleep`].
+pub struct Sleep {
+	clock: Clock,
+	key: (Duration, u64),
+}
+
+impl Future for Sleep {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+		let mut state = self.clock.0.lock();
+		if state.now >= self.key.0 {
+			state.timers.remove(&self.key);
+			return Poll::Ready(());
+		}
+
+		state.timers.insert(self.key, Some(cx.waker().clone()));
+		Poll::Pending
+	}
+}
+
+impl Drop for Sleep {
+	fn drop(&mut self) {
+		self.clock.0.lock().timers.remove(&self.key);
+	}
+}
+
+type SpawnedTasks = Arc<Mutex<VecDeque<(&'static str, BoxFuture<'static, ()>)>>>;
+
+/// Spawns tasks onto a [`Scheduler`]. This is the spawner to hand to overseers and subsystems.
+///
+/// Blocking tasks are run like any other task, subsystems are expected not to block in a
+/// simulation.
+#[derive(Clone)]
+pub struct Spawner(SpawnedTasks);
+
+impl SpawnNamed for Spawner {
+	fn spawn_blocking(&self, name: &'static str, future: BoxFuture<'static, ()>) {
+		self.0.lock().push_back((name, future));
+	}
+
+	fn spawn(&self, name: &'static str, future: BoxFuture<'static, ()>) {
+		self.0.lock().push_back((name, future));
+	}
+}
+
+struct TaskWaker {
+	index: usize,
+	queued: AtomicBool,
+	ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl ArcWake for TaskWaker {
+	fn wake_by_ref(arc_self: &Arc<Self>) {
+		if !arc_self.queued.swap(true, Ordering::SeqCst) {
+			arc_self.ready.lock().push_back(arc_self.index);
+		}
+	}
+}
+
+struct Task {
+	name: &'static str,
+	future: BoxFuture<'static, ()>,
+	waker: Arc<TaskWaker>,
+}
+
+/// Runs all the tasks of a simulation on the current thread.
+///
+/// Tasks are polled in the order they were woken, and timers fire in the order of their
+/// deadlines, so the same inputs always lead to the same interleaving. This only holds for
+/// wakeups coming from inside the simulation: tasks waiting on wall-clock timers or on other
+/// threads are woken whenever those fire, and are merely picked up by the next run.
+pub struct Scheduler {
+	clock: Clock,
+	tasks: Vec<Option<Task>>,
+	ready: Arc<Mutex<VecDeque<usize>>>,
+	spawned: SpawnedTasks,
+}
+
+impl Default for Scheduler {
+	fn default() -> Self {
+		Scheduler {
+			clock: Clock::default(),
+			tasks: Vec::new(),
+			ready: Arc::new(Mutex::new(VecDeque::new())),
+			spawned: Arc::new(Mutex::new(VecDeque::new())),
+		}
+	}
+}
+
+impl Scheduler {
+	/// Create a scheduler without any tasks, at time zero.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The clock of the simulation.
+	pub fn clock(&self) -> &Clock {
+		&self.clock
+	}
+
+	/// A spawner for tasks to be run by this scheduler.
+	pub fn spawner(&self) -> Spawner {
+		Spawner(self.spawned.clone())
+	}
+
+	/// Spawn a task. It is first polled by the next run.
+	pub fn spawn(&self, name: &'static str, future: impl Future<Output = ()> + Send + 'static) {
+		self.spawned.lock().push_back((name, future.boxed()));
+	}
+
+	/// The names of the tasks which haven't finished yet, in the order they were spawned.
+	pub fn running_tasks(&self) -> Vec<&'static str> {
+		self.tasks.iter().filter_map(|task| task.as_ref().map(|task| task.name)).collect()
+	}
+
+	/// Poll the tasks until none of them can make progress without the time advancing.
+	///
+	/// Panics if the tasks keep waking each other up forever.
+	pub fn run_until_stalled(&mut self) {
+		for _ in 0..MAX_POLLS_PER_RUN {
+			self.adopt_spawned();
+
+			let index = match self.ready.lock().pop_front() {
+				Some(index) => index,
+				None => return,
+			};
+
+			self.poll_task(index);
+		}
+
+		panic!(
+			"Tasks still running after {} polls without advancing time, they are likely livelocked: {:?}",
+			MAX_POLLS_PER_RUN,
+			self.running_tasks(),
+		);
+	}
+
+	/// Run the tasks for `duration` of simulated time.
+	pub fn run_for(&mut self, duration: Duration) {
+		let end = self.clock.now() + duration;
+		self.run_until_time(end, || false);
+	}
+
+	/// Run the tasks until `future` resolves or `timeout` of simulated time has passed, whichever
+	/// comes first. `None` in case of the latter.
+	pub fn run_until<T: Send + 'static>(
+		&mut self,
+		future: impl Future<Output = T> + Send + 'static,
+		timeout: Duration,
+	) -> Option<T> {
+		let output = Arc::new(Mutex::new(None));
+		let task_output = output.clone();
+		self.spawn("simulation-run-until", async move {
+			let value = future.await;
+			*task_output.lock() = Some(value);
+		});
+
+		let end = self.clock.now() + timeout;
+		self.run_until_time(end, || output.lock().is_some());
+
+		let value = output.lock().take();
+		value
+	}
+
+	fn run_until_time(&mut self, end: Duration, mut done: impl FnMut() -> bool) {
+		loop {
+			self.run_until_stalled();
+			if done() {
+				return;
+			}
+
+			match self.clock.next_deadline() {
+				Some(deadline) if deadline <= end => self.clock.advance_to(deadline),
+				_ => {
+					self.clock.advance_to(end);
+					self.run_until_stalled();
+					return;
+				}
+			}
+		}
+	}
+
+	fn adopt_spawned(&mut self) {
+		loop {
+			let (name, future) = match self.spawned.lock().pop_front() {
+				Some(spawned) => spawned,
+				None => return,
+			};
+
+			let index = self.tasks.len();
+			let waker = Arc::new(TaskWaker {
+				index,
+				queued: AtomicBool::new(true),
+				ready: self.ready.clone(),
+			});
+
+			self.tasks.push(Some(Task { name, future, waker }));
+			self.ready.lock().push_back(index);
+		}
+	}
+
+	fn poll_task(&mut self, index: usize) {
+		let finished = match self.tasks.get_mut(index).and_then(Option::as_mut) {
+			// woken after it finished.
+			None => return,
+			Some(task) => {
+				task.waker.queued.store(false, Ordering::SeqCst);
+
+				let waker = future_task::waker(task.waker.clone());
+				let mut cx = Context::from_waker(&waker);
+				task.future.as_mut().poll(&mut cx).is_ready()
+			}
+		};
+
+		if finished {
+			self.tasks[index] = None;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::channel::mpsc;
+	use futures::StreamExt;
+
+	#[test]
+	fn timers_fire_in_order_of_deadline() {
+		let mut scheduler = Scheduler::new();
+		let (tx, mut rx) = mpsc::unbounded();
+
+		for &(name, delay) in &[("c", 30), ("a", 10), ("b", 20), ("a2", 10)] {
+			let clock = scheduler.clock().clone();
+			let tx = tx.clone();
+			scheduler.spawn("timer", async move {
+				clock.sleep(Duration::from_millis(delay)).await;
+				tx.unbounded_send((name, clock.now())).unwrap();
+			});
+		}
+
+		scheduler.run_for(Duration::from_millis(25));
+		assert_eq!(scheduler.clock().now(), Duration::from_millis(25));
+
+		let mut fired = Vec::new();
+		while let Ok(Some(event)) = rx.try_next() {
+			fired.push(event);
+		}
+
+		assert_eq!(fired, vec![
+			("a", Duration::from_millis(10)),
+			("a2", Duration::from_millis(10)),
+			("b", Duration::from_millis(20)),
+		]);
+
+		let last = scheduler.run_until(async move { rx.next().await }, Duration::from_secs(1));
+		assert_eq!(last, Some(Some(("c", Duration::from_millis(30)))));
+		assert_eq!(scheduler.clock().now(), Duration::from_millis(30));
+	}
+
+	#[test]
+	fn run_until_gives_up_at_timeout() {
+		let mut scheduler = Scheduler::new();
+		let clock = scheduler.clock().clone();
+
+		let res = scheduler.run_until(
+			async move { clock.sleep(Duration::from_secs(10)).await },
+			Duration::from_secs(3),
+		);
+
+		assert!(res.is_none());
+		assert_eq!(scheduler.clock().now(), Duration::from_secs(3));
+	}
+
+	#[test]
+	fn interleaving_is_reproducible() {
+		fn run() -> Vec<usize> {
+			let mut scheduler = Scheduler::new();
+			let spawner = scheduler.spawner();
+			let log = Arc::new(Mutex::new(Vec::new()));
+
+			for i in 0..5 {
+				let log = log.clone();
+				let spawner = spawner.clone();
+				scheduler.spawn("parent", async move {
+					for _ in 0..3 {
+						log.lock().push(i);
+						futures::pending!();
+					}
+
+					let log = log.clone();
+					spawner.spawn("child", async move { log.lock().push(i + 10) }.boxed());
+				});
+			}
+
+			// `pending!` doesn't wake the task, so nudge them all until they are done.
+			while !scheduler.running_tasks().is_empty() {
+				for task in scheduler.tasks.iter().flatten() {
+					ArcWake::wake_by_ref(&task.waker);
+				}
+				scheduler.run_until_stalled();
+			}
+
+			let log = log.lock().clone();
+			log
+		}
+
+		let first = run();
+		assert_eq!(first.len(), 20);
+		assert_eq!(first, run());
+	}
+}
@@ -0,0 +1,774 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use std::collections::BTreeMap;
+
+use bitvec::bitvec;
+use futures::channel::{mpsc, oneshot};
+use polkadot_availability_bitfield_distribution::BitfieldDistribution;
+use polkadot_availability_distribution::AvailabilityDistributionSubsystem;
+use polkadot_node_core_backing::CandidateBackingSubsystem;
+use polkadot_node_primitives::{ValidationOutputs, ValidationResult};
+use polkadot_erasure_coding::{branches, obtain_chunks_v1 as obtain_chunks};
+use polkadot_gossip_support::GossipSupport;
+use polkadot_overseer::AllSubsystems;
+use polkadot_primitives::v1::{
+	AvailabilityBitfield, AvailableData, BlockData, CandidateCommitments, CandidateDescriptor,
+	CommittedCandidateReceipt, CoreState, ErasureChunk, GroupIndex, GroupRotationInfo, Id as ParaId,
+	OccupiedCore, PoV, ScheduledCore, Signed, SigningContext, ValidatorId, ValidatorIndex,
+	ValidatorPair,
+};
+use polkadot_subsystem::{
+	DummySubsystem, FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem, SubsystemContext,
+	messages::{
+		AvailabilityStoreMessage, BitfieldDistributionMessage, CandidateBackingMessage,
+		CandidateValidationMessage, ProvisionableData, ProvisionerMessage, RuntimeApiRequest,
+	},
+};
+use sp_keyring::Sr25519Keyring;
+
+const KEYS: [Sr25519Keyring; 3] = [Sr25519Keyring::Alice, Sr25519Keyring::Bob, Sr25519Keyring::Charlie];
+const SESSION_INDEX: u32 = 1;
+
+/// A bitfield reaching the provisioner of a node, and when it did.
+type Provisioned = (usize, Hash, Duration);
+
+/// A provisioner reporting the bitfields it is given.
+struct ProbeProvisioner {
+	node: usize,
+	clock: Clock,
+	tx: mpsc::UnboundedSender<Provisioned>,
+}
+
+impl<C> Subsystem<C> for ProbeProvisioner
+	where C: SubsystemContext<Message = ProvisionerMessage>
+{
+	type Metrics = ();
+
+	fn start(self, mut ctx: C) -> SpawnedSubsystem {
+		let future = async move {
+			loop {
+				match ctx.recv().await {
+					Ok(FromOverseer::Communication {
+						msg: ProvisionerMessage::ProvisionableData(ProvisionableData::Bitfield(relay_parent, _)),
+					}) => {
+						let _ = self.tx.unbounded_send((self.node, relay_parent, self.clock.now()));
+					}
+					Ok(FromOverseer::Signal(OverseerSignal::Conclude)) | Err(_) => return,
+					Ok(_) => {}
+				}
+			}
+		};
+
+		SpawnedSubsystem {
+			name: "probe-provisioner-subsystem",
+			future: future.boxed(),
+		}
+	}
+}
+
+fn validators() -> Vec<ValidatorId> {
+	KEYS.iter().map(|key| key.public().into()).collect()
+}
+
+fn add_validator(sim: &mut Simulation, tx: mpsc::UnboundedSender<Provisioned>) -> usize {
+	let clock = sim.scheduler().clock().clone();
+
+	sim.add_node(move |mut setup| {
+		let runtime_api = MockRuntimeApi::new(|_, request| match request {
+			RuntimeApiRequest::Validators(tx) => {
+				let _ = tx.send(Ok(validators()));
+			}
+			RuntimeApiRequest::SessionIndexForChild(tx) => {
+				let _ = tx.send(Ok(SESSION_INDEX));
+			}
+			_ => {}
+		});
+
+		let all_subsystems = AllSubsystems {
+			candidate_validation: DummySubsystem,
+			candidate_backing: DummySubsystem,
+			candidate_selection: DummySubsystem,
+			statement_distribution: DummySubsystem,
+			availability_distribution: DummySubsystem,
+			bitfield_signing: DummySubsystem,
+			bitfield_distribution: BitfieldDistribution,
+			provisioner: ProbeProvisioner { node: setup.index(), clock, tx },
+			pov_distribution: DummySubsystem,
+			runtime_api,
+			availability_store: DummySubsystem,
+			network_bridge: setup.network_bridge(),
+			chain_api: setup.chain_api(),
+			collation_generation: DummySubsystem,
+			collator_protocol: DummySubsystem,
+			gossip_support: DummySubsystem,
+		};
+
+		Overseer::new(
+			setup.leaves(),
+			all_subsystems,
+			Default::default(),
+			Default::default(),
+			None,
+			setup.spawner(),
+		)
+	}).expect("overseer of a simulated node can be built")
+}
+
+/// Have the validator at `node` distribute its bitfield for `relay_parent`.
+fn distribute_bitfield(sim: &mut Simulation, node: usize, relay_parent: Hash) {
+	let signing_context = SigningContext { parent_hash: relay_parent, session_index: SESSION_INDEX };
+	let payload = AvailabilityBitfield(bitvec![bitvec::order::Lsb0, u8; 1u8; 32]);
+	let signed = Signed::<AvailabilityBitfield>::sign(
+		payload,
+		&signing_context,
+		node as _,
+		&KEYS[node].pair().into(),
+	);
+
+	sim.send_message(
+		node,
+		AllMessages::BitfieldDistribution(BitfieldDistributionMessage::DistributeBitfield(relay_parent, signed)),
+	);
+}
+
+/// Three connected validators which all know of a block on top of genesis.
+fn setup(config: NetworkConfig, seed: u64) -> (Simulation, mpsc::UnboundedReceiver<Provisioned>, Hash) {
+	let mut sim = Simulation::new(config, seed);
+	let (tx, rx) = mpsc::unbounded();
+
+	for _ in 0..KEYS.len() {
+		add_validator(&mut sim, tx.clone());
+	}
+
+	// peers only learn of each other's views when those change, so connect before importing.
+	sim.network().connect_all();
+	sim.run_for(Duration::from_secs(1));
+
+	let genesis = sim.chain().genesis();
+	let block = sim.import_block(genesis);
+	sim.run_for(Duration::from_secs(1));
+
+	(sim, rx, block)
+}
+
+/// When the bitfield first reached the provisioner of every node, or nothing if it didn't reach
+/// them all within a while.
+fn collect_all(sim: &mut Simulation, mut rx: mpsc::UnboundedReceiver<Provisioned>) -> BTreeMap<usize, Duration> {
+	let nodes = sim.node_count();
+	let first_arrivals = async move {
+		let mut arrivals = BTreeMap::new();
+		while arrivals.len() < nodes {
+			let (node, _, at) = rx.next().await?;
+			arrivals.entry(node).or_insert(at);
+		}
+
+		Some(arrivals)
+	};
+
+	sim.run_until(first_arrivals, Duration::from_secs(10)).flatten().unwrap_or_default()
+}
+
+#[test]
+fn bitfield_reaches_all_provisioners_through_the_network() {
+	let config = NetworkConfig::default();
+	let latency = config.latency;
+	let (mut sim, rx, block) = setup(config, 42);
+
+	let sent_at = sim.scheduler().clock().now();
+	distribute_bitfield(&mut sim, 0, block);
+
+	let arrivals = collect_all(&mut sim, rx);
+	assert_eq!(arrivals.len(), 3, "bitfield reached only {:?}", arrivals);
+
+	// the node's own provisioner hears of it right away, the others only across the network.
+	assert_eq!(arrivals[&0], sent_at);
+	assert!(arrivals[&1] >= sent_at + latency);
+	assert!(arrivals[&2] >= sent_at + latency);
+
+	assert!(sim.network().stats().delivered > 0);
+	assert!(sim.network().reports().iter().all(|report| report.change.value >= 0));
+
+	sim.stop();
+}
+
+#[test]
+fn same_seed_leads_to_same_arrival_times() {
+	let config = NetworkConfig {
+		jitter: Duration::from_millis(100),
+		..Default::default()
+	};
+
+	let run = |seed| {
+		let (mut sim, rx, block) = setup(config.clone(), seed);
+		distribute_bitfield(&mut sim, 1, block);
+		let arrivals = collect_all(&mut sim, rx);
+		sim.stop();
+		arrivals
+	};
+
+	let first = run(7);
+	assert_eq!(first.len(), 3);
+	assert_eq!(first, run(7));
+}
+
+#[test]
+fn nothing_arrives_when_every_packet_is_lost() {
+	let (mut sim, rx, block) = setup(NetworkConfig::default(), 42);
+	sim.network().set_config(NetworkConfig { packet_loss: 1.0, ..Default::default() });
+	let lost_before = sim.network().stats().lost;
+
+	distribute_bitfield(&mut sim, 2, block);
+	let arrivals = collect_all(&mut sim, rx);
+
+	assert!(arrivals.is_empty());
+	assert!(sim.network().stats().lost > lost_before);
+
+	sim.stop();
+}
+
+/// A node running the gossip support subsystem as the validator with the given key, in a chain
+/// whose session index is the number of the block and whose validators leave one by one.
+fn add_gossiping_validator(sim: &mut Simulation, key: Sr25519Keyring) -> usize {
+	sim.add_node(move |mut setup| {
+		let chain = setup.chain().clone();
+		let runtime_api = MockRuntimeApi::new(move |relay_parent, request| {
+			let number = chain.header(&relay_parent).map_or(0, |header| header.number);
+			let session_validators: Vec<_> = validators().into_iter()
+				.take(KEYS.len().saturating_sub(number as usize))
+				.collect();
+
+			match request {
+				RuntimeApiRequest::SessionIndexForChild(tx) => {
+					let _ = tx.send(Ok(number));
+				}
+				RuntimeApiRequest::Validators(tx) => {
+					let _ = tx.send(Ok(session_validators));
+				}
+				RuntimeApiRequest::ValidatorGroups(tx) => {
+					let group = (0..session_validators.len() as u32).collect();
+					let rotation_info = GroupRotationInfo {
+						session_start_block: number,
+						group_rotation_frequency: 10,
+						now: number,
+					};
+					let _ = tx.send(Ok((vec![group], rotation_info)));
+				}
+				_ => {}
+			}
+		});
+
+		let keystore = sc_keystore::Store::new_in_memory();
+		keystore.write()
+			.insert_ephemeral_from_seed::<ValidatorPair>(&key.to_seed())
+			.expect("in-memory keystore accepts keys; qed");
+
+		let all_subsystems = AllSubsystems {
+			candidate_validation: DummySubsystem,
+			candidate_backing: DummySubsystem,
+			candidate_selection: DummySubsystem,
+			statement_distribution: DummySubsystem,
+			availability_distribution: DummySubsystem,
+			bitfield_signing: DummySubsystem,
+			bitfield_distribution: DummySubsystem,
+			provisioner: DummySubsystem,
+			pov_distribution: DummySubsystem,
+			runtime_api,
+			availability_store: DummySubsystem,
+			network_bridge: setup.network_bridge(),
+			chain_api: setup.chain_api(),
+			collation_generation: DummySubsystem,
+			collator_protocol: DummySubsystem,
+			gossip_support: GossipSupport::new(keystore).with_grace_period(Duration::from_millis(10)),
+		};
+
+		Overseer::new(
+			setup.leaves(),
+			all_subsystems,
+			Default::default(),
+			Default::default(),
+			None,
+			setup.spawner(),
+		)
+	}).expect("overseer of a simulated node can be built")
+}
+
+#[test]
+fn gossip_support_connects_validators_of_the_session() {
+	let mut sim = Simulation::new(NetworkConfig::default(), 42);
+
+	for (key, validator) in KEYS.iter().zip(validators()) {
+		let node = add_gossiping_validator(&mut sim, *key);
+		sim.network().register_validator(validator, node);
+	}
+
+	// the nodes aren't connected to begin with, but the validators of the session at genesis
+	// ask for connections to each other.
+	sim.run_for(Duration::from_secs(1));
+	assert!(sim.network().is_connected(0, 1));
+	assert!(sim.network().is_connected(0, 2));
+	assert!(sim.network().is_connected(1, 2));
+
+	// Charlie isn't a validator in the session of the next block, so the others drop him once
+	// the grace period is over. It runs on the wall-clock.
+	let genesis = sim.chain().genesis();
+	sim.import_block(genesis);
+	sim.run_for(Duration::from_secs(1));
+	std::thread::sleep(Duration::from_millis(200));
+	sim.run_for(Duration::from_secs(1));
+
+	assert!(sim.network().is_connected(0, 1));
+	assert!(!sim.network().is_connected(0, 2));
+	assert!(!sim.network().is_connected(1, 2));
+
+	sim.stop();
+}
+
+/// A chunk reaching the availability store of a node.
+type Stored = (usize, ValidatorIndex);
+
+/// An availability store holding all chunks of a candidate in case of its backers, and nothing
+/// otherwise. Reports the chunks it is asked to store, and holds all chunks once it is given the
+/// available data of a candidate.
+struct ProbeAvailabilityStore {
+	node: usize,
+	chunks: Option<Vec<ErasureChunk>>,
+	tx: mpsc::UnboundedSender<Stored>,
+}
+
+impl<C> Subsystem<C> for ProbeAvailabilityStore
+	where C: SubsystemContext<Message = AvailabilityStoreMessage>
+{
+	type Metrics = ();
+
+	fn start(mut self, mut ctx: C) -> SpawnedSubsystem {
+		let future = async move {
+			loop {
+				let msg = match ctx.recv().await {
+					Ok(FromOverseer::Communication { msg }) => msg,
+					Ok(FromOverseer::Signal(OverseerSignal::Conclude)) | Err(_) => return,
+					Ok(FromOverseer::Signal(_)) => continue,
+				};
+
+				match msg {
+					AvailabilityStoreMessage::StoreAvailableData(_, _, _, available_data, tx) => {
+						self.chunks = Some(erasure_chunks(&available_data));
+						let _ = tx.send(Ok(()));
+					}
+					AvailabilityStoreMessage::QueryDataAvailability(_, tx) => {
+						let _ = tx.send(self.chunks.is_some());
+					}
+					AvailabilityStoreMessage::QueryChunk(_, index, tx) => {
+						let chunk = self.chunks.as_ref().and_then(|chunks| chunks.get(index as usize));
+						let _ = tx.send(chunk.cloned());
+					}
+					AvailabilityStoreMessage::StoreChunk(_, index, _, tx) => {
+						let _ = self.tx.unbounded_send((self.node, index));
+						let _ = tx.send(Ok(()));
+					}
+					_ => {}
+				}
+			}
+		};
+
+		SpawnedSubsystem {
+			name: "probe-availability-store-subsystem",
+			future: future.boxed(),
+		}
+	}
+}
+
+/// The erasure chunks of some available data, with their proofs.
+fn erasure_chunks(available_data: &AvailableData) -> Vec<ErasureChunk> {
+	let chunks = obtain_chunks(KEYS.len(), available_data).expect("there are enough validators; qed");
+
+	branches(&chunks)
+		.enumerate()
+		.map(|(index, (proof, chunk))| ErasureChunk {
+			chunk: chunk.to_vec(),
+			index: index as _,
+			proof,
+		})
+		.collect()
+}
+
+/// A node running availability distribution as the validator with the given key, in a chain
+/// where the candidate is pending availability on the only core at every block. Gossip support
+/// connects it to the other validators.
+fn add_distributing_validator(
+	sim: &mut Simulation,
+	key: Sr25519Keyring,
+	candidate: CommittedCandidateReceipt,
+	chunks: Option<Vec<ErasureChunk>>,
+	tx: mpsc::UnboundedSender<Stored>,
+) -> usize {
+	sim.add_node(move |mut setup| {
+		let para_id = candidate.descriptor.para_id;
+		let runtime_api = MockRuntimeApi::new(move |_, request| match request {
+			RuntimeApiRequest::Validators(tx) => {
+				let _ = tx.send(Ok(validators()));
+			}
+			RuntimeApiRequest::SessionIndexForChild(tx) => {
+				let _ = tx.send(Ok(SESSION_INDEX));
+			}
+			RuntimeApiRequest::ValidatorGroups(tx) => {
+				let group = (0..KEYS.len() as u32).collect();
+				let rotation_info = GroupRotationInfo {
+					session_start_block: 0,
+					group_rotation_frequency: 10,
+					now: 0,
+				};
+				let _ = tx.send(Ok((vec![group], rotation_info)));
+			}
+			RuntimeApiRequest::AvailabilityCores(tx) => {
+				let _ = tx.send(Ok(vec![CoreState::Occupied(OccupiedCore {
+					para_id,
+					next_up_on_available: None,
+					occupied_since: 0,
+					time_out_at: 5,
+					next_up_on_time_out: None,
+					availability: Default::default(),
+					group_responsible: GroupIndex::from(0),
+				})]));
+			}
+			RuntimeApiRequest::CandidatePendingAvailability(para, tx) => {
+				let _ = tx.send(Ok(Some(candidate.clone()).filter(|_| para == para_id)));
+			}
+			_ => {}
+		});
+
+		let keystore = sc_keystore::Store::new_in_memory();
+		keystore.write()
+			.insert_ephemeral_from_seed::<ValidatorPair>(&key.to_seed())
+			.expect("in-memory keystore accepts keys; qed");
+
+		let all_subsystems = AllSubsystems {
+			candidate_validation: DummySubsystem,
+			candidate_backing: DummySubsystem,
+			candidate_selection: DummySubsystem,
+			statement_distribution: DummySubsystem,
+			availability_distribution: AvailabilityDistributionSubsystem::new(keystore.clone()),
+			bitfield_signing: DummySubsystem,
+			bitfield_distribution: DummySubsystem,
+			provisioner: DummySubsystem,
+			pov_distribution: DummySubsystem,
+			runtime_api,
+			availability_store: ProbeAvailabilityStore { node: setup.index(), chunks, tx },
+			network_bridge: setup.network_bridge(),
+			chain_api: setup.chain_api(),
+			collation_generation: DummySubsystem,
+			collator_protocol: DummySubsystem,
+			gossip_support: GossipSupport::new(keystore),
+		};
+
+		Overseer::new(
+			setup.leaves(),
+			all_subsystems,
+			Default::default(),
+			Default::default(),
+			None,
+			setup.spawner(),
+		)
+	}).expect("overseer of a simulated node can be built")
+}
+
+#[test]
+fn backer_sends_chunks_to_their_owners_through_the_network() {
+	let mut sim = Simulation::new(NetworkConfig::default(), 42);
+	let (tx, mut rx) = mpsc::unbounded();
+
+	let available_data = AvailableData {
+		pov: PoV { block_data: BlockData(vec![1, 2, 3]) },
+		validation_data: Default::default(),
+	};
+	let chunks = erasure_chunks(&available_data);
+	let candidate = CommittedCandidateReceipt {
+		descriptor: CandidateDescriptor {
+			para_id: ParaId::from(1),
+			relay_parent: sim.chain().genesis(),
+			pov_hash: available_data.pov.hash(),
+			..Default::default()
+		},
+		commitments: CandidateCommitments {
+			erasure_root: branches(&obtain_chunks(KEYS.len(), &available_data).unwrap()).root(),
+			..Default::default()
+		},
+	};
+
+	// Alice backed the candidate, so she holds all of its chunks.
+	for (index, (key, validator)) in KEYS.iter().zip(validators()).enumerate() {
+		let chunks = Some(chunks.clone()).filter(|_| index == 0);
+		let node = add_distributing_validator(&mut sim, *key, candidate.clone(), chunks, tx.clone());
+		sim.network().register_validator(validator, node);
+	}
+
+	// the nodes aren't connected to begin with: gossip support asks for connections to the
+	// validators of the session, and availability distribution learns which peer is whose from
+	// the network bridge.
+	let stored = async move {
+		let mut stored = BTreeMap::new();
+		while stored.len() < 2 {
+			let (node, index) = rx.next().await?;
+			stored.insert(node, index);
+		}
+
+		Some(stored)
+	};
+
+	let stored = sim.run_until(stored, Duration::from_secs(10)).flatten().unwrap_or_default();
+	let expected: BTreeMap<usize, ValidatorIndex> = vec![(1, 1), (2, 2)].into_iter().collect();
+	assert_eq!(stored, expected);
+	assert!(sim.network().is_connected(0, 1));
+	assert!(sim.network().is_connected(0, 2));
+
+	sim.stop();
+}
+
+/// A candidate validation subsystem finding every candidate valid, with empty outputs.
+struct AcceptingCandidateValidation;
+
+impl<C> Subsystem<C> for AcceptingCandidateValidation
+	where C: SubsystemContext<Message = CandidateValidationMessage>
+{
+	type Metrics = ();
+
+	fn start(self, mut ctx: C) -> SpawnedSubsystem {
+		let future = async move {
+			loop {
+				match ctx.recv().await {
+					Ok(FromOverseer::Communication {
+						msg: CandidateValidationMessage::ValidateFromChainState(_, _, tx),
+					}) => {
+						let _ = tx.send(Ok(ValidationResult::Valid(ValidationOutputs {
+							head_data: Default::default(),
+							validation_data: Default::default(),
+							upward_messages: Vec::new(),
+							fees: 0,
+							new_validation_code: None,
+						})));
+					}
+					Ok(FromOverseer::Signal(OverseerSignal::Conclude)) | Err(_) => return,
+					Ok(_) => {}
+				}
+			}
+		};
+
+		SpawnedSubsystem {
+			name: "accepting-candidate-validation-subsystem",
+			future: future.boxed(),
+		}
+	}
+}
+
+/// A node running the subsystems a candidate passes through from backing to the provisioner, as
+/// the validator with the given key. Alice is the only validator assigned to the single core,
+/// which is free at block 1 and occupied by `candidate` from block 2 on.
+fn add_full_validator(
+	sim: &mut Simulation,
+	key: Sr25519Keyring,
+	candidate: CommittedCandidateReceipt,
+	stored_tx: mpsc::UnboundedSender<Stored>,
+	provisioned_tx: mpsc::UnboundedSender<Provisioned>,
+) -> usize {
+	let clock = sim.scheduler().clock().clone();
+
+	sim.add_node(move |mut setup| {
+		let chain = setup.chain().clone();
+		let para_id = candidate.descriptor.para_id;
+		let runtime_api = MockRuntimeApi::new(move |relay_parent, request| {
+			let number = chain.header(&relay_parent).map_or(0, |header| header.number);
+			let pending = Some(candidate.clone()).filter(|_| number >= 2);
+
+			match request {
+				RuntimeApiRequest::Validators(tx) => {
+					let _ = tx.send(Ok(validators()));
+				}
+				RuntimeApiRequest::SessionIndexForChild(tx) => {
+					let _ = tx.send(Ok(SESSION_INDEX));
+				}
+				RuntimeApiRequest::ValidatorGroups(tx) => {
+					let groups = (0..KEYS.len() as u32).map(|index| vec![index]).collect();
+					let rotation_info = GroupRotationInfo {
+						session_start_block: 0,
+						group_rotation_frequency: 10,
+						now: number,
+					};
+					let _ = tx.send(Ok((groups, rotation_info)));
+				}
+				RuntimeApiRequest::AvailabilityCores(tx) => {
+					let core = match pending {
+						Some(_) => CoreState::Occupied(OccupiedCore {
+							para_id,
+							next_up_on_available: None,
+							occupied_since: 2,
+							time_out_at: 10,
+							next_up_on_time_out: None,
+							availability: Default::default(),
+							group_responsible: GroupIndex::from(0),
+						}),
+						None => CoreState::Scheduled(ScheduledCore { para_id, collator: None }),
+					};
+					let _ = tx.send(Ok(vec![core]));
+				}
+				RuntimeApiRequest::CandidatePendingAvailability(para, tx) => {
+					let _ = tx.send(Ok(pending.filter(|_| para == para_id)));
+				}
+				_ => {}
+			}
+		});
+
+		let keystore = sc_keystore::Store::new_in_memory();
+		keystore.write()
+			.insert_ephemeral_from_seed::<ValidatorPair>(&key.to_seed())
+			.expect("in-memory keystore accepts keys; qed");
+
+		let all_subsystems = AllSubsystems {
+			candidate_validation: AcceptingCandidateValidation,
+			candidate_backing: CandidateBackingSubsystem::new(
+				setup.spawner(),
+				keystore.clone(),
+				Default::default(),
+			),
+			candidate_selection: DummySubsystem,
+			statement_distribution: DummySubsystem,
+			availability_distribution: AvailabilityDistributionSubsystem::new(keystore.clone()),
+			bitfield_signing: DummySubsystem,
+			bitfield_distribution: BitfieldDistribution,
+			provisioner: ProbeProvisioner { node: setup.index(), clock, tx: provisioned_tx },
+			pov_distribution: DummySubsystem,
+			runtime_api,
+			availability_store: ProbeAvailabilityStore { node: setup.index(), chunks: None, tx: stored_tx },
+			network_bridge: setup.network_bridge(),
+			chain_api: setup.chain_api(),
+			collation_generation: DummySubsystem,
+			collator_protocol: DummySubsystem,
+			gossip_support: GossipSupport::new(keystore),
+		};
+
+		Overseer::new(
+			setup.leaves(),
+			all_subsystems,
+			Default::default(),
+			Default::default(),
+			None,
+			setup.spawner(),
+		)
+	}).expect("overseer of a simulated node can be built")
+}
+
+#[test]
+fn backed_candidate_becomes_available_and_its_bitfields_reach_the_provisioner() {
+	let mut sim = Simulation::new(NetworkConfig::default(), 42);
+	let (stored_tx, mut stored_rx) = mpsc::unbounded();
+	let (provisioned_tx, mut provisioned_rx) = mpsc::unbounded();
+
+	let genesis = sim.chain().genesis();
+	let block_1 = sim.chain().add_block(genesis);
+
+	let pov = PoV { block_data: BlockData(vec![1, 2, 3]) };
+	let available_data = AvailableData { pov: pov.clone(), validation_data: Default::default() };
+	let commitments = CandidateCommitments {
+		erasure_root: branches(&obtain_chunks(KEYS.len(), &available_data).unwrap()).root(),
+		..Default::default()
+	};
+	let candidate = CommittedCandidateReceipt {
+		descriptor: CandidateDescriptor {
+			para_id: ParaId::from(1),
+			relay_parent: block_1,
+			pov_hash: pov.hash(),
+			..Default::default()
+		},
+		commitments,
+	};
+
+	for (key, validator) in KEYS.iter().zip(validators()) {
+		let node = add_full_validator(
+			&mut sim,
+			*key,
+			candidate.clone(),
+			stored_tx.clone(),
+			provisioned_tx.clone(),
+		);
+		sim.network().register_validator(validator, node);
+	}
+
+	// the nodes start at genesis, and gossip support connects them.
+	sim.run_for(Duration::from_secs(1));
+	for node in 0..sim.node_count() {
+		sim.announce_block(node, block_1);
+	}
+	sim.run_for(Duration::from_secs(1));
+
+	// Alice is asked to second the candidate. Backing erasure codes it on another thread, so wait
+	// for it in real time.
+	sim.send_message(0, AllMessages::CandidateBacking(
+		CandidateBackingMessage::Second(block_1, candidate.to_plain(), pov),
+	));
+	let mut backed = Vec::new();
+	for _ in 0..100 {
+		let (tx, mut rx) = oneshot::channel();
+		sim.send_message(0, AllMessages::CandidateBacking(
+			CandidateBackingMessage::GetBackedCandidates(block_1, tx),
+		));
+		sim.run_for(Duration::from_millis(100));
+
+		backed = rx.try_recv().ok().flatten().unwrap_or_default();
+		if !backed.is_empty() {
+			break;
+		}
+		std::thread::sleep(Duration::from_millis(10));
+	}
+	assert_eq!(backed.len(), 1, "the candidate wasn't backed");
+	assert_eq!(backed[0].0.candidate, candidate);
+
+	// the candidate is pending availability from block 2 on, and Alice sends the others their
+	// chunks from the available data backing stored.
+	let block_2 = sim.import_block(block_1);
+	let stored = async move {
+		let mut stored = BTreeMap::new();
+		while stored.len() < 2 {
+			let (node, index) = stored_rx.next().await?;
+			stored.insert(node, index);
+		}
+
+		Some(stored)
+	};
+	let stored = sim.run_until(stored, Duration::from_secs(10)).flatten().unwrap_or_default();
+	let expected: BTreeMap<usize, ValidatorIndex> = vec![(1, 1), (2, 2)].into_iter().collect();
+	assert_eq!(stored, expected);
+
+	// with their chunks stored, Bob and Charlie attest to the availability of the candidate, and
+	// the provisioner of Alice, the block author to be, hears of both.
+	for &node in stored.keys() {
+		distribute_bitfield(&mut sim, node, block_2);
+	}
+	let provisioned = async move {
+		let mut attested = 0;
+		while attested < 2 {
+			let (node, relay_parent, _) = provisioned_rx.next().await?;
+			if node == 0 && relay_parent == block_2 {
+				attested += 1;
+			}
+		}
+
+		Some(())
+	};
+	assert!(sim.run_until(provisioned, Duration::from_secs(10)).flatten().is_some());
+
+	sim.stop();
+}
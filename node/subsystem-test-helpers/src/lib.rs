@@ -16,8 +16,12 @@
 
 //! Utilities for testing subsystems.
 
-use polkadot_node_subsystem::messages::AllMessages;
-use polkadot_node_subsystem::{FromOverseer, SubsystemContext, SubsystemError, SubsystemResult};
+use polkadot_node_subsystem::messages::{AllMessages, SubsystemKind};
+use polkadot_node_subsystem::record::{RecordEntry, RecordedEvent};
+use polkadot_node_subsystem::{
+	FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem, SubsystemContext, SubsystemError,
+	SubsystemResult,
+};
 use polkadot_node_subsystem_util::TimeoutExt;
 
 use futures::channel::mpsc;
@@ -26,7 +30,7 @@ use futures::prelude::*;
 use parking_lot::Mutex;
 use sp_core::{testing::TaskExecutor, traits::SpawnNamed};
 
-use std::convert::Infallible;
+use std::convert::{Infallible, TryFrom};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
@@ -283,3 +287,122 @@ pub fn subsystem_test_harness<M, OverseerFactory, Overseer, TestFactory, Test>(
 			.expect("test timed out instead of completing")
 	});
 }
+
+/// The outcome of replaying a recording against a subsystem. See [`replay_recording`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+	/// The messages the subsystem sent while the recording was made, in their `Debug` representation.
+	pub expected: Vec<String>,
+	/// The messages the subsystem sent during the replay, in their `Debug` representation.
+	pub actual: Vec<String>,
+	/// The number of recorded messages to the subsystem which could not be replayed.
+	pub skipped: usize,
+}
+
+impl ReplayReport {
+	/// Whether the subsystem behaved exactly as it did while the recording was made.
+	pub fn matches(&self) -> bool {
+		self.expected == self.actual
+	}
+
+	/// The index of the first message at which the replay diverged from the recording, if any.
+	pub fn first_divergence(&self) -> Option<usize> {
+		if self.matches() {
+			return None;
+		}
+
+		Some(
+			self.expected.iter()
+				.zip(self.actual.iter())
+				.position(|(expected, actual)| expected != actual)
+				.unwrap_or_else(|| self.expected.len().min(self.actual.len()))
+		)
+	}
+}
+
+/// Replay a recording made by the overseer against a single subsystem, mocking the overseer.
+///
+/// Every signal and every replayable message destined to the subsystem of the given `kind` is fed
+/// to it in the recorded order, up to and including the first `Conclude`. The messages the subsystem
+/// sends are compared to the ones it sent while the recording was made.
+///
+/// Requests which expect an answer on a channel are never answered, so the replay only reproduces
+/// subsystems which can make progress without them.
+///
+/// Times out in two seconds.
+pub fn replay_recording<M, Sub>(
+	recording: &[RecordEntry],
+	kind: SubsystemKind,
+	subsystem: Sub,
+) -> ReplayReport
+where
+	M: TryFrom<AllMessages> + Send + 'static,
+	Sub: Subsystem<TestSubsystemContext<M, TaskExecutor>>,
+{
+	let mut report = ReplayReport::default();
+	let mut inputs = Vec::new();
+
+	for entry in recording {
+		match entry.event {
+			RecordedEvent::Signal(ref signal) => {
+				let signal = OverseerSignal::from(signal.clone());
+				let conclude = matches!(signal, OverseerSignal::Conclude);
+
+				inputs.push(FromOverseer::Signal(signal));
+
+				if conclude {
+					break;
+				}
+			}
+			RecordedEvent::Message(ref recorded) => {
+				if recorded.origin == Some(kind) {
+					report.expected.push(recorded.debug.clone());
+				}
+
+				if recorded.destination != kind {
+					continue;
+				}
+
+				let msg = recorded.payload.clone()
+					.and_then(|payload| payload.into_message())
+					.and_then(|msg| M::try_from(msg).ok());
+
+				match msg {
+					Some(msg) => inputs.push(FromOverseer::Communication { msg }),
+					None => report.skipped += 1,
+				}
+			}
+		}
+	}
+
+	if !matches!(inputs.last(), Some(FromOverseer::Signal(OverseerSignal::Conclude))) {
+		inputs.push(FromOverseer::Signal(OverseerSignal::Conclude));
+	}
+
+	let pool = TaskExecutor::new();
+	let (context, mut handle) = make_subsystem_context(pool);
+	let SpawnedSubsystem { future, .. } = subsystem.start(context);
+
+	let overseer = async move {
+		for input in inputs {
+			handle.send(input).await;
+		}
+
+		let mut actual = Vec::new();
+		while let Some(msg) = handle.try_recv().await {
+			actual.push(format!("{:?}", msg));
+		}
+
+		actual
+	};
+
+	report.actual = futures::executor::block_on(async move {
+		future::join(future, overseer)
+			.timeout(Duration::from_secs(2))
+			.await
+			.expect("replay timed out instead of completing")
+			.1
+	});
+
+	report
+}
@@ -673,6 +673,23 @@ pub struct JobManager<Spawner, Context, Job: JobTrait> {
 	errors: Option<mpsc::Sender<(Option<Hash>, JobsError<Job::Error>)>>,
 }
 
+// Implemented by hand, as deriving would require `Context` and `Job` to be `Clone` as well.
+impl<Spawner: Clone, Context, Job: JobTrait> Clone for JobManager<Spawner, Context, Job>
+where
+	Job::RunArgs: Clone,
+{
+	fn clone(&self) -> Self {
+		Self {
+			spawner: self.spawner.clone(),
+			run_args: self.run_args.clone(),
+			metrics: self.metrics.clone(),
+			context: std::marker::PhantomData,
+			job: std::marker::PhantomData,
+			errors: self.errors.clone(),
+		}
+	}
+}
+
 impl<Spawner, Context, Job> JobManager<Spawner, Context, Job>
 where
 	Spawner: SpawnNamed + Clone + Send + Unpin,
@@ -1020,7 +1037,7 @@ impl<F: Future> Future for Timeout<F> {
 mod tests {
 	use super::{Error as UtilError, JobManager, JobTrait, JobsError, TimeoutExt, ToJobTrait};
 	use polkadot_node_subsystem::{
-		messages::{AllMessages, CandidateSelectionMessage},
+		messages::{AllMessages, CandidateSelectionMessage, CollatorProtocolMessage},
 		ActiveLeavesUpdate, FromOverseer, OverseerSignal, SpawnedSubsystem, Subsystem,
 	};
 	use assert_matches::assert_matches;
@@ -1030,7 +1047,7 @@ mod tests {
 		stream::{self, StreamExt},
 		future, Future, FutureExt, SinkExt,
 	};
-	use polkadot_primitives::v1::Hash;
+	use polkadot_primitives::v1::{CollatorId, Hash};
 	use polkadot_node_subsystem_test_helpers::{self as test_helpers, make_subsystem_context};
 	use std::{collections::HashMap, convert::TryFrom, pin::Pin, time::Duration};
 
@@ -1044,6 +1061,7 @@ mod tests {
 	// most will want to retain the sender and receiver, as well as whatever other data they like
 	struct FakeCandidateSelectionJob {
 		receiver: mpsc::Receiver<ToJob>,
+		sender: mpsc::Sender<FromJob>,
 	}
 
 	// ToJob implementations require the following properties:
@@ -1096,12 +1114,16 @@ mod tests {
 	#[derive(Clone)]
 	enum FromJob {
 		Test,
+		ReportCollator(CollatorId),
 	}
 
 	impl From<FromJob> for AllMessages {
 		fn from(from_job: FromJob) -> AllMessages {
 			match from_job {
 				FromJob::Test => AllMessages::CandidateSelection(CandidateSelectionMessage::default()),
+				FromJob::ReportCollator(id) => AllMessages::CollatorProtocol(
+					CollatorProtocolMessage::ReportCollator(id),
+				),
 			}
 		}
 	}
@@ -1142,7 +1164,6 @@ mod tests {
 			mut sender: mpsc::Sender<FromJob>,
 		) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send>> {
 			async move {
-				let job = FakeCandidateSelectionJob { receiver };
 
 				// most jobs will have a request-response cycle at the heart of their run loop.
 				// however, in this case, we never receive valid messages, so we may as well
@@ -1151,6 +1172,8 @@ mod tests {
 				let mut stream = stream::iter(mock_output.into_iter().map(Ok));
 				sender.send_all(&mut stream).await?;
 
+				let job = FakeCandidateSelectionJob { receiver, sender };
+
 				// it isn't necessary to break run_loop into its own function,
 				// but it's convenient to separate the concerns in this way
 				job.run_loop().await
@@ -1163,9 +1186,12 @@ mod tests {
 		async fn run_loop(mut self) -> Result<(), Error> {
 			while let Some(msg) = self.receiver.next().await {
 				match msg {
-					ToJob::CandidateSelection(_csm) => {
-						unimplemented!("we'd report the collator to the peer set manager here, but that's not implemented yet");
+					// the collator protocol keeps track of the collator's reputation, so all we
+					// have to do is pass the report on
+					ToJob::CandidateSelection(CandidateSelectionMessage::Invalid(_, receipt)) => {
+						self.sender.send(FromJob::ReportCollator(receipt.descriptor.collator)).await?;
 					}
+					ToJob::CandidateSelection(_) => {}
 					ToJob::Stop => break,
 				}
 			}
@@ -38,6 +38,7 @@ use crate::messages::AllMessages;
 
 pub mod errors;
 pub mod messages;
+pub mod record;
 
 /// How many slots are stack-reserved for active leaves updates
 ///
@@ -213,6 +214,7 @@ pub trait Subsystem<C: SubsystemContext> {
 
 /// A dummy subsystem that implements [`Subsystem`] for all
 /// types of messages. Used for tests or as a placeholder.
+#[derive(Clone)]
 pub struct DummySubsystem;
 
 impl<C: SubsystemContext> Subsystem<C> for DummySubsystem {
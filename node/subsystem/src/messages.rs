@@ -23,9 +23,10 @@
 //! Subsystems' APIs are defined separately from their implementation, leading to easier mocking.
 
 use futures::channel::{mpsc, oneshot};
+use parity_scale_codec::{Encode, Decode};
 
 use polkadot_node_network_protocol::{
-	v1 as protocol_v1, NetworkBridgeEvent, ReputationChange, PeerId, PeerSet,
+	v1 as protocol_v1, IncomingRequest, NetworkBridgeEvent, ReputationChange, PeerId, PeerSet,
 };
 use polkadot_node_primitives::{
 	CollationGenerationConfig, MisbehaviorReport, SignedFullStatement, ValidationResult,
@@ -33,11 +34,12 @@ use polkadot_node_primitives::{
 use polkadot_primitives::v1::{
 	AvailableData, BackedCandidate, BlockNumber, CandidateDescriptor, CandidateEvent,
 	CandidateReceipt, CollatorId, CommittedCandidateReceipt,
-	CoreState, ErasureChunk, GroupRotationInfo, Hash, Id as ParaId,
+	CoreState, ErasureChunk, GroupRotationInfo, Hash, Header, Id as ParaId,
 	OccupiedCoreAssumption, PersistedValidationData, PoV, SessionIndex, SignedAvailabilityBitfield,
-	TransientValidationData, ValidationCode, ValidatorId, ValidationData, ValidatorIndex,
-	ValidatorSignature,
+	TransientValidationData, UncheckedExtrinsic, ValidationCode, ValidatorId, ValidationData,
+	ValidatorIndex, ValidatorSignature,
 };
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 /// A notification of a new backed candidate.
@@ -199,8 +201,29 @@ pub enum NetworkBridgeMessage {
 	/// Connect to peers who represent the given `ValidatorId`s at the given relay-parent.
 	///
 	/// Also accepts a response channel by which the issuer can learn the `PeerId`s of those
-	/// validators.
+	/// validators. The subsystems of the peer-set learn about them through
+	/// `NetworkBridgeEvent::ValidatorPeers`.
 	ConnectToValidators(PeerSet, Vec<ValidatorId>, oneshot::Sender<Vec<(ValidatorId, PeerId)>>),
+
+	/// Stop keeping up the connections to the given validators, which were requested with
+	/// `ConnectToValidators`.
+	ReleaseValidators(PeerSet, Vec<ValidatorId>),
+
+	/// Fetch a PoV from the first of the given peers which is able to provide it.
+	///
+	/// The peers are asked one after the other, in the given order. Answers with the PoV and
+	/// the peer which provided it, or `None` if none of the peers did.
+	FetchPoV(Vec<PeerId>, protocol_v1::PoVFetchingRequest, oneshot::Sender<Option<(PeerId, PoV)>>),
+
+	/// Fetch an erasure chunk from the first of the given peers which is able to provide it.
+	///
+	/// The peers are asked one after the other, in the given order. Answers with the chunk and
+	/// the peer which provided it, or `None` if none of the peers did.
+	FetchChunk(
+		Vec<PeerId>,
+		protocol_v1::ChunkFetchingRequest,
+		oneshot::Sender<Option<(PeerId, ErasureChunk)>>,
+	),
 }
 
 impl NetworkBridgeMessage {
@@ -211,6 +234,9 @@ impl NetworkBridgeMessage {
 			Self::SendValidationMessage(_, _) => None,
 			Self::SendCollationMessage(_, _) => None,
 			Self::ConnectToValidators(_, _, _) => None,
+			Self::ReleaseValidators(_, _) => None,
+			Self::FetchPoV(_, request, _) => Some(request.relay_parent),
+			Self::FetchChunk(_, _, _) => None,
 		}
 	}
 }
@@ -220,6 +246,13 @@ impl NetworkBridgeMessage {
 pub enum AvailabilityDistributionMessage {
 	/// Event from the network bridge.
 	NetworkBridgeUpdateV1(NetworkBridgeEvent<protocol_v1::AvailabilityDistributionMessage>),
+	/// A peer requested an erasure chunk from us.
+	ChunkFetchingRequest(
+		IncomingRequest<protocol_v1::ChunkFetchingRequest, protocol_v1::ChunkFetchingResponse>,
+	),
+	/// Recover the available data of a live candidate, by its hash, from the erasure chunks
+	/// held by the validators. `None` if it can't be recovered.
+	RecoverAvailableData(Hash, oneshot::Sender<Option<AvailableData>>),
 }
 
 impl AvailabilityDistributionMessage {
@@ -227,6 +260,8 @@ impl AvailabilityDistributionMessage {
 	pub fn relay_parent(&self) -> Option<Hash> {
 		match self {
 			Self::NetworkBridgeUpdateV1(_) => None,
+			Self::ChunkFetchingRequest(_) => None,
+			Self::RecoverAvailableData(_, _) => None,
 		}
 	}
 }
@@ -316,6 +351,10 @@ impl AvailabilityStoreMessage {
 /// A response channel for the result of a chain API request.
 pub type ChainApiResponseChannel<T> = oneshot::Sender<Result<T, crate::errors::ChainApiError>>;
 
+/// The weight of a block as used by BABE's fork choice rule, i.e. the number of
+/// primary slots claimed in the chain up to and including the block.
+pub type BlockWeight = u32;
+
 /// Chain API request subsystem message.
 #[derive(Debug)]
 pub enum ChainApiMessage {
@@ -341,6 +380,32 @@ pub enum ChainApiMessage {
 		/// The response channel.
 		response_channel: ChainApiResponseChannel<Vec<Hash>>,
 	},
+	/// Request the header of the block with the given hash.
+	/// Returns `None` if a block with the given hash is not present in the db.
+	BlockHeader(Hash, ChainApiResponseChannel<Option<Header>>),
+	/// Request the body, i.e. the extrinsics, of the block with the given hash.
+	/// Returns `None` if the body of the block is not present in the db.
+	BlockBody(Hash, ChainApiResponseChannel<Option<Vec<UncheckedExtrinsic>>>),
+	/// Request the weight of the block with the given hash.
+	/// Returns `None` if no weight is known for the block.
+	BlockWeight(Hash, ChainApiResponseChannel<Option<BlockWeight>>),
+	/// Request the `k` descendants block hashes of a block with the given hash
+	/// on the best chain.
+	/// The response channel may return a `Vec` of size up to `k`
+	/// filled with descendants hashes with the following order:
+	/// `child`, `grandchild`, ...
+	/// It is empty if the block is not on the best chain.
+	Descendants {
+		/// The hash of the block in question.
+		hash: Hash,
+		/// The number of descendants to request.
+		k: usize,
+		/// The response channel.
+		response_channel: ChainApiResponseChannel<Vec<Hash>>,
+	},
+	/// Subscribe to every block finalized from now on, sent as `(hash, number)` in ascending
+	/// order. The subscription ends when the receiver is dropped.
+	FinalityNotifications(mpsc::UnboundedSender<(Hash, BlockNumber)>),
 }
 
 impl ChainApiMessage {
@@ -488,6 +553,10 @@ pub enum PoVDistributionMessage {
 	DistributePoV(Hash, CandidateDescriptor, Arc<PoV>),
 	/// An update from the network bridge.
 	NetworkBridgeUpdateV1(NetworkBridgeEvent<protocol_v1::PoVDistributionMessage>),
+	/// A peer requested a PoV from us.
+	PoVFetchingRequest(
+		IncomingRequest<protocol_v1::PoVFetchingRequest, protocol_v1::PoVFetchingResponse>,
+	),
 }
 
 impl PoVDistributionMessage {
@@ -497,6 +566,7 @@ impl PoVDistributionMessage {
 			Self::FetchPoV(hash, _, _) => Some(*hash),
 			Self::DistributePoV(hash, _, _) => Some(*hash),
 			Self::NetworkBridgeUpdateV1(_) => None,
+			Self::PoVFetchingRequest(request) => Some(request.payload.relay_parent),
 		}
 	}
 }
@@ -515,6 +585,19 @@ impl CollationGenerationMessage {
 	}
 }
 
+/// Message to the Gossip Support subsystem.
+///
+/// The subsystem acts on overseer signals only.
+#[derive(Debug)]
+pub enum GossipSupportMessage {}
+
+impl GossipSupportMessage {
+	/// If the current variant contains the relay parent hash, return it.
+	pub fn relay_parent(&self) -> Option<Hash> {
+		match *self {}
+	}
+}
+
 /// A message type tying together all message types that are used across Subsystems.
 #[derive(Debug)]
 pub enum AllMessages {
@@ -548,4 +631,127 @@ pub enum AllMessages {
 	NetworkBridge(NetworkBridgeMessage),
 	/// Message for the Collation Generation subsystem
 	CollationGeneration(CollationGenerationMessage),
+	/// Message for the Gossip Support subsystem.
+	GossipSupport(GossipSupportMessage),
 }
+
+/// Identifies a subsystem by the variant of [`AllMessages`] that is routed to it.
+///
+/// [`AllMessages`]: enum.AllMessages.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub enum SubsystemKind {
+	/// The validation subsystem.
+	CandidateValidation,
+	/// The candidate backing subsystem.
+	CandidateBacking,
+	/// The candidate selection subsystem.
+	CandidateSelection,
+	/// The Chain API subsystem.
+	ChainApi,
+	/// The Collator Protocol subsystem.
+	CollatorProtocol,
+	/// The statement distribution subsystem.
+	StatementDistribution,
+	/// The availability distribution subsystem.
+	AvailabilityDistribution,
+	/// The bitfield distribution subsystem.
+	BitfieldDistribution,
+	/// The bitfield signing subsystem.
+	BitfieldSigning,
+	/// The Provisioner subsystem.
+	Provisioner,
+	/// The PoV Distribution subsystem.
+	PoVDistribution,
+	/// The Runtime API subsystem.
+	RuntimeApi,
+	/// The availability store subsystem.
+	AvailabilityStore,
+	/// The network bridge subsystem.
+	NetworkBridge,
+	/// The Collation Generation subsystem.
+	CollationGeneration,
+	/// The Gossip Support subsystem.
+	GossipSupport,
+}
+
+impl SubsystemKind {
+	/// The name of the [`AllMessages`] variant routed to the subsystem, e.g. for metric labels.
+	///
+	/// [`AllMessages`]: enum.AllMessages.html
+	pub fn name(&self) -> &'static str {
+		match self {
+			SubsystemKind::CandidateValidation => "CandidateValidation",
+			SubsystemKind::CandidateBacking => "CandidateBacking",
+			SubsystemKind::CandidateSelection => "CandidateSelection",
+			SubsystemKind::ChainApi => "ChainApi",
+			SubsystemKind::CollatorProtocol => "CollatorProtocol",
+			SubsystemKind::StatementDistribution => "StatementDistribution",
+			SubsystemKind::AvailabilityDistribution => "AvailabilityDistribution",
+			SubsystemKind::BitfieldDistribution => "BitfieldDistribution",
+			SubsystemKind::BitfieldSigning => "BitfieldSigning",
+			SubsystemKind::Provisioner => "Provisioner",
+			SubsystemKind::PoVDistribution => "PoVDistribution",
+			SubsystemKind::RuntimeApi => "RuntimeApi",
+			SubsystemKind::AvailabilityStore => "AvailabilityStore",
+			SubsystemKind::NetworkBridge => "NetworkBridge",
+			SubsystemKind::CollationGeneration => "CollationGeneration",
+			SubsystemKind::GossipSupport => "GossipSupport",
+		}
+	}
+}
+
+impl AllMessages {
+	/// The subsystem this message is routed to.
+	pub fn destination(&self) -> SubsystemKind {
+		match self {
+			Self::CandidateValidation(_) => SubsystemKind::CandidateValidation,
+			Self::CandidateBacking(_) => SubsystemKind::CandidateBacking,
+			Self::CandidateSelection(_) => SubsystemKind::CandidateSelection,
+			Self::ChainApi(_) => SubsystemKind::ChainApi,
+			Self::CollatorProtocol(_) => SubsystemKind::CollatorProtocol,
+			Self::StatementDistribution(_) => SubsystemKind::StatementDistribution,
+			Self::AvailabilityDistribution(_) => SubsystemKind::AvailabilityDistribution,
+			Self::BitfieldDistribution(_) => SubsystemKind::BitfieldDistribution,
+			Self::BitfieldSigning(_) => SubsystemKind::BitfieldSigning,
+			Self::Provisioner(_) => SubsystemKind::Provisioner,
+			Self::PoVDistribution(_) => SubsystemKind::PoVDistribution,
+			Self::RuntimeApi(_) => SubsystemKind::RuntimeApi,
+			Self::AvailabilityStore(_) => SubsystemKind::AvailabilityStore,
+			Self::NetworkBridge(_) => SubsystemKind::NetworkBridge,
+			Self::CollationGeneration(_) => SubsystemKind::CollationGeneration,
+			Self::GossipSupport(_) => SubsystemKind::GossipSupport,
+		}
+	}
+}
+
+macro_rules! impl_try_from_all_messages {
+	($variant:ident, $message:ty) => {
+		impl TryFrom<AllMessages> for $message {
+			type Error = AllMessages;
+
+			fn try_from(msg: AllMessages) -> Result<Self, AllMessages> {
+				match msg {
+					AllMessages::$variant(msg) => Ok(msg),
+					other => Err(other),
+				}
+			}
+		}
+	}
+}
+
+impl_try_from_all_messages!(CandidateValidation, CandidateValidationMessage);
+impl_try_from_all_messages!(CandidateBacking, CandidateBackingMessage);
+impl_try_from_all_messages!(CandidateSelection, CandidateSelectionMessage);
+impl_try_from_all_messages!(ChainApi, ChainApiMessage);
+impl_try_from_all_messages!(CollatorProtocol, CollatorProtocolMessage);
+impl_try_from_all_messages!(StatementDistribution, StatementDistributionMessage);
+impl_try_from_all_messages!(AvailabilityDistribution, AvailabilityDistributionMessage);
+impl_try_from_all_messages!(BitfieldDistribution, BitfieldDistributionMessage);
+impl_try_from_all_messages!(BitfieldSigning, BitfieldSigningMessage);
+impl_try_from_all_messages!(Provisioner, ProvisionerMessage);
+impl_try_from_all_messages!(PoVDistribution, PoVDistributionMessage);
+impl_try_from_all_messages!(RuntimeApi, RuntimeApiMessage);
+impl_try_from_all_messages!(AvailabilityStore, AvailabilityStoreMessage);
+impl_try_from_all_messages!(NetworkBridge, NetworkBridgeMessage);
+impl_try_from_all_messages!(CollationGeneration, CollationGenerationMessage);
+impl_try_from_all_messages!(GossipSupport, GossipSupportMessage);
@@ -0,0 +1,134 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A collator for the adder test parachain, which is registered in the genesis of the test chain
+//! spec under [`ADDER_PARA_ID`].
+
+use std::{collections::HashMap, sync::Arc};
+
+use futures::future::{self, Future};
+use parking_lot::Mutex;
+use polkadot_node_primitives::{Collation, CollationGenerationConfig};
+use polkadot_primitives::v1::{
+	BlockData, CollatorId, CollatorPair, HeadData, Id as ParaId, PoV, ValidationCode, ValidationData,
+};
+use sp_core::Pair;
+use sp_runtime::codec::{Decode, Encode};
+use test_parachain_adder::{BlockData as AdderBlockData, HeadData as AdderHeadData};
+
+/// The ID the adder parachain is registered under in the test chain spec.
+pub const ADDER_PARA_ID: u32 = 100;
+
+/// What every collated block adds to the state of the adder parachain.
+const ADD_PER_BLOCK: u64 = 1;
+
+/// The head of the adder parachain at genesis, with a state of zero.
+pub fn adder_genesis_head() -> HeadData {
+	HeadData(AdderHeadData {
+		number: 0,
+		parent_hash: [0; 32],
+		post_state: test_parachain_adder::hash_state(0),
+	}.encode())
+}
+
+/// The validation code of the adder parachain.
+pub fn adder_validation_code() -> ValidationCode {
+	ValidationCode(test_parachain_adder::wasm_binary_unwrap().to_vec())
+}
+
+/// Collates blocks of the adder parachain on top of any head it produced before, or the genesis
+/// head.
+#[derive(Clone)]
+pub struct AdderCollator {
+	key: Arc<CollatorPair>,
+	/// The state of the parachain after each of the heads we know about, by the hash of the head.
+	states: Arc<Mutex<HashMap<[u8; 32], u64>>>,
+}
+
+impl Default for AdderCollator {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl AdderCollator {
+	/// Create a collator with a fresh key, knowing only the genesis head.
+	pub fn new() -> Self {
+		let genesis = AdderHeadData::decode(&mut &adder_genesis_head().0[..])
+			.expect("the genesis head is an encoded adder head; qed");
+
+		let mut states = HashMap::new();
+		states.insert(genesis.hash(), 0);
+
+		AdderCollator {
+			key: Arc::new(CollatorPair::generate().0),
+			states: Arc::new(Mutex::new(states)),
+		}
+	}
+
+	/// The ID of the collator, which it signs its collations with.
+	pub fn collator_id(&self) -> CollatorId {
+		self.key.public()
+	}
+
+	/// The configuration to initialize the collation generation subsystem of a collator node
+	/// with.
+	pub fn collation_generation_config(&self) -> CollationGenerationConfig {
+		let collator = self.clone();
+
+		CollationGenerationConfig {
+			key: (*self.key).clone(),
+			collator: Box::new(move |validation_data: &ValidationData| {
+				let collation = collator.collate(&validation_data.persisted.parent_head);
+				Box::new(future::ready(collation)) as Box<dyn Future<Output = Collation> + Unpin + Send>
+			}),
+			para_id: ParaId::from(ADDER_PARA_ID),
+		}
+	}
+
+	/// Build a block on top of `parent_head`, remembering the state it leads to.
+	fn collate(&self, parent_head: &HeadData) -> Collation {
+		let parent = AdderHeadData::decode(&mut &parent_head.0[..])
+			.expect("the relay chain only includes heads which passed the adder's validation; qed");
+		let parent_hash = parent.hash();
+
+		let mut states = self.states.lock();
+		// we are the only collator of the parachain, so every head included was built by us.
+		let state = states.get(&parent_hash).copied().unwrap_or_else(|| {
+			log::warn!("Collating on top of unknown adder head #{}, the block will be invalid", parent.number);
+			0
+		});
+
+		let block_data = AdderBlockData { state, add: ADD_PER_BLOCK };
+		let new_state = state.wrapping_add(ADD_PER_BLOCK);
+		let head = AdderHeadData {
+			number: parent.number + 1,
+			parent_hash,
+			post_state: test_parachain_adder::hash_state(new_state),
+		};
+		states.insert(head.hash(), new_state);
+
+		Collation {
+			fees: 0,
+			upward_messages: Vec::new(),
+			new_validation_code: None,
+			head_data: HeadData(head.encode()),
+			proof_of_validity: PoV {
+				block_data: BlockData(block_data.encode()),
+			},
+		}
+	}
+}
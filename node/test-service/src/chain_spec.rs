@@ -14,15 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
+use authority_discovery_primitives::AuthorityId as AuthorityDiscoveryId;
 use babe_primitives::AuthorityId as BabeId;
 use grandpa::AuthorityId as GrandpaId;
 use pallet_staking::Forcing;
 use polkadot_primitives::v0::{ValidatorId, AccountId};
+use polkadot_runtime_parachains::{configuration::HostConfiguration, paras::ParaGenesisArgs};
 use polkadot_service::chain_spec::{get_account_id_from_seed, get_from_seed, Extensions};
 use polkadot_test_runtime::constants::currency::DOTS;
 use sc_chain_spec::{ChainSpec, ChainType};
 use sp_core::{sr25519, ChangesTrieConfiguration};
 use sp_runtime::Perbill;
+use crate::adder_collator::{ADDER_PARA_ID, adder_genesis_head, adder_validation_code};
 
 const DEFAULT_PROTOCOL_ID: &str = "dot";
 
@@ -64,13 +67,14 @@ pub fn polkadot_local_testnet_genesis(
 /// Helper function to generate stash, controller and session key from seed
 fn get_authority_keys_from_seed(
 	seed: &str,
-) -> (AccountId, AccountId, BabeId, GrandpaId, ValidatorId) {
+) -> (AccountId, AccountId, BabeId, GrandpaId, ValidatorId, AuthorityDiscoveryId) {
 	(
 		get_account_id_from_seed::<sr25519::Public>(&format!("{}//stash", seed)),
 		get_account_id_from_seed::<sr25519::Public>(seed),
 		get_from_seed::<BabeId>(seed),
 		get_from_seed::<GrandpaId>(seed),
 		get_from_seed::<ValidatorId>(seed),
+		get_from_seed::<AuthorityDiscoveryId>(seed),
 	)
 }
 
@@ -93,7 +97,7 @@ fn testnet_accounts() -> Vec<AccountId> {
 
 /// Helper function to create polkadot GenesisConfig for testing
 fn polkadot_testnet_genesis(
-	initial_authorities: Vec<(AccountId, AccountId, BabeId, GrandpaId, ValidatorId)>,
+	initial_authorities: Vec<(AccountId, AccountId, BabeId, GrandpaId, ValidatorId, AuthorityDiscoveryId)>,
 	root_key: AccountId,
 	endowed_accounts: Option<Vec<AccountId>>,
 	changes_trie_config: Option<ChangesTrieConfiguration>,
@@ -154,13 +158,40 @@ fn polkadot_testnet_genesis(
 		}),
 		pallet_babe: Some(Default::default()),
 		pallet_grandpa: Some(Default::default()),
-		pallet_authority_discovery: Some(polkadot::AuthorityDiscoveryConfig { keys: vec![] }),
+		pallet_authority_discovery: Some(polkadot::AuthorityDiscoveryConfig {
+			keys: initial_authorities.iter().map(|x| x.5.clone()).collect(),
+		}),
 		claims: Some(polkadot::ClaimsConfig {
 			claims: vec![],
 			vesting: vec![],
 		}),
 		pallet_vesting: Some(polkadot::VestingConfig { vesting: vec![] }),
 		pallet_sudo: Some(polkadot::SudoConfig { key: root_key }),
+		parachains_configuration: Some(polkadot::ParachainsConfigurationConfig {
+			config: HostConfiguration {
+				validation_upgrade_frequency: 10u32,
+				validation_upgrade_delay: 5,
+				acceptance_period: 1200,
+				max_code_size: 5 * 1024 * 1024,
+				max_head_data_size: 32 * 1024,
+				group_rotation_frequency: 20,
+				chain_availability_period: 4,
+				thread_availability_period: 4,
+				scheduling_lookahead: 1,
+				..Default::default()
+			},
+		}),
+		parachains_paras: Some(polkadot::ParasConfig {
+			paras: vec![(
+				ADDER_PARA_ID.into(),
+				ParaGenesisArgs {
+					genesis_head: adder_genesis_head(),
+					validation_code: adder_validation_code(),
+					parachain: true,
+				},
+			)],
+			_phdata: Default::default(),
+		}),
 	}
 }
 
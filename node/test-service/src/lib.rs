@@ -18,38 +18,45 @@
 
 #![warn(missing_docs)]
 
+mod adder_collator;
 mod chain_spec;
 
+pub use adder_collator::*;
 pub use chain_spec::*;
-use futures::future::Future;
-use polkadot_primitives::v0::{
-	Block, Hash, CollatorId, Id as ParaId,
+use futures::{future::{self, Future, FutureExt}, stream::StreamExt};
+use polkadot_node_core_av_store::{AvailabilityStoreSubsystem, Config as AvailabilityConfig};
+use polkadot_node_primitives::CollationGenerationConfig;
+use polkadot_overseer::{AllSubsystems, BlockInfo, OverflowPolicies, Overseer, OverseerHandler};
+use polkadot_parachain::wasm_executor::{ValidationExecutionMode, ValidationPoolConfig};
+use polkadot_primitives::v1::{
+	Block, BlockId, Hash, HeadData, Id as ParaId, OccupiedCoreAssumption, ParachainHost,
 };
 use polkadot_runtime_common::BlockHashCount;
-use polkadot_service::{
-	new_full, FullNodeHandles, AbstractClient, ClientHandle, ExecuteWithClient,
-};
+use polkadot_service::{new_partial, FullNodeHandles, ClientHandle, ExecuteWithClient};
+use polkadot_subsystem::messages::{AllMessages, CollationGenerationMessage};
 use polkadot_test_runtime::{Runtime, SignedExtra, SignedPayload, VERSION};
 use sc_chain_spec::ChainSpec;
-use sc_client_api::{execution_extensions::ExecutionStrategies, BlockchainEvents};
+use sc_client_api::{execution_extensions::ExecutionStrategies, BlockchainEvents, ExecutorProvider};
 use sc_executor::native_executor_instance;
 use sc_informant::OutputFormat;
 use sc_network::{
 	config::{NetworkConfiguration, TransportConfig},
-	multiaddr, NetworkService,
+	multiaddr, Event, NetworkService,
 };
 use service::{
 	config::{DatabaseConfig, KeystoreConfig, MultiaddrWithPeerId, WasmExecutionMethod},
 	error::Error as ServiceError,
 	RpcHandlers, TaskExecutor, TaskManager,
 };
-use service::{BasePath, Configuration, Role, TFullBackend};
+use service::{BasePath, Configuration, Role};
+use sp_api::ProvideRuntimeApi;
 use sp_arithmetic::traits::SaturatedConversion;
 use sp_blockchain::HeaderBackend;
+use sp_core::{Pair, traits::BareCryptoStorePtr};
 use sp_keyring::Sr25519Keyring;
 use sp_runtime::{codec::Encode, generic};
 use sp_state_machine::BasicExternalities;
-use std::sync::Arc;
+use std::{pin::Pin, sync::Arc, time::Duration};
 use substrate_test_client::{BlockchainEventsExt, RpcHandlersExt, RpcTransactionOutput, RpcTransactionError};
 
 native_executor_instance!(
@@ -59,35 +66,308 @@ native_executor_instance!(
 	frame_benchmarking::benchmarking::HostFunctions,
 );
 
-/// Create a new Polkadot test service for a full node.
+/// The client of a Polkadot test node.
+pub type Client = polkadot_service::FullClient<polkadot_test_runtime::RuntimeApi, PolkadotTestExecutor>;
+
+/// Create a new Polkadot test service for a full node, running the parachain subsystems under an
+/// overseer. Validators author blocks including the backed candidates and availability bitfields
+/// the subsystems came up with, and a node given a `collator` configuration collates for its
+/// parachain.
 pub fn polkadot_test_new_full(
-	config: Configuration,
-	collating_for: Option<(CollatorId, ParaId)>,
+	mut config: Configuration,
+	collator: Option<CollationGenerationConfig>,
 	authority_discovery_enabled: bool,
 ) -> Result<
 	(
 		TaskManager,
-		Arc<polkadot_service::FullClient<polkadot_test_runtime::RuntimeApi, PolkadotTestExecutor>>,
+		Arc<Client>,
 		FullNodeHandles,
 		Arc<NetworkService<Block, Hash>>,
 		RpcHandlers,
+		OverseerHandler,
 	),
 	ServiceError,
 > {
-	let (task_manager, client, handles, network, rpc_handlers) =
-		new_full::<polkadot_test_runtime::RuntimeApi, PolkadotTestExecutor>(
-			config,
-			collating_for,
-			authority_discovery_enabled,
-			None,
-			true,
-		)?;
-
-	Ok((task_manager, client, handles, network, rpc_handlers))
+	let role = config.role.clone();
+	let force_authoring = config.force_authoring;
+	let name = config.network.node_name.clone();
+	let parachains_db = config.base_path.as_ref()
+		.map(|base_path| base_path.path().join("parachains"))
+		.ok_or_else(|| ServiceError::Other("A test node needs a base path".into()))?;
+
+	config.network.notifications_protocols.extend(polkadot_network_bridge::notifications_protocol_info());
+	let (request_protocols, incoming_requests) = polkadot_network_bridge::request_response_protocol_info();
+	config.network.request_response_protocols.extend(request_protocols);
+
+	let service::PartialComponents {
+		client, backend, mut task_manager, keystore, select_chain, import_queue, transaction_pool,
+		inherent_data_providers,
+		other: (rpc_extensions_builder, import_setup, rpc_setup)
+	} = new_partial::<polkadot_test_runtime::RuntimeApi, PolkadotTestExecutor>(&mut config, true)?;
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	let finality_proof_provider =
+		grandpa::FinalityProofProvider::new_for_service(backend.clone(), client.clone());
+
+	let (network, network_status_sinks, system_rpc_tx, network_starter) =
+		service::build_network(service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+			finality_proof_request_builder: None,
+			finality_proof_provider: Some(finality_proof_provider),
+		})?;
+
+	let telemetry_connection_sinks = service::TelemetryConnectionSinks::default();
+
+	let rpc_handlers = service::spawn_tasks(service::SpawnTasksParams {
+		config,
+		backend: backend.clone(),
+		client: client.clone(),
+		keystore: keystore.clone(),
+		network: network.clone(),
+		rpc_extensions_builder: Box::new(rpc_extensions_builder),
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut task_manager,
+		on_demand: None,
+		remote_blockchain: None,
+		telemetry_connection_sinks: telemetry_connection_sinks.clone(),
+		network_status_sinks, system_rpc_tx,
+	})?;
+
+	let (block_import, link_half, babe_link) = import_setup;
+	let shared_voter_state = rpc_setup;
+
+	// validators publish their addresses, the other nodes only look validators up.
+	let authority_discovery_role = if role.is_authority() {
+		authority_discovery::Role::Authority(keystore.clone())
+	} else {
+		authority_discovery::Role::Sentry
+	};
+	let dht_event_stream = network.event_stream("authority-discovery")
+		.filter_map(|e| async move { match e {
+			Event::Dht(e) => Some(e),
+			_ => None,
+		}})
+		.boxed();
+	let (authority_discovery_worker, authority_discovery_service) = authority_discovery::new_worker_and_service(
+		client.clone(),
+		network.clone(),
+		Vec::new(),
+		dht_event_stream,
+		authority_discovery_role,
+		prometheus_registry.clone(),
+	);
+	if authority_discovery_enabled {
+		task_manager.spawn_handle().spawn("authority-discovery-worker", authority_discovery_worker);
+	}
+
+	// The test binaries can't act as validation workers.
+	let validation_pool = ValidationPoolConfig::new(ValidationExecutionMode::InProcess);
+	let (overseer, overseer_handler) = test_overseer(
+		&client,
+		&keystore,
+		task_manager.spawn_handle(),
+		network.clone(),
+		authority_discovery_service,
+		incoming_requests,
+		parachains_db,
+		collator.as_ref().map(|collator| collator.key.public()),
+		validation_pool.clone(),
+	)?;
+
+	{
+		let client = client.clone();
+		let overseer_handler = overseer_handler.clone();
+
+		task_manager.spawn_essential_handle().spawn_blocking("overseer", Box::pin(async move {
+			let forward = polkadot_overseer::forward_events(client, overseer_handler).fuse();
+			let overseer = overseer.run().fuse();
+
+			futures::pin_mut!(forward, overseer);
+
+			futures::select! {
+				_ = forward => (),
+				_ = overseer => (),
+			}
+		}));
+	}
+
+	if let Some(collator) = collator {
+		let mut overseer_handler = overseer_handler.clone();
+
+		task_manager.spawn_handle().spawn("collation-generation-initialization", async move {
+			let message = AllMessages::CollationGeneration(CollationGenerationMessage::Initialize(collator));
+			if let Err(e) = overseer_handler.send_msg(message).await {
+				log::warn!("Failed to initialize collation generation: {:?}", e);
+			}
+		});
+	}
+
+	if role.is_authority() {
+		let proposer = polkadot_node_core_proposer::ProposerFactory::new(
+			client.clone(),
+			transaction_pool,
+			overseer_handler.clone(),
+		);
+
+		let can_author_with =
+			consensus_common::CanAuthorWithNativeVersion::new(client.executor().clone());
+
+		let babe_config = babe::BabeParams {
+			keystore: keystore.clone(),
+			client: client.clone(),
+			select_chain,
+			block_import,
+			env: proposer,
+			sync_oracle: network.clone(),
+			inherent_data_providers: inherent_data_providers.clone(),
+			force_authoring,
+			babe_link,
+			can_author_with,
+		};
+
+		let babe = babe::start_babe(babe_config)?;
+		task_manager.spawn_essential_handle().spawn_blocking("babe", babe);
+	}
+
+	let grandpa_config = grandpa::Config {
+		gossip_duration: Duration::from_millis(1000),
+		justification_period: 512,
+		name: Some(name),
+		observer_enabled: false,
+		keystore: if role.is_authority() { Some(keystore as BareCryptoStorePtr) } else { None },
+		is_authority: role.is_network_authority(),
+	};
+
+	let grandpa_params = grandpa::GrandpaParams {
+		config: grandpa_config,
+		link: link_half,
+		network: network.clone(),
+		inherent_data_providers,
+		telemetry_on_connect: Some(telemetry_connection_sinks.on_connect_stream()),
+		voting_rule: grandpa::VotingRulesBuilder::default().build(),
+		prometheus_registry,
+		shared_voter_state,
+	};
+
+	task_manager.spawn_essential_handle().spawn_blocking(
+		"grandpa-voter",
+		grandpa::run_grandpa_voter(grandpa_params)?
+	);
+
+	network_starter.start_network();
+
+	Ok((task_manager, client, FullNodeHandles { validation_pool }, network, rpc_handlers, overseer_handler))
+}
+
+/// Build the overseer of a test node, running every subsystem for real. Candidates are validated
+/// on a pool with the given configuration.
+#[allow(clippy::too_many_arguments)]
+fn test_overseer(
+	client: &Arc<Client>,
+	keystore: &sc_keystore::KeyStorePtr,
+	spawner: service::SpawnTaskHandle,
+	network: Arc<NetworkService<Block, Hash>>,
+	authority_discovery: authority_discovery::Service,
+	incoming_requests: polkadot_network_bridge::IncomingRequests,
+	parachains_db: std::path::PathBuf,
+	collator_id: Option<polkadot_primitives::v1::CollatorId>,
+	validation_pool: ValidationPoolConfig,
+) -> Result<(Overseer<service::SpawnTaskHandle>, OverseerHandler), ServiceError> {
+	let leaves = {
+		let info = client.info();
+		let parent_hash = client.header(BlockId::Hash(info.best_hash))?
+			.map(|header| header.parent_hash)
+			.unwrap_or_default();
+
+		vec![BlockInfo { hash: info.best_hash, parent_hash, number: info.best_number }]
+	};
+
+	let all_subsystems = AllSubsystems {
+		candidate_validation: polkadot_node_core_candidate_validation::CandidateValidationSubsystem::with_pool_config(
+			spawner.clone(),
+			Default::default(),
+			validation_pool,
+		),
+		candidate_backing: polkadot_node_core_backing::CandidateBackingSubsystem::new(
+			spawner.clone(),
+			keystore.clone(),
+			Default::default(),
+		),
+		candidate_selection: polkadot_node_core_candidate_selection::CandidateSelectionSubsystem::new(
+			spawner.clone(),
+			(),
+			Default::default(),
+		),
+		statement_distribution: polkadot_statement_distribution::StatementDistribution::new(
+			keystore.clone(),
+			Default::default(),
+		),
+		availability_distribution: polkadot_availability_distribution::AvailabilityDistributionSubsystem::new(
+			keystore.clone(),
+		),
+		bitfield_signing: polkadot_node_core_bitfield_signing::BitfieldSigningSubsystem::new(
+			spawner.clone(),
+			keystore.clone(),
+			Default::default(),
+		),
+		bitfield_distribution: polkadot_availability_bitfield_distribution::BitfieldDistribution,
+		provisioner: polkadot_node_core_provisioner::ProvisioningSubsystem::new(
+			spawner.clone(),
+			(),
+			Default::default(),
+		),
+		pov_distribution: polkadot_pov_distribution::PoVDistribution,
+		runtime_api: polkadot_node_core_runtime_api::RuntimeApiSubsystem::new(
+			client.clone(),
+			Default::default(),
+		),
+		availability_store: AvailabilityStoreSubsystem::new_on_disk(
+			AvailabilityConfig {
+				cache_size: None,
+				path: parachains_db.join("availability"),
+			},
+			Default::default(),
+		)?,
+		network_bridge: polkadot_network_bridge::NetworkBridge::with_incoming_requests(
+			network,
+			authority_discovery,
+			incoming_requests,
+		),
+		chain_api: polkadot_node_core_chain_api::ChainApiSubsystem::new(client.clone(), Default::default()),
+		collation_generation: polkadot_node_collation_generation::CollationGenerationSubsystem::new(
+			Default::default(),
+		),
+		collator_protocol: match collator_id {
+			Some(collator_id) => polkadot_collator_protocol::CollatorProtocolSubsystem::new(Some(collator_id)),
+			None => polkadot_collator_protocol::CollatorProtocolSubsystem::new_validator_on_disk(
+				polkadot_collator_protocol::ReputationConfig {
+					cache_size: None,
+					path: parachains_db.join("collator-reputations"),
+				},
+			)?,
+		},
+		gossip_support: polkadot_gossip_support::GossipSupport::new(keystore.clone()),
+	};
+
+	Overseer::new(
+		leaves,
+		all_subsystems,
+		Default::default(),
+		OverflowPolicies::drop_oldest_gossip(),
+		None,
+		spawner,
+	).map_err(|e| ServiceError::Other(format!("Failed to create the overseer: {:?}", e)))
 }
 
 /// A wrapper for the test client that implements `ClientHandle`.
-pub struct TestClient(pub Arc<polkadot_service::FullClient<polkadot_test_runtime::RuntimeApi, PolkadotTestExecutor>>);
+pub struct TestClient(pub Arc<Client>);
 
 impl ClientHandle for TestClient {
 	fn execute_with<T: ExecuteWithClient>(&self, t: T) -> T::Output {
@@ -199,15 +479,33 @@ pub fn run_test_node(
 	key: Sr25519Keyring,
 	storage_update_func: impl Fn(),
 	boot_nodes: Vec<MultiaddrWithPeerId>,
-) -> PolkadotTestNode<
-	TaskManager,
-	impl AbstractClient<Block, TFullBackend<Block>>,
-> {
+) -> PolkadotTestNode<TaskManager, Client> {
 	let config = node_config(storage_update_func, task_executor, key, boot_nodes);
+	start_test_node(config, None)
+}
+
+/// Run a Polkadot test node collating for a parachain, see [`AdderCollator`] for one registered in the test chain
+/// spec. The node follows the chain without authoring blocks, and needs boot nodes to reach the validators.
+pub fn run_collator_node(
+	task_executor: TaskExecutor,
+	key: Sr25519Keyring,
+	storage_update_func: impl Fn(),
+	boot_nodes: Vec<MultiaddrWithPeerId>,
+	collator: CollationGenerationConfig,
+) -> PolkadotTestNode<TaskManager, Client> {
+	let mut config = node_config(storage_update_func, task_executor, key, boot_nodes);
+	config.role = Role::Full;
+	start_test_node(config, Some(collator))
+}
+
+fn start_test_node(
+	config: Configuration,
+	collator: Option<CollationGenerationConfig>,
+) -> PolkadotTestNode<TaskManager, Client> {
 	let multiaddr = config.network.listen_addresses[0].clone();
-	let authority_discovery_enabled = false;
-	let (task_manager, client, handles, network, rpc_handlers) =
-		polkadot_test_new_full(config, None, authority_discovery_enabled)
+	let authority_discovery_enabled = true;
+	let (task_manager, client, handles, network, rpc_handlers, overseer_handler) =
+		polkadot_test_new_full(config, collator, authority_discovery_enabled)
 			.expect("could not create Polkadot test service");
 
 	let peer_id = network.local_peer_id().clone();
@@ -219,6 +517,7 @@ pub fn run_test_node(
 		handles,
 		addr,
 		rpc_handlers,
+		overseer_handler,
 	}
 }
 
@@ -234,6 +533,8 @@ pub struct PolkadotTestNode<S, C> {
 	pub addr: MultiaddrWithPeerId,
 	/// RPCHandlers to make RPC queries.
 	pub rpc_handlers: RpcHandlers,
+	/// Handler of the overseer running the node's subsystems.
+	pub overseer_handler: OverseerHandler,
 }
 
 impl<S, C> PolkadotTestNode<S, C>
@@ -299,3 +600,112 @@ where
 		self.client.wait_for_blocks(count)
 	}
 }
+
+impl<S, C> PolkadotTestNode<S, C>
+where
+	C: BlockchainEvents<Block> + ProvideRuntimeApi<Block>,
+	C::Api: ParachainHost<Block>,
+{
+	/// The head of a parachain included as of the node's block `at`, if the parachain is registered.
+	pub fn para_head(&self, para_id: ParaId, at: Hash) -> Option<HeadData> {
+		included_para_head(&*self.client, para_id, at)
+	}
+
+	/// Wait for a block to be imported in the node in which the included head of the parachain satisfies `condition`,
+	/// and return that head. This function will not return if no such block is ever imported, thus you should
+	/// restrict the maximum amount of time of the test execution.
+	pub fn wait_for_para_head(
+		&self,
+		para_id: ParaId,
+		mut condition: impl FnMut(&HeadData) -> bool,
+	) -> impl Future<Output = HeadData> {
+		let client = self.client.clone();
+		let mut imports = client.import_notification_stream();
+
+		async move {
+			while let Some(notification) = imports.next().await {
+				if let Some(head) = included_para_head(&*client, para_id, notification.hash) {
+					if condition(&head) {
+						return head;
+					}
+				}
+			}
+
+			future::pending().await
+		}
+	}
+}
+
+fn included_para_head<C>(client: &C, para_id: ParaId, at: Hash) -> Option<HeadData>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ParachainHost<Block>,
+{
+	client.runtime_api()
+		// the included head, regardless of any candidate pending availability.
+		.persisted_validation_data(&BlockId::Hash(at), para_id, OccupiedCoreAssumption::TimedOut)
+		.ok()
+		.flatten()
+		.map(|data| data.parent_head)
+}
+
+/// Run a network of Polkadot test validators, one node per key. Every node uses the first one as boot node, so they
+/// all end up connected over the in-memory transport. The `storage_update_func` is applied to the genesis storage of
+/// every node.
+///
+/// The keys should be authorities of the test chain spec (see [`polkadot_local_testnet_genesis`]), otherwise the
+/// corresponding nodes will only follow the chain.
+pub fn run_validator_network(
+	task_executor: TaskExecutor,
+	keys: &[Sr25519Keyring],
+	storage_update_func: impl Fn(),
+) -> PolkadotTestNetwork<TaskManager, Client> {
+	let mut nodes = Vec::with_capacity(keys.len());
+	let mut boot_nodes = Vec::new();
+
+	for key in keys {
+		let node = run_test_node(task_executor.clone(), *key, &storage_update_func, boot_nodes.clone());
+		if boot_nodes.is_empty() {
+			boot_nodes.push(node.addr.clone());
+		}
+		nodes.push(node);
+	}
+
+	PolkadotTestNetwork { nodes }
+}
+
+/// A set of Polkadot test nodes connected to each other, as started by [`run_validator_network`].
+pub struct PolkadotTestNetwork<S, C> {
+	/// The nodes of the network, in the order of the keys they were started with.
+	pub nodes: Vec<PolkadotTestNode<S, C>>,
+}
+
+impl<C> PolkadotTestNetwork<TaskManager, C>
+where
+	C: BlockchainEvents<Block>,
+{
+	/// Wait for `count` blocks to be imported by every node of the network. This function will not return if any of
+	/// the nodes stops importing blocks, thus you should restrict the maximum amount of time of the test execution.
+	pub fn wait_for_blocks(&self, count: usize) -> impl Future<Output = ()> {
+		future::join_all(self.nodes.iter().map(|node| node.wait_for_blocks(count))).map(|_| ())
+	}
+
+	/// Resolves with the index of the first node whose essential tasks stopped, which means that node failed.
+	pub fn any_node_failed<'a>(&'a mut self) -> impl Future<Output = usize> + 'a {
+		let node_futures = self.nodes
+			.iter_mut()
+			.enumerate()
+			.map(|(index, node)| -> Pin<Box<dyn Future<Output = usize> + 'a>> {
+				Box::pin(node.task_manager.future().map(move |_| index))
+			});
+
+		future::select_all(node_futures).map(|(index, _, _)| index)
+	}
+
+	/// Shut down all the nodes of the network.
+	pub async fn clean_shutdown(mut self) {
+		for node in &mut self.nodes {
+			node.task_manager.clean_shutdown().await;
+		}
+	}
+}
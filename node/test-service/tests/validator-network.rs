@@ -0,0 +1,114 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use futures::{pin_mut, select, FutureExt};
+use futures_timer::Delay;
+use polkadot_primitives::v1::HeadData;
+use polkadot_test_service::*;
+use service::TaskExecutor;
+use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie, Dave};
+use sp_runtime::codec::Decode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use test_parachain_adder::HeadData as AdderHeadData;
+
+/// How long the network may take to make progress before the test fails.
+const TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[substrate_test_utils::test]
+async fn validator_network_builds_blocks(task_executor: TaskExecutor) {
+	let mut network = run_validator_network(task_executor, &[Alice, Bob, Charlie], || {});
+	assert_eq!(network.nodes.len(), 3);
+
+	let timed_out = {
+		let blocks = network.wait_for_blocks(3).fuse();
+		let failure = network.any_node_failed().fuse();
+		let timeout = Delay::new(TIMEOUT).fuse();
+
+		pin_mut!(blocks, failure, timeout);
+
+		select! {
+			_ = blocks => false,
+			index = failure => panic!("service of node {} failed", index),
+			_ = timeout => true,
+		}
+	};
+
+	if timed_out {
+		let best_numbers: Vec<_> = network.nodes.iter()
+			.map(|node| node.client.chain_info().best_number)
+			.collect();
+		panic!("timed out waiting for blocks, the best blocks of the nodes are {:?}", best_numbers);
+	}
+
+	network.clean_shutdown().await;
+}
+
+#[substrate_test_utils::test]
+async fn validator_network_includes_adder_collations(task_executor: TaskExecutor) {
+	let mut network = run_validator_network(task_executor.clone(), &[Alice, Bob, Charlie], || {});
+	let collator = AdderCollator::new();
+	let mut collator_node = run_collator_node(
+		task_executor,
+		Dave,
+		|| {},
+		vec![network.nodes[0].addr.clone()],
+		collator.collation_generation_config(),
+	);
+
+	let adder_head_number = |head: &HeadData| AdderHeadData::decode(&mut &head.0[..])
+		.expect("the adder parachain only has adder heads")
+		.number;
+
+	{
+		// the genesis head is included from the start, wait for the head to advance twice.
+		let mut first = None;
+		let last_seen = Arc::new(Mutex::new(None));
+		let head = network.nodes[0].wait_for_para_head(ADDER_PARA_ID.into(), {
+			let last_seen = last_seen.clone();
+			move |head| {
+				let number = adder_head_number(head);
+				*last_seen.lock().unwrap() = Some(number);
+				match first {
+					None if number > 0 => {
+						first = Some(number);
+						false
+					},
+					Some(first) => number > first,
+					None => false,
+				}
+			}
+		}).fuse();
+		let failure = network.any_node_failed().fuse();
+		let collator_failure = collator_node.task_manager.future().fuse();
+		let timeout = Delay::new(TIMEOUT).fuse();
+
+		pin_mut!(head, failure, collator_failure, timeout);
+
+		select! {
+			head = head => assert!(adder_head_number(&head) >= 2),
+			index = failure => panic!("service of node {} failed", index),
+			_ = collator_failure => panic!("service of the collator failed"),
+			_ = timeout => panic!(
+				"timed out waiting for the adder head to advance, the last head seen is {:?}",
+				last_seen.lock().unwrap(),
+			),
+		}
+	}
+
+	collator_node.task_manager.clean_shutdown().await;
+	network.clean_shutdown().await;
+}
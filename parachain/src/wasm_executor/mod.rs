@@ -28,8 +28,13 @@ use sp_externalities::Extensions;
 use sp_wasm_interface::HostFunctions as _;
 
 #[cfg(not(any(target_os = "android", target_os = "unknown")))]
-pub use validation_host::{run_worker, ValidationPool, EXECUTION_TIMEOUT_SEC, ValidationExecutionMode};
+pub use validation_host::{
+	run_worker, ValidationPool, ValidationPoolConfig, ValidationPoolObserver, WorkerStats,
+	EXECUTION_TIMEOUT_SEC, ValidationExecutionMode, DEFAULT_MAX_WORKERS, DEFAULT_MAX_JOBS_PER_WORKER,
+	DEFAULT_HEALTH_CHECK_INTERVAL,
+};
 
+mod sandbox;
 mod validation_host;
 
 // maximum memory in bytes
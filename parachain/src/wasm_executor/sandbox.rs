@@ -0,0 +1,195 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Locking down validation worker processes before they run untrusted code.
+//!
+//! On Linux, a worker drops the resources it has no use for and installs a seccomp filter on all
+//! of its threads, which only lets through the system calls needed to wait on the shared memory,
+//! manage memory and write logs. Every other system call, such as opening a file or a socket or
+//! starting a process, kills the worker with `SIGSYS`. This leaves the worker without any view of
+//! the filesystem or the network.
+//!
+//! Since the filter only lets through system calls that don't create threads, processes or file
+//! descriptors, everything the worker needs has to be set up before it is locked down.
+
+#![cfg(not(any(target_os = "android", target_os = "unknown")))]
+
+use std::process::ExitStatus;
+
+/// Whether the worker is locked down on this platform.
+pub const SUPPORTED: bool = cfg!(all(
+	target_os = "linux",
+	any(target_arch = "x86_64", target_arch = "aarch64"),
+));
+
+/// Lock down the current process, for good. The process must not need anything but the resources
+/// it has already acquired.
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn lock_down() -> Result<(), String> {
+	linux::set_rlimits()?;
+	linux::install_seccomp_filter()
+}
+
+/// Lock down the current process, for good. Not supported on this platform, so this only warns.
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+pub fn lock_down() -> Result<(), String> {
+	log::warn!("Validation workers are not sandboxed on this platform");
+	Ok(())
+}
+
+/// Whether a worker process exited because it made a system call its sandbox denies.
+#[cfg(unix)]
+pub fn denied_syscall(status: &ExitStatus) -> bool {
+	use std::os::unix::process::ExitStatusExt;
+
+	SUPPORTED && status.signal() == Some(libc::SIGSYS)
+}
+
+/// Whether a worker process exited because it made a system call its sandbox denies.
+#[cfg(not(unix))]
+pub fn denied_syscall(_: &ExitStatus) -> bool {
+	false
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod linux {
+	use std::io;
+
+	// Constants from `linux/seccomp.h`, `linux/filter.h` and `linux/audit.h`, not all of which
+	// `libc` provides.
+	const SECCOMP_SET_MODE_FILTER: libc::c_ulong = 1;
+	const SECCOMP_FILTER_FLAG_TSYNC: libc::c_ulong = 1;
+	const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+	const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+	const BPF_LD_W_ABS: u16 = 0x20;
+	const BPF_JMP_JEQ_K: u16 = 0x15;
+	const BPF_RET_K: u16 = 0x06;
+
+	// Offsets in `struct seccomp_data`.
+	const SECCOMP_DATA_NR: u32 = 0;
+	const SECCOMP_DATA_ARCH: u32 = 4;
+
+	#[cfg(target_arch = "x86_64")]
+	const AUDIT_ARCH: u32 = 0xc000_003e;
+	#[cfg(target_arch = "aarch64")]
+	const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+	/// The system calls a worker makes once it is ready.
+	const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+		// shared memory events and thread synchronization.
+		libc::SYS_futex,
+		libc::SYS_sched_yield,
+		libc::SYS_nanosleep,
+		libc::SYS_clock_nanosleep,
+		libc::SYS_clock_gettime,
+		libc::SYS_gettimeofday,
+		// memory management, including the memory of wasm instances.
+		libc::SYS_brk,
+		libc::SYS_mmap,
+		libc::SYS_munmap,
+		libc::SYS_mremap,
+		libc::SYS_mprotect,
+		libc::SYS_madvise,
+		// reading the pipe to the host, and logging.
+		libc::SYS_read,
+		libc::SYS_write,
+		libc::SYS_writev,
+		libc::SYS_close,
+		// random seeds of hash maps.
+		libc::SYS_getrandom,
+		libc::SYS_getpid,
+		libc::SYS_gettid,
+		// signals and exiting.
+		libc::SYS_rt_sigreturn,
+		libc::SYS_rt_sigprocmask,
+		libc::SYS_sigaltstack,
+		libc::SYS_restart_syscall,
+		libc::SYS_exit,
+		libc::SYS_exit_group,
+	];
+
+	/// Forbid the process from opening files, writing to them or dumping its core.
+	pub fn set_rlimits() -> Result<(), String> {
+		let limits = [
+			(libc::RLIMIT_NOFILE, "RLIMIT_NOFILE"),
+			(libc::RLIMIT_FSIZE, "RLIMIT_FSIZE"),
+			(libc::RLIMIT_NPROC, "RLIMIT_NPROC"),
+			(libc::RLIMIT_CORE, "RLIMIT_CORE"),
+		];
+
+		for &(resource, name) in limits.iter() {
+			let limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+			if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+				return Err(format!("Failed to set {}: {}", name, io::Error::last_os_error()));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Only let the system calls in `ALLOWED_SYSCALLS` through, on every thread of the process.
+	pub fn install_seccomp_filter() -> Result<(), String> {
+		let mut filter = Vec::with_capacity(ALLOWED_SYSCALLS.len() * 2 + 5);
+
+		// system call numbers depend on the architecture.
+		filter.push(statement(BPF_LD_W_ABS, SECCOMP_DATA_ARCH));
+		filter.push(jump(BPF_JMP_JEQ_K, AUDIT_ARCH, 1, 0));
+		filter.push(statement(BPF_RET_K, SECCOMP_RET_KILL_PROCESS));
+
+		filter.push(statement(BPF_LD_W_ABS, SECCOMP_DATA_NR));
+		for &syscall in ALLOWED_SYSCALLS {
+			filter.push(jump(BPF_JMP_JEQ_K, syscall as u32, 0, 1));
+			filter.push(statement(BPF_RET_K, SECCOMP_RET_ALLOW));
+		}
+		filter.push(statement(BPF_RET_K, SECCOMP_RET_KILL_PROCESS));
+
+		let program = libc::sock_fprog {
+			len: filter.len() as libc::c_ushort,
+			filter: filter.as_mut_ptr(),
+		};
+
+		// required to install a filter without `CAP_SYS_ADMIN`, and keeps the process from
+		// gaining privileges through `execve` should the filter ever let it through.
+		if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+			return Err(format!("Failed to set no_new_privs: {}", io::Error::last_os_error()));
+		}
+
+		let res = unsafe {
+			libc::syscall(
+				libc::SYS_seccomp,
+				SECCOMP_SET_MODE_FILTER,
+				SECCOMP_FILTER_FLAG_TSYNC,
+				&program as *const libc::sock_fprog,
+			)
+		};
+
+		match res {
+			0 => Ok(()),
+			// the id of a thread the filter couldn't be synchronized to.
+			thread if thread > 0 => Err(format!("Failed to install the seccomp filter on thread {}", thread)),
+			_ => Err(format!("Failed to install the seccomp filter: {}", io::Error::last_os_error())),
+		}
+	}
+
+	fn statement(code: u16, k: u32) -> libc::sock_filter {
+		libc::sock_filter { code, jt: 0, jf: 0, k }
+	}
+
+	fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+		libc::sock_filter { code, jt, jf, k }
+	}
+}
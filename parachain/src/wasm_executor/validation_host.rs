@@ -16,16 +16,19 @@
 
 #![cfg(not(any(target_os = "android", target_os = "unknown")))]
 
-use std::{process, env, sync::Arc, sync::atomic, path::PathBuf};
+use std::{
+	collections::VecDeque, process, env, path::PathBuf,
+	sync::{atomic, Arc, Weak}, time::{Duration, Instant},
+};
 use codec::{Decode, Encode};
 use crate::primitives::{ValidationParams, ValidationResult};
 use super::{
-	validate_candidate_internal, ValidationError, InvalidCandidate, InternalError,
+	sandbox, validate_candidate_internal, ValidationError, InvalidCandidate, InternalError,
 	MAX_CODE_MEM, MAX_RUNTIME_MEM, MAX_VALIDATION_RESULT_HEADER_MEM,
 };
 use shared_memory::{SharedMem, SharedMemConf, EventState, WriteLockable, EventWait, EventSet};
-use parking_lot::Mutex;
-use log::{debug, trace};
+use parking_lot::{Condvar, Mutex};
+use log::{debug, trace, warn};
 use futures::executor::ThreadPool;
 use sp_core::traits::SpawnNamed;
 
@@ -40,6 +43,9 @@ pub const EXECUTION_TIMEOUT_SEC: u64 =  30;
 #[cfg(not(debug_assertions))]
 pub const EXECUTION_TIMEOUT_SEC: u64 =  5;
 
+/// How often to check whether the worker is still alive while waiting for its result.
+const WORKER_LIVENESS_CHECK_MS: usize = 100;
+
 enum Event {
 	CandidateReady = 0,
 	ResultReady = 1,
@@ -71,10 +77,11 @@ pub enum ValidationExecutionMode {
 	/// The validation worker is ran in a thread inside the same process.
 	InProcess,
 	/// The validation worker is ran using the process' executable and the subcommand `validation-worker` is passed
-	/// following by the address of the shared memory.
+	/// following by the address of the shared memory. The worker process is sandboxed on Linux.
 	ExternalProcessSelfHost,
 	/// The validation worker is ran using the command provided and the argument provided. The address of the shared
-	/// memory is added at the end of the arguments.
+	/// memory is added at the end of the arguments. The worker process is sandboxed on Linux, as long as it runs the
+	/// worker with `run_worker`.
 	ExternalProcessCustomHost {
 		/// Path to the validation worker. The file must exists and be executable.
 		binary: PathBuf,
@@ -84,22 +91,163 @@ pub enum ValidationExecutionMode {
 	},
 }
 
-/// A pool of hosts.
+/// The number of workers of a pool is, by default, the number of CPUs up to this.
+pub const DEFAULT_MAX_WORKERS: usize = 8;
+
+/// By default, a worker is restarted once it validated this many candidates.
+pub const DEFAULT_MAX_JOBS_PER_WORKER: u64 = 1000;
+
+/// By default, idle workers are checked this often.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Configuration of a `ValidationPool`.
+#[derive(Debug, Clone)]
+pub struct ValidationPoolConfig {
+	/// How the workers are run.
+	pub execution_mode: ValidationExecutionMode,
+	/// The number of workers. Defaults to the number of CPUs, up to `DEFAULT_MAX_WORKERS`.
+	pub num_workers: Option<usize>,
+	/// Restart a worker once it validated this many candidates, if any. Workers running in process
+	/// are never restarted.
+	pub max_jobs_per_worker: Option<u64>,
+	/// How often idle workers are checked, and restarted if they died. If `None`, dead workers are
+	/// only restarted when they are next used.
+	pub health_check_interval: Option<Duration>,
+}
+
+impl ValidationPoolConfig {
+	/// The default configuration of a pool running its workers with the given execution mode.
+	pub fn new(execution_mode: ValidationExecutionMode) -> Self {
+		ValidationPoolConfig {
+			execution_mode,
+			num_workers: None,
+			max_jobs_per_worker: Some(DEFAULT_MAX_JOBS_PER_WORKER),
+			health_check_interval: Some(DEFAULT_HEALTH_CHECK_INTERVAL),
+		}
+	}
+
+	fn num_workers(&self) -> usize {
+		self.num_workers
+			.unwrap_or_else(|| num_cpus::get().min(DEFAULT_MAX_WORKERS))
+			.max(1)
+	}
+}
+
+/// Observes the workers of a `ValidationPool`, e.g. to export metrics about them. Workers are
+/// identified by their index in the pool.
+pub trait ValidationPoolObserver: Send + Sync {
+	/// A candidate waited `wait` for `worker` to be free.
+	fn on_queued(&self, worker: usize, wait: Duration);
+
+	/// `worker` took `duration` to validate a candidate, including starting it if needed.
+	fn on_executed(&self, worker: usize, duration: Duration);
+
+	/// `worker` died, while validating a candidate or idle.
+	fn on_crashed(&self, worker: usize);
+}
+
+/// Statistics of a worker of a `ValidationPool`, over all the processes it ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+	/// The number of candidates the worker was given.
+	pub jobs: u64,
+	/// The number of times the worker died.
+	pub crashes: u64,
+	/// The number of times the worker was started again, after dying, timing out or reaching
+	/// `max_jobs_per_worker`.
+	pub restarts: u64,
+}
+
+/// A pool of validation workers.
+///
+/// Candidates are given to the worker that has been free for the longest, or wait for one to be.
+/// Unless disabled, a background thread starts the workers ahead of time and keeps the idle ones
+/// running.
 #[derive(Clone)]
 pub struct ValidationPool {
-	hosts: Arc<Vec<Mutex<ValidationHost>>>,
-	execution_mode: ValidationExecutionMode,
+	inner: Arc<PoolInner>,
 }
 
-const DEFAULT_NUM_HOSTS: usize = 8;
+struct PoolInner {
+	config: ValidationPoolConfig,
+	hosts: Vec<Mutex<ValidationHost>>,
+	stats: Vec<Mutex<WorkerStats>>,
+	// the indices of the free workers, the longest free first.
+	idle: Mutex<VecDeque<usize>>,
+	worker_freed: Condvar,
+	// shared with the maintenance thread, which must not keep the pool alive while it waits.
+	restart: Arc<RestartSignal>,
+	observer: Option<Arc<dyn ValidationPoolObserver>>,
+}
+
+/// Wakes the maintenance thread before the next health check.
+#[derive(Default)]
+struct RestartSignal {
+	// whether a worker needs to be restarted, or the pool is gone.
+	pending: Mutex<bool>,
+	requested: Condvar,
+}
+
+impl RestartSignal {
+	fn notify(&self) {
+		*self.pending.lock() = true;
+		self.requested.notify_one();
+	}
+
+	/// Wait until notified, or for `timeout` at most.
+	fn wait(&self, timeout: Duration) {
+		let mut pending = self.pending.lock();
+		if !*pending {
+			self.requested.wait_for(&mut pending, timeout);
+		}
+		*pending = false;
+	}
+}
 
 impl ValidationPool {
 	/// Creates a validation pool with the default configuration.
 	pub fn new(execution_mode: ValidationExecutionMode) -> ValidationPool {
-		ValidationPool {
-			hosts: Arc::new((0..DEFAULT_NUM_HOSTS).map(|_| Default::default()).collect()),
-			execution_mode,
+		Self::with_config(ValidationPoolConfig::new(execution_mode), None)
+	}
+
+	/// Creates a validation pool with the given configuration, reporting on its workers to
+	/// `observer`, if any.
+	pub fn with_config(
+		config: ValidationPoolConfig,
+		observer: Option<Arc<dyn ValidationPoolObserver>>,
+	) -> ValidationPool {
+		let num_workers = config.num_workers();
+		let inner = Arc::new(PoolInner {
+			config,
+			hosts: (0..num_workers).map(|_| Default::default()).collect(),
+			stats: (0..num_workers).map(|_| Default::default()).collect(),
+			idle: Mutex::new((0..num_workers).collect()),
+			worker_freed: Condvar::new(),
+			restart: Default::default(),
+			observer,
+		});
+
+		let weak = Arc::downgrade(&inner);
+		let spawned = std::thread::Builder::new()
+			.name("validation-pool".into())
+			.spawn(move || PoolInner::run_maintenance(weak));
+
+		if let Err(e) = spawned {
+			// workers are then started when first used.
+			warn!("Failed to spawn the validation pool maintenance thread: {}", e);
 		}
+
+		ValidationPool { inner }
+	}
+
+	/// The number of workers of the pool.
+	pub fn num_workers(&self) -> usize {
+		self.inner.hosts.len()
+	}
+
+	/// The statistics of every worker of the pool, by index.
+	pub fn stats(&self) -> Vec<WorkerStats> {
+		self.inner.stats.iter().map(|stats| stats.lock().clone()).collect()
 	}
 
 	/// Validate a candidate under the given validation code using the next
@@ -111,20 +259,158 @@ impl ValidationPool {
 		validation_code: &[u8],
 		params: ValidationParams,
 	) -> Result<ValidationResult, ValidationError> {
-		for host in self.hosts.iter() {
-			if let Some(mut host) = host.try_lock() {
-				return host.validate_candidate(validation_code, params, self.execution_mode.clone());
+		let inner = &*self.inner;
+
+		let queued = Instant::now();
+		let lease = inner.acquire();
+		let index = lease.index;
+		inner.observe(|observer| observer.on_queued(index, queued.elapsed()));
+
+		let mut host = inner.hosts[index].lock();
+		let crashes = host.crashes;
+		let started = Instant::now();
+		let result = host.validate_candidate(validation_code, params, inner.config.execution_mode.clone());
+		inner.observe(|observer| observer.on_executed(index, started.elapsed()));
+
+		if let Some(max_jobs) = inner.config.max_jobs_per_worker {
+			if host.jobs >= max_jobs {
+				debug!("Worker {} validated {} candidates, restarting it", index, host.jobs);
+				host.stop_worker();
+			}
+		}
+
+		inner.record(index, &host, crashes);
+		if host.needs_start() {
+			inner.request_restart();
+		}
+
+		result
+	}
+}
+
+impl Drop for PoolInner {
+	fn drop(&mut self) {
+		// let the maintenance thread know right away.
+		self.restart.notify();
+	}
+}
+
+/// A worker taken off the idle queue, put back when dropped.
+struct Lease<'a> {
+	pool: &'a PoolInner,
+	index: usize,
+}
+
+impl<'a> Drop for Lease<'a> {
+	fn drop(&mut self) {
+		self.pool.idle.lock().push_back(self.index);
+		self.pool.worker_freed.notify_one();
+	}
+}
+
+impl PoolInner {
+	/// Wait for the worker free for the longest.
+	fn acquire(&self) -> Lease<'_> {
+		let mut idle = self.idle.lock();
+		loop {
+			if let Some(index) = idle.pop_front() {
+				return Lease { pool: self, index };
 			}
+
+			self.worker_freed.wait(&mut idle);
 		}
+	}
 
-		// all workers are busy, just wait for the first one
-		self.hosts[0].lock().validate_candidate(validation_code, params, self.execution_mode.clone())
+	/// Take the given worker, if it is free.
+	fn try_acquire(&self, index: usize) -> Option<Lease<'_>> {
+		let mut idle = self.idle.lock();
+		let position = idle.iter().position(|&i| i == index)?;
+		idle.remove(position);
+		Some(Lease { pool: self, index })
+	}
+
+	fn observe(&self, f: impl FnOnce(&dyn ValidationPoolObserver)) {
+		if let Some(observer) = &self.observer {
+			f(&**observer)
+		}
+	}
+
+	/// Update the statistics of a worker, and report the crashes it had since it had crashed
+	/// `crashes_before` times.
+	fn record(&self, index: usize, host: &ValidationHost, crashes_before: u64) {
+		{
+			let mut stats = self.stats[index].lock();
+			stats.jobs = host.total_jobs;
+			stats.crashes = host.crashes;
+			stats.restarts = host.starts.saturating_sub(1);
+		}
+
+		for _ in crashes_before..host.crashes {
+			self.observe(|observer| observer.on_crashed(index));
+		}
+	}
+
+	fn request_restart(&self) {
+		self.restart.notify();
+	}
+
+	/// Start the workers, then check the idle ones every `health_check_interval`, or as soon as
+	/// one needs restarting, until the pool is dropped.
+	fn run_maintenance(pool: Weak<PoolInner>) {
+		loop {
+			let pool = match pool.upgrade() {
+				Some(pool) => pool,
+				None => return,
+			};
+
+			pool.maintain();
+
+			let interval = match pool.config.health_check_interval {
+				Some(interval) => interval,
+				None => return,
+			};
+
+			let restart = pool.restart.clone();
+			drop(pool);
+			restart.wait(interval);
+		}
+	}
+
+	/// Start every idle worker that isn't running, or died since last used.
+	fn maintain(&self) {
+		for index in 0..self.hosts.len() {
+			// busy workers are checked by the candidates they validate.
+			let _lease = match self.try_acquire(index) {
+				Some(lease) => lease,
+				None => continue,
+			};
+
+			let mut host = self.hosts[index].lock();
+			let crashes = host.crashes;
+			host.check_health();
+
+			if let Err(e) = host.start_worker(self.config.execution_mode.clone()) {
+				warn!("Failed to start validation worker {}: {:?}", index, e);
+			}
+
+			self.record(index, &host, crashes);
+		}
 	}
 }
 
 /// Validation worker process entry point. Runs a loop waiting for candidates to validate
 /// and sends back results via shared memory.
+///
+/// The process is sandboxed before it validates any candidate, see the `sandbox` module.
 pub fn run_worker(mem_id: &str) -> Result<(), String> {
+	run_worker_with(mem_id, true, validate_candidate_internal)
+}
+
+/// Run a worker loop validating candidates with `validate`, locking the process down first if
+/// `sandboxed`. Workers running on a thread of the host can't be.
+fn run_worker_with<F>(mem_id: &str, sandboxed: bool, validate: F) -> Result<(), String>
+	where F: Fn(&[u8], &[u8], TaskExecutor) -> Result<ValidationResult, ValidationError>
+{
 	let mut memory = match SharedMem::open(mem_id) {
 		Ok(memory) => memory,
 		Err(e) => {
@@ -146,6 +432,12 @@ pub fn run_worker(mem_id: &str) -> Result<(), String> {
 		exit.store(true, atomic::Ordering::Relaxed);
 	});
 
+	// the threads above are all the worker gets, the sandbox doesn't let it start any more.
+	if sandboxed {
+		sandbox::lock_down()
+			.map_err(|e| format!("{} Error sandboxing the worker: {}", process::id(), e))?;
+	}
+
 	memory.set(Event::WorkerReady as usize, EventState::Signaled)
 		.map_err(|e| format!("{} Error setting shared event: {:?}", process::id(), e))?;
 
@@ -182,7 +474,7 @@ pub fn run_worker(mem_id: &str) -> Result<(), String> {
 				let (call_data, _) = rest.split_at_mut(MAX_RUNTIME_MEM);
 				let (call_data, _) = call_data.split_at_mut(header.params_size as usize);
 
-				let result = validate_candidate_internal(code, call_data, task_executor.clone());
+				let result = validate(code, call_data, task_executor.clone());
 				debug!("{} Candidate validated: {:?}", process::id(), result);
 
 				match result {
@@ -230,6 +522,12 @@ struct ValidationHost {
 	worker_thread: Option<std::thread::JoinHandle<Result<(), String>>>,
 	memory: Option<SharedMem>,
 	id: u32,
+	// candidates given to the current worker, and to all of them.
+	jobs: u64,
+	total_jobs: u64,
+	// the number of workers started, and of those that died.
+	starts: u64,
+	crashes: u64,
 }
 
 impl Drop for ValidationHost {
@@ -253,6 +551,31 @@ impl ValidationHost {
 		Ok(mem_config.create()?)
 	}
 
+	/// Whether there is no worker running, as far as the host knows.
+	fn needs_start(&self) -> bool {
+		self.worker.is_none() && self.worker_thread.is_none()
+	}
+
+	/// Forget the worker if it died. Workers running in process can't be checked.
+	fn check_health(&mut self) {
+		let exit_status = self.worker.as_mut().and_then(|worker| worker.try_wait().ok().flatten());
+		if let Some(status) = exit_status {
+			warn!("Validation worker {} died while idle: {}", self.id, status);
+			self.worker = None;
+			self.memory = None;
+			self.crashes += 1;
+		}
+	}
+
+	/// Kill the worker, if it runs in its own process.
+	fn stop_worker(&mut self) {
+		if let Some(mut worker) = self.worker.take() {
+			worker.kill().ok();
+			worker.wait().ok();
+			self.memory = None;
+		}
+	}
+
 	fn start_worker(&mut self, execution_mode: ValidationExecutionMode) -> Result<(), InternalError> {
 		if let Some(ref mut worker) = self.worker {
 			// Check if still alive
@@ -282,7 +605,9 @@ impl ValidationHost {
 		match execution_mode {
 			ValidationExecutionMode::InProcess => {
 				let mem_id = memory.get_os_path().to_string();
-				self.worker_thread = Some(std::thread::spawn(move || run_worker(mem_id.as_str())));
+				self.worker_thread = Some(std::thread::spawn(move || {
+					run_worker_with(mem_id.as_str(), false, validate_candidate_internal)
+				}));
 			},
 			ValidationExecutionMode::ExternalProcessSelfHost => run_worker_process(
 				env::current_exe()?,
@@ -294,11 +619,19 @@ impl ValidationHost {
 			)?,
 		};
 
-		memory.wait(
+		let ready = memory.wait(
 			Event::WorkerReady as usize,
 			shared_memory::Timeout::Sec(EXECUTION_TIMEOUT_SEC as usize),
-		)?;
+		);
+		if let Err(e) = ready {
+			// don't leave a worker behind that would be taken for ready.
+			self.stop_worker();
+			return Err(e.into());
+		}
+
 		self.memory = Some(memory);
+		self.jobs = 0;
+		self.starts += 1;
 		Ok(())
 	}
 
@@ -315,6 +648,7 @@ impl ValidationHost {
 			return Err(ValidationError::InvalidCandidate(InvalidCandidate::CodeTooLarge(validation_code.len())));
 		}
 		// First, check if need to spawn the child process
+		self.check_health();
 		self.start_worker(execution_mode)?;
 		let memory = self.memory.as_mut()
 			.expect("memory is always `Some` after `start_worker` completes successfully");
@@ -344,17 +678,38 @@ impl ValidationHost {
 		debug!("{} Signaling candidate", self.id);
 		memory.set(Event::CandidateReady as usize, EventState::Signaled)
 			.map_err(|e| ValidationError::Internal(e.into()))?;
+		self.jobs += 1;
+		self.total_jobs += 1;
 
 		debug!("{} Waiting for results", self.id);
-		match memory.wait(Event::ResultReady as usize, shared_memory::Timeout::Sec(EXECUTION_TIMEOUT_SEC as usize)) {
-			Err(e) => {
-				debug!("Worker timeout: {:?}", e);
-				if let Some(mut worker) = self.worker.take() {
-					worker.kill().ok();
+		let deadline = Instant::now() + Duration::from_secs(EXECUTION_TIMEOUT_SEC);
+		loop {
+			match memory.wait(Event::ResultReady as usize, shared_memory::Timeout::Milli(WORKER_LIVENESS_CHECK_MS)) {
+				Ok(()) => break,
+				Err(e) if Instant::now() >= deadline => {
+					debug!("Worker timeout: {:?}", e);
+					if let Some(mut worker) = self.worker.take() {
+						worker.kill().ok();
+					}
+					return Err(ValidationError::InvalidCandidate(InvalidCandidate::Timeout));
 				}
-				return Err(ValidationError::InvalidCandidate(InvalidCandidate::Timeout));
+				Err(_) => {}
+			}
+
+			let exit_status = self.worker.as_mut().and_then(|worker| worker.try_wait().ok().flatten());
+			if let Some(status) = exit_status {
+				debug!("{} Worker exited while validating: {}", self.id, status);
+				self.worker = None;
+				self.crashes += 1;
+
+				let reason = if sandbox::denied_syscall(&status) {
+					"validation worker made a system call its sandbox denies".to_string()
+				} else {
+					format!("validation worker exited while validating: {}", status)
+				};
+
+				return Err(ValidationError::InvalidCandidate(InvalidCandidate::ExternalWasmExecutor(reason)));
 			}
-			Ok(()) => {}
 		}
 
 		{
@@ -385,3 +740,210 @@ impl ValidationHost {
 		}
 	}
 }
+
+#[cfg(all(test, target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tests {
+	use super::*;
+	use crate::primitives::{BlockData, HeadData};
+
+	const SANDBOXED_WORKER: &str = "wasm_executor::validation_host::tests::sandboxed_worker";
+
+	fn params() -> ValidationParams {
+		ValidationParams {
+			parent_head: HeadData(vec![0]),
+			block_data: BlockData(vec![1, 2, 3]),
+			relay_chain_height: 1,
+			hrmp_mqc_heads: Vec::new(),
+		}
+	}
+
+	// This is not an actual test, but the entry point of the workers of the tests below, like
+	// the `validation_worker` test of the test parachains. The operation it attempts while
+	// validating, as if the candidate escaped the wasm executor, is given as the last argument.
+	#[test]
+	fn sandboxed_worker() {
+		let id = match std::env::args().find(|a| a.starts_with("/shmem_rs_")) {
+			Some(id) => id,
+			None => return,
+		};
+
+		let operation = std::env::args().find(|a| a.starts_with("attempt-")).unwrap_or_default();
+		run_worker_with(&id, true, |_, _, _| {
+			match operation.as_str() {
+				"attempt-open" => {
+					let _ = std::fs::File::open("/etc/hostname");
+				}
+				"attempt-connect" => {
+					let _ = std::net::TcpStream::connect(("127.0.0.1", 30333));
+				}
+				"attempt-spawn" => {
+					let _ = process::Command::new("true").spawn();
+				}
+				_ => {}
+			}
+
+			Ok(ValidationResult {
+				head_data: HeadData(vec![1]),
+				new_validation_code: None,
+				upward_messages: Vec::new(),
+				processed_downward_messages: 0,
+			})
+		}).unwrap()
+	}
+
+	fn execution_mode_attempting(operation: &str) -> ValidationExecutionMode {
+		ValidationExecutionMode::ExternalProcessCustomHost {
+			binary: env::current_exe().unwrap(),
+			args: vec![
+				"--nocapture".to_string(),
+				"--exact".to_string(),
+				SANDBOXED_WORKER.to_string(),
+				operation.to_string(),
+			],
+		}
+	}
+
+	fn validate_attempting(operation: &str) -> Result<ValidationResult, ValidationError> {
+		let pool = ValidationPool::new(execution_mode_attempting(operation));
+		pool.validate_candidate(&[], params())
+	}
+
+	#[derive(Default)]
+	struct CountingObserver {
+		queued: atomic::AtomicUsize,
+		executed: atomic::AtomicUsize,
+		crashed: atomic::AtomicUsize,
+	}
+
+	impl ValidationPoolObserver for CountingObserver {
+		fn on_queued(&self, worker: usize, _: Duration) {
+			assert_eq!(worker, 0);
+			self.queued.fetch_add(1, atomic::Ordering::SeqCst);
+		}
+
+		fn on_executed(&self, worker: usize, _: Duration) {
+			assert_eq!(worker, 0);
+			self.executed.fetch_add(1, atomic::Ordering::SeqCst);
+		}
+
+		fn on_crashed(&self, worker: usize) {
+			assert_eq!(worker, 0);
+			self.crashed.fetch_add(1, atomic::Ordering::SeqCst);
+		}
+	}
+
+	fn assert_denied(operation: &str) {
+		match validate_attempting(operation) {
+			Err(ValidationError::InvalidCandidate(InvalidCandidate::ExternalWasmExecutor(reason))) => {
+				assert!(reason.contains("sandbox"), "unexpected reason: {}", reason);
+			}
+			other => panic!("candidate attempting {} wasn't rejected: {:?}", operation, other),
+		}
+	}
+
+	#[test]
+	fn sandboxed_worker_validates_candidates() {
+		let result = validate_attempting("attempt-nothing").unwrap();
+		assert_eq!(result.head_data, HeadData(vec![1]));
+	}
+
+	#[test]
+	fn candidate_opening_a_file_is_rejected() {
+		assert_denied("attempt-open");
+	}
+
+	#[test]
+	fn candidate_connecting_to_the_network_is_rejected() {
+		assert_denied("attempt-connect");
+	}
+
+	#[test]
+	fn candidate_spawning_a_process_is_rejected() {
+		assert_denied("attempt-spawn");
+	}
+
+	#[test]
+	fn pool_is_sized_from_config_and_cpus() {
+		let mut config = ValidationPoolConfig::new(ValidationExecutionMode::InProcess);
+		assert!(config.num_workers() >= 1);
+		assert!(config.num_workers() <= DEFAULT_MAX_WORKERS);
+
+		config.num_workers = Some(DEFAULT_MAX_WORKERS * 2);
+		assert_eq!(config.num_workers(), DEFAULT_MAX_WORKERS * 2);
+
+		config.num_workers = Some(0);
+		assert_eq!(config.num_workers(), 1);
+	}
+
+	#[test]
+	fn worker_is_restarted_after_max_jobs() {
+		let pool = ValidationPool::with_config(
+			ValidationPoolConfig {
+				execution_mode: execution_mode_attempting("attempt-nothing"),
+				num_workers: Some(1),
+				max_jobs_per_worker: Some(2),
+				health_check_interval: None,
+			},
+			None,
+		);
+
+		for _ in 0..5 {
+			pool.validate_candidate(&[], params()).unwrap();
+		}
+
+		assert_eq!(pool.num_workers(), 1);
+		assert_eq!(pool.stats(), vec![WorkerStats { jobs: 5, crashes: 0, restarts: 2 }]);
+	}
+
+	#[test]
+	fn crashed_worker_is_reported_and_restarted() {
+		let observer = Arc::new(CountingObserver::default());
+		let pool = ValidationPool::with_config(
+			ValidationPoolConfig {
+				execution_mode: execution_mode_attempting("attempt-open"),
+				num_workers: Some(1),
+				max_jobs_per_worker: None,
+				health_check_interval: Some(Duration::from_millis(100)),
+			},
+			Some(observer.clone()),
+		);
+
+		for _ in 0..2 {
+			pool.validate_candidate(&[], params()).unwrap_err();
+		}
+
+		let stats = pool.stats();
+		assert_eq!(stats[0].jobs, 2);
+		assert_eq!(stats[0].crashes, 2);
+		assert!(stats[0].restarts >= 1);
+
+		assert_eq!(observer.queued.load(atomic::Ordering::SeqCst), 2);
+		assert_eq!(observer.executed.load(atomic::Ordering::SeqCst), 2);
+		assert_eq!(observer.crashed.load(atomic::Ordering::SeqCst), 2);
+	}
+
+	#[test]
+	fn dropped_pool_is_not_kept_alive_by_maintenance() {
+		let pool = ValidationPool::with_config(
+			ValidationPoolConfig {
+				execution_mode: ValidationExecutionMode::InProcess,
+				num_workers: Some(1),
+				max_jobs_per_worker: None,
+				health_check_interval: Some(Duration::from_secs(3600)),
+			},
+			None,
+		);
+
+		// let the maintenance thread start the worker and wait for the next health check.
+		std::thread::sleep(Duration::from_millis(100));
+
+		let inner = Arc::downgrade(&pool.inner);
+		drop(pool);
+
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while inner.upgrade().is_some() {
+			assert!(Instant::now() < deadline, "the pool outlived its last handle");
+			std::thread::sleep(Duration::from_millis(10));
+		}
+	}
+}
@@ -0,0 +1,88 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validation of adder blocks in-process, which includes instantiating the PVF every time.
+//!
+//! Compare against a saved baseline with `scripts/compare-benches.sh`.
+
+use adder::{hash_state, BlockData, HeadData};
+use codec::Encode;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use parachain::{
+	primitives::{BlockData as GenericBlockData, HeadData as GenericHeadData, ValidationParams},
+	wasm_executor::{validate_candidate, ExecutionMode},
+};
+
+/// The numbers of blocks validated one after the other.
+const CHAIN_LENGTHS: [u64; 3] = [1, 10, 50];
+
+/// A block of the chain, as the encoded parent head, the encoded block data and the relay-chain
+/// height it is validated at.
+type Block = (Vec<u8>, Vec<u8>, u32);
+
+/// A chain of `length` blocks, each adding one.
+fn chain(length: u64) -> Vec<Block> {
+	let mut parent_head = HeadData {
+		number: 0,
+		parent_hash: [0; 32],
+		post_state: hash_state(0),
+	};
+
+	(0..length).map(|state| {
+		let block_data = BlockData { state, add: 1 };
+		let block = (parent_head.encode(), block_data.encode(), state as u32 + 1);
+
+		parent_head = adder::execute(parent_head.hash(), parent_head.clone(), &block_data)
+			.expect("the chain is built on valid states; qed");
+
+		block
+	}).collect()
+}
+
+fn params((parent_head, block_data, relay_chain_height): &Block) -> ValidationParams {
+	ValidationParams {
+		parent_head: GenericHeadData(parent_head.clone()),
+		block_data: GenericBlockData(block_data.clone()),
+		relay_chain_height: *relay_chain_height,
+		hrmp_mqc_heads: Vec::new(),
+	}
+}
+
+fn validate_adder(c: &mut Criterion) {
+	let mut group = c.benchmark_group("validate_candidate_adder");
+	group.sample_size(10);
+
+	for &length in CHAIN_LENGTHS.iter() {
+		let chain = chain(length);
+
+		group.throughput(Throughput::Elements(length));
+		group.bench_with_input(BenchmarkId::from_parameter(length), &chain, |b, chain| {
+			b.iter(|| for block in chain {
+				validate_candidate(
+					adder::wasm_binary_unwrap(),
+					params(block),
+					ExecutionMode::Local,
+					sp_core::testing::TaskExecutor::new(),
+				).unwrap();
+			})
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, validate_adder);
+criterion_main!(benches);
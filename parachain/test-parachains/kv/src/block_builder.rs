@@ -0,0 +1,127 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Building blocks on the collator, which holds the whole state.
+
+use parachain::primitives::{ParachainDispatchOrigin, UpwardMessage, ValidationCode};
+use sp_std::vec::Vec;
+
+use crate::{BlockData, ExecutionError, HeadData, Operation, Outcome, State};
+
+/// Builds the blocks of the parachain on top of each other.
+///
+/// Operations are queued until [`build`](Self::build) turns them into a block, which carries the
+/// proof of the part of the state they touch.
+pub struct BlockBuilder {
+	state: State,
+	head: HeadData,
+	operations: Vec<Operation>,
+	processed_downward_messages: u32,
+}
+
+impl BlockBuilder {
+	/// Start from the genesis head of a parachain whose state holds the given values.
+	pub fn new(genesis_state: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>) -> Self {
+		let mut state = State::default();
+		for (key, value) in genesis_state {
+			state.set(&key, Some(value)).expect("the whole state is known; qed");
+		}
+
+		let head = HeadData {
+			number: 0,
+			parent_hash: [0; 32],
+			state_root: state.root().expect("the whole state is well-formed; qed"),
+		};
+
+		BlockBuilder {
+			state,
+			head,
+			operations: Vec::new(),
+			processed_downward_messages: 0,
+		}
+	}
+
+	/// The head of the last block built, or the genesis head.
+	pub fn head(&self) -> &HeadData {
+		&self.head
+	}
+
+	/// The value stored at `key` as of the last block built, ignoring queued operations.
+	pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.state.get(key).expect("the whole state is known; qed").map(|value| value.to_vec())
+	}
+
+	/// Queue storing a value.
+	pub fn set(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> &mut Self {
+		self.push(Operation::Set { key: key.into(), value: value.into() })
+	}
+
+	/// Queue removing a value.
+	pub fn remove(&mut self, key: impl Into<Vec<u8>>) -> &mut Self {
+		self.push(Operation::Remove { key: key.into() })
+	}
+
+	/// Queue sending a message to the relay chain.
+	pub fn send_upward(&mut self, origin: ParachainDispatchOrigin, data: Vec<u8>) -> &mut Self {
+		self.push(Operation::SendUpward(UpwardMessage { origin, data }))
+	}
+
+	/// Queue upgrading the validation code.
+	pub fn upgrade_code(&mut self, code: impl Into<Vec<u8>>) -> &mut Self {
+		self.push(Operation::UpgradeCode(ValidationCode(code.into())))
+	}
+
+	/// Declare the next block processed `count` more downward messages.
+	pub fn process_downward_messages(&mut self, count: u32) -> &mut Self {
+		self.processed_downward_messages += count;
+		self
+	}
+
+	/// Push an arbitrary operation.
+	pub fn push(&mut self, operation: Operation) -> &mut Self {
+		self.operations.push(operation);
+		self
+	}
+
+	/// Turn the queued operations into a block on top of the last one, and execute it.
+	///
+	/// The queued operations are dropped if the block is invalid, leaving the state as it was.
+	pub fn build(&mut self) -> Result<(BlockData, Outcome), ExecutionError> {
+		let operations = sp_std::mem::take(&mut self.operations);
+		let processed_downward_messages = sp_std::mem::take(&mut self.processed_downward_messages);
+
+		let block_data = BlockData {
+			state: self.state.prune(operations.iter().filter_map(Operation::key))?,
+			operations,
+			processed_downward_messages,
+		};
+
+		let outcome = crate::execute(self.head.hash(), self.head.clone(), block_data.clone())?;
+
+		for operation in &block_data.operations {
+			match operation {
+				Operation::Set { key, value } => self.state.set(key, Some(value.clone()))?,
+				Operation::Remove { key } => self.state.set(key, None)?,
+				Operation::SendUpward(_) | Operation::UpgradeCode(_) => {}
+			}
+		}
+
+		debug_assert_eq!(self.state.root(), Ok(outcome.head.state_root));
+		self.head = outcome.head.clone();
+
+		Ok((block_data, outcome))
+	}
+}
@@ -0,0 +1,196 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parachain keeping a key-value store, of which every block carries the Merkle proof of the part
+//! it touches. Blocks can also send upward messages and upgrade the validation code.
+
+#![no_std]
+
+#![cfg_attr(not(feature = "std"), feature(core_intrinsics, lang_items, core_panic_info, alloc_error_handler))]
+
+use codec::{Encode, Decode};
+use parachain::primitives::{UpwardMessage, ValidationCode};
+use sp_std::vec::Vec;
+
+mod state;
+
+#[cfg(feature = "std")]
+mod block_builder;
+
+#[cfg(not(feature = "std"))]
+mod wasm_validation;
+
+pub use state::{State, StateError};
+
+#[cfg(feature = "std")]
+pub use block_builder::BlockBuilder;
+
+#[cfg(not(feature = "std"))]
+#[global_allocator]
+static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+// Make the WASM binary available.
+#[cfg(feature = "std")]
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+#[cfg(feature = "std")]
+/// Wasm binary unwrapped. If built with `BUILD_DUMMY_WASM_BINARY`, the function panics.
+pub fn wasm_binary_unwrap() -> &'static [u8] {
+	WASM_BINARY.expect("Development wasm binary is not available. Testing is only \
+						supported with the flag disabled.")
+}
+
+/// Head data for this parachain.
+#[derive(Default, Clone, Hash, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct HeadData {
+	/// Block number
+	pub number: u64,
+	/// parent block keccak256
+	pub parent_hash: [u8; 32],
+	/// Merkle root of the post-execution state.
+	pub state_root: [u8; 32],
+}
+
+impl HeadData {
+	/// The keccak256 hash of the encoded head.
+	pub fn hash(&self) -> [u8; 32] {
+		tiny_keccak::keccak256(&self.encode())
+	}
+}
+
+/// An operation carried out by a block, in order.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Operation {
+	/// Store a value.
+	Set {
+		/// The key to store the value at.
+		key: Vec<u8>,
+		/// The value.
+		value: Vec<u8>,
+	},
+	/// Remove a value, if any.
+	Remove {
+		/// The key of the value.
+		key: Vec<u8>,
+	},
+	/// Send a message to the relay chain.
+	SendUpward(UpwardMessage),
+	/// Schedule an upgrade of the validation code. At most one per block.
+	UpgradeCode(ValidationCode),
+}
+
+impl Operation {
+	/// The key of the state this operation touches, if any.
+	pub fn key(&self) -> Option<&[u8]> {
+		match self {
+			Operation::Set { key, .. } | Operation::Remove { key } => Some(&key[..]),
+			Operation::SendUpward(_) | Operation::UpgradeCode(_) => None,
+		}
+	}
+}
+
+/// Block data for this parachain.
+#[derive(Default, Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BlockData {
+	/// The part of the parent state the operations touch.
+	pub state: State,
+	/// The operations to carry out.
+	pub operations: Vec<Operation>,
+	/// The number of downward messages processed by the block.
+	///
+	/// Validation isn't given the downward messages yet, so the block merely declares how many of
+	/// them it processed.
+	pub processed_downward_messages: u32,
+}
+
+/// What executing a block leads to, besides the new head.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Outcome {
+	/// The new head.
+	pub head: HeadData,
+	/// The code to upgrade to, if any.
+	pub new_validation_code: Option<ValidationCode>,
+	/// The messages sent to the relay chain.
+	pub upward_messages: Vec<UpwardMessage>,
+	/// The number of downward messages processed.
+	pub processed_downward_messages: u32,
+}
+
+/// Why a block is invalid.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ExecutionError {
+	/// The state carried by the block doesn't match the parent head.
+	StateRootMismatch,
+	/// The state carried by the block is malformed or doesn't cover all the operations.
+	State(StateError),
+	/// The block upgrades the code more than once.
+	MultipleCodeUpgrades,
+}
+
+impl From<StateError> for ExecutionError {
+	fn from(e: StateError) -> Self {
+		ExecutionError::State(e)
+	}
+}
+
+/// Execute a block body on top of given parent head, producing new parent head
+/// if valid.
+pub fn execute(
+	parent_hash: [u8; 32],
+	parent_head: HeadData,
+	block_data: BlockData,
+) -> Result<Outcome, ExecutionError> {
+	debug_assert_eq!(parent_hash, parent_head.hash());
+
+	let BlockData { mut state, operations, processed_downward_messages } = block_data;
+	if state.root()? != parent_head.state_root {
+		return Err(ExecutionError::StateRootMismatch);
+	}
+
+	let mut new_validation_code = None;
+	let mut upward_messages = Vec::new();
+
+	for operation in operations {
+		match operation {
+			Operation::Set { key, value } => state.set(&key, Some(value))?,
+			Operation::Remove { key } => state.set(&key, None)?,
+			Operation::SendUpward(message) => upward_messages.push(message),
+			Operation::UpgradeCode(code) => {
+				if new_validation_code.is_some() {
+					return Err(ExecutionError::MultipleCodeUpgrades);
+				}
+
+				new_validation_code = Some(code);
+			}
+		}
+	}
+
+	Ok(Outcome {
+		head: HeadData {
+			number: parent_head.number + 1,
+			parent_hash,
+			state_root: state.root()?,
+		},
+		new_validation_code,
+		upward_messages,
+		processed_downward_messages,
+	})
+}
@@ -0,0 +1,211 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The key-value state of the parachain, as a sparse Merkle tree.
+//!
+//! Every value sits at the bottom of a binary tree of depth 256, at the path given by the
+//! keccak256 hash of its key. Empty subtrees hash to zero, so only the branches leading to values
+//! have to be stored. A block only carries the branches leading to the keys it touches, with every
+//! other subtree replaced by its hash, which is enough to compute the root before and after the
+//! block.
+
+use codec::{Decode, Encode};
+use sp_std::{boxed::Box, vec::Vec};
+
+/// The depth of the tree, in bits of the hashed keys.
+const DEPTH: usize = 256;
+
+/// The hash of an empty subtree.
+const EMPTY: [u8; 32] = [0; 32];
+
+/// The state is incomplete or malformed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum StateError {
+	/// A key was accessed that the state proof doesn't cover.
+	MissingNode,
+	/// A branch or a value sits at the wrong depth.
+	Malformed,
+}
+
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+enum Node {
+	/// A subtree without any values.
+	Empty,
+	/// A subtree left out of a state proof, of which only the hash is known.
+	Pruned([u8; 32]),
+	/// A branch, to the left for a zero bit of the path.
+	Branch(Box<Node>, Box<Node>),
+	/// A value, at the bottom of the tree.
+	Leaf(Vec<u8>),
+}
+
+/// The key-value state of the parachain, either whole or only the part a block touches.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct State(Node);
+
+impl Default for State {
+	fn default() -> Self {
+		State(Node::Empty)
+	}
+}
+
+impl State {
+	/// The Merkle root of the state.
+	pub fn root(&self) -> Result<[u8; 32], StateError> {
+		hash_node(&self.0, 0)
+	}
+
+	/// The value stored at `key`, if any.
+	pub fn get(&self, key: &[u8]) -> Result<Option<&[u8]>, StateError> {
+		let path = tiny_keccak::keccak256(key);
+		let mut node = &self.0;
+
+		for depth in 0..=DEPTH {
+			node = match node {
+				Node::Empty => return Ok(None),
+				Node::Pruned(hash) if *hash == EMPTY => return Ok(None),
+				Node::Pruned(_) => return Err(StateError::MissingNode),
+				Node::Leaf(value) if depth == DEPTH => return Ok(Some(&value[..])),
+				Node::Branch(left, right) if depth < DEPTH => if bit(&path, depth) {
+					&**right
+				} else {
+					&**left
+				},
+				_ => return Err(StateError::Malformed),
+			};
+		}
+
+		// every node at the bottom returns.
+		Err(StateError::Malformed)
+	}
+
+	/// Store `value` at `key`, or remove the value stored there if `None`.
+	pub fn set(&mut self, key: &[u8], value: Option<Vec<u8>>) -> Result<(), StateError> {
+		let path = tiny_keccak::keccak256(key);
+		set_at(&mut self.0, &path, 0, value)
+	}
+
+	/// The part of the state needed to access `keys`, with every other subtree replaced by its
+	/// hash.
+	pub fn prune<'a>(&self, keys: impl IntoIterator<Item = &'a [u8]>) -> Result<State, StateError> {
+		let mut paths: Vec<_> = keys.into_iter().map(tiny_keccak::keccak256).collect();
+		paths.sort();
+		paths.dedup();
+
+		prune_at(&self.0, &paths, 0).map(State)
+	}
+}
+
+fn bit(path: &[u8; 32], depth: usize) -> bool {
+	path[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn hash_node(node: &Node, depth: usize) -> Result<[u8; 32], StateError> {
+	match node {
+		Node::Empty => Ok(EMPTY),
+		Node::Pruned(hash) => Ok(*hash),
+		Node::Leaf(value) if depth == DEPTH => {
+			Ok(tiny_keccak::keccak256(&[&[1u8][..], &value[..]].concat()))
+		}
+		Node::Branch(left, right) if depth < DEPTH => {
+			let left = hash_node(left, depth + 1)?;
+			let right = hash_node(right, depth + 1)?;
+
+			if left == EMPTY && right == EMPTY {
+				Ok(EMPTY)
+			} else {
+				Ok(tiny_keccak::keccak256(&[&[0u8][..], &left[..], &right[..]].concat()))
+			}
+		}
+		_ => Err(StateError::Malformed),
+	}
+}
+
+fn set_at(
+	node: &mut Node,
+	path: &[u8; 32],
+	depth: usize,
+	value: Option<Vec<u8>>,
+) -> Result<(), StateError> {
+	let is_empty = match node {
+		Node::Empty => true,
+		Node::Pruned(hash) if *hash == EMPTY => true,
+		Node::Pruned(_) => return Err(StateError::MissingNode),
+		Node::Leaf(_) if depth == DEPTH => false,
+		Node::Branch(..) if depth < DEPTH => false,
+		_ => return Err(StateError::Malformed),
+	};
+
+	if depth == DEPTH {
+		*node = value.map_or(Node::Empty, Node::Leaf);
+		return Ok(());
+	}
+
+	if is_empty {
+		if value.is_none() {
+			*node = Node::Empty;
+			return Ok(());
+		}
+
+		*node = Node::Branch(Box::new(Node::Empty), Box::new(Node::Empty));
+	}
+
+	if let Node::Branch(left, right) = node {
+		let child = if bit(path, depth) { &mut **right } else { &mut **left };
+		set_at(child, path, depth + 1, value)?;
+
+		// keep removed values from leaving empty branches behind.
+		let collapse = match (&**left, &**right) {
+			(Node::Empty, Node::Empty) => true,
+			_ => false,
+		};
+
+		if collapse {
+			*node = Node::Empty;
+		}
+	}
+
+	Ok(())
+}
+
+/// Prune the subtree at `node`, keeping the branches leading to the sorted `paths`.
+fn prune_at(node: &Node, paths: &[[u8; 32]], depth: usize) -> Result<Node, StateError> {
+	if paths.is_empty() {
+		let hash = hash_node(node, depth)?;
+		return Ok(if hash == EMPTY { Node::Empty } else { Node::Pruned(hash) });
+	}
+
+	match node {
+		Node::Branch(left, right) if depth < DEPTH => {
+			// the paths share their first `depth` bits and are sorted, so those going left come
+			// first.
+			let split = paths.iter().position(|path| bit(path, depth)).unwrap_or(paths.len());
+
+			Ok(Node::Branch(
+				Box::new(prune_at(left, &paths[..split], depth + 1)?),
+				Box::new(prune_at(right, &paths[split..], depth + 1)?),
+			))
+		}
+		Node::Pruned(hash) if *hash != EMPTY => Err(StateError::MissingNode),
+		_ => {
+			hash_node(node, depth)?;
+			Ok(node.clone())
+		}
+	}
+}
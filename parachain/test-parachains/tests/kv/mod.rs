@@ -0,0 +1,217 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parachain keeping a Merkle-proved key-value store.
+
+const WORKER_ARGS_TEST: &[&'static str] = &["--nocapture", "validation_worker"];
+
+use parachain::{
+	primitives::{
+		BlockData as GenericBlockData,
+		HeadData as GenericHeadData,
+		ParachainDispatchOrigin,
+		UpwardMessage,
+		ValidationCode,
+		ValidationParams,
+		ValidationResult,
+	},
+	wasm_executor::{ValidationError, ValidationPool, ValidationExecutionMode},
+};
+use codec::{Decode, Encode};
+use kv::{BlockBuilder, BlockData, ExecutionError, HeadData, Operation, State, StateError};
+
+fn validation_pool() -> ValidationPool {
+	let execution_mode = ValidationExecutionMode::ExternalProcessCustomHost {
+		binary: std::env::current_exe().unwrap(),
+		args: WORKER_ARGS_TEST.iter().map(|x| x.to_string()).collect(),
+	};
+
+	ValidationPool::new(execution_mode)
+}
+
+fn validate(
+	pool: &ValidationPool,
+	parent_head: &HeadData,
+	block_data: &BlockData,
+) -> Result<ValidationResult, ValidationError> {
+	parachain::wasm_executor::validate_candidate(
+		kv::wasm_binary_unwrap(),
+		ValidationParams {
+			parent_head: GenericHeadData(parent_head.encode()),
+			block_data: GenericBlockData(block_data.encode()),
+			relay_chain_height: parent_head.number as u32 + 1,
+			hrmp_mqc_heads: Vec::new(),
+		},
+		parachain::wasm_executor::ExecutionMode::Remote(pool),
+		sp_core::testing::TaskExecutor::new(),
+	)
+}
+
+fn genesis() -> BlockBuilder {
+	BlockBuilder::new(vec![
+		(b"alice".to_vec(), 100u64.encode()),
+		(b"bob".to_vec(), 50u64.encode()),
+	])
+}
+
+#[test]
+fn state_proof_covers_touched_keys_only() {
+	let mut state = State::default();
+	for i in 0u32..16 {
+		state.set(&i.encode(), Some(vec![i as u8])).unwrap();
+	}
+
+	let root = state.root().unwrap();
+	let proof = state.prune(vec![&3u32.encode()[..], &42u32.encode()[..]]).unwrap();
+	assert_eq!(proof.root(), Ok(root));
+	assert!(proof.encoded_size() < state.encoded_size());
+
+	assert_eq!(proof.get(&3u32.encode()), Ok(Some(&[3u8][..])));
+	assert_eq!(proof.get(&42u32.encode()), Ok(None));
+	assert_eq!(proof.get(&4u32.encode()), Err(StateError::MissingNode));
+
+	// updating the proof leads to the same root as updating the whole state.
+	let mut updated = proof.clone();
+	updated.set(&3u32.encode(), None).unwrap();
+	updated.set(&42u32.encode(), Some(vec![42])).unwrap();
+	assert_eq!(updated.set(&5u32.encode(), None), Err(StateError::MissingNode));
+
+	state.set(&3u32.encode(), None).unwrap();
+	state.set(&42u32.encode(), Some(vec![42])).unwrap();
+	assert_eq!(updated.root(), state.root());
+	assert_ne!(updated.root(), Ok(root));
+}
+
+#[test]
+fn removing_every_value_empties_the_state() {
+	let mut state = State::default();
+	let empty_root = state.root().unwrap();
+
+	state.set(b"a", Some(vec![1])).unwrap();
+	state.set(b"b", Some(vec![2])).unwrap();
+	assert_ne!(state.root(), Ok(empty_root));
+
+	state.set(b"a", None).unwrap();
+	state.set(b"b", None).unwrap();
+	assert_eq!(state, State::default());
+	assert_eq!(state.root(), Ok(empty_root));
+}
+
+#[test]
+fn execute_good_chain_with_inprocess_validation() {
+	let pool = ValidationPool::new(ValidationExecutionMode::InProcess);
+	execute_good_chain(pool);
+}
+
+#[test]
+fn execute_good_chain_with_external_process_validation() {
+	execute_good_chain(validation_pool());
+}
+
+fn execute_good_chain(pool: ValidationPool) {
+	let mut builder = genesis();
+
+	for block in 0u64..4 {
+		let parent_head = builder.head().clone();
+		builder
+			.set(&b"alice"[..], (100 - block).encode())
+			.set(&b"bob"[..], (50 + block).encode())
+			.set(block.encode(), b"receipt".to_vec())
+			.send_upward(ParachainDispatchOrigin::Parachain, block.encode())
+			.process_downward_messages(block as u32);
+
+		let (block_data, outcome) = builder.build().unwrap();
+		let ret = validate(&pool, &parent_head, &block_data).unwrap();
+
+		let new_head = HeadData::decode(&mut &ret.head_data.0[..]).unwrap();
+		assert_eq!(new_head, outcome.head);
+		assert_eq!(&new_head, builder.head());
+		assert_eq!(new_head.number, block + 1);
+		assert_eq!(new_head.parent_hash, parent_head.hash());
+
+		assert_eq!(ret.new_validation_code, None);
+		assert_eq!(ret.upward_messages, vec![UpwardMessage {
+			origin: ParachainDispatchOrigin::Parachain,
+			data: block.encode(),
+		}]);
+		assert_eq!(ret.processed_downward_messages, block as u32);
+	}
+
+	assert_eq!(builder.get(b"alice"), Some(97u64.encode()));
+	assert_eq!(builder.get(b"bob"), Some(53u64.encode()));
+}
+
+#[test]
+fn execute_code_upgrade_and_removal() {
+	let pool = validation_pool();
+	let mut builder = genesis();
+	let parent_head = builder.head().clone();
+
+	builder.remove(&b"bob"[..]).upgrade_code(vec![1, 2, 3]);
+	let (block_data, _) = builder.build().unwrap();
+	let ret = validate(&pool, &parent_head, &block_data).unwrap();
+
+	assert_eq!(ret.new_validation_code, Some(ValidationCode(vec![1, 2, 3])));
+	assert!(ret.upward_messages.is_empty());
+	assert_eq!(builder.get(b"bob"), None);
+	assert_eq!(builder.get(b"alice"), Some(100u64.encode()));
+
+	let new_head = HeadData::decode(&mut &ret.head_data.0[..]).unwrap();
+	assert_eq!(&new_head, builder.head());
+}
+
+#[test]
+fn builder_rejects_multiple_code_upgrades() {
+	let mut builder = genesis();
+	let genesis_head = builder.head().clone();
+
+	builder.set(&b"alice"[..], vec![0]).upgrade_code(vec![1]).upgrade_code(vec![2]);
+	assert_eq!(builder.build().unwrap_err(), ExecutionError::MultipleCodeUpgrades);
+
+	// the state is left untouched.
+	assert_eq!(builder.head(), &genesis_head);
+	assert_eq!(builder.get(b"alice"), Some(100u64.encode()));
+}
+
+#[test]
+fn execute_bad_state_proof() {
+	let pool = validation_pool();
+	let mut builder = genesis();
+	let parent_head = builder.head().clone();
+
+	builder.set(&b"alice"[..], 0u64.encode()).set(&b"carol"[..], 150u64.encode());
+	let (block_data, _) = builder.build().unwrap();
+
+	// the proof doesn't cover an operation.
+	let mut uncovered = block_data.clone();
+	uncovered.operations.push(Operation::Remove { key: b"bob".to_vec() });
+	assert_eq!(
+		kv::execute(parent_head.hash(), parent_head.clone(), uncovered.clone()).unwrap_err(),
+		ExecutionError::State(StateError::MissingNode),
+	);
+	validate(&pool, &parent_head, &uncovered).unwrap_err();
+
+	// the proof is of another state.
+	let other_parent = HeadData { state_root: [1; 32], ..parent_head.clone() };
+	assert_eq!(
+		kv::execute(other_parent.hash(), other_parent.clone(), block_data.clone()).unwrap_err(),
+		ExecutionError::StateRootMismatch,
+	);
+	validate(&pool, &other_parent, &block_data).unwrap_err();
+
+	// the block is fine otherwise.
+	validate(&pool, &parent_head, &block_data).unwrap();
+}
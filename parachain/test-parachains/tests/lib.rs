@@ -15,6 +15,7 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 mod adder;
+mod kv;
 mod wasm_executor;
 
 use parachain::wasm_executor::run_worker;
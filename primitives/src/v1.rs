@@ -425,6 +425,200 @@ pub fn check_candidate_backing<H: AsRef<[u8]> + Clone + Encode>(
 	Ok(signed)
 }
 
+/// Self-contained proofs of misbehavior by validators in backing.
+///
+/// Every proof carries the signing context and the index of the validator which misbehaved,
+/// so it can be checked against the validator set of the session alone, in the runtime as well
+/// as on the node.
+///
+/// Signing the same statement twice is not provable misbehavior: sr25519 signatures are
+/// randomized, so an honest validator re-signing a statement it made before produces a second,
+/// different signature.
+pub mod misbehavior {
+	use super::*;
+
+	/// A statement signed by the validator a proof is about.
+	#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub struct SignedProofStatement {
+		/// The statement.
+		pub statement: CompactStatement,
+		/// The signature on the statement, in the signing context of the proof.
+		pub signature: ValidatorSignature,
+	}
+
+	impl SignedProofStatement {
+		fn check_signature(&self, signing_context: &SigningContext, key: &ValidatorId) -> bool {
+			let payload = (&self.statement, signing_context).encode();
+			self.signature.verify(&payload[..], key)
+		}
+	}
+
+	/// Proof that a validator voted against a candidate it also voted for, explicitly or implicitly
+	/// by seconding it.
+	///
+	/// Exactly one of the statements is `Invalid`.
+	#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub struct ValidityDoubleVoteProof {
+		/// The context the statements were signed in.
+		pub signing_context: SigningContext,
+		/// The validator which signed both statements.
+		pub validator_index: ValidatorIndex,
+		/// The first statement.
+		pub first: SignedProofStatement,
+		/// The second statement, on the same candidate.
+		pub second: SignedProofStatement,
+	}
+
+	/// Proof that a validator seconded two candidates.
+	#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub struct MultipleCandidatesProof {
+		/// The context the statements were signed in.
+		pub signing_context: SigningContext,
+		/// The validator which seconded both candidates.
+		pub validator_index: ValidatorIndex,
+		/// The hash and signature of the first candidate.
+		pub first: (Hash, ValidatorSignature),
+		/// The hash and signature of the second candidate.
+		pub second: (Hash, ValidatorSignature),
+	}
+
+	/// Proof that a validator made a statement on a candidate of a para it was not assigned to.
+	///
+	/// Whether the validator was assigned to the para depends on the group assignments at the
+	/// relay-parent, which the proof doesn't carry, so `verify` only checks that the statement
+	/// was made on the candidate. The caller has to check the assignment.
+	#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub struct UnauthorizedStatementProof {
+		/// The context the statement was signed in.
+		pub signing_context: SigningContext,
+		/// The validator which made the statement.
+		pub validator_index: ValidatorIndex,
+		/// The statement.
+		pub statement: SignedProofStatement,
+		/// The candidate the statement is on, which determines the para.
+		pub candidate: CandidateReceipt,
+	}
+
+	impl UnauthorizedStatementProof {
+		/// The para the validator was not assigned to.
+		pub fn para_id(&self) -> Id {
+			self.candidate.descriptor.para_id
+		}
+	}
+
+	/// A proof of any kind of misbehavior.
+	#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub enum MisbehaviorProof {
+		/// Voted for and against a candidate.
+		#[codec(index = "0")]
+		ValidityDoubleVote(ValidityDoubleVoteProof),
+		/// Seconded multiple candidates.
+		#[codec(index = "1")]
+		MultipleCandidates(MultipleCandidatesProof),
+		/// Made a statement on a candidate of a para it was not assigned to.
+		#[codec(index = "2")]
+		UnauthorizedStatement(UnauthorizedStatementProof),
+	}
+
+	impl MisbehaviorProof {
+		/// The context the statements of the proof were signed in.
+		pub fn signing_context(&self) -> &SigningContext {
+			match self {
+				MisbehaviorProof::ValidityDoubleVote(p) => &p.signing_context,
+				MisbehaviorProof::MultipleCandidates(p) => &p.signing_context,
+				MisbehaviorProof::UnauthorizedStatement(p) => &p.signing_context,
+			}
+		}
+
+		/// The index of the validator which misbehaved.
+		pub fn validator_index(&self) -> ValidatorIndex {
+			match self {
+				MisbehaviorProof::ValidityDoubleVote(p) => p.validator_index,
+				MisbehaviorProof::MultipleCandidates(p) => p.validator_index,
+				MisbehaviorProof::UnauthorizedStatement(p) => p.validator_index,
+			}
+		}
+	}
+
+	/// Why a proof of misbehavior is not valid.
+	#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode)]
+	#[cfg_attr(feature = "std", derive(Debug))]
+	pub enum InvalidProof {
+		/// The statements were signed in another session.
+		WrongSession,
+		/// There is no validator with the index of the proof.
+		ValidatorIndexOutOfBounds,
+		/// A signature is not valid.
+		InvalidSignature,
+		/// The statements don't conflict.
+		NoConflict,
+	}
+
+	/// Verify a proof of misbehavior against the validators of the given session.
+	pub fn verify(
+		proof: &MisbehaviorProof,
+		validators: &[ValidatorId],
+		session: SessionIndex,
+	) -> Result<(), InvalidProof> {
+		let signing_context = proof.signing_context();
+		if signing_context.session_index != session {
+			return Err(InvalidProof::WrongSession);
+		}
+
+		let key = validators.get(proof.validator_index() as usize)
+			.ok_or(InvalidProof::ValidatorIndexOutOfBounds)?;
+
+		let check = |statement: &SignedProofStatement| if statement.check_signature(signing_context, key) {
+			Ok(())
+		} else {
+			Err(InvalidProof::InvalidSignature)
+		};
+
+		match proof {
+			MisbehaviorProof::ValidityDoubleVote(p) => {
+				let is_invalid = |s: &SignedProofStatement| match s.statement {
+					CompactStatement::Invalid(_) => true,
+					CompactStatement::Candidate(_) | CompactStatement::Valid(_) => false,
+				};
+				let conflicting = p.first.statement.candidate_hash() == p.second.statement.candidate_hash()
+					&& is_invalid(&p.first) != is_invalid(&p.second);
+				if !conflicting {
+					return Err(InvalidProof::NoConflict);
+				}
+
+				check(&p.first)?;
+				check(&p.second)
+			}
+			MisbehaviorProof::MultipleCandidates(p) => {
+				if p.first.0 == p.second.0 {
+					return Err(InvalidProof::NoConflict);
+				}
+
+				check(&SignedProofStatement {
+					statement: CompactStatement::Candidate(p.first.0),
+					signature: p.first.1.clone(),
+				})?;
+				check(&SignedProofStatement {
+					statement: CompactStatement::Candidate(p.second.0),
+					signature: p.second.1.clone(),
+				})
+			}
+			MisbehaviorProof::UnauthorizedStatement(p) => {
+				if p.statement.statement.candidate_hash() != &p.candidate.hash() {
+					return Err(InvalidProof::NoConflict);
+				}
+
+				check(&p.statement)
+			}
+		}
+	}
+}
+
 /// The unique (during session) index of a core.
 #[derive(Encode, Decode, Default, PartialOrd, Ord, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -733,6 +927,106 @@ mod tests {
 		assert_eq!(info.last_rotation_at(), 0);
 	}
 
+	mod misbehavior_proofs {
+		use super::*;
+		use super::super::misbehavior::*;
+		use primitives::crypto::Pair;
+
+		fn sign(
+			key: &ValidatorPair,
+			statement: CompactStatement,
+			signing_context: &SigningContext,
+		) -> SignedProofStatement {
+			let signature = key.sign(&(&statement, signing_context).encode()[..]);
+			SignedProofStatement { statement, signature }
+		}
+
+		fn setup() -> (Vec<ValidatorPair>, Vec<ValidatorId>, SigningContext) {
+			let keys: Vec<_> = (0..3).map(|_| ValidatorPair::generate().0).collect();
+			let validators = keys.iter().map(|k| k.public()).collect();
+			let signing_context = SigningContext { session_index: 5, parent_hash: Hash::repeat_byte(1) };
+
+			(keys, validators, signing_context)
+		}
+
+		#[test]
+		fn validity_double_vote_is_verified() {
+			let (keys, validators, signing_context) = setup();
+			let candidate = Hash::repeat_byte(2);
+
+			let proof = |first, second, validator_index| MisbehaviorProof::ValidityDoubleVote(
+				ValidityDoubleVoteProof {
+					signing_context: signing_context.clone(),
+					validator_index,
+					first: sign(&keys[1], first, &signing_context),
+					second: sign(&keys[1], second, &signing_context),
+				},
+			);
+
+			let double_vote = proof(CompactStatement::Valid(candidate), CompactStatement::Invalid(candidate), 1);
+			assert_eq!(verify(&double_vote, &validators, 5), Ok(()));
+			assert_eq!(verify(&double_vote, &validators, 6), Err(InvalidProof::WrongSession));
+			assert_eq!(verify(&double_vote, &validators[..1], 5), Err(InvalidProof::ValidatorIndexOutOfBounds));
+
+			// the proof survives encoding.
+			let decoded = MisbehaviorProof::decode(&mut &double_vote.encode()[..]).unwrap();
+			assert_eq!(verify(&decoded, &validators, 5), Ok(()));
+
+			let other_validator = proof(CompactStatement::Valid(candidate), CompactStatement::Invalid(candidate), 2);
+			assert_eq!(verify(&other_validator, &validators, 5), Err(InvalidProof::InvalidSignature));
+
+			let seconded_and_invalid = proof(
+				CompactStatement::Candidate(candidate),
+				CompactStatement::Invalid(candidate),
+				1,
+			);
+			assert_eq!(verify(&seconded_and_invalid, &validators, 5), Ok(()));
+
+			let same_vote = proof(CompactStatement::Valid(candidate), CompactStatement::Valid(candidate), 1);
+			assert_eq!(verify(&same_vote, &validators, 5), Err(InvalidProof::NoConflict));
+
+			// seconding a candidate is a vote for it, so it doesn't conflict with another one.
+			let seconded_and_valid = proof(
+				CompactStatement::Candidate(candidate),
+				CompactStatement::Valid(candidate),
+				1,
+			);
+			assert_eq!(verify(&seconded_and_valid, &validators, 5), Err(InvalidProof::NoConflict));
+
+			let both_invalid = proof(CompactStatement::Invalid(candidate), CompactStatement::Invalid(candidate), 1);
+			assert_eq!(verify(&both_invalid, &validators, 5), Err(InvalidProof::NoConflict));
+
+			let other_candidates = proof(
+				CompactStatement::Valid(candidate),
+				CompactStatement::Invalid(Hash::repeat_byte(3)),
+				1,
+			);
+			assert_eq!(verify(&other_candidates, &validators, 5), Err(InvalidProof::NoConflict));
+		}
+
+		#[test]
+		fn multiple_candidates_are_verified() {
+			let (keys, validators, signing_context) = setup();
+			let candidate = |hash: Hash| {
+				let signed = sign(&keys[0], CompactStatement::Candidate(hash), &signing_context);
+				(hash, signed.signature)
+			};
+
+			let proof = |second| MisbehaviorProof::MultipleCandidates(MultipleCandidatesProof {
+				signing_context: signing_context.clone(),
+				validator_index: 0,
+				first: candidate(Hash::repeat_byte(2)),
+				second,
+			});
+
+			assert_eq!(verify(&proof(candidate(Hash::repeat_byte(3))), &validators, 5), Ok(()));
+			assert_eq!(
+				verify(&proof(candidate(Hash::repeat_byte(2))), &validators, 5),
+				Err(InvalidProof::NoConflict),
+			);
+		}
+	}
+
 	#[test]
 	fn collator_signature_payload_is_valid() {
 		// if this fails, collator signature verification code has to be updated.
@@ -172,6 +172,15 @@ impl<T: Trait> Module<T> {
 		inclusion::Module::<T>::initializer_on_new_session(&notification);
 	}
 
+	/// Should be called with the genesis validators. They take over in the first block, like in
+	/// any other session change, so that parachains are scheduled from the start rather than only
+	/// after the first session change.
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, ValidatorId)>
+	{
+		Self::on_new_session(false, 0, validators, None);
+	}
+
 	/// Should be called when a new session occurs. Buffers the session notification to be applied
 	/// at the next block. If `queued` is `None`, the `validators` are considered queued.
 	fn on_new_session<'a, I: 'a>(
@@ -205,10 +214,10 @@ impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
 impl<T: pallet_session::Trait + Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 	type Key = ValidatorId;
 
-	fn on_genesis_session<'a, I: 'a>(_validators: I)
+	fn on_genesis_session<'a, I: 'a>(validators: I)
 		where I: Iterator<Item=(&'a T::AccountId, Self::Key)>
 	{
-
+		<Module<T>>::on_genesis_session(validators);
 	}
 
 	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, queued: I)
@@ -224,10 +233,26 @@ impl<T: pallet_session::Trait + Trait> pallet_session::OneSessionHandler<T::Acco
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::mock::{new_test_ext, Initializer, Test, System};
+	use crate::mock::{new_test_ext, GenesisConfig, Initializer, Test, System};
+	use crate::paras::ParaGenesisArgs;
+	use keyring::Sr25519Keyring;
 
 	use frame_support::traits::{OnFinalize, OnInitialize};
 
+	fn genesis_with_parachain() -> GenesisConfig {
+		GenesisConfig {
+			paras: paras::GenesisConfig {
+				paras: vec![(100.into(), ParaGenesisArgs {
+					genesis_head: vec![1, 2, 3].into(),
+					validation_code: vec![4, 5, 6].into(),
+					parachain: true,
+				})],
+				..Default::default()
+			},
+			..Default::default()
+		}
+	}
+
 	#[test]
 	fn session_change_before_initialize_is_still_buffered_after() {
 		new_test_ext(Default::default()).execute_with(|| {
@@ -268,6 +293,80 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn genesis_session_is_applied_in_the_first_block() {
+		new_test_ext(genesis_with_parachain()).execute_with(|| {
+			let accounts = [1u64, 2, 3];
+			let validators: Vec<ValidatorId> = vec![
+				Sr25519Keyring::Alice.public().into(),
+				Sr25519Keyring::Bob.public().into(),
+				Sr25519Keyring::Charlie.public().into(),
+			];
+
+			Initializer::on_genesis_session(accounts.iter().zip(validators.iter().cloned()));
+
+			System::set_block_number(1);
+			Initializer::on_initialize(1);
+
+			assert!(<BufferedSessionChanges<Test>>::get().is_empty());
+			assert_eq!(inclusion::Module::<Test>::validators(), validators);
+			assert_eq!(inclusion::Module::<Test>::session_index(), 0);
+			assert_eq!(scheduler::Module::<Test>::session_start_block(), 1);
+			assert_eq!(scheduler::Module::<Test>::availability_cores().len(), 1);
+			assert_eq!(scheduler::Module::<Test>::validator_groups().len(), 1);
+			assert_eq!(scheduler::Module::<Test>::validator_groups()[0].len(), validators.len());
+		});
+	}
+
+	#[test]
+	fn first_session_change_after_genesis_is_applied_once() {
+		new_test_ext(genesis_with_parachain()).execute_with(|| {
+			let accounts = [1u64, 2];
+			let genesis_validators: Vec<ValidatorId> = vec![
+				Sr25519Keyring::Alice.public().into(),
+				Sr25519Keyring::Bob.public().into(),
+			];
+			let validators: Vec<ValidatorId> = vec![
+				Sr25519Keyring::Charlie.public().into(),
+				Sr25519Keyring::Dave.public().into(),
+			];
+
+			Initializer::on_genesis_session(accounts.iter().zip(genesis_validators.iter().cloned()));
+
+			System::set_block_number(1);
+			Initializer::on_initialize(1);
+			Initializer::on_finalize(1);
+
+			System::set_block_number(10);
+			Initializer::on_new_session(
+				true,
+				1,
+				accounts.iter().zip(validators.iter().cloned()),
+				None,
+			);
+
+			// only the new session is waiting to be applied.
+			assert_eq!(<BufferedSessionChanges<Test>>::get().len(), 1);
+
+			Initializer::on_initialize(10);
+			Initializer::on_finalize(10);
+
+			System::set_block_number(11);
+			Initializer::on_initialize(11);
+			Initializer::on_finalize(11);
+
+			assert!(<BufferedSessionChanges<Test>>::get().is_empty());
+			assert_eq!(inclusion::Module::<Test>::validators(), validators);
+			assert_eq!(inclusion::Module::<Test>::session_index(), 1);
+			assert_eq!(scheduler::Module::<Test>::session_start_block(), 11);
+
+			System::set_block_number(12);
+			Initializer::on_initialize(12);
+
+			assert_eq!(scheduler::Module::<Test>::session_start_block(), 11);
+		});
+	}
+
 	#[test]
 	fn sets_flag_on_initialize() {
 		new_test_ext(Default::default()).execute_with(|| {
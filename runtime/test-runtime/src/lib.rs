@@ -21,15 +21,26 @@
 #![recursion_limit="256"]
 
 use rstd::prelude::*;
-use codec::{Encode, Decode};
-use primitives::v0 as p_v0;
+use codec::Encode;
 use primitives::v1::{
 	AccountId, AccountIndex, Balance, BlockNumber, Hash as HashT, Nonce, Signature, Moment,
+	GroupRotationInfo, CoreState, Id, ValidationData, ValidationCode, CandidateEvent,
+	ValidatorId, ValidatorIndex, CommittedCandidateReceipt, OccupiedCoreAssumption,
+	PersistedValidationData,
 };
 use runtime_common::{
 	claims, SlowAdjustingFeeUpdate, impls::CurrencyToVoteHandler,
 	BlockHashCount, MaximumBlockWeight, AvailableBlockRatio,
-	MaximumBlockLength, BlockExecutionWeight, ExtrinsicBaseWeight, ParachainSessionKeyPlaceholder,
+	MaximumBlockLength, BlockExecutionWeight, ExtrinsicBaseWeight,
+};
+use runtime_parachains::{
+	configuration as parachains_configuration,
+	inclusion as parachains_inclusion,
+	inclusion_inherent as parachains_inclusion_inherent,
+	initializer as parachains_initializer,
+	paras as parachains_paras,
+	runtime_api_impl::v1 as parachains_runtime_api_impl,
+	scheduler as parachains_scheduler,
 };
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
@@ -228,7 +239,7 @@ impl_opaque_keys! {
 	pub struct SessionKeys {
 		pub grandpa: Grandpa,
 		pub babe: Babe,
-		pub parachain_validator: ParachainSessionKeyPlaceholder<Runtime>,
+		pub parachain_validator: Initializer,
 	}
 }
 
@@ -409,6 +420,22 @@ impl pallet_vesting::Trait for Runtime {
 	type WeightInfo = ();
 }
 
+impl parachains_configuration::Trait for Runtime {}
+
+impl parachains_inclusion::Trait for Runtime {
+	type Event = Event;
+}
+
+impl parachains_inclusion_inherent::Trait for Runtime {}
+
+impl parachains_initializer::Trait for Runtime {
+	type Randomness = RandomnessCollectiveFlip;
+}
+
+impl parachains_paras::Trait for Runtime {}
+
+impl parachains_scheduler::Trait for Runtime {}
+
 impl pallet_sudo::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -447,6 +474,14 @@ construct_runtime! {
 		// Vesting. Usable initially, but removed once all vesting is finished.
 		Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
 
+		// Parachains runtime modules
+		ParachainsConfiguration: parachains_configuration::{Module, Call, Storage, Config<T>},
+		Inclusion: parachains_inclusion::{Module, Call, Storage, Event<T>},
+		InclusionInherent: parachains_inclusion_inherent::{Module, Call, Storage, Inherent},
+		Initializer: parachains_initializer::{Module, Call, Storage},
+		Paras: parachains_paras::{Module, Call, Storage, Config<T>},
+		Scheduler: parachains_scheduler::{Module, Call, Storage},
+
 		// Sudo. Last module.
 		Sudo: pallet_sudo::{Module, Call, Storage, Config<T>, Event<T>},
 	}
@@ -547,59 +582,54 @@ sp_api::impl_runtime_apis! {
 
 	impl authority_discovery_primitives::AuthorityDiscoveryApi<Block> for Runtime {
 		fn authorities() -> Vec<AuthorityDiscoveryId> {
-			Vec::new()
+			AuthorityDiscovery::authorities()
 		}
 	}
 
-	// Dummy implementation to continue supporting old parachains runtime temporarily.
-	impl p_v0::ParachainHost<Block> for Runtime {
-		fn validators() -> Vec<p_v0::ValidatorId> {
-			// this is a compile-time check of size equality. note that we don't invoke
-			// the function and nothing here is unsafe.
-			let _ = core::mem::transmute::<p_v0::ValidatorId, AccountId>;
-
-			// Yes, these aren't actually the parachain session keys.
-			// It doesn't matter, but we shouldn't return a zero-sized vector here.
-			// As there are no parachains
-			Session::validators()
-				.into_iter()
-				.map(|k| k.using_encoded(|s| Decode::decode(&mut &s[..]))
-					.expect("correct size and raw-bytes; qed"))
-				.collect()
-		}
-		fn duty_roster() -> p_v0::DutyRoster {
-			let v = Session::validators();
-			p_v0::DutyRoster { validator_duty: (0..v.len()).map(|_| p_v0::Chain::Relay).collect() }
-		}
-		fn active_parachains() -> Vec<(p_v0::Id, Option<(p_v0::CollatorId, p_v0::Retriable)>)> {
-			Vec::new()
-		}
-		fn global_validation_data() -> p_v0::GlobalValidationData {
-			p_v0::GlobalValidationData {
-				max_code_size: 1,
-				max_head_data_size: 1,
-				block_number: System::block_number().saturating_sub(1),
-			}
+	impl primitives::v1::ParachainHost<Block, HashT, BlockNumber> for Runtime {
+		fn validators() -> Vec<ValidatorId> {
+			parachains_runtime_api_impl::validators::<Runtime>()
 		}
-		fn local_validation_data(_id: p_v0::Id) -> Option<p_v0::LocalValidationData> {
-			None
+
+		fn validator_groups() -> (Vec<Vec<ValidatorIndex>>, GroupRotationInfo<BlockNumber>) {
+			parachains_runtime_api_impl::validator_groups::<Runtime>()
 		}
-		fn parachain_code(_id: p_v0::Id) -> Option<p_v0::ValidationCode> {
-			None
+
+		fn availability_cores() -> Vec<CoreState<BlockNumber>> {
+			parachains_runtime_api_impl::availability_cores::<Runtime>()
 		}
-		fn get_heads(_extrinsics: Vec<<Block as BlockT>::Extrinsic>)
-			-> Option<Vec<p_v0::AbridgedCandidateReceipt>>
+
+		fn full_validation_data(para_id: Id, assumption: OccupiedCoreAssumption)
+			-> Option<ValidationData<BlockNumber>>
 		{
-			None
+			parachains_runtime_api_impl::full_validation_data::<Runtime>(para_id, assumption)
 		}
-		fn signing_context() -> p_v0::SigningContext {
-			p_v0::SigningContext {
-				parent_hash: System::parent_hash(),
-				session_index: Session::current_index(),
-			}
+
+		fn persisted_validation_data(para_id: Id, assumption: OccupiedCoreAssumption)
+			-> Option<PersistedValidationData<BlockNumber>>
+		{
+			parachains_runtime_api_impl::persisted_validation_data::<Runtime>(para_id, assumption)
+		}
+
+		fn session_index_for_child() -> SessionIndex {
+			parachains_runtime_api_impl::session_index_for_child::<Runtime>()
+		}
+
+		fn validation_code(para_id: Id, assumption: OccupiedCoreAssumption)
+			-> Option<ValidationCode>
+		{
+			parachains_runtime_api_impl::validation_code::<Runtime>(para_id, assumption)
 		}
-		fn downward_messages(_id: p_v0::Id) -> Vec<p_v0::DownwardMessage> {
-			Vec::new()
+
+		fn candidate_pending_availability(para_id: Id) -> Option<CommittedCandidateReceipt<HashT>> {
+			parachains_runtime_api_impl::candidate_pending_availability::<Runtime>(para_id)
+		}
+
+		fn candidate_events() -> Vec<CandidateEvent<HashT>> {
+			parachains_runtime_api_impl::candidate_events::<Runtime, _>(|ev| match ev {
+				Event::parachains_inclusion(ev) => Some(ev),
+				_ => None,
+			})
 		}
 	}
 
@@ -0,0 +1,64 @@
+#!/usr/bin/env bash
+
+# Compare the benchmarks of the working tree against those of a base revision.
+#
+# The benchmarks of the base revision are run from a temporary worktree and saved as a criterion
+# baseline, then the benchmarks of the working tree are run against it. Criterion reports the
+# change of every benchmark, and the reports end up in `target/criterion/report/index.html`.
+#
+# Usage: scripts/compare-benches.sh [<base revision>] [<package>...]
+#
+# The base revision defaults to `master`. The packages default to all packages with benchmarks.
+# Benchmarks which don't exist in the base revision have nothing to be compared against, so they
+# are run on their own and listed as new.
+
+set -e
+
+BASE=${1:-master}
+shift || true
+PACKAGES=("$@")
+if [ ${#PACKAGES[@]} -eq 0 ]; then
+	PACKAGES=(polkadot-erasure-coding polkadot-statement-table test-parachains)
+fi
+
+ROOT=$(git rev-parse --show-toplevel)
+BASELINE=$(echo "$BASE" | tr -c '[:alnum:]_\n-' '_')
+WORKTREE=$(mktemp -d)
+
+# Both runs share a target directory, which is where criterion keeps its baselines.
+export CARGO_TARGET_DIR=${CARGO_TARGET_DIR:-$ROOT/target}
+
+cleanup() {
+	git -C "$ROOT" worktree remove --force "$WORKTREE"
+}
+trap cleanup EXIT
+
+echo "*** Running benchmarks of $BASE"
+git -C "$ROOT" worktree add --detach "$WORKTREE" "$BASE"
+for package in "${PACKAGES[@]}"; do
+	(cd "$WORKTREE" && cargo bench -p "$package" --benches -- --save-baseline "$BASELINE") \
+		|| echo "*** $package has no benchmarks in $BASE"
+done
+
+# Criterion refuses to compare a benchmark without a baseline, so the benchmarks are run one by one,
+# each matched exactly by its id.
+NEW=()
+echo "*** Comparing benchmarks of the working tree against $BASE"
+for package in "${PACKAGES[@]}"; do
+	IDS=$(cd "$ROOT" && cargo bench -p "$package" --benches -- --list | sed -n 's/: bench$//p')
+	while IFS= read -r id; do
+		[ -n "$id" ] || continue
+		filter="^$(printf '%s' "$id" | sed 's/[][\.*^$+?(){}|]/\\&/g')\$"
+		if [ -d "$CARGO_TARGET_DIR/criterion/$id/$BASELINE" ]; then
+			(cd "$ROOT" && cargo bench -p "$package" --benches -- --baseline "$BASELINE" "$filter")
+		else
+			(cd "$ROOT" && cargo bench -p "$package" --benches -- "$filter")
+			NEW+=("$package: $id")
+		fi
+	done <<< "$IDS"
+done
+
+if [ ${#NEW[@]} -gt 0 ]; then
+	echo "*** New benchmarks, not in $BASE:"
+	printf '\t%s\n' "${NEW[@]}"
+fi
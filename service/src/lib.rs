@@ -47,6 +47,8 @@ pub use sp_runtime::traits::{Block as BlockT, self as runtime_traits, BlakeTwo25
 pub use chain_spec::AcuityChainSpec;
 #[cfg(feature = "full-node")]
 pub use consensus::run_validation_worker;
+#[cfg(feature = "full-node")]
+pub use polkadot_parachain::wasm_executor::{ValidationExecutionMode, ValidationPoolConfig};
 pub use codec::Codec;
 pub use acuity_runtime;
 pub use self::client::*;
@@ -222,6 +224,7 @@ pub fn new_full<RuntimeApi, Executor>(
 	collating_for: Option<(CollatorId, parachain::Id)>,
 	authority_discovery_enabled: bool,
 	grandpa_pause: Option<(u32, u32)>,
+	validation_pool: ValidationPoolConfig,
 	test: bool,
 ) -> Result<(
 	TaskManager,
@@ -430,7 +433,7 @@ pub fn new_full<RuntimeApi, Executor>(
 
 	network_starter.start_network();
 
-	Ok((task_manager, client, FullNodeHandles, network, rpc_handlers))
+	Ok((task_manager, client, FullNodeHandles { validation_pool }, network, rpc_handlers))
 }
 
 /// Builds a new service for a light client.
@@ -562,6 +565,7 @@ pub fn acuity_new_full(
 	collating_for: Option<(CollatorId, parachain::Id)>,
 	authority_discovery_enabled: bool,
 	grandpa_pause: Option<(u32, u32)>,
+	validation_pool: ValidationPoolConfig,
 ) -> Result<(
 		TaskManager,
 		Arc<impl AbstractClient<Block, FullBackend>>,
@@ -573,6 +577,7 @@ pub fn acuity_new_full(
 		collating_for,
 		authority_discovery_enabled,
 		grandpa_pause,
+		validation_pool,
 		false,
 	)?;
 
@@ -582,8 +587,11 @@ pub fn acuity_new_full(
 /// Handles to other sub-services that full nodes instantiate, which consumers
 /// of the node may use.
 #[cfg(feature = "full-node")]
-#[derive(Default)]
-pub struct FullNodeHandles;
+pub struct FullNodeHandles {
+	/// Configuration of the pool parachain candidates are validated on, for the
+	/// candidate validation subsystem of the node.
+	pub validation_pool: ValidationPoolConfig,
+}
 
 /// Build a new light node.
 pub fn build_light(config: Configuration) -> Result<(TaskManager, RpcHandlers), ServiceError> {
@@ -597,12 +605,14 @@ pub fn build_full(
 	collating_for: Option<(CollatorId, parachain::Id)>,
 	authority_discovery_enabled: bool,
 	grandpa_pause: Option<(u32, u32)>,
+	validation_pool: ValidationPoolConfig,
 ) -> Result<(TaskManager, Client, FullNodeHandles), ServiceError> {
 	new_full::<acuity_runtime::RuntimeApi, AcuityExecutor>(
 		config,
 		collating_for,
 		authority_discovery_enabled,
 		grandpa_pause,
+		validation_pool,
 		false,
 	).map(|(task_manager, client, handles, _, _)| (task_manager, Client::Acuity(client), handles))
 }
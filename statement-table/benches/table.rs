@@ -0,0 +1,144 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Statement import and candidate proposal, parameterised by the number of groups and the
+//! size of the groups, which together determine the number of statements.
+//!
+//! Compare against a saved baseline with `scripts/compare-benches.sh`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use polkadot_statement_table::generic::{Context, SignedStatement, Statement, Table};
+
+/// (number of groups, validators per group)
+const SHAPES: [(usize, usize); 4] = [(10, 5), (50, 5), (100, 10), (200, 10)];
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct AuthorityId(usize);
+
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq)]
+struct GroupId(usize);
+
+// group, body
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq)]
+struct Candidate(usize, usize);
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct Signature(usize);
+
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq)]
+struct Digest(usize);
+
+/// Validator `v` is in group `v / group_size`.
+struct BenchContext {
+	group_size: usize,
+}
+
+impl Context for BenchContext {
+	type AuthorityId = AuthorityId;
+	type Digest = Digest;
+	type Candidate = Candidate;
+	type GroupId = GroupId;
+	type Signature = Signature;
+
+	fn candidate_digest(candidate: &Candidate) -> Digest {
+		Digest(candidate.1)
+	}
+
+	fn candidate_group(candidate: &Candidate) -> GroupId {
+		GroupId(candidate.0)
+	}
+
+	fn is_member_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+		authority.0 / self.group_size == group.0
+	}
+
+	fn requisite_votes(&self, _group: &GroupId) -> usize {
+		self.group_size / 2 + 1
+	}
+}
+
+type BenchStatement = SignedStatement<Candidate, Digest, AuthorityId, Signature>;
+
+/// The first validator of each group seconds a candidate, all others of the group vote for it.
+fn statements(n_groups: usize, group_size: usize) -> Vec<BenchStatement> {
+	(0..n_groups).flat_map(|group| {
+		let first = group * group_size;
+		let seconded = std::iter::once(SignedStatement {
+			statement: Statement::Candidate(Candidate(group, group)),
+			signature: Signature(first),
+			sender: AuthorityId(first),
+		});
+		let votes = (first + 1..first + group_size).map(move |v| SignedStatement {
+			statement: Statement::Valid(Digest(group)),
+			signature: Signature(v),
+			sender: AuthorityId(v),
+		});
+
+		seconded.chain(votes)
+	}).collect()
+}
+
+fn import_statements(c: &mut Criterion) {
+	let mut group = c.benchmark_group("import_statement");
+
+	for &(n_groups, group_size) in SHAPES.iter() {
+		let context = BenchContext { group_size };
+		let statements = statements(n_groups, group_size);
+
+		group.throughput(Throughput::Elements(statements.len() as u64));
+		group.bench_with_input(
+			BenchmarkId::new(n_groups.to_string(), group_size),
+			&statements,
+			|b, statements| b.iter_batched(
+				|| statements.clone(),
+				|statements| {
+					let mut table = Table::default();
+					for statement in statements {
+						table.import_statement(&context, statement);
+					}
+					table
+				},
+				BatchSize::SmallInput,
+			),
+		);
+	}
+
+	group.finish();
+}
+
+fn proposed_candidates(c: &mut Criterion) {
+	let mut group = c.benchmark_group("proposed_candidates");
+
+	for &(n_groups, group_size) in SHAPES.iter() {
+		let context = BenchContext { group_size };
+		let mut table = Table::default();
+		for statement in statements(n_groups, group_size) {
+			table.import_statement(&context, statement);
+		}
+		assert_eq!(table.includable_count(), n_groups);
+
+		group.bench_with_input(
+			BenchmarkId::new(n_groups.to_string(), group_size),
+			&table,
+			|b, table| b.iter(|| table.proposed_candidates(&context)),
+		);
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, import_statements, proposed_candidates);
+criterion_main!(benches);
@@ -37,7 +37,7 @@ pub trait Context {
 	/// A authority ID
 	type AuthorityId: Debug + Hash + Eq + Clone;
 	/// The digest (hash or other unique attribute) of a candidate.
-	type Digest: Debug + Hash + Eq + Clone;
+	type Digest: Debug + Hash + Ord + Eq + Clone;
 	/// The group ID type
 	type GroupId: Debug + Hash + Ord + Eq + Clone;
 	/// A signature type.
@@ -57,6 +57,65 @@ pub trait Context {
 
 	/// requisite number of votes for validity from a group.
 	fn requisite_votes(&self, group: &Self::GroupId) -> usize;
+
+	/// The policy deciding whether a candidate of a group has enough votes for validity.
+	///
+	/// Defaults to requiring `requisite_votes`, counting the implicit vote of the seconder.
+	fn threshold_policy(&self, group: &Self::GroupId) -> ThresholdPolicy {
+		ThresholdPolicy::min_backers(self.requisite_votes(group))
+	}
+}
+
+/// The number of validity votes a candidate needs in order to be included.
+///
+/// This is the larger of `min_backers` and `group_fraction` of `group_size`, rounded up.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ThresholdPolicy {
+	/// The minimum number of validity votes.
+	pub min_backers: usize,
+	/// The part of the group which has to vote for validity, as numerator and denominator.
+	pub group_fraction: (usize, usize),
+	/// The number of validators in the group.
+	pub group_size: usize,
+	/// Whether the implicit validity vote of the validator seconding the candidate counts.
+	pub seconder_counts: bool,
+}
+
+impl ThresholdPolicy {
+	/// A policy requiring `votes` votes, counting the implicit vote of the seconder.
+	pub fn min_backers(votes: usize) -> Self {
+		ThresholdPolicy {
+			min_backers: votes,
+			group_fraction: (0, 1),
+			group_size: 0,
+			seconder_counts: true,
+		}
+	}
+
+	/// The number of votes required.
+	pub fn required_votes(&self) -> usize {
+		let (numerator, denominator) = self.group_fraction;
+		let of_group = match denominator {
+			0 => 0,
+			_ => (self.group_size.saturating_mul(numerator) + denominator - 1) / denominator,
+		};
+
+		std::cmp::max(self.min_backers, of_group)
+	}
+}
+
+/// Why a candidate cannot be included yet.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum NotIncludable {
+	/// An authority voted the candidate invalid.
+	IndicatedBad,
+	/// There are not enough votes for the validity of the candidate.
+	NotEnoughVotes {
+		/// The votes which count toward the threshold.
+		counted: usize,
+		/// The votes required by the threshold policy of the group.
+		required: usize,
+	},
 }
 
 /// Statements circulated among peers.
@@ -218,46 +277,78 @@ impl<C: Context> CandidateData<C> {
 
 	/// Yield a full attestation for a candidate.
 	/// If the candidate can be included, it will return `Some`.
-	pub fn attested(&self, validity_threshold: usize)
+	///
+	/// The attestation contains the votes counting toward the threshold, and the implicit vote of
+	/// the seconder, whether it counts or not.
+	pub fn attested(&self, policy: &ThresholdPolicy)
 		-> Option<AttestedCandidate<
 			C::GroupId, C::Candidate, C::AuthorityId, C::Signature,
 		>>
 	{
-		if self.can_be_included(validity_threshold) {
-			let validity_votes: Vec<_> = self.validity_votes.iter()
+		if self.can_be_included(policy) {
+			let required = policy.required_votes();
+			let issued = self.validity_votes.iter()
 				.filter_map(|(a, v)| match *v {
-					ValidityVote::Invalid(_) => None,
+					ValidityVote::Issued(ref s) => Some((a, ValidityAttestation::Implicit(s.clone()))),
+					_ => None,
+				});
+			let explicit = self.validity_votes.iter()
+				.filter_map(|(a, v)| match *v {
+					ValidityVote::Valid(ref s) => Some((a, ValidityAttestation::Explicit(s.clone()))),
+					_ => None,
+				});
 
-					ValidityVote::Valid(ref s) =>
-						Some((a, ValidityAttestation::Explicit(s.clone()))),
-					ValidityVote::Issued(ref s) =>
-						Some((a, ValidityAttestation::Implicit(s.clone()))),
-				})
-				.take(validity_threshold)
-				.map(|(k, v)| (k.clone(), v.clone()))
-				.collect();
+			let validity_votes: Vec<_> = if policy.seconder_counts {
+				issued.chain(explicit).take(required).collect()
+			} else {
+				issued.chain(explicit.take(required)).collect()
+			};
 
 			assert!(
-				validity_votes.len() == validity_threshold,
+				validity_votes.len() >= required,
 				"candidate is includable; therefore there are enough validity votes; qed",
 			);
 
 			Some(AttestedCandidate {
 				group_id: self.group_id.clone(),
 				candidate: self.candidate.clone(),
-				validity_votes,
+				validity_votes: validity_votes.into_iter().map(|(k, v)| (k.clone(), v)).collect(),
 			})
 		} else {
 			None
 		}
 	}
 
+	// The validity votes which count toward the threshold of `policy`.
+	fn counted_votes(&self, policy: &ThresholdPolicy) -> usize {
+		self.validity_votes.values()
+			.filter(|v| match v {
+				ValidityVote::Valid(_) => true,
+				ValidityVote::Issued(_) => policy.seconder_counts,
+				ValidityVote::Invalid(_) => false,
+			})
+			.count()
+	}
+
 	// Candidate data can be included in a proposal
 	// if it has enough validity votes
 	// and no authorities have called it bad.
-	fn can_be_included(&self, validity_threshold: usize) -> bool {
-		self.indicated_bad_by.is_empty()
-			&& self.validity_votes.len() >= validity_threshold
+	fn includability(&self, policy: &ThresholdPolicy) -> Result<(), NotIncludable> {
+		if !self.indicated_bad_by.is_empty() {
+			return Err(NotIncludable::IndicatedBad);
+		}
+
+		let counted = self.counted_votes(policy);
+		let required = policy.required_votes();
+		if counted < required {
+			return Err(NotIncludable::NotEnoughVotes { counted, required });
+		}
+
+		Ok(())
+	}
+
+	fn can_be_included(&self, policy: &ThresholdPolicy) -> bool {
+		self.includability(policy).is_ok()
 	}
 
 	fn summary(&self, digest: C::Digest) -> Summary<C::Digest, C::GroupId> {
@@ -289,6 +380,15 @@ pub type ImportResult<C> = Result<
 	MisbehaviorFor<C>
 >;
 
+/// The outcome of `Table::proposed_candidates`.
+pub struct Proposals<C: Context> {
+	/// The best includable candidate of each group, sorted in ascending order by group id.
+	pub attested: Vec<AttestedCandidate<C::GroupId, C::Candidate, C::AuthorityId, C::Signature>>,
+	/// The candidates which cannot be included, with the reason why, sorted in ascending order
+	/// by digest.
+	pub not_includable: Vec<(C::Digest, NotIncludable)>,
+}
+
 /// Stores votes
 pub struct Table<C: Context> {
 	authority_data: HashMap<C::AuthorityId, AuthorityData<C>>,
@@ -313,52 +413,56 @@ impl<C: Context> Table<C> {
 	///
 	/// This will be at most one per group, consisting of the
 	/// best candidate for each group with requisite votes for inclusion.
+	/// The candidates which cannot be included are returned along with the reason.
 	///
-	/// The vector is sorted in ascending order by group id.
-	pub fn proposed_candidates(&self, context: &C) -> Vec<AttestedCandidate<
-		C::GroupId, C::Candidate, C::AuthorityId, C::Signature,
-	>> {
+	/// The proposed candidates are sorted in ascending order by group id, and the ones which
+	/// cannot be included by digest.
+	pub fn proposed_candidates(&self, context: &C) -> Proposals<C> {
 		use std::collections::BTreeMap;
 		use std::collections::btree_map::Entry as BTreeEntry;
 
 		let mut best_candidates = BTreeMap::new();
-		for candidate_data in self.candidate_votes.values() {
+		let mut not_includable = Vec::new();
+		for (digest, candidate_data) in self.candidate_votes.iter() {
 			let group_id = &candidate_data.group_id;
+			let policy = context.threshold_policy(group_id);
 
-			if !self.includable_count.contains_key(group_id) {
+			if let Err(reason) = candidate_data.includability(&policy) {
+				not_includable.push((digest.clone(), reason));
 				continue
 			}
 
-			let threshold = context.requisite_votes(group_id);
-
-			if !candidate_data.can_be_included(threshold) { continue }
 			match best_candidates.entry(group_id.clone()) {
 				BTreeEntry::Vacant(vacant) => {
-					vacant.insert((candidate_data, threshold));
+					vacant.insert((candidate_data, policy));
 				},
 				BTreeEntry::Occupied(mut occ) => {
 					let candidate_ref = occ.get_mut();
 					if candidate_ref.0.candidate > candidate_data.candidate {
-						candidate_ref.0 = candidate_data;
+						*candidate_ref = (candidate_data, policy);
 					}
 				}
 			}
 		}
 
-		best_candidates.values()
-			.map(|&(candidate_data, threshold)|
-				candidate_data.attested(threshold)
+		let attested = best_candidates.values()
+			.map(|(candidate_data, policy)|
+				candidate_data.attested(policy)
 					.expect("candidate has been checked includable; \
 						therefore an attestation can be constructed; qed")
 			)
-			.collect::<Vec<_>>()
+			.collect::<Vec<_>>();
+
+		// the votes are in no particular order.
+		not_includable.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+		Proposals { attested, not_includable }
 	}
 
 	/// Whether a candidate can be included.
 	pub fn candidate_includable(&self, digest: &C::Digest, context: &C) -> bool {
 		self.candidate_votes.get(digest).map_or(false, |data| {
-			let v_threshold = context.requisite_votes(&data.group_id);
-			data.can_be_included(v_threshold)
+			data.can_be_included(&context.threshold_policy(&data.group_id))
 		})
 	}
 
@@ -515,8 +619,8 @@ impl<C: Context> Table<C> {
 			Some(votes) => votes,
 		};
 
-		let v_threshold = context.requisite_votes(&votes.group_id);
-		let was_includable = votes.can_be_included(v_threshold);
+		let policy = context.threshold_policy(&votes.group_id);
+		let was_includable = votes.can_be_included(&policy);
 
 		// check that this authority actually can vote in this group.
 		if !context.is_member_of(&from, &votes.group_id) {
@@ -588,7 +692,7 @@ impl<C: Context> Table<C> {
 			}
 		}
 
-		let is_includable = votes.can_be_included(v_threshold);
+		let is_includable = votes.can_be_included(&policy);
 		update_includable_count(&mut self.includable_count, &votes.group_id, was_includable, is_includable);
 
 		Ok(Some(votes.summary(digest)))
@@ -632,7 +736,7 @@ mod tests {
 	#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 	struct Signature(usize);
 
-	#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+	#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
 	struct Digest(usize);
 
 	#[derive(Debug, PartialEq, Eq)]
@@ -971,6 +1075,7 @@ mod tests {
 	#[test]
 	fn candidate_can_be_included() {
 		let validity_threshold = 6;
+		let policy = ThresholdPolicy::min_backers(validity_threshold);
 
 		let mut candidate = CandidateData::<TestContext> {
 			group_id: GroupId(4),
@@ -979,17 +1084,139 @@ mod tests {
 			indicated_bad_by: Vec::new(),
 		};
 
-		assert!(!candidate.can_be_included(validity_threshold));
+		assert!(!candidate.can_be_included(&policy));
 
 		for i in 0..validity_threshold {
 			candidate.validity_votes.insert(AuthorityId(i + 100), ValidityVote::Valid(Signature(i + 100)));
 		}
 
-		assert!(candidate.can_be_included(validity_threshold));
+		assert!(candidate.can_be_included(&policy));
 
 		candidate.indicated_bad_by.push(AuthorityId(1024));
 
-		assert!(!candidate.can_be_included(validity_threshold));
+		assert!(!candidate.can_be_included(&policy));
+	}
+
+	#[test]
+	fn threshold_policy_counts_seconder_and_group_fraction() {
+		let mut candidate = CandidateData::<TestContext> {
+			group_id: GroupId(4),
+			candidate: Candidate(4, 12345),
+			validity_votes: HashMap::new(),
+			indicated_bad_by: Vec::new(),
+		};
+
+		candidate.validity_votes.insert(AuthorityId(1), ValidityVote::Issued(Signature(1)));
+		candidate.validity_votes.insert(AuthorityId(2), ValidityVote::Valid(Signature(2)));
+
+		let mut policy = ThresholdPolicy::min_backers(2);
+		assert_eq!(candidate.includability(&policy), Ok(()));
+		assert_eq!(candidate.attested(&policy).unwrap().validity_votes.len(), 2);
+
+		policy.seconder_counts = false;
+		assert_eq!(
+			candidate.includability(&policy),
+			Err(NotIncludable::NotEnoughVotes { counted: 1, required: 2 }),
+		);
+
+		candidate.validity_votes.insert(AuthorityId(3), ValidityVote::Valid(Signature(3)));
+		assert_eq!(candidate.includability(&policy), Ok(()));
+
+		// the seconder's vote is part of the attestation, even though it doesn't count.
+		let attested = candidate.attested(&policy).unwrap();
+		assert_eq!(attested.validity_votes.len(), 3);
+		assert!(attested.validity_votes.contains(&(AuthorityId(1), ValidityAttestation::Implicit(Signature(1)))));
+
+		// two thirds of a group of five, rounded up.
+		policy.group_fraction = (2, 3);
+		policy.group_size = 5;
+		assert_eq!(policy.required_votes(), 4);
+		assert_eq!(
+			candidate.includability(&policy),
+			Err(NotIncludable::NotEnoughVotes { counted: 2, required: 4 }),
+		);
+	}
+
+	#[test]
+	fn proposed_candidates_tell_why_candidates_are_not_includable() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), GroupId(2));
+				map.insert(AuthorityId(2), GroupId(2));
+				map.insert(AuthorityId(3), GroupId(3));
+				map.insert(AuthorityId(4), GroupId(3));
+				map
+			}
+		};
+
+		let mut table = create();
+		let second = |candidate: Candidate, authority| SignedStatement {
+			statement: Statement::Candidate(candidate),
+			signature: Signature(authority),
+			sender: AuthorityId(authority),
+		};
+
+		// group 2 backs its candidate.
+		table.import_statement(&context, second(Candidate(2, 100), 1));
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Valid(Digest(100)),
+			signature: Signature(2),
+			sender: AuthorityId(2),
+		});
+
+		// group 3 only seconds its candidate.
+		table.import_statement(&context, second(Candidate(3, 200), 3));
+
+		let proposals = table.proposed_candidates(&context);
+		assert_eq!(proposals.attested.len(), 1);
+		assert_eq!(proposals.attested[0].group_id, GroupId(2));
+		assert_eq!(
+			proposals.not_includable,
+			vec![(Digest(200), NotIncludable::NotEnoughVotes { counted: 1, required: 2 })],
+		);
+
+		// now group 3 declares it invalid.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Invalid(Digest(200)),
+			signature: Signature(4),
+			sender: AuthorityId(4),
+		});
+
+		let proposals = table.proposed_candidates(&context);
+		assert_eq!(proposals.not_includable, vec![(Digest(200), NotIncludable::IndicatedBad)]);
+	}
+
+	#[test]
+	fn candidates_not_includable_are_sorted_by_digest() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), GroupId(1));
+				map.insert(AuthorityId(2), GroupId(1));
+				map.insert(AuthorityId(3), GroupId(2));
+				map.insert(AuthorityId(4), GroupId(2));
+				map.insert(AuthorityId(5), GroupId(3));
+				map.insert(AuthorityId(6), GroupId(3));
+				map
+			}
+		};
+
+		let mut table = create();
+		for &(group, digest, authority) in &[(1, 300, 1), (2, 100, 3), (3, 200, 5)] {
+			table.import_statement(&context, SignedStatement {
+				statement: Statement::Candidate(Candidate(group, digest)),
+				signature: Signature(authority),
+				sender: AuthorityId(authority),
+			});
+		}
+
+		let proposals = table.proposed_candidates(&context);
+		assert!(proposals.attested.is_empty());
+		assert_eq!(
+			proposals.not_includable.iter().map(|(digest, _)| *digest).collect::<Vec<_>>(),
+			vec![Digest(100), Digest(200), Digest(300)],
+		);
 	}
 
 	#[test]
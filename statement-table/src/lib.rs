@@ -65,7 +65,11 @@ pub mod v1 {
 	use primitives::v1::{
 		Hash,
 		Id, CommittedCandidateReceipt, CompactStatement as PrimitiveStatement,
-		ValidatorSignature, ValidatorIndex,
+		ValidatorSignature, ValidatorIndex, SigningContext,
+		misbehavior::{
+			MisbehaviorProof, SignedProofStatement, ValidityDoubleVoteProof,
+			MultipleCandidatesProof, UnauthorizedStatementProof,
+		},
 	};
 
 	/// Statements about candidates on the network.
@@ -99,4 +103,238 @@ pub mod v1 {
 			}
 		}
 	}
+
+	/// Turn misbehavior detected by `table` into a self-contained proof of it.
+	///
+	/// `signing_context` is the one the statements imported into the table were checked against.
+	/// Returns `None` for unauthorized votes on candidates the table doesn't know, and for
+	/// misbehavior which isn't provable: seconding and voting valid on the same candidate, and
+	/// signing the same statement twice.
+	pub fn misbehavior_proof<C>(
+		table: &generic::Table<C>,
+		validator_index: ValidatorIndex,
+		misbehavior: &Misbehavior,
+		signing_context: SigningContext,
+	) -> Option<MisbehaviorProof>
+	where
+		C: generic::Context<
+			Candidate = CommittedCandidateReceipt,
+			Digest = Hash,
+			AuthorityId = ValidatorIndex,
+			Signature = ValidatorSignature,
+		>,
+	{
+		let signed = |statement: PrimitiveStatement, signature: &ValidatorSignature| SignedProofStatement {
+			statement,
+			signature: signature.clone(),
+		};
+
+		let proof = match misbehavior {
+			generic::Misbehavior::ValidityDoubleVote(double_vote) => {
+				let (first, second) = match double_vote {
+					// both statements are votes for the candidate.
+					generic::ValidityDoubleVote::IssuedAndValidity(..) => return None,
+					generic::ValidityDoubleVote::IssuedAndInvalidity((candidate, a), (digest, b)) => (
+						signed(PrimitiveStatement::Candidate(candidate.hash()), a),
+						signed(PrimitiveStatement::Invalid(*digest), b),
+					),
+					generic::ValidityDoubleVote::ValidityAndInvalidity(candidate, a, b) => (
+						signed(PrimitiveStatement::Valid(candidate.hash()), a),
+						signed(PrimitiveStatement::Invalid(candidate.hash()), b),
+					),
+				};
+
+				MisbehaviorProof::ValidityDoubleVote(ValidityDoubleVoteProof {
+					signing_context,
+					validator_index,
+					first,
+					second,
+				})
+			}
+			generic::Misbehavior::MultipleCandidates(multiple) => {
+				MisbehaviorProof::MultipleCandidates(MultipleCandidatesProof {
+					signing_context,
+					validator_index,
+					first: (multiple.first.0.hash(), multiple.first.1.clone()),
+					second: (multiple.second.0.hash(), multiple.second.1.clone()),
+				})
+			}
+			generic::Misbehavior::UnauthorizedStatement(unauthorized) => {
+				let statement = &unauthorized.statement;
+				let candidate = match &statement.statement {
+					generic::Statement::Candidate(candidate) => candidate,
+					generic::Statement::Valid(digest) | generic::Statement::Invalid(digest) =>
+						table.get_candidate(digest)?,
+				};
+
+				MisbehaviorProof::UnauthorizedStatement(UnauthorizedStatementProof {
+					signing_context,
+					validator_index,
+					statement: signed((&statement.statement).into(), &statement.signature),
+					candidate: candidate.to_plain(),
+				})
+			}
+			// sr25519 signatures are randomized, so signing a statement twice is not misbehavior.
+			generic::Misbehavior::DoubleSign(_) => return None,
+		};
+
+		Some(proof)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{generic, v1};
+	use codec::Encode;
+	use primitives::v1::{
+		Hash, Id as ParaId, CommittedCandidateReceipt, CompactStatement, SigningContext,
+		ValidatorIndex, ValidatorPair, ValidatorId, ValidatorSignature,
+		misbehavior::{verify, MisbehaviorProof},
+	};
+	use sp_core::crypto::Pair;
+
+	// only validator 1 is assigned, to every para.
+	struct TestContext;
+
+	impl generic::Context for TestContext {
+		type AuthorityId = ValidatorIndex;
+		type Digest = Hash;
+		type GroupId = ParaId;
+		type Signature = ValidatorSignature;
+		type Candidate = CommittedCandidateReceipt;
+
+		fn candidate_digest(candidate: &CommittedCandidateReceipt) -> Hash {
+			candidate.hash()
+		}
+
+		fn candidate_group(candidate: &CommittedCandidateReceipt) -> ParaId {
+			candidate.descriptor().para_id
+		}
+
+		fn is_member_of(&self, authority: &ValidatorIndex, _group: &ParaId) -> bool {
+			*authority == 1
+		}
+
+		fn requisite_votes(&self, _group: &ParaId) -> usize {
+			1
+		}
+	}
+
+	fn candidate(para_id: u32, head: u8) -> CommittedCandidateReceipt {
+		let mut candidate = CommittedCandidateReceipt::default();
+		candidate.descriptor.para_id = para_id.into();
+		candidate.commitments.head_data = vec![head].into();
+		candidate
+	}
+
+	#[test]
+	fn provable_misbehavior_becomes_a_valid_proof() {
+		let key = ValidatorPair::generate().0;
+		let validators: Vec<ValidatorId> = vec![key.public()];
+		let signing_context = SigningContext { session_index: 3, parent_hash: Hash::repeat_byte(1) };
+		let sign = |statement: CompactStatement| key.sign(&(&statement, &signing_context).encode()[..]);
+
+		let a = candidate(1, 1);
+		let b = candidate(1, 2);
+		let unassigned = candidate(2, 3);
+
+		// the table has to know the candidate for votes on it to be provable.
+		let mut table = generic::Table::<TestContext>::default();
+		table.import_statement(&TestContext, generic::SignedStatement {
+			statement: generic::Statement::Candidate(unassigned.clone()),
+			signature: sign(CompactStatement::Candidate(unassigned.hash())),
+			sender: 1,
+		});
+		assert!(table.get_candidate(&unassigned.hash()).is_some());
+
+		let misbehaviors = vec![
+			generic::Misbehavior::ValidityDoubleVote(generic::ValidityDoubleVote::IssuedAndInvalidity(
+				(a.clone(), sign(CompactStatement::Candidate(a.hash()))),
+				(a.hash(), sign(CompactStatement::Invalid(a.hash()))),
+			)),
+			generic::Misbehavior::ValidityDoubleVote(generic::ValidityDoubleVote::ValidityAndInvalidity(
+				a.clone(),
+				sign(CompactStatement::Valid(a.hash())),
+				sign(CompactStatement::Invalid(a.hash())),
+			)),
+			generic::Misbehavior::MultipleCandidates(generic::MultipleCandidates {
+				first: (a.clone(), sign(CompactStatement::Candidate(a.hash()))),
+				second: (b.clone(), sign(CompactStatement::Candidate(b.hash()))),
+			}),
+			generic::Misbehavior::UnauthorizedStatement(generic::UnauthorizedStatement {
+				statement: generic::SignedStatement {
+					statement: generic::Statement::Candidate(unassigned.clone()),
+					signature: sign(CompactStatement::Candidate(unassigned.hash())),
+					sender: 0,
+				},
+			}),
+			generic::Misbehavior::UnauthorizedStatement(generic::UnauthorizedStatement {
+				statement: generic::SignedStatement {
+					statement: generic::Statement::Valid(unassigned.hash()),
+					signature: sign(CompactStatement::Valid(unassigned.hash())),
+					sender: 0,
+				},
+			}),
+		];
+
+		for misbehavior in &misbehaviors {
+			let proof = v1::misbehavior_proof(&table, 0, misbehavior, signing_context.clone())
+				.expect("all candidates are known to the table; qed");
+
+			assert_eq!(verify(&proof, &validators, 3), Ok(()), "{:?}", misbehavior);
+		}
+
+		// behavior an honest validator may show can't be proven.
+		let unprovable = vec![
+			generic::Misbehavior::ValidityDoubleVote(generic::ValidityDoubleVote::IssuedAndValidity(
+				(a.clone(), sign(CompactStatement::Candidate(a.hash()))),
+				(a.hash(), sign(CompactStatement::Valid(a.hash()))),
+			)),
+			generic::Misbehavior::DoubleSign(generic::DoubleSign::Candidate(
+				a.clone(),
+				sign(CompactStatement::Candidate(a.hash())),
+				sign(CompactStatement::Candidate(a.hash())),
+			)),
+			generic::Misbehavior::DoubleSign(generic::DoubleSign::Validity(
+				a.hash(),
+				sign(CompactStatement::Valid(a.hash())),
+				sign(CompactStatement::Valid(a.hash())),
+			)),
+			generic::Misbehavior::DoubleSign(generic::DoubleSign::Invalidity(
+				a.hash(),
+				sign(CompactStatement::Invalid(a.hash())),
+				sign(CompactStatement::Invalid(a.hash())),
+			)),
+		];
+
+		for misbehavior in &unprovable {
+			assert!(
+				v1::misbehavior_proof(&table, 0, misbehavior, signing_context.clone()).is_none(),
+				"{:?}",
+				misbehavior,
+			);
+		}
+
+		// a vote on a candidate the table doesn't know can't be proven.
+		let unknown_vote = generic::Misbehavior::UnauthorizedStatement(generic::UnauthorizedStatement {
+			statement: generic::SignedStatement {
+				statement: generic::Statement::Valid(b.hash()),
+				signature: sign(CompactStatement::Valid(b.hash())),
+				sender: 0,
+			},
+		});
+		assert!(v1::misbehavior_proof(&table, 0, &unknown_vote, signing_context.clone()).is_none());
+
+		// proofs are bound to the validator which misbehaved.
+		let proof = v1::misbehavior_proof(&table, 0, &misbehaviors[0], signing_context).unwrap();
+		match proof {
+			MisbehaviorProof::ValidityDoubleVote(mut p) => {
+				p.validator_index = 1;
+				let other_key = ValidatorPair::generate().0;
+				let validators = vec![key.public(), other_key.public()];
+				assert!(verify(&MisbehaviorProof::ValidityDoubleVote(p), &validators, 3).is_err());
+			}
+			_ => panic!("converted to the wrong kind of proof"),
+		}
+	}
 }